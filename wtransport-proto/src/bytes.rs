@@ -1,6 +1,7 @@
 use crate::varint::VarInt;
 use octets::Octets;
 use octets::OctetsMut;
+use std::borrow::Cow;
 use std::ops::Deref;
 use std::ops::DerefMut;
 
@@ -9,6 +10,14 @@ use std::ops::DerefMut;
 #[derive(Debug)]
 pub struct EndOfBuffer;
 
+impl std::fmt::Display for EndOfBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "end of buffer")
+    }
+}
+
+impl std::error::Error for EndOfBuffer {}
+
 /// Reads bytes or varint from a source.
 pub trait BytesReader<'a> {
     /// Reads an unsigned variable-length integer in network byte-order from
@@ -22,6 +31,16 @@ pub trait BytesReader<'a> {
     ///
     /// Returns [`None`] if not enough capacity (offset is not advanced in that case).
     fn get_bytes(&mut self, len: usize) -> Option<&'a [u8]>;
+
+    /// Reads `len` bytes from the current offset and advances the offset.
+    ///
+    /// Bytes are copied only if they are not contiguous in the source
+    /// (see [`ChainedBufferReader`]).
+    ///
+    /// Returns [`None`] if not enough capacity (offset is not advanced in that case).
+    fn get_bytes_cow(&mut self, len: usize) -> Option<Cow<'a, [u8]>> {
+        self.get_bytes(len).map(Cow::Borrowed)
+    }
 }
 
 impl<'a> BytesReader<'a> for &'a [u8] {
@@ -56,6 +75,8 @@ pub trait BytesWriter {
     fn put_bytes(&mut self, bytes: &[u8]) -> Result<(), EndOfBuffer>;
 }
 
+// With the `bytes` feature, `Vec<u8>` is covered by the `BufMut` implementation
+#[cfg(not(feature = "bytes"))]
 impl BytesWriter for Vec<u8> {
     fn put_varint(&mut self, varint: VarInt) -> Result<(), EndOfBuffer> {
         let offset = self.len();
@@ -75,6 +96,77 @@ impl BytesWriter for Vec<u8> {
     }
 }
 
+/// Any [`bytes::BufMut`] (e.g., [`Vec<u8>`], [`bytes::BytesMut`]) can be written.
+///
+/// Writes fail with [`EndOfBuffer`] if the buffer cannot grow enough
+/// (see [`bytes::BufMut::remaining_mut`]).
+#[cfg(feature = "bytes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
+impl<B> BytesWriter for B
+where
+    B: ::bytes::BufMut,
+{
+    fn put_varint(&mut self, varint: VarInt) -> Result<(), EndOfBuffer> {
+        let mut buffer = [0; VarInt::MAX_SIZE];
+        let mut buffer_writer = BufferWriter::new(&mut buffer);
+
+        buffer_writer
+            .put_varint(varint)
+            .expect("Enough capacity preallocated");
+
+        // `BufMut` has a `put_bytes` method too
+        BytesWriter::put_bytes(self, buffer_writer.buffer_written())
+    }
+
+    fn put_bytes(&mut self, bytes: &[u8]) -> Result<(), EndOfBuffer> {
+        if self.remaining_mut() < bytes.len() {
+            return Err(EndOfBuffer);
+        }
+
+        self.put_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Reads varints from any [`bytes::Buf`], including non-contiguous ones
+/// (e.g., chains of buffers).
+///
+/// Contiguous buffers (e.g., [`bytes::Bytes`]) can be read without copying
+/// through their slice, which implements [`BytesReader`].
+#[cfg(feature = "bytes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bytes")))]
+pub trait BufVarInt {
+    /// Reads an unsigned variable-length integer in network byte-order and
+    /// advances the buffer.
+    ///
+    /// Returns [`None`] if not enough data (the buffer is not advanced in that case).
+    fn try_get_varint(&mut self) -> Option<VarInt>;
+}
+
+#[cfg(feature = "bytes")]
+impl<B> BufVarInt for B
+where
+    B: ::bytes::Buf,
+{
+    fn try_get_varint(&mut self) -> Option<VarInt> {
+        let first = *self.chunk().first()?;
+        let varint_size = VarInt::parse_size(first);
+
+        if self.remaining() < varint_size {
+            return None;
+        }
+
+        let mut buffer = [0; VarInt::MAX_SIZE];
+        self.copy_to_slice(&mut buffer[..varint_size]);
+
+        let varint = BufferReader::new(&buffer[..varint_size])
+            .get_varint()
+            .expect("Varint parsable");
+
+        Some(varint)
+    }
+}
+
 /// A zero-copy immutable byte-buffer reader.
 ///
 /// Internally, it stores an offset that is increased during reading.
@@ -153,6 +245,144 @@ impl<'a> BytesReader<'a> for BufferReader<'a> {
     }
 }
 
+/// A zero-copy immutable reader over a sequence of buffers (segments),
+/// read as if they were concatenated.
+///
+/// Varints split across segments are parsed transparently. Byte sequences
+/// split across segments cannot be borrowed: [`BytesReader::get_bytes`] returns
+/// [`None`] for them, while [`BytesReader::get_bytes_cow`] copies them. Frames and
+/// capsules are parsed with the latter, so they can straddle segments.
+///
+/// # Example
+/// ```
+/// use wtransport_proto::bytes::BytesReader;
+/// use wtransport_proto::bytes::ChainedBufferReader;
+///
+/// let mut reader = ChainedBufferReader::new([&[0x40][..], &[0x25, 0x01, 0x02][..]]);
+///
+/// assert_eq!(reader.get_varint().unwrap().into_inner(), 37);
+/// assert_eq!(reader.get_bytes(2), Some(&[0x01, 0x02][..]));
+/// assert_eq!(reader.capacity(), 0);
+/// ```
+pub struct ChainedBufferReader<'a> {
+    segments: Vec<&'a [u8]>,
+    segment: usize,
+    segment_offset: usize,
+    offset: usize,
+}
+
+impl<'a> ChainedBufferReader<'a> {
+    /// Creates a reader over `segments`, without copying them.
+    pub fn new<I>(segments: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        Self {
+            segments: segments
+                .into_iter()
+                .filter(|segment| !segment.is_empty())
+                .collect(),
+            segment: 0,
+            segment_offset: 0,
+            offset: 0,
+        }
+    }
+
+    /// Returns the remaining capacity across all the segments.
+    pub fn capacity(&self) -> usize {
+        self.segments[self.segment.min(self.segments.len())..]
+            .iter()
+            .map(|segment| segment.len())
+            .sum::<usize>()
+            - self.segment_offset
+    }
+
+    /// Returns the number of bytes read so far.
+    #[inline(always)]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The unread part of the current segment.
+    fn current(&self) -> &'a [u8] {
+        self.segments
+            .get(self.segment)
+            .map_or(&[], |segment| &segment[self.segment_offset..])
+    }
+
+    /// Advances the offset by `len` bytes, which must be available.
+    fn advance(&mut self, mut len: usize) {
+        self.offset += len;
+
+        while len > 0 {
+            let available = self.current().len();
+
+            if len < available {
+                self.segment_offset += len;
+                return;
+            }
+
+            // The segment is exhausted: move to the next one
+            len -= available;
+            self.segment += 1;
+            self.segment_offset = 0;
+        }
+    }
+
+    /// Copies the next `buffer.len()` bytes, which must be available, advancing the offset.
+    fn copy_to(&mut self, buffer: &mut [u8]) {
+        let mut copied = 0;
+
+        while copied < buffer.len() {
+            let current = self.current();
+            let len = current.len().min(buffer.len() - copied);
+
+            buffer[copied..copied + len].copy_from_slice(&current[..len]);
+            copied += len;
+            self.advance(len);
+        }
+    }
+}
+
+impl<'a> BytesReader<'a> for ChainedBufferReader<'a> {
+    fn get_varint(&mut self) -> Option<VarInt> {
+        let varint_size = VarInt::parse_size(*self.current().first()?);
+
+        if self.capacity() < varint_size {
+            return None;
+        }
+
+        let mut buffer = [0; VarInt::MAX_SIZE];
+        self.copy_to(&mut buffer[..varint_size]);
+
+        let varint = BufferReader::new(&buffer[..varint_size])
+            .get_varint()
+            .expect("Varint parsable");
+
+        Some(varint)
+    }
+
+    fn get_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.current().get(..len)?;
+        self.advance(len);
+        Some(bytes)
+    }
+
+    fn get_bytes_cow(&mut self, len: usize) -> Option<Cow<'a, [u8]>> {
+        if let Some(bytes) = self.get_bytes(len) {
+            return Some(Cow::Borrowed(bytes));
+        }
+
+        if self.capacity() < len {
+            return None;
+        }
+
+        let mut bytes = vec![0; len];
+        self.copy_to(&mut bytes);
+        Some(Cow::Owned(bytes))
+    }
+}
+
 /// It acts like a copy of a parent [`BufferReader`].
 ///
 /// You can create this from [`BufferReader::child`].
@@ -273,6 +503,17 @@ pub mod r#async {
         Closed,
     }
 
+    impl std::fmt::Display for IoError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                IoError::NotConnected => write!(f, "transport not connected"),
+                IoError::Closed => write!(f, "transport closed"),
+            }
+        }
+    }
+
+    impl std::error::Error for IoError {}
+
     impl From<std::io::Error> for IoError {
         fn from(error: std::io::Error) -> Self {
             match error.kind() {
@@ -305,7 +546,10 @@ pub mod r#async {
         ///
         /// On success, returns `Poll::Ready(num_bytes_written)`.
         ///
-        /// **Note**: this should never return `Ok(0)` if `buf.len() > 0`.
+        /// Writing an empty `buf` returns `Ok(0)`. For a non-empty `buf`, `Ok(0)`
+        /// means that the destination cannot accept any more bytes (e.g., it is
+        /// closed): the writers of this crate (e.g., [`BytesWriterAsync::put_buffer`])
+        /// fail with [`IoError::Closed`].
         fn poll_write(
             self: Pin<&mut Self>,
             cx: &mut Context<'_>,
@@ -540,8 +784,10 @@ pub mod r#async {
                     &this.buffer[this.offset..this.varint_size]
                 ))?;
 
-                // TODO(bfesta): what if AsyncWrite returns Ok(0)? maybe wake and pending?
-                debug_assert!(written > 0);
+                // The destination cannot accept any more bytes
+                if written == 0 {
+                    return Poll::Ready(Err(IoError::Closed));
+                }
 
                 this.offset += written;
             }
@@ -589,8 +835,10 @@ pub mod r#async {
                     &this.buffer[this.offset..]
                 ))?;
 
-                // TODO(bfesta): what if AsyncWrite returns Ok(0)? maybe wake and pending?
-                debug_assert!(written > 0);
+                // The destination cannot accept any more bytes
+                if written == 0 {
+                    return Poll::Ready(Err(IoError::Closed));
+                }
 
                 this.offset += written;
             }
@@ -629,6 +877,228 @@ pub mod r#async {
             Poll::Ready(IoResult::Ok(buf.len()))
         }
     }
+
+    /// Adapter between the I/O traits of this crate and the ones of `tokio`.
+    ///
+    /// It works in both directions:
+    ///  * if `T` implements [`tokio::io::AsyncRead`] (or [`tokio::io::AsyncWrite`]),
+    ///    the adapter implements [`AsyncRead`] (or [`AsyncWrite`]), so that, for
+    ///    example, frames can be read from a TCP stream;
+    ///  * if `T` implements [`AsyncRead`] (or [`AsyncWrite`]), the adapter
+    ///    implements the `tokio` counterpart. As [`AsyncWrite`] does not buffer,
+    ///    flushing and shutting down are no-ops.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use wtransport_proto::bytes::TokioIo;
+    /// use wtransport_proto::stream::StreamHeader;
+    ///
+    /// # async fn run<S>(tcp_stream: S)
+    /// # where
+    /// #     S: tokio::io::AsyncRead + Unpin,
+    /// # {
+    /// let mut stream = TokioIo::new(tcp_stream);
+    /// let stream_header = StreamHeader::read_async(&mut stream).await;
+    /// # }
+    /// ```
+    #[cfg(feature = "tokio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio")))]
+    #[derive(Debug)]
+    pub struct TokioIo<T>(T);
+
+    #[cfg(feature = "tokio")]
+    impl<T> TokioIo<T> {
+        /// Wraps `inner`.
+        pub fn new(inner: T) -> Self {
+            Self(inner)
+        }
+
+        /// Returns a reference to the wrapped value.
+        pub fn get_ref(&self) -> &T {
+            &self.0
+        }
+
+        /// Returns a mutable reference to the wrapped value.
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+
+        /// Returns the wrapped value.
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    impl<T> AsyncRead for TokioIo<T>
+    where
+        T: tokio::io::AsyncRead + Unpin,
+    {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<IoResult<usize>> {
+            let mut buffer = tokio::io::ReadBuf::new(buf);
+            ready!(tokio::io::AsyncRead::poll_read(
+                Pin::new(&mut self.0),
+                cx,
+                &mut buffer
+            ))?;
+            Poll::Ready(Ok(buffer.filled().len()))
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    impl<T> AsyncWrite for TokioIo<T>
+    where
+        T: tokio::io::AsyncWrite + Unpin,
+    {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<IoResult<usize>> {
+            tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    impl<T> tokio::io::AsyncRead for TokioIo<T>
+    where
+        T: AsyncRead + Unpin,
+    {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> Poll<IoResult<()>> {
+            let read = ready!(AsyncRead::poll_read(
+                Pin::new(&mut self.0),
+                cx,
+                buf.initialize_unfilled()
+            ))?;
+            buf.advance(read);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    impl<T> tokio::io::AsyncWrite for TokioIo<T>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<IoResult<usize>> {
+            AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Adapter between the I/O traits of this crate and the ones of `futures`.
+    ///
+    /// It works in both directions, like [`TokioIo`].
+    #[cfg(feature = "futures-io")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "futures-io")))]
+    #[derive(Debug)]
+    pub struct FuturesIo<T>(T);
+
+    #[cfg(feature = "futures-io")]
+    impl<T> FuturesIo<T> {
+        /// Wraps `inner`.
+        pub fn new(inner: T) -> Self {
+            Self(inner)
+        }
+
+        /// Returns a reference to the wrapped value.
+        pub fn get_ref(&self) -> &T {
+            &self.0
+        }
+
+        /// Returns a mutable reference to the wrapped value.
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.0
+        }
+
+        /// Returns the wrapped value.
+        pub fn into_inner(self) -> T {
+            self.0
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    impl<T> AsyncRead for FuturesIo<T>
+    where
+        T: futures_io::AsyncRead + Unpin,
+    {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<IoResult<usize>> {
+            futures_io::AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    impl<T> AsyncWrite for FuturesIo<T>
+    where
+        T: futures_io::AsyncWrite + Unpin,
+    {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<IoResult<usize>> {
+            futures_io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    impl<T> futures_io::AsyncRead for FuturesIo<T>
+    where
+        T: AsyncRead + Unpin,
+    {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<IoResult<usize>> {
+            AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    impl<T> futures_io::AsyncWrite for FuturesIo<T>
+    where
+        T: AsyncWrite + Unpin,
+    {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<IoResult<usize>> {
+            AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<IoResult<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
 }
 
 #[cfg(feature = "async")]
@@ -646,6 +1116,12 @@ pub use r#async::BytesWriterAsync;
 #[cfg(feature = "async")]
 pub use r#async::IoError;
 
+#[cfg(feature = "tokio")]
+pub use r#async::TokioIo;
+
+#[cfg(feature = "futures-io")]
+pub use r#async::FuturesIo;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -692,6 +1168,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn chained_varint() {
+        for (varint_buffer, value_expect) in utils::VARINT_TEST_CASES {
+            for split in 0..=varint_buffer.len() {
+                let (head, tail) = varint_buffer.split_at(split);
+                let mut reader = ChainedBufferReader::new([head, tail]);
+
+                assert_eq!(reader.capacity(), varint_buffer.len());
+                assert_eq!(reader.get_varint(), Some(value_expect));
+                assert_eq!(reader.offset(), varint_buffer.len());
+                assert_eq!(reader.capacity(), 0);
+                assert_eq!(reader.get_varint(), None);
+            }
+        }
+
+        let mut truncated = ChainedBufferReader::new([&[0xc2][..], &[0x19][..]]);
+        assert_eq!(truncated.get_varint(), None);
+        assert_eq!(truncated.offset(), 0);
+    }
+
+    #[test]
+    fn chained_bytes() {
+        let mut reader = ChainedBufferReader::new([&[1, 2][..], &[][..], &[3, 4, 5][..]]);
+
+        assert_eq!(reader.get_bytes(1), Some(&[1][..]));
+        assert_eq!(reader.get_bytes(2), None);
+        assert_eq!(reader.offset(), 1);
+
+        assert!(matches!(reader.get_bytes_cow(2), Some(Cow::Owned(bytes)) if bytes == [2, 3]));
+        assert!(matches!(
+            reader.get_bytes_cow(2),
+            Some(Cow::Borrowed(&[4, 5]))
+        ));
+        assert_eq!(reader.get_bytes_cow(1), None);
+        assert_eq!(reader.capacity(), 0);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn read_varint_buf() {
+        use ::bytes::Buf;
+
+        for (varint_buffer, value_expect) in utils::VARINT_TEST_CASES {
+            // Split the varint across two chunks
+            let (head, tail) = varint_buffer.split_at(varint_buffer.len() / 2);
+            let mut buf = head.chain(tail);
+
+            assert_eq!(buf.try_get_varint(), Some(value_expect));
+            assert!(!buf.has_remaining());
+        }
+
+        let mut truncated: &[u8] = &[0xc2, 0x19];
+        assert_eq!(truncated.try_get_varint(), None);
+        assert_eq!(truncated.len(), 2);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn write_varint_slice_end_of_buffer() {
+        let mut buffer = [0; 2];
+        let mut slice = &mut buffer[..];
+
+        assert!(slice.put_varint(VarInt::from_u32(1_000_000)).is_err());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn write_varint_bytes_mut() {
+        let mut buffer = ::bytes::BytesMut::new();
+
+        for (varint_buffer, value) in utils::VARINT_TEST_CASES {
+            buffer.clear();
+            buffer.put_varint(value).unwrap();
+            assert_eq!(&buffer[..], varint_buffer);
+        }
+    }
+
     #[cfg(feature = "async")]
     #[tokio::test]
     async fn write_varint_async() {
@@ -767,6 +1320,72 @@ mod tests {
         assert!(writer.put_buffer(&[0x0]).await.is_err());
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn tokio_io() {
+        use tokio::io::AsyncReadExt;
+        use tokio::io::AsyncWriteExt;
+
+        for (varint_buffer, value) in utils::VARINT_TEST_CASES {
+            // tokio reader as a source of varints
+            let mut reader = TokioIo::new(varint_buffer);
+            assert_eq!(reader.get_varint().await.unwrap(), value);
+
+            // Writer of this crate used through tokio
+            let mut writer = TokioIo::new(utils::StepWriter::new(None));
+            writer.write_all(varint_buffer).await.unwrap();
+            assert_eq!(writer.get_ref().written(), varint_buffer);
+
+            // Reader of this crate used through tokio
+            let mut reader = TokioIo::new(utils::StepReader::new(varint_buffer));
+            let mut buffer = Vec::new();
+            reader.read_to_end(&mut buffer).await.unwrap();
+            assert_eq!(buffer, varint_buffer);
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn zero_write_async() {
+        use std::pin::Pin;
+        use std::task::Context;
+        use std::task::Poll;
+
+        struct FullWriter;
+
+        impl AsyncWrite for FullWriter {
+            fn poll_write(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                _buf: &[u8],
+            ) -> Poll<Result<usize, std::io::Error>> {
+                Poll::Ready(Ok(0))
+            }
+        }
+
+        let mut writer = FullWriter;
+        assert!(matches!(
+            writer.put_varint(VarInt::from_u32(0)).await,
+            Err(IoError::Closed)
+        ));
+        assert!(matches!(
+            writer.put_buffer(&[0x0]).await,
+            Err(IoError::Closed)
+        ));
+        assert!(writer.put_buffer(&[]).await.is_ok());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn empty_buffer_async() {
+        let mut reader = utils::StepReader::new(vec![]);
+        assert!(reader.get_buffer(&mut []).await.is_ok());
+
+        let mut writer = utils::StepWriter::new(Some(0));
+        assert!(writer.put_buffer(&[]).await.is_ok());
+        assert!(writer.written().is_empty());
+    }
+
     mod utils {
         use super::*;
 
@@ -0,0 +1,672 @@
+use crate::bytes::BufferReader;
+use crate::bytes::BufferWriter;
+use crate::bytes::BytesReader;
+use crate::bytes::BytesWriter;
+use crate::bytes::EndOfBuffer;
+use crate::error::ErrorCode;
+use crate::frame::Frame;
+use crate::varint::VarInt;
+use std::borrow::Cow;
+
+#[cfg(feature = "async")]
+use crate::bytes::AsyncRead;
+
+#[cfg(feature = "async")]
+use crate::bytes::AsyncWrite;
+
+#[cfg(feature = "async")]
+use crate::bytes::IoError;
+
+/// A [`Capsule`] type.
+#[derive(Copy, Clone, Debug)]
+pub enum CapsuleKind {
+    /// CLOSE_WEBTRANSPORT_SESSION capsule type.
+    CloseWebTransportSession,
+
+    /// DRAIN_WEBTRANSPORT_SESSION capsule type.
+    DrainWebTransportSession,
+
+    /// WT_MAX_DATA capsule type.
+    WtMaxData,
+
+    /// WT_MAX_STREAMS capsule type (bidirectional streams).
+    WtMaxStreamsBidi,
+
+    /// WT_MAX_STREAMS capsule type (unidirectional streams).
+    WtMaxStreamsUni,
+
+    /// WT_DATA_BLOCKED capsule type.
+    WtDataBlocked,
+
+    /// WT_STREAMS_BLOCKED capsule type (bidirectional streams).
+    WtStreamsBlockedBidi,
+
+    /// WT_STREAMS_BLOCKED capsule type (unidirectional streams).
+    WtStreamsBlockedUni,
+
+    /// Unknown capsule type.
+    ///
+    /// Capsules of unknown type must be silently skipped by the receiver.
+    Unknown(VarInt),
+}
+
+impl CapsuleKind {
+    /// Returns the [`CapsuleKind`] of a capsule type `id`.
+    ///
+    /// Types not defined by WebTransport are [`CapsuleKind::Unknown`].
+    #[inline(always)]
+    pub const fn from_id(id: VarInt) -> Self {
+        Self::parse(id)
+    }
+
+    /// Returns the capsule type identifier.
+    #[inline(always)]
+    pub const fn id(self) -> VarInt {
+        match self {
+            CapsuleKind::CloseWebTransportSession => capsule_type_ids::CLOSE_WEBTRANSPORT_SESSION,
+            CapsuleKind::DrainWebTransportSession => capsule_type_ids::DRAIN_WEBTRANSPORT_SESSION,
+            CapsuleKind::WtMaxData => capsule_type_ids::WT_MAX_DATA,
+            CapsuleKind::WtMaxStreamsBidi => capsule_type_ids::WT_MAX_STREAMS_BIDI,
+            CapsuleKind::WtMaxStreamsUni => capsule_type_ids::WT_MAX_STREAMS_UNI,
+            CapsuleKind::WtDataBlocked => capsule_type_ids::WT_DATA_BLOCKED,
+            CapsuleKind::WtStreamsBlockedBidi => capsule_type_ids::WT_STREAMS_BLOCKED_BIDI,
+            CapsuleKind::WtStreamsBlockedUni => capsule_type_ids::WT_STREAMS_BLOCKED_UNI,
+            CapsuleKind::Unknown(id) => id,
+        }
+    }
+
+    const fn parse(id: VarInt) -> Self {
+        match id {
+            capsule_type_ids::CLOSE_WEBTRANSPORT_SESSION => CapsuleKind::CloseWebTransportSession,
+            capsule_type_ids::DRAIN_WEBTRANSPORT_SESSION => CapsuleKind::DrainWebTransportSession,
+            capsule_type_ids::WT_MAX_DATA => CapsuleKind::WtMaxData,
+            capsule_type_ids::WT_MAX_STREAMS_BIDI => CapsuleKind::WtMaxStreamsBidi,
+            capsule_type_ids::WT_MAX_STREAMS_UNI => CapsuleKind::WtMaxStreamsUni,
+            capsule_type_ids::WT_DATA_BLOCKED => CapsuleKind::WtDataBlocked,
+            capsule_type_ids::WT_STREAMS_BLOCKED_BIDI => CapsuleKind::WtStreamsBlockedBidi,
+            capsule_type_ids::WT_STREAMS_BLOCKED_UNI => CapsuleKind::WtStreamsBlockedUni,
+            id => CapsuleKind::Unknown(id),
+        }
+    }
+}
+
+/// An HTTP capsule.
+///
+/// Capsules are carried on the *session stream* inside HTTP3 `DATA` frames.
+/// See [`Capsule::generate_frame`].
+pub struct Capsule<'a> {
+    kind: CapsuleKind,
+    payload: Cow<'a, [u8]>,
+}
+
+impl<'a> Capsule<'a> {
+    /// Creates a new capsule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    pub fn new(kind: CapsuleKind, payload: Cow<'a, [u8]>) -> Self {
+        assert!(payload.len() <= VarInt::MAX.into_inner() as usize);
+        Self { kind, payload }
+    }
+
+    /// Reads a [`Capsule`] from a [`BytesReader`].
+    ///
+    /// It returns [`None`] if the `bytes_reader` does not contain enough bytes
+    /// to parse an entire capsule.
+    ///
+    /// In case [`None`], `bytes_reader` might be partially read.
+    pub fn read<R>(bytes_reader: &mut R) -> Option<Self>
+    where
+        R: BytesReader<'a>,
+    {
+        let kind = CapsuleKind::parse(bytes_reader.get_varint()?);
+        let payload_len = bytes_reader.get_varint()?.into_inner() as usize;
+        let payload = bytes_reader.get_bytes_cow(payload_len)?;
+
+        Some(Self::new(kind, payload))
+    }
+
+    /// Reads a [`Capsule`] from a `reader`.
+    ///
+    /// The payload is read in chunks, so memory is allocated only as
+    /// payload bytes are actually received.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_async<R>(reader: &mut R) -> Result<Capsule<'a>, IoError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        use crate::bytes::BytesReaderAsync;
+
+        let kind = CapsuleKind::parse(reader.get_varint().await?);
+        let payload_len = reader.get_varint().await?.into_inner() as usize;
+
+        const CHUNK_SIZE: usize = 4096;
+
+        let mut payload = Vec::with_capacity(payload_len.min(CHUNK_SIZE));
+
+        while payload.len() < payload_len {
+            let offset = payload.len();
+            let chunk_len = (payload_len - offset).min(CHUNK_SIZE);
+
+            payload.resize(offset + chunk_len, 0);
+            reader.get_buffer(&mut payload[offset..]).await?;
+        }
+
+        Ok(Self::new(kind, Cow::Owned(payload)))
+    }
+
+    /// Reads a [`Capsule`] from a [`BufferReader`].
+    ///
+    /// It returns [`None`] if the `buffer_reader` does not contain enough bytes
+    /// to parse an entire capsule.
+    ///
+    /// In case [`None`], `buffer_reader` offset if not advanced.
+    pub fn read_from_buffer(buffer_reader: &mut BufferReader<'a>) -> Option<Self> {
+        let mut buffer_reader_child = buffer_reader.child();
+
+        let capsule = Self::read(&mut *buffer_reader_child)?;
+        buffer_reader_child.commit();
+
+        Some(capsule)
+    }
+
+    /// Writes a [`Capsule`] into a [`BytesWriter`].
+    ///
+    /// It returns [`Err`] if the `bytes_writer` does not have enough capacity
+    /// to write the entire capsule.
+    /// See [`Self::write_size`] to retrieve the extact amount of required capacity.
+    ///
+    /// In case [`Err`], `bytes_writer` might be partially written.
+    pub fn write<W>(&self, bytes_writer: &mut W) -> Result<(), EndOfBuffer>
+    where
+        W: BytesWriter,
+    {
+        bytes_writer.put_varint(self.kind.id())?;
+        bytes_writer.put_varint(self.payload_len())?;
+        bytes_writer.put_bytes(&self.payload)?;
+
+        Ok(())
+    }
+
+    /// Writes a [`Capsule`] into a `writer`.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn write_async<W>(&self, writer: &mut W) -> Result<(), IoError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+    {
+        use crate::bytes::BytesWriterAsync;
+
+        writer.put_varint(self.kind.id()).await?;
+        writer.put_varint(self.payload_len()).await?;
+        writer.put_buffer(&self.payload).await?;
+
+        Ok(())
+    }
+
+    /// Writes this [`Capsule`] into a buffer via [`BufferWriter`].
+    ///
+    /// In case [`Err`], `buffer_writer` is not advanced.
+    pub fn write_to_buffer(&self, buffer_writer: &mut BufferWriter) -> Result<(), EndOfBuffer> {
+        if buffer_writer.capacity() < self.write_size() {
+            return Err(EndOfBuffer);
+        }
+
+        self.write(buffer_writer)
+            .expect("Enough capacity for capsule");
+
+        Ok(())
+    }
+
+    /// Returns the needed capacity to write this capsule into a buffer.
+    pub fn write_size(&self) -> usize {
+        self.kind.id().size() + self.payload_len().size() + self.payload.len()
+    }
+
+    /// Generates a [`FrameKind::Data`](crate::frame::FrameKind::Data) frame
+    /// carrying this capsule.
+    pub fn generate_frame(&self) -> Frame<'static> {
+        let mut payload = Vec::with_capacity(self.write_size());
+        self.write(&mut payload).expect("Vec does not have EOF");
+
+        Frame::new_data(Cow::Owned(payload))
+    }
+
+    /// Returns the [`CapsuleKind`] of this [`Capsule`].
+    #[inline(always)]
+    pub const fn kind(&self) -> CapsuleKind {
+        self.kind
+    }
+
+    /// Returns the payload of this [`Capsule`].
+    #[inline(always)]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn payload_len(&self) -> VarInt {
+        VarInt::try_from(self.payload.len() as u64)
+            .expect("Payload cannot be larger than varint max")
+    }
+
+    #[cfg(test)]
+    fn into_owned<'b>(self) -> Capsule<'b> {
+        Capsule {
+            kind: self.kind,
+            payload: Cow::Owned(self.payload.into_owned()),
+        }
+    }
+}
+
+/// Payload of a [`CapsuleKind::CloseWebTransportSession`] capsule.
+#[derive(Clone, Debug)]
+pub struct CloseWebTransportSession {
+    error_code: u32,
+    reason: String,
+}
+
+impl CloseWebTransportSession {
+    /// Maximum length (in bytes) of the reason message.
+    pub const MAX_REASON_SIZE: usize = 1024;
+
+    /// Creates a new close session payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `reason` is longer than [`Self::MAX_REASON_SIZE`].
+    pub fn new<S>(error_code: u32, reason: S) -> Self
+    where
+        S: Into<String>,
+    {
+        let reason = reason.into();
+        assert!(reason.len() <= Self::MAX_REASON_SIZE);
+
+        Self { error_code, reason }
+    }
+
+    /// Constructs the payload parsing a [`Capsule`].
+    ///
+    /// Returns an [`Err`] in case of malformed capsule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capsule` is not type [`CapsuleKind::CloseWebTransportSession`].
+    pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+        assert!(matches!(
+            capsule.kind(),
+            CapsuleKind::CloseWebTransportSession
+        ));
+
+        let mut buffer_reader = BufferReader::new(capsule.payload());
+
+        let error_code = buffer_reader
+            .get_bytes(4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().expect("Four bytes read")))
+            .ok_or(ErrorCode::Message)?;
+
+        let reason = buffer_reader.buffer_remaining();
+
+        if reason.len() > Self::MAX_REASON_SIZE {
+            return Err(ErrorCode::Message);
+        }
+
+        let reason = std::str::from_utf8(reason).map_err(|_| ErrorCode::Message)?;
+
+        Ok(Self {
+            error_code,
+            reason: reason.to_string(),
+        })
+    }
+
+    /// Generates a [`Capsule`] with this payload.
+    pub fn generate_capsule(&self) -> Capsule<'static> {
+        let mut payload = Vec::with_capacity(4 + self.reason.len());
+        payload.extend_from_slice(&self.error_code.to_be_bytes());
+        payload.extend_from_slice(self.reason.as_bytes());
+
+        Capsule::new(CapsuleKind::CloseWebTransportSession, Cow::Owned(payload))
+    }
+
+    /// Returns the application error code.
+    #[inline(always)]
+    pub fn error_code(&self) -> u32 {
+        self.error_code
+    }
+
+    /// Returns the application error message.
+    #[inline(always)]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+/// Payload of a [`CapsuleKind::DrainWebTransportSession`] capsule.
+///
+/// The capsule carries no data: it signals the peer should not open new
+/// streams or send new datagrams on the session, and it should close the
+/// session once the ongoing exchanges complete.
+#[derive(Clone, Debug)]
+pub struct DrainWebTransportSession;
+
+impl DrainWebTransportSession {
+    /// Constructs the payload parsing a [`Capsule`].
+    ///
+    /// Returns an [`Err`] in case of malformed capsule (i.e., with a non-empty payload).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capsule` is not type [`CapsuleKind::DrainWebTransportSession`].
+    pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+        assert!(matches!(
+            capsule.kind(),
+            CapsuleKind::DrainWebTransportSession
+        ));
+
+        if capsule.payload().is_empty() {
+            Ok(Self)
+        } else {
+            Err(ErrorCode::Message)
+        }
+    }
+
+    /// Generates a [`Capsule`] with this payload.
+    pub fn generate_capsule(&self) -> Capsule<'static> {
+        Capsule::new(
+            CapsuleKind::DrainWebTransportSession,
+            Cow::Owned(Vec::new()),
+        )
+    }
+}
+
+/// Payload of a session flow control capsule.
+///
+/// Flow control capsules carry a single *varint*: a limit (for `WT_MAX_*`
+/// capsules) or the limit the sender is blocked at (for `WT_*_BLOCKED`
+/// capsules). Limits on streams are the cumulative number of streams the
+/// peer may open in the session; limits on data are the cumulative number
+/// of bytes the peer may send on all the streams of the session.
+#[derive(Copy, Clone, Debug)]
+pub struct FlowControlCapsule {
+    kind: CapsuleKind,
+    value: VarInt,
+}
+
+impl FlowControlCapsule {
+    /// Creates a new flow control payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `kind` is not a flow control capsule type (see [`Self::is_flow_control`]).
+    pub fn new(kind: CapsuleKind, value: VarInt) -> Self {
+        assert!(Self::is_flow_control(kind));
+        Self { kind, value }
+    }
+
+    /// Whether `kind` is a flow control capsule type.
+    pub const fn is_flow_control(kind: CapsuleKind) -> bool {
+        matches!(
+            kind,
+            CapsuleKind::WtMaxData
+                | CapsuleKind::WtMaxStreamsBidi
+                | CapsuleKind::WtMaxStreamsUni
+                | CapsuleKind::WtDataBlocked
+                | CapsuleKind::WtStreamsBlockedBidi
+                | CapsuleKind::WtStreamsBlockedUni
+        )
+    }
+
+    /// Constructs the payload parsing a [`Capsule`].
+    ///
+    /// Returns an [`Err`] in case of malformed capsule.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capsule` is not a flow control capsule type.
+    pub fn with_capsule(capsule: &Capsule) -> Result<Self, ErrorCode> {
+        assert!(Self::is_flow_control(capsule.kind()));
+
+        let mut buffer_reader = BufferReader::new(capsule.payload());
+        let value = buffer_reader.get_varint().ok_or(ErrorCode::Message)?;
+
+        if !buffer_reader.buffer_remaining().is_empty() {
+            return Err(ErrorCode::Message);
+        }
+
+        Ok(Self {
+            kind: capsule.kind(),
+            value,
+        })
+    }
+
+    /// Generates a [`Capsule`] with this payload.
+    pub fn generate_capsule(&self) -> Capsule<'static> {
+        let mut payload = Vec::with_capacity(self.value.size());
+        payload
+            .put_varint(self.value)
+            .expect("Vec does not have EOF");
+
+        Capsule::new(self.kind, Cow::Owned(payload))
+    }
+
+    /// Returns the capsule type.
+    #[inline(always)]
+    pub fn kind(&self) -> CapsuleKind {
+        self.kind
+    }
+
+    /// Returns the limit carried by the capsule.
+    #[inline(always)]
+    pub fn value(&self) -> VarInt {
+        self.value
+    }
+}
+
+mod capsule_type_ids {
+    use crate::varint::VarInt;
+
+    pub const CLOSE_WEBTRANSPORT_SESSION: VarInt = VarInt::from_u32(0x2843);
+    pub const DRAIN_WEBTRANSPORT_SESSION: VarInt = VarInt::from_u32(0x78ae);
+    pub const WT_MAX_DATA: VarInt = VarInt::from_u32(0x190b4d3d);
+    pub const WT_MAX_STREAMS_BIDI: VarInt = VarInt::from_u32(0x190b4d3f);
+    pub const WT_MAX_STREAMS_UNI: VarInt = VarInt::from_u32(0x190b4d40);
+    pub const WT_DATA_BLOCKED: VarInt = VarInt::from_u32(0x190b4d41);
+    pub const WT_STREAMS_BLOCKED_BIDI: VarInt = VarInt::from_u32(0x190b4d43);
+    pub const WT_STREAMS_BLOCKED_UNI: VarInt = VarInt::from_u32(0x190b4d44);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_webtransport_session() {
+        let close = CloseWebTransportSession::new(42, "bye");
+
+        let capsule = close.generate_capsule();
+        assert!(matches!(
+            capsule.kind(),
+            CapsuleKind::CloseWebTransportSession
+        ));
+
+        let capsule = utils::assert_serde(capsule);
+        let close = CloseWebTransportSession::with_capsule(&capsule).unwrap();
+        assert_eq!(close.error_code(), 42);
+        assert_eq!(close.reason(), "bye");
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn close_webtransport_session_async() {
+        let close = CloseWebTransportSession::new(42, "bye");
+
+        let capsule = utils::assert_serde_async(close.generate_capsule()).await;
+        let close = CloseWebTransportSession::with_capsule(&capsule).unwrap();
+        assert_eq!(close.error_code(), 42);
+        assert_eq!(close.reason(), "bye");
+    }
+
+    #[test]
+    fn close_webtransport_session_malformed() {
+        let capsule = Capsule::new(
+            CapsuleKind::CloseWebTransportSession,
+            Cow::Borrowed(&[0x0, 0x0]),
+        );
+        assert!(matches!(
+            CloseWebTransportSession::with_capsule(&capsule),
+            Err(ErrorCode::Message)
+        ));
+
+        let capsule = Capsule::new(
+            CapsuleKind::CloseWebTransportSession,
+            Cow::Owned(vec![0x0; 4 + CloseWebTransportSession::MAX_REASON_SIZE + 1]),
+        );
+        assert!(matches!(
+            CloseWebTransportSession::with_capsule(&capsule),
+            Err(ErrorCode::Message)
+        ));
+    }
+
+    #[test]
+    fn drain_webtransport_session() {
+        let capsule = DrainWebTransportSession.generate_capsule();
+        assert!(matches!(
+            capsule.kind(),
+            CapsuleKind::DrainWebTransportSession
+        ));
+        assert!(capsule.payload().is_empty());
+
+        let capsule = utils::assert_serde(capsule);
+        DrainWebTransportSession::with_capsule(&capsule).unwrap();
+
+        let capsule = Capsule::new(
+            CapsuleKind::DrainWebTransportSession,
+            Cow::Borrowed(b"PAYLOAD"),
+        );
+        assert!(matches!(
+            DrainWebTransportSession::with_capsule(&capsule),
+            Err(ErrorCode::Message)
+        ));
+    }
+
+    #[test]
+    fn flow_control() {
+        let max_data = FlowControlCapsule::new(CapsuleKind::WtMaxData, VarInt::from_u32(65536));
+
+        let capsule = utils::assert_serde(max_data.generate_capsule());
+        assert!(matches!(capsule.kind(), CapsuleKind::WtMaxData));
+
+        let max_data = FlowControlCapsule::with_capsule(&capsule).unwrap();
+        assert!(matches!(max_data.kind(), CapsuleKind::WtMaxData));
+        assert_eq!(max_data.value(), VarInt::from_u32(65536));
+
+        assert!(!FlowControlCapsule::is_flow_control(
+            CapsuleKind::DrainWebTransportSession
+        ));
+    }
+
+    #[test]
+    fn flow_control_malformed() {
+        let capsule = Capsule::new(CapsuleKind::WtMaxStreamsBidi, Cow::Borrowed(&[]));
+        assert!(matches!(
+            FlowControlCapsule::with_capsule(&capsule),
+            Err(ErrorCode::Message)
+        ));
+
+        let capsule = Capsule::new(CapsuleKind::WtMaxStreamsUni, Cow::Borrowed(&[0x1, 0x2]));
+        assert!(matches!(
+            FlowControlCapsule::with_capsule(&capsule),
+            Err(ErrorCode::Message)
+        ));
+    }
+
+    #[test]
+    fn unknown_capsule() {
+        let capsule = Capsule::new(
+            CapsuleKind::Unknown(VarInt::from_u32(0x42)),
+            Cow::Borrowed(b"PAYLOAD"),
+        );
+
+        let capsule = utils::assert_serde(capsule);
+        assert!(matches!(capsule.kind(), CapsuleKind::Unknown(id) if id == VarInt::from_u32(0x42)));
+        assert_eq!(capsule.payload(), b"PAYLOAD");
+    }
+
+    #[test]
+    fn kind_from_id() {
+        for kind in [
+            CapsuleKind::CloseWebTransportSession,
+            CapsuleKind::DrainWebTransportSession,
+            CapsuleKind::WtMaxData,
+            CapsuleKind::WtMaxStreamsBidi,
+            CapsuleKind::WtMaxStreamsUni,
+            CapsuleKind::WtDataBlocked,
+            CapsuleKind::WtStreamsBlockedBidi,
+            CapsuleKind::WtStreamsBlockedUni,
+            CapsuleKind::Unknown(VarInt::from_u32(0x42)),
+        ] {
+            assert_eq!(CapsuleKind::from_id(kind.id()).id(), kind.id());
+        }
+
+        assert!(matches!(
+            CapsuleKind::from_id(VarInt::from_u32(0x2843)),
+            CapsuleKind::CloseWebTransportSession
+        ));
+    }
+
+    #[test]
+    fn read_eof() {
+        let mut buffer = Vec::new();
+        CloseWebTransportSession::new(0, "")
+            .generate_capsule()
+            .write(&mut buffer)
+            .unwrap();
+
+        assert!(Capsule::read(&mut &buffer[..buffer.len() - 1]).is_none());
+
+        let mut buffer_reader = BufferReader::new(&buffer[..buffer.len() - 1]);
+        assert!(Capsule::read_from_buffer(&mut buffer_reader).is_none());
+        assert_eq!(buffer_reader.offset(), 0);
+    }
+
+    #[test]
+    fn data_frame() {
+        let capsule = CloseWebTransportSession::new(0, "").generate_capsule();
+        let frame = capsule.generate_frame();
+
+        assert!(matches!(frame.kind(), crate::frame::FrameKind::Data));
+        assert_eq!(frame.payload().len(), capsule.write_size());
+    }
+
+    mod utils {
+        use super::*;
+
+        pub fn assert_serde(capsule: Capsule) -> Capsule {
+            let mut buffer = Vec::new();
+
+            capsule.write(&mut buffer).unwrap();
+            assert_eq!(buffer.len(), capsule.write_size());
+
+            let mut buffer = buffer.as_slice();
+            let capsule = Capsule::read(&mut buffer).unwrap();
+            assert!(buffer.is_empty());
+
+            capsule.into_owned()
+        }
+
+        #[cfg(feature = "async")]
+        pub async fn assert_serde_async(capsule: Capsule<'_>) -> Capsule {
+            let mut buffer = Vec::new();
+
+            capsule.write_async(&mut buffer).await.unwrap();
+            assert_eq!(buffer.len(), capsule.write_size());
+
+            let mut buffer = buffer.as_slice();
+            let capsule = Capsule::read_async(&mut buffer).await.unwrap();
+            assert!(buffer.is_empty());
+
+            capsule.into_owned()
+        }
+    }
+}
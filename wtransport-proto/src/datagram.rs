@@ -6,8 +6,57 @@ use crate::bytes::EndOfBuffer;
 use crate::error::ErrorCode;
 use crate::ids::InvalidQStreamId;
 use crate::ids::QStreamId;
+use std::fmt;
 
-/// An HTTP3 datagram.
+#[cfg(feature = "datagram-context")]
+use crate::varint::VarInt;
+
+/// Error reading an HTTP3 datagram.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatagramReadError {
+    /// The datagram is too short to contain the quarter stream ID.
+    MissingQStreamId,
+
+    /// The quarter stream ID is out of range (i.e., it does not map
+    /// to a client-initiated bidirectional stream).
+    InvalidQStreamId,
+
+    /// The datagram is too short to contain the context ID
+    /// (only with the `datagram-context` feature).
+    MissingContextId,
+}
+
+impl DatagramReadError {
+    /// Returns the HTTP3 error code to close the connection with.
+    #[inline(always)]
+    pub fn error_code(self) -> ErrorCode {
+        ErrorCode::Datagram
+    }
+}
+
+impl fmt::Display for DatagramReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatagramReadError::MissingQStreamId => write!(f, "missing quarter stream ID"),
+            DatagramReadError::InvalidQStreamId => write!(f, "invalid quarter stream ID"),
+            DatagramReadError::MissingContextId => write!(f, "missing context ID"),
+        }
+    }
+}
+
+impl std::error::Error for DatagramReadError {}
+
+impl From<DatagramReadError> for ErrorCode {
+    #[inline(always)]
+    fn from(error: DatagramReadError) -> Self {
+        error.error_code()
+    }
+}
+
+/// An HTTP3 datagram (RFC 9297).
+///
+/// The payload is prefixed by the *quarter stream ID*, identifying the
+/// request stream (see [`QStreamId`]).
 pub struct Datagram<'a> {
     qstream_id: QStreamId,
     payload: &'a [u8],
@@ -24,13 +73,15 @@ impl<'a> Datagram<'a> {
     }
 
     /// Reads [`Datagram`] from a QUIC datagram.
-    pub fn read(quic_datagram: &'a [u8]) -> Result<Self, ErrorCode> {
+    pub fn read(quic_datagram: &'a [u8]) -> Result<Self, DatagramReadError> {
         let mut buffer_reader = BufferReader::new(quic_datagram);
 
-        let varint = buffer_reader.get_varint().ok_or(ErrorCode::Datagram)?;
+        let varint = buffer_reader
+            .get_varint()
+            .ok_or(DatagramReadError::MissingQStreamId)?;
 
-        let qstream_id =
-            QStreamId::try_from_varint(varint).map_err(|InvalidQStreamId| ErrorCode::Datagram)?;
+        let qstream_id = QStreamId::try_from_varint(varint)
+            .map_err(|InvalidQStreamId| DatagramReadError::InvalidQStreamId)?;
 
         let payload = buffer_reader.buffer_remaining();
 
@@ -84,6 +135,100 @@ impl<'a> Datagram<'a> {
     }
 }
 
+/// An HTTP3 datagram whose payload is prefixed by a *context ID*.
+///
+/// Context IDs are not defined by RFC 9297: they are used by extensions
+/// (e.g., CONNECT-UDP) to multiplex different kinds of datagrams.
+#[cfg(feature = "datagram-context")]
+#[cfg_attr(docsrs, doc(cfg(feature = "datagram-context")))]
+pub struct ContextDatagram<'a> {
+    qstream_id: QStreamId,
+    context_id: VarInt,
+    payload: &'a [u8],
+}
+
+#[cfg(feature = "datagram-context")]
+impl<'a> ContextDatagram<'a> {
+    /// Creates a new [`ContextDatagram`] with a given payload.
+    #[inline(always)]
+    pub fn new(qstream_id: QStreamId, context_id: VarInt, payload: &'a [u8]) -> Self {
+        Self {
+            qstream_id,
+            context_id,
+            payload,
+        }
+    }
+
+    /// Reads [`ContextDatagram`] from a QUIC datagram.
+    pub fn read(quic_datagram: &'a [u8]) -> Result<Self, DatagramReadError> {
+        let datagram = Datagram::read(quic_datagram)?;
+        let mut buffer_reader = BufferReader::new(datagram.payload());
+
+        let context_id = buffer_reader
+            .get_varint()
+            .ok_or(DatagramReadError::MissingContextId)?;
+
+        Ok(Self {
+            qstream_id: datagram.qstream_id(),
+            context_id,
+            payload: buffer_reader.buffer_remaining(),
+        })
+    }
+
+    /// Writes a [`ContextDatagram`] as QUIC datagram into `buffer`.
+    ///
+    /// It returns the number of bytes written.
+    /// It returns [`Err`] if the `buffer` does not have enough capacity.
+    /// See [`Self::write_size`].
+    ///
+    /// In case of [`Err`], `buffer` is not written.
+    pub fn write(&self, buffer: &mut [u8]) -> Result<usize, EndOfBuffer> {
+        if buffer.len() < self.write_size() {
+            return Err(EndOfBuffer);
+        }
+
+        let mut buffer_writer = BufferWriter::new(buffer);
+
+        buffer_writer
+            .put_varint(self.qstream_id.into_varint())
+            .expect("Buffer has capacity");
+
+        buffer_writer
+            .put_varint(self.context_id)
+            .expect("Buffer has capacity");
+
+        buffer_writer
+            .put_bytes(self.payload)
+            .expect("Buffer has capacity");
+
+        Ok(buffer_writer.offset())
+    }
+
+    /// Returns the needed capacity to write this datagram into a buffer.
+    #[inline(always)]
+    pub fn write_size(&self) -> usize {
+        self.qstream_id.into_varint().size() + self.context_id.size() + self.payload.len()
+    }
+
+    /// Returns the associated [`QStreamId`].
+    #[inline(always)]
+    pub fn qstream_id(&self) -> QStreamId {
+        self.qstream_id
+    }
+
+    /// Returns the context ID.
+    #[inline(always)]
+    pub fn context_id(&self) -> VarInt {
+        self.context_id
+    }
+
+    /// Returns the payload.
+    #[inline(always)]
+    pub fn payload(&self) -> &[u8] {
+        self.payload
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +250,19 @@ mod tests {
         assert_eq!(dgram.payload(), PAYLOAD);
     }
 
+    #[test]
+    fn read_empty_payload() {
+        let dgram = build_datagram(QStreamIdType::Valid, &[]);
+
+        let mut buffer = vec![0; dgram.write_size()];
+        let written = dgram.write(&mut buffer).unwrap();
+        assert_eq!(written, QStreamId::MAX.into_varint().size());
+
+        let dgram = Datagram::read(&buffer[..written]).unwrap();
+        assert_eq!(dgram.qstream_id(), QStreamId::MAX);
+        assert!(dgram.payload().is_empty());
+    }
+
     #[test]
     fn read_too_short() {
         let dgram = build_datagram(QStreamIdType::Valid, PAYLOAD);
@@ -114,7 +272,7 @@ mod tests {
 
         assert!(matches!(
             Datagram::read(&buffer[..1]),
-            Err(ErrorCode::Datagram)
+            Err(DatagramReadError::MissingQStreamId)
         ));
     }
 
@@ -127,7 +285,7 @@ mod tests {
 
         assert!(matches!(
             Datagram::read(&buffer[..written]),
-            Err(ErrorCode::Datagram)
+            Err(DatagramReadError::InvalidQStreamId)
         ));
     }
 
@@ -150,6 +308,40 @@ mod tests {
         assert!(dgram.write(&mut buffer).is_err());
     }
 
+    #[test]
+    fn read_error_code() {
+        assert!(matches!(
+            ErrorCode::from(DatagramReadError::InvalidQStreamId),
+            ErrorCode::Datagram
+        ));
+    }
+
+    #[cfg(feature = "datagram-context")]
+    #[test]
+    fn context_datagram() {
+        let qstream_id = QStreamId::MAX;
+        let context_id = VarInt::from_u32(42);
+        let dgram = ContextDatagram::new(qstream_id, context_id, PAYLOAD);
+
+        let mut buffer = vec![0; dgram.write_size()];
+        let written = dgram.write(&mut buffer).unwrap();
+        assert_eq!(written, dgram.write_size());
+
+        let dgram = ContextDatagram::read(&buffer[..written]).unwrap();
+        assert_eq!(dgram.qstream_id(), qstream_id);
+        assert_eq!(dgram.context_id(), context_id);
+        assert_eq!(dgram.payload(), PAYLOAD);
+
+        let dgram = Datagram::new(qstream_id, &[]);
+        let mut buffer = vec![0; dgram.write_size()];
+        let written = dgram.write(&mut buffer).unwrap();
+
+        assert!(matches!(
+            ContextDatagram::read(&buffer[..written]),
+            Err(DatagramReadError::MissingContextId)
+        ));
+    }
+
     mod utils {
         use super::*;
 
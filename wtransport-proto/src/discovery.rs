@@ -0,0 +1,157 @@
+use crate::WEBTRANSPORT_ALPN;
+use std::fmt;
+use std::fmt::Write;
+
+/// HTTP/3 endpoint advertisement, for QUIC discovery.
+///
+/// It produces the value of an `Alt-Svc` header ([RFC 7838](https://www.rfc-editor.org/rfc/rfc7838))
+/// and the parameters of an `HTTPS`/`SVCB` DNS record ([RFC 9460](https://www.rfc-editor.org/rfc/rfc9460)),
+/// so that both advertise the same data.
+///
+/// # Example
+/// ```
+/// use wtransport_proto::discovery::AltSvc;
+///
+/// let alt_svc = AltSvc::new(4433).with_max_age(86400);
+///
+/// assert_eq!(alt_svc.header_value(), r#"h3=":4433"; ma=86400"#);
+/// assert_eq!(alt_svc.svcb_params(), "alpn=h3 port=4433");
+/// ```
+#[derive(Clone, Debug)]
+pub struct AltSvc {
+    port: u16,
+    host: Option<String>,
+    max_age: Option<u64>,
+    persist: bool,
+}
+
+impl AltSvc {
+    /// Advertises the HTTP/3 endpoint on `port` of the same host.
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            host: None,
+            max_age: None,
+            persist: false,
+        }
+    }
+
+    /// Advertises the endpoint on a different `host`.
+    ///
+    /// IPv6 literals are enclosed in brackets when needed.
+    /// It only affects [`Self::header_value`]: in DNS records the target is
+    /// part of the record itself, not of its parameters.
+    pub fn with_host<S>(mut self, host: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// Sets the number of seconds the advertisement is fresh for (`ma` parameter).
+    ///
+    /// If not set, clients apply the default of 24 hours.
+    pub fn with_max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Asks clients to keep the advertisement across network changes (`persist=1`).
+    pub fn with_persist(mut self) -> Self {
+        self.persist = true;
+        self
+    }
+
+    /// Returns the port of the advertised endpoint.
+    #[inline(always)]
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Returns the value of the `Alt-Svc` header (e.g., `h3=":443"; ma=3600`).
+    pub fn header_value(&self) -> String {
+        let mut value = String::new();
+
+        write!(value, "{}=\"", Self::protocol_id()).expect("Write to string");
+
+        match self.host.as_deref() {
+            Some(host) if host.contains(':') && !host.starts_with('[') => {
+                write!(value, "[{host}]").expect("Write to string")
+            }
+            Some(host) => value.push_str(host),
+            None => {}
+        }
+
+        write!(value, ":{}\"", self.port).expect("Write to string");
+
+        if let Some(max_age) = self.max_age {
+            write!(value, "; ma={max_age}").expect("Write to string");
+        }
+
+        if self.persist {
+            value.push_str("; persist=1");
+        }
+
+        value
+    }
+
+    /// Returns the `SvcParams` of an `HTTPS`/`SVCB` record, in zone file
+    /// presentation format (e.g., `alpn=h3 port=443`).
+    ///
+    /// A full record looks like `example.com. 3600 IN HTTPS 1 . alpn=h3 port=443`.
+    pub fn svcb_params(&self) -> String {
+        format!("alpn={} port={}", Self::protocol_id(), self.port)
+    }
+
+    /// The ALPN protocol ID, as a string.
+    fn protocol_id() -> &'static str {
+        std::str::from_utf8(WEBTRANSPORT_ALPN).expect("ALPN is ASCII")
+    }
+}
+
+impl fmt::Display for AltSvc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.header_value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_value() {
+        assert_eq!(AltSvc::new(443).header_value(), r#"h3=":443""#);
+
+        assert_eq!(
+            AltSvc::new(443)
+                .with_host("example.com")
+                .with_max_age(3600)
+                .with_persist()
+                .header_value(),
+            r#"h3="example.com:443"; ma=3600; persist=1"#
+        );
+    }
+
+    #[test]
+    fn header_value_ipv6() {
+        assert_eq!(
+            AltSvc::new(4433).with_host("::1").header_value(),
+            r#"h3="[::1]:4433""#
+        );
+
+        assert_eq!(
+            AltSvc::new(4433).with_host("[::1]").header_value(),
+            r#"h3="[::1]:4433""#
+        );
+    }
+
+    #[test]
+    fn svcb_params() {
+        assert_eq!(
+            AltSvc::new(8443).with_host("example.com").svcb_params(),
+            "alpn=h3 port=8443"
+        );
+    }
+}
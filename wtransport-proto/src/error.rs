@@ -12,6 +12,9 @@ pub enum ErrorCode {
     /// H3_NO_ERROR.
     NoError,
 
+    /// H3_INTERNAL_ERROR.
+    InternalError,
+
     /// H3_STREAM_CREATION_ERROR.
     StreamCreation,
 
@@ -24,6 +27,9 @@ pub enum ErrorCode {
     /// H3_FRAME_ERROR.
     Frame,
 
+    /// H3_EXCESSIVE_LOAD.
+    ExcessiveLoad,
+
     /// H3_ID_ERROR.
     Id,
 
@@ -36,6 +42,9 @@ pub enum ErrorCode {
     /// H3_MESSAGE_ERROR.
     Message,
 
+    /// H3_REQUEST_REJECTED.
+    RequestRejected,
+
     /// QPACK_DECOMPRESSION_FAILED.
     Decompression,
 
@@ -44,6 +53,9 @@ pub enum ErrorCode {
 
     /// WEBTRANSPORT_SESSION_GONE.
     SessionGone,
+
+    /// WEBTRANSPORT_FLOW_CONTROL_ERROR.
+    FlowControl,
 }
 
 impl ErrorCode {
@@ -52,19 +64,91 @@ impl ErrorCode {
         match self {
             ErrorCode::Datagram => h3_error_codes::H3_DATAGRAM_ERROR,
             ErrorCode::NoError => h3_error_codes::H3_NO_ERROR,
+            ErrorCode::InternalError => h3_error_codes::H3_INTERNAL_ERROR,
             ErrorCode::StreamCreation => h3_error_codes::H3_STREAM_CREATION_ERROR,
             ErrorCode::ClosedCriticalStream => h3_error_codes::H3_CLOSED_CRITICAL_STREAM,
             ErrorCode::FrameUnexpected => h3_error_codes::H3_FRAME_UNEXPECTED,
             ErrorCode::Frame => h3_error_codes::H3_FRAME_ERROR,
+            ErrorCode::ExcessiveLoad => h3_error_codes::H3_EXCESSIVE_LOAD,
             ErrorCode::Id => h3_error_codes::H3_ID_ERROR,
             ErrorCode::Settings => h3_error_codes::H3_SETTINGS_ERROR,
             ErrorCode::MissingSettings => h3_error_codes::H3_MISSING_SETTINGS,
             ErrorCode::Message => h3_error_codes::H3_MESSAGE_ERROR,
+            ErrorCode::RequestRejected => h3_error_codes::H3_REQUEST_REJECTED,
             ErrorCode::Decompression => qpack_error_codes::QPACK_DECOMPRESSION_FAILED,
             ErrorCode::BufferedStreamRejected => {
                 wt_error_codes::WEBTRANSPORT_BUFFERED_STREAM_REJECTED
             }
             ErrorCode::SessionGone => wt_error_codes::WEBTRANSPORT_SESSION_GONE,
+            ErrorCode::FlowControl => wt_error_codes::WEBTRANSPORT_FLOW_CONTROL_ERROR,
+        }
+    }
+
+    /// Returns the error corresponding to an integer `code`.
+    ///
+    /// Returns [`None`] if `code` is not one of the known codes.
+    pub fn from_code(code: VarInt) -> Option<Self> {
+        Self::ALL.into_iter().find(|error| error.to_code() == code)
+    }
+
+    const ALL: [ErrorCode; 17] = [
+        ErrorCode::Datagram,
+        ErrorCode::NoError,
+        ErrorCode::InternalError,
+        ErrorCode::StreamCreation,
+        ErrorCode::ClosedCriticalStream,
+        ErrorCode::FrameUnexpected,
+        ErrorCode::Frame,
+        ErrorCode::ExcessiveLoad,
+        ErrorCode::Id,
+        ErrorCode::Settings,
+        ErrorCode::MissingSettings,
+        ErrorCode::Message,
+        ErrorCode::RequestRejected,
+        ErrorCode::Decompression,
+        ErrorCode::BufferedStreamRejected,
+        ErrorCode::SessionGone,
+        ErrorCode::FlowControl,
+    ];
+
+    /// Maps a WebTransport application error code into the HTTP3 error code space.
+    ///
+    /// Application error codes are carried on the wire (e.g., on stream RESET and
+    /// STOP_SENDING) as HTTP3 codes in the range reserved by the WebTransport
+    /// protocol. The mapping skips the reserved *GREASE* codepoints.
+    pub const fn to_http3(app_code: u32) -> VarInt {
+        let app_code = app_code as u64;
+        let h3_code =
+            wt_error_codes::WEBTRANSPORT_APP_ERROR_FIRST.into_inner() + app_code + app_code / 0x1e;
+
+        // SAFETY: the result is bounded by WEBTRANSPORT_APP_ERROR_LAST
+        unsafe {
+            debug_assert!(h3_code <= wt_error_codes::WEBTRANSPORT_APP_ERROR_LAST.into_inner());
+            VarInt::from_u64_unchecked(h3_code)
+        }
+    }
+
+    /// Maps an HTTP3 error code back into a WebTransport application error code.
+    ///
+    /// Returns [`None`] if `h3_code` is not in the range reserved for
+    /// application errors, or if it is a *GREASE* codepoint.
+    pub const fn from_http3(h3_code: VarInt) -> Option<u32> {
+        let h3_code = h3_code.into_inner();
+
+        if h3_code < wt_error_codes::WEBTRANSPORT_APP_ERROR_FIRST.into_inner()
+            || h3_code > wt_error_codes::WEBTRANSPORT_APP_ERROR_LAST.into_inner()
+            || (h3_code - 0x21) % 0x1f == 0
+        {
+            return None;
+        }
+
+        let shifted = h3_code - wt_error_codes::WEBTRANSPORT_APP_ERROR_FIRST.into_inner();
+        let app_code = shifted - shifted / 0x1f;
+
+        if app_code <= u32::MAX as u64 {
+            Some(app_code as u32)
+        } else {
+            None
         }
     }
 }
@@ -80,47 +164,162 @@ impl Display for ErrorCode {
         match self {
             ErrorCode::Datagram => write!(f, "DatagramError"),
             ErrorCode::NoError => write!(f, "NoError"),
+            ErrorCode::InternalError => write!(f, "InternalError"),
             ErrorCode::StreamCreation => write!(f, "StreamCreationError"),
             ErrorCode::ClosedCriticalStream => write!(f, "ClosedCriticalStreamError"),
             ErrorCode::FrameUnexpected => write!(f, "FrameUnexpectedError"),
             ErrorCode::Frame => write!(f, "FrameError"),
+            ErrorCode::ExcessiveLoad => write!(f, "ExcessiveLoad"),
             ErrorCode::Id => write!(f, "IdError"),
             ErrorCode::Settings => write!(f, "SettingsError"),
             ErrorCode::MissingSettings => write!(f, "MissingSettingsError"),
             ErrorCode::Message => write!(f, "MessageError"),
+            ErrorCode::RequestRejected => write!(f, "RequestRejected"),
             ErrorCode::Decompression => write!(f, "DecompressionError"),
             ErrorCode::BufferedStreamRejected => write!(f, "BufferedStreamRejected"),
             ErrorCode::SessionGone => write!(f, "SessionGone"),
+            ErrorCode::FlowControl => write!(f, "FlowControlError"),
         }
     }
 }
 
 impl std::error::Error for ErrorCode {}
 
-mod h3_error_codes {
+/// HTTP3 error codes ([RFC 9114](https://www.rfc-editor.org/rfc/rfc9114#section-8.1)
+/// and [RFC 9297](https://www.rfc-editor.org/rfc/rfc9297#section-5.2)).
+pub mod h3_error_codes {
     use crate::varint::VarInt;
 
+    /// H3_DATAGRAM_ERROR.
     pub const H3_DATAGRAM_ERROR: VarInt = VarInt::from_u32(0x33);
+
+    /// H3_NO_ERROR.
     pub const H3_NO_ERROR: VarInt = VarInt::from_u32(0x0100);
+
+    /// H3_INTERNAL_ERROR.
+    pub const H3_INTERNAL_ERROR: VarInt = VarInt::from_u32(0x0102);
+
+    /// H3_STREAM_CREATION_ERROR.
     pub const H3_STREAM_CREATION_ERROR: VarInt = VarInt::from_u32(0x0103);
+
+    /// H3_CLOSED_CRITICAL_STREAM.
     pub const H3_CLOSED_CRITICAL_STREAM: VarInt = VarInt::from_u32(0x0104);
+
+    /// H3_FRAME_UNEXPECTED.
     pub const H3_FRAME_UNEXPECTED: VarInt = VarInt::from_u32(0x0105);
+
+    /// H3_FRAME_ERROR.
     pub const H3_FRAME_ERROR: VarInt = VarInt::from_u32(0x0106);
+
+    /// H3_EXCESSIVE_LOAD.
+    pub const H3_EXCESSIVE_LOAD: VarInt = VarInt::from_u32(0x0107);
+
+    /// H3_ID_ERROR.
     pub const H3_ID_ERROR: VarInt = VarInt::from_u32(0x0108);
+
+    /// H3_SETTINGS_ERROR.
     pub const H3_SETTINGS_ERROR: VarInt = VarInt::from_u32(0x0109);
+
+    /// H3_MISSING_SETTINGS.
     pub const H3_MISSING_SETTINGS: VarInt = VarInt::from_u32(0x010a);
+
+    /// H3_REQUEST_REJECTED.
+    pub const H3_REQUEST_REJECTED: VarInt = VarInt::from_u32(0x010b);
+
+    /// H3_REQUEST_CANCELLED.
+    pub const H3_REQUEST_CANCELLED: VarInt = VarInt::from_u32(0x010c);
+
+    /// H3_MESSAGE_ERROR.
     pub const H3_MESSAGE_ERROR: VarInt = VarInt::from_u32(0x010e);
 }
 
-mod qpack_error_codes {
+/// QPACK error codes ([RFC 9204](https://www.rfc-editor.org/rfc/rfc9204#section-6)).
+pub mod qpack_error_codes {
     use crate::varint::VarInt;
 
+    /// QPACK_DECOMPRESSION_FAILED.
     pub const QPACK_DECOMPRESSION_FAILED: VarInt = VarInt::from_u32(0x0200);
 }
 
-mod wt_error_codes {
+/// WebTransport error codes.
+pub mod wt_error_codes {
     use crate::varint::VarInt;
 
+    /// WEBTRANSPORT_BUFFERED_STREAM_REJECTED.
     pub const WEBTRANSPORT_BUFFERED_STREAM_REJECTED: VarInt = VarInt::from_u32(0x3994bd84);
+
+    /// WEBTRANSPORT_SESSION_GONE.
     pub const WEBTRANSPORT_SESSION_GONE: VarInt = VarInt::from_u32(0x170d7b68);
+
+    /// WEBTRANSPORT_FLOW_CONTROL_ERROR.
+    pub const WEBTRANSPORT_FLOW_CONTROL_ERROR: VarInt = VarInt::from_u32(0x045d4487);
+
+    /// First code of the range reserved to application errors
+    /// (see [`ErrorCode::to_http3`](super::ErrorCode::to_http3)).
+    // SAFETY: value is smaller than varint max
+    pub const WEBTRANSPORT_APP_ERROR_FIRST: VarInt =
+        unsafe { VarInt::from_u64_unchecked(0x52e4a40fa8db) };
+
+    /// Last code of the range reserved to application errors.
+    // SAFETY: value is smaller than varint max
+    pub const WEBTRANSPORT_APP_ERROR_LAST: VarInt =
+        unsafe { VarInt::from_u64_unchecked(0x52e5ac983162) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_code_mapping() {
+        for app_code in [0, 1, 0x1d, 0x1e, 0x1f, 0x3c, 0xffff, u32::MAX - 1, u32::MAX] {
+            let h3_code = ErrorCode::to_http3(app_code);
+            assert_eq!(ErrorCode::from_http3(h3_code), Some(app_code));
+        }
+
+        assert_eq!(
+            ErrorCode::to_http3(0),
+            wt_error_codes::WEBTRANSPORT_APP_ERROR_FIRST
+        );
+        assert_eq!(
+            ErrorCode::to_http3(u32::MAX),
+            wt_error_codes::WEBTRANSPORT_APP_ERROR_LAST
+        );
+    }
+
+    #[test]
+    fn code_roundtrip() {
+        for error in ErrorCode::ALL {
+            let code = error.to_code();
+            assert_eq!(ErrorCode::from_code(code).unwrap().to_code(), code);
+        }
+
+        assert!(ErrorCode::from_code(VarInt::from_u32(0x42)).is_none());
+    }
+
+    #[test]
+    fn app_code_mapping_monotonic() {
+        let mut prev = ErrorCode::to_http3(0);
+
+        for app_code in 1..1024 {
+            let h3_code = ErrorCode::to_http3(app_code);
+            assert!(h3_code > prev);
+            prev = h3_code;
+        }
+    }
+
+    #[test]
+    fn not_app_code() {
+        assert!(ErrorCode::from_http3(ErrorCode::NoError.to_code()).is_none());
+        assert!(ErrorCode::from_http3(ErrorCode::SessionGone.to_code()).is_none());
+
+        let first = wt_error_codes::WEBTRANSPORT_APP_ERROR_FIRST.into_inner();
+        let last = wt_error_codes::WEBTRANSPORT_APP_ERROR_LAST.into_inner();
+
+        assert!(ErrorCode::from_http3(VarInt::try_from_u64(first - 1).unwrap()).is_none());
+        assert!(ErrorCode::from_http3(VarInt::try_from_u64(last + 1).unwrap()).is_none());
+
+        // GREASE codepoint
+        assert!(ErrorCode::from_http3(VarInt::try_from_u64(first + 0x1e).unwrap()).is_none());
+    }
 }
@@ -3,10 +3,13 @@ use crate::bytes::BufferWriter;
 use crate::bytes::BytesReader;
 use crate::bytes::BytesWriter;
 use crate::bytes::EndOfBuffer;
+use crate::error::ErrorCode;
 use crate::ids::InvalidSessionId;
 use crate::ids::SessionId;
 use crate::varint::VarInt;
+use crate::ParseMode;
 use std::borrow::Cow;
+use std::fmt;
 
 #[cfg(feature = "async")]
 use crate::bytes::AsyncRead;
@@ -17,6 +20,9 @@ use crate::bytes::AsyncWrite;
 #[cfg(feature = "async")]
 use crate::bytes::IoError;
 
+#[cfg(feature = "async")]
+use crate::ParseLimits;
+
 /// Error frame read operation.
 #[derive(Debug)]
 pub enum FrameReadError {
@@ -25,6 +31,9 @@ pub enum FrameReadError {
 
     /// Error for invalid session ID.
     InvalidSessionId,
+
+    /// Error for a frame payload exceeding the [`ParseLimits`](crate::ParseLimits).
+    ExcessiveLoad,
 }
 
 /// An error during async frame read operation.
@@ -39,6 +48,38 @@ pub enum FrameReadAsyncError {
     IO(IoError),
 }
 
+impl fmt::Display for FrameReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameReadError::UnknownFrame => write!(f, "unknown frame type"),
+            FrameReadError::InvalidSessionId => write!(f, "invalid session ID"),
+            FrameReadError::ExcessiveLoad => write!(f, "frame payload exceeds limits"),
+        }
+    }
+}
+
+impl std::error::Error for FrameReadError {}
+
+#[cfg(feature = "async")]
+impl fmt::Display for FrameReadAsyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameReadAsyncError::Frame(_) => write!(f, "cannot parse frame"),
+            FrameReadAsyncError::IO(_) => write!(f, "cannot read frame"),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl std::error::Error for FrameReadAsyncError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FrameReadAsyncError::Frame(error) => Some(error),
+            FrameReadAsyncError::IO(error) => Some(error),
+        }
+    }
+}
+
 #[cfg(feature = "async")]
 impl From<IoError> for FrameReadAsyncError {
     fn from(io_error: IoError) -> Self {
@@ -58,11 +99,20 @@ pub enum FrameKind {
     /// SETTINGS frame type.
     Settings,
 
+    /// GOAWAY frame type.
+    GoAway,
+
     /// WebTransport frame type.
     WebTransport,
 
     /// Exercise frame.
     Exercise(VarInt),
+
+    /// Frame of a type unknown to this implementation (e.g., an extension frame).
+    ///
+    /// Frames of unknown type are read only by [`Frame::read_with_unknown`]
+    /// (and its variants).
+    Unknown(VarInt),
 }
 
 impl FrameKind {
@@ -72,24 +122,40 @@ impl FrameKind {
         id.into_inner() >= 0x21 && ((id.into_inner() - 0x21) % 0x1f == 0)
     }
 
+    /// Checks whether an `id` is valid for a [`FrameKind::Unknown`], i.e.,
+    /// it is not the type of a known, exercise or reserved (HTTP/2) frame.
+    #[inline(always)]
+    pub const fn is_id_unknown(id: VarInt) -> bool {
+        FrameKind::parse(id).is_none() && !FrameKind::is_id_reserved(id)
+    }
+
     const fn parse(id: VarInt) -> Option<Self> {
         match id {
             frame_kind_ids::DATA => Some(FrameKind::Data),
             frame_kind_ids::HEADERS => Some(FrameKind::Headers),
             frame_kind_ids::SETTINGS => Some(FrameKind::Settings),
+            frame_kind_ids::GOAWAY => Some(FrameKind::GoAway),
             frame_kind_ids::WEBTRANSPORT_STREAM => Some(FrameKind::WebTransport),
             id if FrameKind::is_id_exercise(id) => Some(FrameKind::Exercise(id)),
             _ => None,
         }
     }
 
+    /// Checks whether an `id` is reserved (i.e., an HTTP/2 frame type).
+    #[inline(always)]
+    pub const fn is_id_reserved(id: VarInt) -> bool {
+        matches!(id.into_inner(), 0x02 | 0x06 | 0x08 | 0x09)
+    }
+
     const fn id(self) -> VarInt {
         match self {
             FrameKind::Data => frame_kind_ids::DATA,
             FrameKind::Headers => frame_kind_ids::HEADERS,
             FrameKind::Settings => frame_kind_ids::SETTINGS,
+            FrameKind::GoAway => frame_kind_ids::GOAWAY,
             FrameKind::WebTransport => frame_kind_ids::WEBTRANSPORT_STREAM,
             FrameKind::Exercise(id) => id,
+            FrameKind::Unknown(id) => id,
         }
     }
 }
@@ -102,6 +168,16 @@ pub struct Frame<'a> {
 }
 
 impl<'a> Frame<'a> {
+    /// Creates a new frame of type [`FrameKind::Data`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `payload` size if greater than [`VarInt::MAX`].
+    #[inline(always)]
+    pub fn new_data(payload: Cow<'a, [u8]>) -> Self {
+        Self::new(FrameKind::Data, payload, None)
+    }
+
     /// Creates a new frame of type [`FrameKind::Headers`].
     ///
     /// # Panics
@@ -122,6 +198,18 @@ impl<'a> Frame<'a> {
         Self::new(FrameKind::Settings, payload, None)
     }
 
+    /// Creates a new frame of type [`FrameKind::GoAway`].
+    ///
+    /// `id` is a *client-initiated bidirectional* stream ID when sent by a server
+    /// (requests with that ID or greater are not processed), or a push ID
+    /// when sent by a client.
+    pub fn new_goaway(id: VarInt) -> Self {
+        let mut payload = Vec::with_capacity(id.size());
+        payload.put_varint(id).expect("Vec does not have EOF");
+
+        Self::new(FrameKind::GoAway, Cow::Owned(payload), None)
+    }
+
     /// Creates a new frame of type [`FrameKind::WebTransport`].
     #[inline(always)]
     pub fn new_webtransport(session_id: SessionId) -> Self {
@@ -143,20 +231,78 @@ impl<'a> Frame<'a> {
         Self::new(FrameKind::Exercise(id), payload, None)
     }
 
+    /// Creates a new frame of type [`FrameKind::Unknown`] (e.g., an extension frame).
+    ///
+    /// # Panics
+    ///
+    /// * Panics if the `payload` size if greater than [`VarInt::MAX`].
+    /// * Panics if `id` is not valid for an unknown frame (see [`FrameKind::is_id_unknown`]).
+    pub fn new_unknown(id: VarInt, payload: Cow<'a, [u8]>) -> Self {
+        assert!(FrameKind::is_id_unknown(id));
+        Self::new(FrameKind::Unknown(id), payload, None)
+    }
+
     /// Reads a [`Frame`] from a [`BytesReader`].
     ///
     /// It returns [`None`] if the `bytes_reader` does not contain enough bytes
     /// to parse an entire frame.
     ///
     /// In case [`None`] or [`Err`], `bytes_reader` might be partially read.
+    ///
+    /// This is equivalent to [`Self::read_with_mode`] with [`ParseMode::Strict`].
     pub fn read<R>(bytes_reader: &mut R) -> Option<Result<Self, FrameReadError>>
     where
         R: BytesReader<'a>,
     {
-        let kind_id = bytes_reader.get_varint()?;
-        let kind = match FrameKind::parse(kind_id) {
-            Some(kind) => kind,
-            None => return Some(Err(FrameReadError::UnknownFrame)),
+        Self::read_with_mode(bytes_reader, ParseMode::Strict)
+    }
+
+    /// Reads a [`Frame`] from a [`BytesReader`], with the given parsing `mode`.
+    ///
+    /// In [`ParseMode::Lenient`], frames of unknown type are skipped.
+    ///
+    /// See [`Self::read`].
+    pub fn read_with_mode<R>(
+        bytes_reader: &mut R,
+        mode: ParseMode,
+    ) -> Option<Result<Self, FrameReadError>>
+    where
+        R: BytesReader<'a>,
+    {
+        Self::read_impl(bytes_reader, mode, false)
+    }
+
+    /// Reads a [`Frame`] from a [`BytesReader`], returning frames of unknown
+    /// type as [`FrameKind::Unknown`] (instead of failing or skipping them).
+    ///
+    /// Frames of reserved (HTTP/2) types are still an error.
+    ///
+    /// See [`Self::read`].
+    pub fn read_with_unknown<R>(bytes_reader: &mut R) -> Option<Result<Self, FrameReadError>>
+    where
+        R: BytesReader<'a>,
+    {
+        Self::read_impl(bytes_reader, ParseMode::Strict, true)
+    }
+
+    fn read_impl<R>(
+        bytes_reader: &mut R,
+        mode: ParseMode,
+        keep_unknown: bool,
+    ) -> Option<Result<Self, FrameReadError>>
+    where
+        R: BytesReader<'a>,
+    {
+        let kind = loop {
+            let kind_id = bytes_reader.get_varint()?;
+            match Self::parse_kind(kind_id, mode, keep_unknown) {
+                Ok(Some(kind)) => break kind,
+                Ok(None) => {
+                    let payload_len = bytes_reader.get_varint()?.into_inner() as usize;
+                    bytes_reader.get_bytes_cow(payload_len)?;
+                }
+                Err(error) => return Some(Err(error)),
+            }
         };
 
         if matches!(kind, FrameKind::WebTransport) {
@@ -168,24 +314,101 @@ impl<'a> Frame<'a> {
             Some(Ok(Self::new_webtransport(session_id)))
         } else {
             let payload_len = bytes_reader.get_varint()?.into_inner() as usize;
-            let payload = bytes_reader.get_bytes(payload_len)?;
+            let payload = bytes_reader.get_bytes_cow(payload_len)?;
 
-            Some(Ok(Self::new(kind, Cow::Borrowed(payload), None)))
+            Some(Ok(Self::new(kind, payload, None)))
         }
     }
 
     /// Reads a [`Frame`] from a `reader`.
+    ///
+    /// This is equivalent to [`Self::read_async_with_mode`] with [`ParseMode::Strict`].
     #[cfg(feature = "async")]
     #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
     pub async fn read_async<R>(reader: &mut R) -> Result<Frame<'a>, FrameReadAsyncError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        Self::read_async_with_mode(reader, ParseMode::Strict).await
+    }
+
+    /// Reads a [`Frame`] from a `reader`, with the given parsing `mode`.
+    ///
+    /// In [`ParseMode::Lenient`], frames of unknown type are skipped.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_async_with_mode<R>(
+        reader: &mut R,
+        mode: ParseMode,
+    ) -> Result<Frame<'a>, FrameReadAsyncError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        Self::read_async_impl(reader, mode, false, &ParseLimits::default()).await
+    }
+
+    /// Reads a [`Frame`] from a `reader`, with the given parsing `mode` and `limits`.
+    ///
+    /// The payload of HEADERS and SETTINGS frames exceeding `limits` is not
+    /// read (nor allocated): it is a [`FrameReadError::ExcessiveLoad`] error.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_async_with_limits<R>(
+        reader: &mut R,
+        mode: ParseMode,
+        limits: &ParseLimits,
+    ) -> Result<Frame<'a>, FrameReadAsyncError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        Self::read_async_impl(reader, mode, false, limits).await
+    }
+
+    /// Reads a [`Frame`] from a `reader`, returning frames of unknown type
+    /// as [`FrameKind::Unknown`].
+    ///
+    /// See [`Self::read_with_unknown`].
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn read_async_with_unknown<R>(
+        reader: &mut R,
+    ) -> Result<Frame<'a>, FrameReadAsyncError>
+    where
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        Self::read_async_impl(reader, ParseMode::Strict, true, &ParseLimits::default()).await
+    }
+
+    #[cfg(feature = "async")]
+    async fn read_async_impl<R>(
+        reader: &mut R,
+        mode: ParseMode,
+        keep_unknown: bool,
+        limits: &ParseLimits,
+    ) -> Result<Frame<'a>, FrameReadAsyncError>
     where
         R: AsyncRead + Unpin + ?Sized,
     {
         use crate::bytes::BytesReaderAsync;
 
-        let kind_id = reader.get_varint().await?;
-        let kind = FrameKind::parse(kind_id)
-            .ok_or(FrameReadAsyncError::Frame(FrameReadError::UnknownFrame))?;
+        let kind = loop {
+            let kind_id = reader.get_varint().await?;
+            match Self::parse_kind(kind_id, mode, keep_unknown)
+                .map_err(FrameReadAsyncError::Frame)?
+            {
+                Some(kind) => break kind,
+                None => {
+                    let mut payload_len = reader.get_varint().await?.into_inner();
+                    let mut buffer = [0; 256];
+
+                    while payload_len > 0 {
+                        let chunk_len = payload_len.min(buffer.len() as u64) as usize;
+                        reader.get_buffer(&mut buffer[..chunk_len]).await?;
+                        payload_len -= chunk_len as u64;
+                    }
+                }
+            }
+        };
 
         if matches!(kind, FrameKind::WebTransport) {
             let session_id = SessionId::try_from_varint(reader.get_varint().await?).map_err(
@@ -195,6 +418,17 @@ impl<'a> Frame<'a> {
             Ok(Self::new_webtransport(session_id))
         } else {
             let payload_len = reader.get_varint().await?.into_inner() as usize;
+
+            let max_payload_len = match kind {
+                FrameKind::Headers => limits.max_header_block_size(),
+                FrameKind::Settings => limits.max_settings_size(),
+                _ => usize::MAX,
+            };
+
+            if payload_len > max_payload_len {
+                return Err(FrameReadAsyncError::Frame(FrameReadError::ExcessiveLoad));
+            }
+
             let mut payload = vec![0; payload_len];
 
             reader.get_buffer(&mut payload).await?;
@@ -211,12 +445,24 @@ impl<'a> Frame<'a> {
     /// to parse an entire frame.
     ///
     /// In case [`None`] or [`Err`], `buffer_reader` offset if not advanced.
+    ///
+    /// This is equivalent to [`Self::read_from_buffer_with_mode`] with [`ParseMode::Strict`].
     pub fn read_from_buffer(
         buffer_reader: &mut BufferReader<'a>,
+    ) -> Option<Result<Self, FrameReadError>> {
+        Self::read_from_buffer_with_mode(buffer_reader, ParseMode::Strict)
+    }
+
+    /// Reads a [`Frame`] from a [`BufferReader`], with the given parsing `mode`.
+    ///
+    /// See [`Self::read_from_buffer`] and [`Self::read_with_mode`].
+    pub fn read_from_buffer_with_mode(
+        buffer_reader: &mut BufferReader<'a>,
+        mode: ParseMode,
     ) -> Option<Result<Self, FrameReadError>> {
         let mut buffer_reader_child = buffer_reader.child();
 
-        match Self::read(&mut *buffer_reader_child)? {
+        match Self::read_with_mode(&mut *buffer_reader_child, mode)? {
             Ok(frame) => {
                 buffer_reader_child.commit();
                 Some(Ok(frame))
@@ -285,6 +531,65 @@ impl<'a> Frame<'a> {
         Ok(())
     }
 
+    /// Writes a frame of type `kind` into a `writer`, streaming the payload
+    /// (`payload_len` bytes) from `payload_reader`.
+    ///
+    /// Unlike [`Self::write_async`], the payload is never entirely buffered in
+    /// memory: it is relayed in small chunks. This is useful, for example, for
+    /// proxies relaying frames between connections.
+    ///
+    /// It returns [`IoError::Closed`] if `payload_reader` reaches EOF before
+    /// `payload_len` bytes have been read. In case of [`Err`], `writer` might
+    /// be partially written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `kind` is [`FrameKind::WebTransport`], as it has no payload.
+    #[cfg(feature = "async")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async")))]
+    pub async fn write_async_into<W, R>(
+        writer: &mut W,
+        kind: FrameKind,
+        payload_len: VarInt,
+        payload_reader: &mut R,
+    ) -> Result<(), IoError>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+        R: AsyncRead + Unpin + ?Sized,
+    {
+        use crate::bytes::BytesWriterAsync;
+        use std::future::poll_fn;
+        use std::pin::Pin;
+
+        const CHUNK_SIZE: usize = 4096;
+
+        assert!(!matches!(kind, FrameKind::WebTransport));
+
+        writer.put_varint(kind.id()).await?;
+        writer.put_varint(payload_len).await?;
+
+        let mut buffer = [0; CHUNK_SIZE];
+        let mut remaining = payload_len.into_inner();
+
+        while remaining > 0 {
+            let chunk_len = remaining.min(CHUNK_SIZE as u64) as usize;
+
+            let read = poll_fn(|cx| {
+                AsyncRead::poll_read(Pin::new(&mut *payload_reader), cx, &mut buffer[..chunk_len])
+            })
+            .await?;
+
+            if read == 0 {
+                return Err(IoError::Closed);
+            }
+
+            writer.put_buffer(&buffer[..read]).await?;
+            remaining -= read as u64;
+        }
+
+        Ok(())
+    }
+
     /// Writes this [`Frame`] into a buffer via [`BufferWriter`].
     ///
     /// In case [`Err`], `buffer_writer` is not advanced.
@@ -328,6 +633,24 @@ impl<'a> Frame<'a> {
         &self.payload
     }
 
+    /// Returns the identifier carried by a [`FrameKind::GoAway`] frame
+    /// (see [`Self::new_goaway`]).
+    ///
+    /// It returns [`None`] if the frame is not a GOAWAY, or [`Err`] with
+    /// [`ErrorCode::Frame`] if its payload is malformed.
+    pub fn goaway_id(&self) -> Option<Result<VarInt, ErrorCode>> {
+        if !matches!(self.kind, FrameKind::GoAway) {
+            return None;
+        }
+
+        let mut payload = self.payload.as_ref();
+
+        match payload.get_varint() {
+            Some(id) if payload.is_empty() => Some(Ok(id)),
+            _ => Some(Err(ErrorCode::Frame)),
+        }
+    }
+
     /// Returns the [`SessionId`] if frame is [`FrameKind::WebTransport`],
     /// otherwise returns [`None`].
     #[inline(always)]
@@ -338,12 +661,38 @@ impl<'a> Frame<'a> {
         })
     }
 
+    /// Whether a frame of unknown type `kind_id` can be skipped in `mode`.
+    #[inline(always)]
+    fn is_skippable(kind_id: VarInt, mode: ParseMode) -> bool {
+        matches!(mode, ParseMode::Lenient) && !FrameKind::is_id_reserved(kind_id)
+    }
+
+    /// Parses the type of a frame being read.
+    ///
+    /// It returns [`None`] if the frame has to be skipped.
+    fn parse_kind(
+        kind_id: VarInt,
+        mode: ParseMode,
+        keep_unknown: bool,
+    ) -> Result<Option<FrameKind>, FrameReadError> {
+        match FrameKind::parse(kind_id) {
+            Some(kind) => Ok(Some(kind)),
+            None if keep_unknown && FrameKind::is_id_unknown(kind_id) => {
+                Ok(Some(FrameKind::Unknown(kind_id)))
+            }
+            None if Self::is_skippable(kind_id, mode) => Ok(None),
+            None => Err(FrameReadError::UnknownFrame),
+        }
+    }
+
     /// # Panics
     ///
     /// Panics if the `payload` size if greater than [`VarInt::MAX`].
     fn new(kind: FrameKind, payload: Cow<'a, [u8]>, session_id: Option<SessionId>) -> Self {
         if let FrameKind::Exercise(id) = kind {
             debug_assert!(FrameKind::is_id_exercise(id))
+        } else if let FrameKind::Unknown(id) = kind {
+            debug_assert!(FrameKind::is_id_unknown(id))
         } else if let FrameKind::WebTransport = kind {
             debug_assert!(payload.is_empty());
             debug_assert!(session_id.is_some())
@@ -368,12 +717,244 @@ impl<'a> Frame<'a> {
     }
 }
 
+/// An event yielded by [`FrameDecoder`].
+#[derive(Debug)]
+pub enum FrameEvent<'a> {
+    /// The header of a new frame.
+    ///
+    /// It is followed by zero or more [`FrameEvent::Payload`] events
+    /// (`payload_len` bytes in total) and a [`FrameEvent::End`].
+    Header {
+        /// The frame type.
+        kind: FrameKind,
+
+        /// The length of the frame payload.
+        payload_len: VarInt,
+    },
+
+    /// A [`FrameKind::WebTransport`] frame (it has no payload, nor [`FrameEvent::End`]).
+    WebTransport(SessionId),
+
+    /// A slice of the payload of the current frame.
+    Payload(&'a [u8]),
+
+    /// The end of the payload of the current frame.
+    End,
+}
+
+/// Incremental decoder of HTTP3 frames.
+///
+/// Unlike [`Frame::read`], it does not need an entire frame in memory: it can be
+/// fed byte chunks of any size, and it yields frame headers and payload slices
+/// (borrowed from the chunks) progressively. Only the frame header is buffered
+/// internally, so memory is bounded regardless of the frame size.
+///
+/// # Example
+/// ```
+/// use wtransport_proto::frame::FrameDecoder;
+/// use wtransport_proto::frame::FrameEvent;
+///
+/// let mut decoder = FrameDecoder::new();
+/// let mut payload = Vec::new();
+///
+/// for mut chunk in [&[0x00, 0x03, b'a'][..], &[b'b', b'c']] {
+///     while let Some(event) = decoder.decode(&mut chunk) {
+///         match event.unwrap() {
+///             FrameEvent::Payload(slice) => payload.extend_from_slice(slice),
+///             FrameEvent::End => assert_eq!(payload, b"abc"),
+///             _ => {}
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FrameDecoder {
+    mode: ParseMode,
+    state: DecoderState,
+    header: [u8; FrameDecoder::MAX_HEADER_SIZE],
+    header_len: usize,
+}
+
+impl FrameDecoder {
+    /// Maximum size of a frame header: two varints (type and length, or type and session ID).
+    const MAX_HEADER_SIZE: usize = 2 * VarInt::MAX_SIZE;
+
+    /// Creates a new decoder.
+    ///
+    /// This is equivalent to [`Self::with_mode`] with [`ParseMode::Strict`].
+    pub fn new() -> Self {
+        Self::with_mode(ParseMode::Strict)
+    }
+
+    /// Creates a new decoder, with the given parsing `mode`.
+    ///
+    /// In [`ParseMode::Lenient`], frames of unknown type are skipped.
+    pub fn with_mode(mode: ParseMode) -> Self {
+        Self {
+            mode,
+            state: DecoderState::Header,
+            header: [0; Self::MAX_HEADER_SIZE],
+            header_len: 0,
+        }
+    }
+
+    /// Decodes the next event from `input`, advancing it past the consumed bytes.
+    ///
+    /// It returns [`None`] when `input` has been entirely consumed and more bytes
+    /// are needed (the decoder keeps its state: feed the next chunk).
+    ///
+    /// After an [`Err`], the stream is corrupted and the decoder must not be used anymore.
+    pub fn decode<'a>(
+        &mut self,
+        input: &mut &'a [u8],
+    ) -> Option<Result<FrameEvent<'a>, FrameReadError>> {
+        loop {
+            match self.state {
+                DecoderState::Header => match self.decode_header(input)? {
+                    Ok(Some(event)) => return Some(Ok(event)),
+                    Ok(None) => continue,
+                    Err(error) => return Some(Err(error)),
+                },
+                DecoderState::Payload { remaining: 0 } => {
+                    self.state = DecoderState::Header;
+                    return Some(Ok(FrameEvent::End));
+                }
+                DecoderState::Payload { remaining } => {
+                    let slice = Self::take(input, remaining)?;
+                    self.state = DecoderState::Payload {
+                        remaining: remaining - slice.len() as u64,
+                    };
+                    return Some(Ok(FrameEvent::Payload(slice)));
+                }
+                DecoderState::Skip { remaining: 0 } => {
+                    self.state = DecoderState::Header;
+                }
+                DecoderState::Skip { remaining } => {
+                    let slice = Self::take(input, remaining)?;
+                    self.state = DecoderState::Skip {
+                        remaining: remaining - slice.len() as u64,
+                    };
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the decoder is at a frame boundary (i.e., it is not
+    /// in the middle of a frame header or payload).
+    ///
+    /// This can be used, for example, to detect a stream truncated mid-frame.
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, DecoderState::Header) && self.header_len == 0
+    }
+
+    /// Buffers header bytes from `input` and parses the header once complete.
+    ///
+    /// Returns `Ok(None)` if a skippable (unknown) frame header has been parsed.
+    fn decode_header<'a>(
+        &mut self,
+        input: &mut &'a [u8],
+    ) -> Option<Result<Option<FrameEvent<'a>>, FrameReadError>> {
+        if input.is_empty() {
+            return None;
+        }
+
+        let copied = input.len().min(Self::MAX_HEADER_SIZE - self.header_len);
+        self.header[self.header_len..self.header_len + copied].copy_from_slice(&input[..copied]);
+
+        let mut header = &self.header[..self.header_len + copied];
+        let parsed = Self::parse_header(&mut header, self.mode);
+        let header_size = self.header_len + copied - header.len();
+
+        let Some(parsed) = parsed else {
+            self.header_len += copied;
+            *input = &input[copied..];
+            return None;
+        };
+
+        // Only the bytes actually part of the header are consumed
+        *input = &input[header_size - self.header_len..];
+        self.header_len = 0;
+
+        let (kind_id, value) = match parsed {
+            Ok(parsed) => parsed,
+            Err(error) => return Some(Err(error)),
+        };
+
+        let event = match FrameKind::parse(kind_id) {
+            Some(FrameKind::WebTransport) => match SessionId::try_from_varint(value) {
+                Ok(session_id) => Some(FrameEvent::WebTransport(session_id)),
+                Err(InvalidSessionId) => return Some(Err(FrameReadError::InvalidSessionId)),
+            },
+            Some(kind) => {
+                self.state = DecoderState::Payload {
+                    remaining: value.into_inner(),
+                };
+                Some(FrameEvent::Header {
+                    kind,
+                    payload_len: value,
+                })
+            }
+            None => {
+                self.state = DecoderState::Skip {
+                    remaining: value.into_inner(),
+                };
+                None
+            }
+        };
+
+        Some(Ok(event))
+    }
+
+    /// Parses a frame type and the following varint (payload length or session ID).
+    fn parse_header(
+        header: &mut &[u8],
+        mode: ParseMode,
+    ) -> Option<Result<(VarInt, VarInt), FrameReadError>> {
+        let kind_id = header.get_varint()?;
+
+        if FrameKind::parse(kind_id).is_none() && !Frame::is_skippable(kind_id, mode) {
+            return Some(Err(FrameReadError::UnknownFrame));
+        }
+
+        let value = header.get_varint()?;
+
+        Some(Ok((kind_id, value)))
+    }
+
+    /// Takes up to `max_len` bytes from `input`, or [`None`] if `input` is empty.
+    fn take<'a>(input: &mut &'a [u8], max_len: u64) -> Option<&'a [u8]> {
+        if input.is_empty() {
+            return None;
+        }
+
+        let len = max_len.min(input.len() as u64) as usize;
+        let (slice, rest) = input.split_at(len);
+        *input = rest;
+
+        Some(slice)
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum DecoderState {
+    Header,
+    Payload { remaining: u64 },
+    Skip { remaining: u64 },
+}
+
 mod frame_kind_ids {
     use crate::varint::VarInt;
 
     pub const DATA: VarInt = VarInt::from_u32(0x00);
     pub const HEADERS: VarInt = VarInt::from_u32(0x01);
     pub const SETTINGS: VarInt = VarInt::from_u32(0x04);
+    pub const GOAWAY: VarInt = VarInt::from_u32(0x07);
     pub const WEBTRANSPORT_STREAM: VarInt = VarInt::from_u32(0x41);
 }
 
@@ -479,6 +1060,163 @@ mod tests {
         assert!(matches!(frame.kind(), FrameKind::WebTransport));
     }
 
+    #[test]
+    fn data_empty() {
+        let frame = Frame::new_data(Cow::Borrowed(&[]));
+        assert!(frame.session_id().is_none());
+        assert!(matches!(frame.kind(), FrameKind::Data));
+
+        let frame = utils::assert_serde(frame);
+        assert!(frame.payload().is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn data_empty_async() {
+        let frame = Frame::new_data(Cow::Borrowed(&[]));
+        assert!(frame.session_id().is_none());
+        assert!(matches!(frame.kind(), FrameKind::Data));
+
+        let frame = utils::assert_serde_async(frame).await;
+        assert!(frame.payload().is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn write_async_into() {
+        let payload = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+        let payload_len = VarInt::try_from(payload.len() as u64).unwrap();
+
+        let mut buffer = Vec::new();
+        Frame::write_async_into(
+            &mut buffer,
+            FrameKind::Data,
+            payload_len,
+            &mut payload.as_slice(),
+        )
+        .await
+        .unwrap();
+
+        let expected = Frame::new_data(Cow::Borrowed(&payload));
+        assert_eq!(buffer.len(), expected.write_size());
+
+        let frame = Frame::read_async(&mut buffer.as_slice()).await.unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Data));
+        assert_eq!(frame.payload(), payload);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn write_async_into_eof() {
+        let payload = [0; 8];
+
+        let mut buffer = Vec::new();
+        let result = Frame::write_async_into(
+            &mut buffer,
+            FrameKind::Data,
+            VarInt::from_u32(16),
+            &mut payload.as_slice(),
+        )
+        .await;
+
+        assert!(matches!(result, Err(IoError::Closed)));
+    }
+
+    #[test]
+    fn unknown_frame_lenient() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(VarInt::from_u32(0x42)).unwrap();
+        buffer.put_varint(VarInt::from_u32(3)).unwrap();
+        buffer.put_bytes(&[1, 2, 3]).unwrap();
+        Frame::new_data(Cow::Borrowed(&[4]))
+            .write(&mut buffer)
+            .unwrap();
+
+        let mut reader = buffer.as_slice();
+        assert!(matches!(
+            Frame::read(&mut reader),
+            Some(Err(FrameReadError::UnknownFrame))
+        ));
+
+        let mut reader = buffer.as_slice();
+        let frame = Frame::read_with_mode(&mut reader, ParseMode::Lenient)
+            .unwrap()
+            .unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Data));
+        assert_eq!(frame.payload(), [4]);
+        assert!(reader.is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn unknown_frame_lenient_async() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(VarInt::from_u32(0x42)).unwrap();
+        buffer.put_varint(VarInt::from_u32(300)).unwrap();
+        buffer.put_bytes(&[0; 300]).unwrap();
+        Frame::new_data(Cow::Borrowed(&[4]))
+            .write(&mut buffer)
+            .unwrap();
+
+        let mut reader = buffer.as_slice();
+        let frame = Frame::read_async_with_mode(&mut reader, ParseMode::Lenient)
+            .await
+            .unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Data));
+        assert_eq!(frame.payload(), [4]);
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn unknown_frame_kept() {
+        let id = VarInt::from_u32(0x42);
+
+        let mut buffer = Vec::new();
+        Frame::new_unknown(id, Cow::Borrowed(b"PAYLOAD"))
+            .write(&mut buffer)
+            .unwrap();
+
+        let mut reader = buffer.as_slice();
+        let frame = Frame::read_with_unknown(&mut reader).unwrap().unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Unknown(x) if x == id));
+        assert_eq!(frame.payload(), b"PAYLOAD");
+        assert!(reader.is_empty());
+
+        assert!(matches!(
+            Frame::read(&mut buffer.as_slice()),
+            Some(Err(FrameReadError::UnknownFrame))
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn unknown_frame_kept_async() {
+        let id = VarInt::from_u32(0x42);
+
+        let mut buffer = Vec::new();
+        Frame::new_unknown(id, Cow::Borrowed(b"PAYLOAD"))
+            .write(&mut buffer)
+            .unwrap();
+
+        let mut reader = buffer.as_slice();
+        let frame = Frame::read_async_with_unknown(&mut reader).await.unwrap();
+        assert!(matches!(frame.kind(), FrameKind::Unknown(x) if x == id));
+        assert_eq!(frame.payload(), b"PAYLOAD");
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn reserved_frame_lenient() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(VarInt::from_u32(0x02)).unwrap();
+        buffer.put_varint(VarInt::from_u32(0)).unwrap();
+
+        assert!(matches!(
+            Frame::read_with_mode(&mut buffer.as_slice(), ParseMode::Lenient),
+            Some(Err(FrameReadError::UnknownFrame))
+        ));
+    }
+
     #[test]
     fn read_eof() {
         let session_id = SessionId::try_from_varint(VarInt::from_u32(0)).unwrap();
@@ -544,6 +1282,31 @@ mod tests {
         ));
     }
 
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn excessive_load_async() {
+        let mut buffer = Vec::new();
+
+        Frame::new_headers(Cow::Owned(vec![0; 64]))
+            .write(&mut buffer)
+            .unwrap();
+
+        let limits = ParseLimits::default().with_max_header_block_size(32);
+
+        assert!(matches!(
+            Frame::read_async_with_limits(&mut buffer.as_slice(), ParseMode::Strict, &limits).await,
+            Err(FrameReadAsyncError::Frame(FrameReadError::ExcessiveLoad))
+        ));
+
+        let limits = ParseLimits::default().with_max_header_block_size(64);
+
+        assert!(
+            Frame::read_async_with_limits(&mut buffer.as_slice(), ParseMode::Strict, &limits)
+                .await
+                .is_ok()
+        );
+    }
+
     #[test]
     fn invalid_session_id() {
         let mut buffer = Vec::new();
@@ -585,6 +1348,136 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn goaway() {
+        let frame = Frame::new_goaway(VarInt::from_u32(16));
+        assert!(matches!(frame.kind(), FrameKind::GoAway));
+
+        let frame = utils::assert_serde(frame);
+        assert_eq!(frame.goaway_id().unwrap().unwrap(), VarInt::from_u32(16));
+
+        assert!(Frame::new_data(Cow::Borrowed(&[])).goaway_id().is_none());
+        assert!(matches!(
+            Frame::new(FrameKind::GoAway, Cow::Borrowed(&[0x04, 0x00]), None).goaway_id(),
+            Some(Err(ErrorCode::Frame))
+        ));
+    }
+
+    #[test]
+    fn decoder_byte_by_byte() {
+        let session_id = SessionId::try_from_varint(VarInt::from_u32(4)).unwrap();
+
+        let mut buffer = Vec::new();
+        Frame::new_data(Cow::Owned(vec![7; 300]))
+            .write(&mut buffer)
+            .unwrap();
+        Frame::new_headers(Cow::Borrowed(&[]))
+            .write(&mut buffer)
+            .unwrap();
+        Frame::new_webtransport(session_id)
+            .write(&mut buffer)
+            .unwrap();
+
+        let mut decoder = FrameDecoder::new();
+        let mut events = Vec::new();
+        let mut payload = Vec::new();
+
+        for byte in buffer.chunks(1) {
+            let mut chunk = byte;
+            while let Some(event) = decoder.decode(&mut chunk) {
+                match event.unwrap() {
+                    FrameEvent::Header { kind, payload_len } => {
+                        events.push(format!("{kind:?}:{payload_len}"))
+                    }
+                    FrameEvent::WebTransport(id) => events.push(format!("wt:{id}")),
+                    FrameEvent::Payload(slice) => payload.extend_from_slice(slice),
+                    FrameEvent::End => events.push("end".to_string()),
+                }
+            }
+            assert!(chunk.is_empty());
+        }
+
+        assert_eq!(events, ["Data:300", "end", "Headers:0", "end", "wt:4"]);
+        assert_eq!(payload, [7; 300]);
+        assert!(decoder.is_idle());
+    }
+
+    #[test]
+    fn decoder_single_chunk() {
+        let mut buffer = Vec::new();
+        Frame::new_data(Cow::Borrowed(b"PAYLOAD"))
+            .write(&mut buffer)
+            .unwrap();
+        buffer.push(0x01);
+
+        let mut decoder = FrameDecoder::new();
+        let mut chunk = buffer.as_slice();
+
+        assert!(matches!(
+            decoder.decode(&mut chunk),
+            Some(Ok(FrameEvent::Header {
+                kind: FrameKind::Data,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            decoder.decode(&mut chunk),
+            Some(Ok(FrameEvent::Payload(b"PAYLOAD")))
+        ));
+        assert!(matches!(
+            decoder.decode(&mut chunk),
+            Some(Ok(FrameEvent::End))
+        ));
+        assert!(decoder.decode(&mut chunk).is_none());
+        assert!(chunk.is_empty());
+        assert!(!decoder.is_idle());
+    }
+
+    #[test]
+    fn decoder_unknown_frame() {
+        let mut buffer = Vec::new();
+        buffer.put_varint(VarInt::from_u32(0x42)).unwrap();
+        buffer.put_varint(VarInt::from_u32(3)).unwrap();
+        buffer.put_bytes(&[1, 2, 3]).unwrap();
+        Frame::new_data(Cow::Borrowed(&[4]))
+            .write(&mut buffer)
+            .unwrap();
+
+        let mut decoder = FrameDecoder::new();
+        assert!(matches!(
+            decoder.decode(&mut buffer.as_slice()),
+            Some(Err(FrameReadError::UnknownFrame))
+        ));
+
+        let mut decoder = FrameDecoder::with_mode(ParseMode::Lenient);
+        let mut chunk = buffer.as_slice();
+        assert!(matches!(
+            decoder.decode(&mut chunk),
+            Some(Ok(FrameEvent::Header {
+                kind: FrameKind::Data,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            decoder.decode(&mut chunk),
+            Some(Ok(FrameEvent::Payload([4])))
+        ));
+    }
+
+    #[test]
+    fn decoder_invalid_session_id() {
+        let mut buffer = Vec::new();
+        buffer
+            .put_varint(frame_kind_ids::WEBTRANSPORT_STREAM)
+            .unwrap();
+        buffer.put_varint(VarInt::from_u32(1)).unwrap();
+
+        assert!(matches!(
+            FrameDecoder::new().decode(&mut buffer.as_slice()),
+            Some(Err(FrameReadError::InvalidSessionId))
+        ));
+    }
+
     mod utils {
         use super::*;
 
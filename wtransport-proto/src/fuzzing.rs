@@ -0,0 +1,169 @@
+//! Each function decodes untrusted bytes as the peer would send them, so
+//! that fuzzers can target the decoding of frames, headers, settings and
+//! the other protocol units directly, without a connection.
+//!
+//! # Example
+//!
+//! A `cargo fuzz` target:
+//!
+//! ```ignore
+//! #![no_main]
+//! use libfuzzer_sys::fuzz_target;
+//! use wtransport_proto::fuzzing;
+//! use wtransport_proto::ParseMode;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     fuzzing::frame_sequence(data, ParseMode::Strict);
+//! });
+//! ```
+
+use crate::bytes::BufferReader;
+use crate::capsule::Capsule;
+use crate::datagram::Datagram;
+use crate::datagram::DatagramReadError;
+use crate::error::ErrorCode;
+use crate::frame::Frame;
+use crate::frame::FrameKind;
+use crate::frame::FrameReadError;
+use crate::headers::Headers;
+use crate::ids::StreamId;
+use crate::settings::Settings;
+use crate::stream::StreamHeader;
+use crate::stream::StreamHeaderReadError;
+use crate::varint::VarInt;
+use crate::ParseMode;
+use std::borrow::Cow;
+
+/// Reads a frame from the beginning of `data`.
+///
+/// It returns [`None`] if `data` does not contain an entire frame.
+pub fn frame(data: &[u8], mode: ParseMode) -> Option<Result<Frame<'_>, FrameReadError>> {
+    Frame::read_from_buffer_with_mode(&mut BufferReader::new(data), mode)
+}
+
+/// Reads the frames of `data` (e.g., the content of a request stream) until
+/// the input is exhausted or invalid, and decodes the payloads of HEADERS
+/// and SETTINGS frames.
+///
+/// It returns the number of frames read.
+pub fn frame_sequence(data: &[u8], mode: ParseMode) -> usize {
+    let mut buffer_reader = BufferReader::new(data);
+    let mut count = 0;
+
+    while let Some(Ok(frame)) = Frame::read_from_buffer_with_mode(&mut buffer_reader, mode) {
+        match frame.kind() {
+            FrameKind::Headers => {
+                let _ = Headers::with_frame_mode(&frame, request_stream_id(), mode);
+            }
+            FrameKind::Settings => {
+                let _ = Settings::with_frame_mode(&frame, mode);
+            }
+            _ => {}
+        }
+
+        count += 1;
+    }
+
+    count
+}
+
+/// Decodes `payload` as the payload of a HEADERS frame.
+pub fn headers(payload: &[u8], mode: ParseMode) -> Result<Headers, ErrorCode> {
+    let frame = Frame::new_headers(Cow::Borrowed(payload));
+    Headers::with_frame_mode(&frame, request_stream_id(), mode)
+}
+
+/// Decodes `payload` as the payload of a SETTINGS frame.
+pub fn settings(payload: &[u8], mode: ParseMode) -> Result<Settings, ErrorCode> {
+    let frame = Frame::new_settings(Cow::Borrowed(payload));
+    Settings::with_frame_mode(&frame, mode)
+}
+
+/// Reads a stream header from the beginning of `data`.
+///
+/// It returns [`None`] if `data` does not contain an entire header.
+pub fn stream_header(data: &[u8]) -> Option<Result<StreamHeader, StreamHeaderReadError>> {
+    StreamHeader::read_from_buffer(&mut BufferReader::new(data))
+}
+
+/// Reads the capsules of `data` (e.g., the content of DATA frames on a
+/// session stream) until the input is exhausted.
+///
+/// It returns the number of capsules read.
+pub fn capsule_sequence(data: &[u8]) -> usize {
+    let mut buffer_reader = BufferReader::new(data);
+    let mut count = 0;
+
+    while Capsule::read_from_buffer(&mut buffer_reader).is_some() {
+        count += 1;
+    }
+
+    count
+}
+
+/// Reads `data` as the payload of a QUIC datagram.
+pub fn datagram(data: &[u8]) -> Result<Datagram<'_>, DatagramReadError> {
+    Datagram::read(data)
+}
+
+/// The stream on which headers are decoded (the first client request stream).
+fn request_stream_id() -> StreamId {
+    StreamId::new(VarInt::from_u32(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytes::BufferWriter;
+
+    #[test]
+    fn frame_sequence_counts_frames() {
+        let mut buffer = vec![0; 64];
+        let mut buffer_writer = BufferWriter::new(&mut buffer);
+
+        Frame::new_data(Cow::Borrowed(b"data"))
+            .write(&mut buffer_writer)
+            .unwrap();
+        Frame::new_settings(Cow::Borrowed(&[]))
+            .write(&mut buffer_writer)
+            .unwrap();
+
+        let written = buffer_writer.offset();
+        assert_eq!(frame_sequence(&buffer[..written], ParseMode::Strict), 2);
+        assert_eq!(frame_sequence(&buffer[..written - 1], ParseMode::Strict), 1);
+    }
+
+    #[test]
+    fn arbitrary_input() {
+        let inputs: [&[u8]; 6] = [
+            &[],
+            &[0x01],
+            &[0x01, 0x02, 0x00, 0x00],
+            &[0x04, 0x03, 0x06, 0x40],
+            &[0x3f, 0xff, 0xff, 0xff],
+            &[0xff; 16],
+        ];
+
+        for input in inputs {
+            let _ = frame(input, ParseMode::Strict);
+            let _ = frame(input, ParseMode::Lenient);
+            let _ = frame_sequence(input, ParseMode::Strict);
+            let _ = headers(input, ParseMode::Strict);
+            let _ = headers(input, ParseMode::Lenient);
+            let _ = settings(input, ParseMode::Strict);
+            let _ = settings(input, ParseMode::Lenient);
+            let _ = stream_header(input);
+            let _ = capsule_sequence(input);
+            let _ = datagram(input);
+        }
+    }
+
+    #[test]
+    fn settings_truncated() {
+        // A setting identifier without value
+        assert!(matches!(
+            settings(&[0x33], ParseMode::Strict),
+            Err(ErrorCode::Frame)
+        ));
+    }
+}
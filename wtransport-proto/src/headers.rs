@@ -2,6 +2,8 @@ use crate::error::ErrorCode;
 use crate::frame::Frame;
 use crate::frame::FrameKind;
 use crate::ids::StreamId;
+use crate::ParseLimits;
+use crate::ParseMode;
 use ls_qpack::decoder::Decoder;
 use ls_qpack::decoder::DecoderOutput;
 use ls_qpack::encoder::Encoder;
@@ -10,7 +12,11 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// HTTP3 headers from the request or response.
-#[derive(Debug)]
+///
+/// Field names are case-insensitive: they are stored lowercased (as HTTP3
+/// requires) and lookups ignore case. Repeated fields are combined into a
+/// single comma-separated value (semicolon-separated for `cookie`).
+#[derive(Clone, Debug)]
 pub struct Headers(HashMap<String, String>);
 
 impl Headers {
@@ -20,28 +26,117 @@ impl Headers {
     ///
     /// Panics if `frame` is not type [`FrameKind::Headers`].
     pub fn with_frame(frame: &Frame, stream_id: StreamId) -> Result<Self, ErrorCode> {
+        Self::with_frame_mode(frame, stream_id, ParseMode::Strict)
+    }
+
+    /// Constructs the headers from a HTTP3 [`Frame`], with the given parsing `mode`.
+    ///
+    /// In [`ParseMode::Strict`], a header section is malformed ([`ErrorCode::Message`])
+    /// if it contains uppercase field names, pseudo-headers after regular fields,
+    /// duplicate pseudo-headers, or connection-specific fields.
+    /// In [`ParseMode::Lenient`], field names are lowercased and none of those
+    /// conditions is checked.
+    ///
+    /// This is equivalent to [`Self::with_frame_limits`] with the default [`ParseLimits`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::Headers`].
+    pub fn with_frame_mode(
+        frame: &Frame,
+        stream_id: StreamId,
+        mode: ParseMode,
+    ) -> Result<Self, ErrorCode> {
+        Self::with_frame_limits(frame, stream_id, mode, &ParseLimits::default())
+    }
+
+    /// Constructs the headers from a HTTP3 [`Frame`], with the given parsing `mode`
+    /// and `limits`.
+    ///
+    /// A header block larger than [`ParseLimits::max_header_block_size`] is not
+    /// decoded, and one with more than [`ParseLimits::max_header_count`] fields
+    /// is discarded: both are [`ErrorCode::ExcessiveLoad`] errors.
+    ///
+    /// See [`Self::with_frame_mode`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::Headers`].
+    pub fn with_frame_limits(
+        frame: &Frame,
+        stream_id: StreamId,
+        mode: ParseMode,
+        limits: &ParseLimits,
+    ) -> Result<Self, ErrorCode> {
         assert!(matches!(frame.kind(), FrameKind::Headers));
 
+        if frame.payload().len() > limits.max_header_block_size() {
+            return Err(ErrorCode::ExcessiveLoad);
+        }
+
+        // The dynamic table capacity is advertised as zero (see SETTINGS), so a
+        // compliant peer only references the static table (possibly with Huffman
+        // encoded literals). A section waiting for dynamic entries is an error.
         let mut decoder = Decoder::new(0, 0);
 
-        match decoder
+        let fields = match decoder
             .decode(stream_id.into(), frame.payload())
             .map_err(|DecoderError| ErrorCode::Decompression)?
         {
-            DecoderOutput::Done(headers) => Ok(headers
-                .into_iter()
-                .map(|h| (h.name().to_string(), h.value().to_string()))
-                .collect()),
-            DecoderOutput::BlockedStream => todo!(),
+            DecoderOutput::Done(fields) => fields,
+            DecoderOutput::BlockedStream => return Err(ErrorCode::Decompression),
+        };
+
+        if fields.len() > limits.max_header_count() {
+            return Err(ErrorCode::ExcessiveLoad);
+        }
+
+        let mut headers = HashMap::new();
+        let mut regular_field_seen = false;
+
+        for field in fields {
+            let (name, value) = (field.name(), field.value());
+
+            match mode {
+                ParseMode::Strict => {
+                    if name.bytes().any(|byte| byte.is_ascii_uppercase()) {
+                        return Err(ErrorCode::Message);
+                    }
+
+                    if name.starts_with(':') {
+                        if regular_field_seen || headers.contains_key(name) {
+                            return Err(ErrorCode::Message);
+                        }
+                    } else {
+                        regular_field_seen = true;
+
+                        if Self::is_connection_specific(name, value) {
+                            return Err(ErrorCode::Message);
+                        }
+                    }
+
+                    Self::insert_field(&mut headers, name.to_string(), value);
+                }
+                ParseMode::Lenient => {
+                    Self::insert_field(&mut headers, name.to_ascii_lowercase(), value);
+                }
+            }
         }
+
+        Ok(Self(headers))
     }
 
     /// Generates a [`Frame`] with these headers.
+    ///
+    /// Pseudo-headers are encoded before regular fields.
     pub fn generate_frame(&self, stream_id: StreamId) -> Frame {
         let mut encoder = Encoder::new();
 
+        let pseudo_headers = self.0.iter().filter(|(name, _)| name.starts_with(':'));
+        let regular_fields = self.0.iter().filter(|(name, _)| !name.starts_with(':'));
+
         let (enc_headers, enc_stream) = encoder
-            .encode_all(stream_id.into(), self.0.iter())
+            .encode_all(stream_id.into(), pseudo_headers.chain(regular_fields))
             .expect("Static encoding is not expected to fail")
             .take();
 
@@ -51,12 +146,118 @@ impl Headers {
     }
 
     /// Returns a reference to the value associated with the key.
-    #[inline(always)]
+    ///
+    /// The lookup is case-insensitive.
     pub fn get<K>(&self, key: K) -> Option<&str>
     where
         K: AsRef<str>,
     {
-        self.0.get(key.as_ref()).map(|s| s.as_str())
+        let key = key.as_ref();
+
+        if key.bytes().any(|byte| byte.is_ascii_uppercase()) {
+            self.0.get(&key.to_ascii_lowercase())
+        } else {
+            self.0.get(key)
+        }
+        .map(|s| s.as_str())
+    }
+
+    /// Checks the pseudo-headers of a request
+    /// ([RFC 9114, Section 4.3.1](https://www.rfc-editor.org/rfc/rfc9114#section-4.3.1)).
+    ///
+    /// A request is malformed ([`ErrorCode::Message`]) if:
+    ///  * it has pseudo-headers other than `:method`, `:scheme`, `:authority`,
+    ///    `:path` and `:protocol`, or it lacks `:method`;
+    ///  * it is an extended CONNECT (with `:protocol`,
+    ///    [RFC 9220](https://www.rfc-editor.org/rfc/rfc9220)) without `:scheme`,
+    ///    `:authority` or `:path`;
+    ///  * it is a plain CONNECT with `:scheme` or `:path`, or without `:authority`;
+    ///  * it is neither of the above and it has `:protocol`, or it lacks
+    ///    `:scheme` or `:path`.
+    pub fn validate_request(&self) -> Result<(), ErrorCode> {
+        const REQUEST_PSEUDO_HEADERS: [&str; 5] =
+            [":method", ":scheme", ":authority", ":path", ":protocol"];
+
+        if self
+            .pseudo_headers()
+            .any(|name| !REQUEST_PSEUDO_HEADERS.contains(&name))
+        {
+            return Err(ErrorCode::Message);
+        }
+
+        let method = self.get(":method").ok_or(ErrorCode::Message)?;
+        let has = |name: &str| self.0.contains_key(name);
+
+        let valid = match (method, has(":protocol")) {
+            ("CONNECT", true) => has(":scheme") && has(":authority") && has(":path"),
+            ("CONNECT", false) => !has(":scheme") && !has(":path") && has(":authority"),
+            (_, protocol) => !protocol && has(":scheme") && has(":path"),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(ErrorCode::Message)
+        }
+    }
+
+    /// Checks the pseudo-headers of a response
+    /// ([RFC 9114, Section 4.3.2](https://www.rfc-editor.org/rfc/rfc9114#section-4.3.2)).
+    ///
+    /// A response is malformed ([`ErrorCode::Message`]) if it has pseudo-headers
+    /// other than `:status`, or if `:status` is not a three-digit code.
+    pub fn validate_response(&self) -> Result<(), ErrorCode> {
+        if self.pseudo_headers().any(|name| name != ":status") {
+            return Err(ErrorCode::Message);
+        }
+
+        match self.get(":status") {
+            Some(status) if status.len() == 3 && status.bytes().all(|b| b.is_ascii_digit()) => {
+                Ok(())
+            }
+            _ => Err(ErrorCode::Message),
+        }
+    }
+
+    /// Returns an iterator over the header fields, as `(name, value)` pairs.
+    ///
+    /// The order of fields is unspecified.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl Headers {
+    /// Inserts a field, combining its value with the one of a previous field
+    /// with the same name (pseudo-headers are replaced instead).
+    fn insert_field(headers: &mut HashMap<String, String>, name: String, value: &str) {
+        let separator = if name == "cookie" { "; " } else { ", " };
+
+        match headers.get_mut(&name) {
+            Some(current) if !name.starts_with(':') => {
+                current.push_str(separator);
+                current.push_str(value);
+            }
+            _ => {
+                headers.insert(name, value.to_string());
+            }
+        }
+    }
+
+    /// Returns an iterator over the names of the pseudo-headers.
+    fn pseudo_headers(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .keys()
+            .map(|name| name.as_str())
+            .filter(|name| name.starts_with(':'))
+    }
+
+    /// Checks whether a field is connection-specific (not allowed in HTTP3).
+    fn is_connection_specific(name: &str, value: &str) -> bool {
+        matches!(
+            name,
+            "connection" | "keep-alive" | "proxy-connection" | "transfer-encoding" | "upgrade"
+        ) || (name == "te" && value != "trailers")
     }
 }
 
@@ -66,11 +267,50 @@ where
     V: ToString,
 {
     fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
-        Self(
-            iter.into_iter()
-                .map(|(k, v)| (k.to_string(), v.to_string()))
-                .collect(),
-        )
+        let mut headers = HashMap::new();
+
+        for (name, value) in iter {
+            Self::insert_field(
+                &mut headers,
+                name.to_string().to_ascii_lowercase(),
+                &value.to_string(),
+            );
+        }
+
+        Self(headers)
+    }
+}
+
+/// Regular fields are converted; pseudo-headers are skipped (they are not
+/// representable in a [`http::HeaderMap`]).
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+impl TryFrom<&Headers> for http::HeaderMap {
+    type Error = http::Error;
+
+    fn try_from(headers: &Headers) -> Result<Self, Self::Error> {
+        let mut header_map = http::HeaderMap::with_capacity(headers.0.len());
+
+        for (name, value) in headers.iter().filter(|(name, _)| !name.starts_with(':')) {
+            header_map.append(
+                http::header::HeaderName::from_bytes(name.as_bytes())?,
+                http::header::HeaderValue::from_str(value)?,
+            );
+        }
+
+        Ok(header_map)
+    }
+}
+
+/// Values which are not valid UTF-8 are converted lossily.
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+impl From<&http::HeaderMap> for Headers {
+    fn from(header_map: &http::HeaderMap) -> Self {
+        header_map
+            .iter()
+            .map(|(name, value)| (name.as_str(), String::from_utf8_lossy(value.as_bytes())))
+            .collect()
     }
 }
 
@@ -80,3 +320,122 @@ impl From<StreamId> for ls_qpack::StreamId {
         ls_qpack::StreamId::new(value.into_u64())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        let stream_id = StreamId::new(crate::varint::VarInt::from_u32(0));
+        let frame = [(":status", "200"), ("server", "wtransport"), ("x-a", "1")]
+            .into_iter()
+            .collect::<Headers>()
+            .generate_frame(stream_id);
+
+        let limits = ParseLimits::default();
+        assert!(Headers::with_frame_limits(&frame, stream_id, ParseMode::Strict, &limits).is_ok());
+
+        let limits = ParseLimits::default().with_max_header_count(2);
+        assert!(matches!(
+            Headers::with_frame_limits(&frame, stream_id, ParseMode::Strict, &limits),
+            Err(ErrorCode::ExcessiveLoad)
+        ));
+
+        let limits = ParseLimits::default().with_max_header_block_size(frame.payload().len() - 1);
+        assert!(matches!(
+            Headers::with_frame_limits(&frame, stream_id, ParseMode::Strict, &limits),
+            Err(ErrorCode::ExcessiveLoad)
+        ));
+    }
+
+    #[test]
+    fn case_insensitive_get() {
+        let headers = [("Origin", "https://example.com")]
+            .into_iter()
+            .collect::<Headers>();
+
+        assert_eq!(headers.get("origin"), Some("https://example.com"));
+        assert_eq!(headers.get("ORIGIN"), Some("https://example.com"));
+    }
+
+    #[test]
+    fn repeated_fields() {
+        let headers = [
+            ("accept", "text/html"),
+            ("accept", "text/plain"),
+            ("cookie", "a=1"),
+            ("cookie", "b=2"),
+        ]
+        .into_iter()
+        .collect::<Headers>();
+
+        assert_eq!(headers.get("accept"), Some("text/html, text/plain"));
+        assert_eq!(headers.get("cookie"), Some("a=1; b=2"));
+    }
+
+    #[test]
+    fn pseudo_headers_first() {
+        let stream_id = StreamId::new(crate::varint::VarInt::from_u32(0));
+        let headers = [
+            ("sec-webtransport-http3-draft", "draft02"),
+            (":status", "200"),
+        ]
+        .into_iter()
+        .collect::<Headers>();
+
+        let headers = Headers::with_frame(&headers.generate_frame(stream_id), stream_id).unwrap();
+        assert_eq!(headers.get(":status"), Some("200"));
+    }
+
+    #[test]
+    fn validate_request() {
+        let extended_connect = [
+            (":method", "CONNECT"),
+            (":protocol", "webtransport"),
+            (":scheme", "https"),
+            (":authority", "example.com"),
+            (":path", "/"),
+        ];
+
+        assert!(extended_connect
+            .into_iter()
+            .collect::<Headers>()
+            .validate_request()
+            .is_ok());
+
+        assert!(extended_connect[..3]
+            .iter()
+            .copied()
+            .collect::<Headers>()
+            .validate_request()
+            .is_err());
+
+        assert!([(":method", "GET"), (":status", "200")]
+            .into_iter()
+            .collect::<Headers>()
+            .validate_request()
+            .is_err());
+    }
+
+    #[test]
+    fn validate_response() {
+        assert!([(":status", "200")]
+            .into_iter()
+            .collect::<Headers>()
+            .validate_response()
+            .is_ok());
+
+        assert!([(":status", "2000")]
+            .into_iter()
+            .collect::<Headers>()
+            .validate_response()
+            .is_err());
+
+        assert!([(":status", "200"), (":path", "/")]
+            .into_iter()
+            .collect::<Headers>()
+            .validate_response()
+            .is_err());
+    }
+}
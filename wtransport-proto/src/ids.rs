@@ -33,6 +33,17 @@ impl StreamId {
         (self.0.into_inner() & 0x1) == (is_server as u64)
     }
 
+    /// Returns the index of the stream among the streams of the same type.
+    ///
+    /// The two least significant bits of a stream id encode its type
+    /// (direction and initiator): the remaining bits are the index.
+    /// For a *client-initiated* *bidirectional* stream, this is the value
+    /// of its [`QStreamId`].
+    #[inline(always)]
+    pub const fn index(self) -> u64 {
+        self.0.into_inner() >> 2
+    }
+
     /// Returns the integer value as `u64`.
     #[inline(always)]
     pub const fn into_u64(self) -> u64 {
@@ -46,6 +57,13 @@ impl StreamId {
     }
 }
 
+impl From<VarInt> for StreamId {
+    #[inline(always)]
+    fn from(varint: VarInt) -> Self {
+        Self(varint)
+    }
+}
+
 impl From<StreamId> for VarInt {
     #[inline(always)]
     fn from(stream_id: StreamId) -> Self {
@@ -71,6 +89,14 @@ impl fmt::Display for StreamId {
 #[derive(Debug)]
 pub struct InvalidSessionId;
 
+impl fmt::Display for InvalidSessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "not a client-initiated bidirectional stream")
+    }
+}
+
+impl std::error::Error for InvalidSessionId {}
+
 /// A WebTransport session id.
 ///
 /// Internally, it corresponds to a *bidirectional* *client-initiated* QUIC stream,
@@ -149,6 +175,14 @@ impl fmt::Display for SessionId {
 #[derive(Debug)]
 pub struct InvalidQStreamId;
 
+impl fmt::Display for InvalidQStreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "quarter stream ID out of range")
+    }
+}
+
+impl std::error::Error for InvalidQStreamId {}
+
 /// HTTP3 Quarter Stream ID.
 #[derive(Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct QStreamId(VarInt);
@@ -208,7 +242,24 @@ impl QStreamId {
         self.0
     }
 
-    pub(crate) fn try_from_varint(varint: VarInt) -> Result<Self, InvalidQStreamId> {
+    /// Creates a quarter stream id from the [`StreamId`] of a request stream.
+    ///
+    /// It fails if `stream_id` is not *client-initiated* *bidirectional*.
+    pub fn try_from_stream_id(stream_id: StreamId) -> Result<Self, InvalidQStreamId> {
+        if !stream_id.is_bidirectional() || !stream_id.is_client_initiated() {
+            return Err(InvalidQStreamId);
+        }
+
+        // SAFETY: after bitwise operation from stream id, result is surely a varint
+        Ok(Self(unsafe {
+            VarInt::from_u64_unchecked(stream_id.into_varint().into_inner() >> 2)
+        }))
+    }
+
+    /// Creates a quarter stream id from its [`VarInt`] value.
+    ///
+    /// It fails if `varint` is greater than [`QStreamId::MAX`].
+    pub fn try_from_varint(varint: VarInt) -> Result<Self, InvalidQStreamId> {
         if varint <= Self::MAX.into_varint() {
             Ok(Self(varint))
         } else {
@@ -277,6 +328,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stream_index() {
+        for (position, (id, stream_type)) in stream_types(1024).enumerate() {
+            let stream_id = StreamId::from(id);
+
+            assert_eq!(stream_id.index(), position as u64 / 4);
+            assert_eq!(stream_id.into_u64() & 0x3, stream_type as u64);
+            assert_eq!(VarInt::from(stream_id), id);
+        }
+    }
+
+    #[test]
+    fn stream_type_bits() {
+        let types = [
+            (0b00, true, true),
+            (0b01, true, false),
+            (0b10, false, true),
+            (0b11, false, false),
+        ];
+
+        for (bits, bidirectional, client_initiated) in types {
+            for index in [0, 1, 42, QStreamId::MAX.into_u64()] {
+                let stream_id = StreamId::new(VarInt::try_from_u64((index << 2) | bits).unwrap());
+
+                assert_eq!(stream_id.is_bidirectional(), bidirectional);
+                assert_eq!(stream_id.is_client_initiated(), client_initiated);
+                assert_eq!(stream_id.is_local(false), client_initiated);
+                assert_eq!(stream_id.is_local(true), !client_initiated);
+                assert_eq!(stream_id.index(), index);
+            }
+        }
+    }
+
+    #[test]
+    fn qstream_id_index() {
+        for (id, stream_type) in stream_types(1024) {
+            if let StreamType::ClientBi = stream_type {
+                let stream_id = StreamId::new(id);
+                let qstream_id = QStreamId::try_from_stream_id(stream_id).unwrap();
+
+                assert_eq!(qstream_id.into_u64(), stream_id.index());
+            }
+        }
+
+        assert_eq!(
+            QStreamId::MAX.into_stream_id().index(),
+            QStreamId::MAX.into_u64()
+        );
+    }
+
     #[test]
     fn session_id() {
         for (id, stream_type) in stream_types(1024) {
@@ -305,6 +406,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn qstream_id_from_stream_id() {
+        for (id, stream_type) in stream_types(1024) {
+            let stream_id = StreamId::new(id);
+
+            match stream_type {
+                StreamType::ClientBi => {
+                    let qstream_id = QStreamId::try_from_stream_id(stream_id).unwrap();
+                    assert_eq!(qstream_id.into_stream_id(), stream_id);
+                }
+                StreamType::ServerBi | StreamType::ClientUni | StreamType::ServerUni => {
+                    assert!(QStreamId::try_from_stream_id(stream_id).is_err());
+                }
+            }
+        }
+    }
+
     mod utils {
         use super::*;
 
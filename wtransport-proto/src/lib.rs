@@ -5,15 +5,26 @@
 /// I/O and buffer operations.
 pub mod bytes;
 
+/// HTTP capsules.
+pub mod capsule;
+
 /// HTTP3 datagrams.
 pub mod datagram;
 
+/// QUIC discovery data (Alt-Svc and HTTPS DNS records).
+pub mod discovery;
+
 /// Errors definitions.
 pub mod error;
 
 /// HTTP3 frame.
 pub mod frame;
 
+/// Entry points of the parsers for arbitrary input (fuzzing).
+#[cfg(feature = "fuzzing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fuzzing")))]
+pub mod fuzzing;
+
 /// HTTP3 HEADERS frame payload.
 pub mod headers;
 
@@ -31,3 +42,121 @@ pub mod varint;
 
 /// Application Layer Protocol Negotiation for WebTransport connections.
 pub const WEBTRANSPORT_ALPN: &[u8; 2] = b"h3";
+
+/// Strictness of parsers (frames, headers and settings).
+///
+/// See, for example, [`frame::Frame::read_with_mode`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Every requirement of the specifications is enforced.
+    ///
+    /// This is the mode servers are expected to use.
+    #[default]
+    Strict,
+
+    /// Known real-world deviations from the specifications are tolerated,
+    /// for interoperability with non-compliant peers:
+    ///
+    /// * frames of unknown type (other than reserved HTTP/2 ones) are skipped;
+    /// * header names with uppercase characters are lowercased; misplaced
+    ///   pseudo-headers and connection-specific headers are accepted;
+    /// * reserved (HTTP/2) and duplicate settings are ignored, and setting
+    ///   values are not validated.
+    Lenient,
+}
+
+/// Limits on the size of peer input decoded by parsers (header blocks,
+/// SETTINGS and capsules), against excessive memory use.
+///
+/// Exceeding a limit is an [`error::ErrorCode::ExcessiveLoad`] error.
+/// See, for example, [`headers::Headers::with_frame_limits`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ParseLimits {
+    max_header_block_size: usize,
+    max_header_count: usize,
+    max_settings_entries: usize,
+    max_capsule_size: usize,
+}
+
+impl ParseLimits {
+    /// The default maximum size of an encoded header block (64 KiB).
+    pub const DEFAULT_MAX_HEADER_BLOCK_SIZE: usize = 64 * 1024;
+
+    /// The default maximum number of fields in a header block.
+    pub const DEFAULT_MAX_HEADER_COUNT: usize = 128;
+
+    /// The default maximum number of entries in a SETTINGS frame.
+    pub const DEFAULT_MAX_SETTINGS_ENTRIES: usize = 64;
+
+    /// The default maximum payload size of a capsule (64 KiB).
+    pub const DEFAULT_MAX_CAPSULE_SIZE: usize = 64 * 1024;
+
+    /// Sets the maximum size (in bytes) of an encoded header block, that is
+    /// the payload of a HEADERS frame.
+    pub fn with_max_header_block_size(mut self, max_header_block_size: usize) -> Self {
+        self.max_header_block_size = max_header_block_size;
+        self
+    }
+
+    /// Sets the maximum number of fields (including pseudo-headers) in a
+    /// header block.
+    pub fn with_max_header_count(mut self, max_header_count: usize) -> Self {
+        self.max_header_count = max_header_count;
+        self
+    }
+
+    /// Sets the maximum number of entries (including reserved and
+    /// duplicate ones) in a SETTINGS frame.
+    pub fn with_max_settings_entries(mut self, max_settings_entries: usize) -> Self {
+        self.max_settings_entries = max_settings_entries;
+        self
+    }
+
+    /// Sets the maximum payload size (in bytes) of a capsule buffered from
+    /// a session stream.
+    pub fn with_max_capsule_size(mut self, max_capsule_size: usize) -> Self {
+        self.max_capsule_size = max_capsule_size;
+        self
+    }
+
+    /// Returns the maximum size of an encoded header block.
+    #[inline(always)]
+    pub fn max_header_block_size(&self) -> usize {
+        self.max_header_block_size
+    }
+
+    /// Returns the maximum number of fields in a header block.
+    #[inline(always)]
+    pub fn max_header_count(&self) -> usize {
+        self.max_header_count
+    }
+
+    /// Returns the maximum number of entries in a SETTINGS frame.
+    #[inline(always)]
+    pub fn max_settings_entries(&self) -> usize {
+        self.max_settings_entries
+    }
+
+    /// Returns the maximum payload size of a capsule.
+    #[inline(always)]
+    pub fn max_capsule_size(&self) -> usize {
+        self.max_capsule_size
+    }
+
+    /// Returns the maximum payload size of a SETTINGS frame within the limits.
+    pub(crate) fn max_settings_size(&self) -> usize {
+        self.max_settings_entries
+            .saturating_mul(2 * varint::VarInt::MAX_SIZE)
+    }
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_header_block_size: Self::DEFAULT_MAX_HEADER_BLOCK_SIZE,
+            max_header_count: Self::DEFAULT_MAX_HEADER_COUNT,
+            max_settings_entries: Self::DEFAULT_MAX_SETTINGS_ENTRIES,
+            max_capsule_size: Self::DEFAULT_MAX_CAPSULE_SIZE,
+        }
+    }
+}
@@ -7,13 +7,14 @@ use crate::error::ErrorCode;
 use crate::frame::Frame;
 use crate::frame::FrameKind;
 use crate::varint::VarInt;
+use crate::ParseLimits;
+use crate::ParseMode;
 use std::borrow::Cow;
 use std::collections::hash_map;
 use std::collections::HashMap;
 
 enum ParseError {
     ReservedSetting,
-    UnknownSetting,
 }
 
 /// Settings IDs for an HTTP3 connection.
@@ -28,14 +29,23 @@ pub enum SettingId {
     /// SETTINGS_QPACK_BLOCKED_STREAMS.
     QPackBlockedStreams,
 
-    /// SETTINGS_H3_DATAGRAM.
+    /// SETTINGS_H3_DATAGRAM, with the codepoint of the drafts (`0xffd277`).
     H3Datagram,
 
-    /// SETTINGS_ENABLE_WEBTRANSPORT.
+    /// SETTINGS_H3_DATAGRAM, with the codepoint of RFC 9297 (`0x33`).
+    H3DatagramRfc,
+
+    /// SETTINGS_ENABLE_WEBTRANSPORT (WebTransport draft-02).
     EnableWebTransport,
 
+    /// SETTINGS_WEBTRANSPORT_MAX_SESSIONS (WebTransport draft-07 and later).
+    WebTransportMaxSessions,
+
     /// Exercise setting.
     Exercise(VarInt),
+
+    /// Setting unknown to this implementation.
+    Unknown(VarInt),
 }
 
 impl SettingId {
@@ -52,30 +62,48 @@ impl SettingId {
                 setting_ids::SETTINGS_MAX_FIELD_SECTION_SIZE => Ok(Self::MaxFieldSectionSize),
                 setting_ids::SETTINGS_QPACK_BLOCKED_STREAMS => Ok(Self::QPackBlockedStreams),
                 setting_ids::SETTINGS_H3_DATAGRAM => Ok(Self::H3Datagram),
+                setting_ids::SETTINGS_H3_DATAGRAM_RFC => Ok(Self::H3DatagramRfc),
                 setting_ids::SETTINGS_ENABLE_WEBTRANSPORT => Ok(Self::EnableWebTransport),
-                _ => Err(ParseError::UnknownSetting),
+                setting_ids::SETTINGS_WEBTRANSPORT_MAX_SESSIONS => {
+                    Ok(Self::WebTransportMaxSessions)
+                }
+                id => Ok(Self::Unknown(id)),
             }
         }
     }
 
-    const fn id(self) -> VarInt {
+    /// Returns the identifier of the setting.
+    pub const fn id(self) -> VarInt {
         match self {
             Self::QPackMaxTableCapacity => setting_ids::SETTINGS_QPACK_MAX_TABLE_CAPACITY,
             Self::MaxFieldSectionSize => setting_ids::SETTINGS_MAX_FIELD_SECTION_SIZE,
             Self::QPackBlockedStreams => setting_ids::SETTINGS_QPACK_BLOCKED_STREAMS,
             Self::H3Datagram => setting_ids::SETTINGS_H3_DATAGRAM,
+            Self::H3DatagramRfc => setting_ids::SETTINGS_H3_DATAGRAM_RFC,
             Self::EnableWebTransport => setting_ids::SETTINGS_ENABLE_WEBTRANSPORT,
+            Self::WebTransportMaxSessions => setting_ids::SETTINGS_WEBTRANSPORT_MAX_SESSIONS,
             Self::Exercise(id) => id,
+            Self::Unknown(id) => id,
         }
     }
 
+    /// Whether the setting value can only be `0` or `1`.
+    #[inline(always)]
+    const fn is_boolean(self) -> bool {
+        matches!(
+            self,
+            Self::H3Datagram | Self::H3DatagramRfc | Self::EnableWebTransport
+        )
+    }
+
     #[inline(always)]
     const fn is_reserved(id: VarInt) -> bool {
         matches!(id.into_inner(), 0x0 | 0x2 | 0x3 | 0x4 | 0x5)
     }
 
+    /// Checks whether an `id` is valid for a [`SettingId::Exercise`] (i.e., a *grease* setting).
     #[inline(always)]
-    const fn is_exercise(id: VarInt) -> bool {
+    pub const fn is_exercise(id: VarInt) -> bool {
         id.into_inner() >= 0x21 && ((id.into_inner() - 0x21) % 0x1f == 0)
     }
 }
@@ -94,34 +122,87 @@ impl Settings {
     ///
     /// Returns an [`Err`] in case of invalid setting or incomplete payload.
     ///
-    /// Unknown settings-ids are ignored.
+    /// Unknown settings-ids are preserved as [`SettingId::Unknown`].
     ///
     /// # Panics
     ///
     /// Panics if `frame` is not type [`FrameKind::Settings`].
     pub fn with_frame(frame: &Frame) -> Result<Self, ErrorCode> {
+        Self::with_frame_mode(frame, ParseMode::Strict)
+    }
+
+    /// Constructs [`Settings`] from a HTTP3 [`Frame`], with the given parsing `mode`.
+    ///
+    /// In [`ParseMode::Strict`], reserved and duplicate settings are errors, as well as
+    /// values other than `0` or `1` for boolean settings (e.g., [`SettingId::H3Datagram`]).
+    /// In [`ParseMode::Lenient`], reserved settings are ignored and the last occurrence
+    /// of a duplicate setting is taken.
+    ///
+    /// This is equivalent to [`Self::with_frame_limits`] with the default [`ParseLimits`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::Settings`].
+    pub fn with_frame_mode(frame: &Frame, mode: ParseMode) -> Result<Self, ErrorCode> {
+        Self::with_frame_limits(frame, mode, &ParseLimits::default())
+    }
+
+    /// Constructs [`Settings`] from a HTTP3 [`Frame`], with the given parsing `mode`
+    /// and `limits`.
+    ///
+    /// More than [`ParseLimits::max_settings_entries`] entries (including the
+    /// ignored ones) are an [`ErrorCode::ExcessiveLoad`] error.
+    ///
+    /// See [`Self::with_frame_mode`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `frame` is not type [`FrameKind::Settings`].
+    pub fn with_frame_limits(
+        frame: &Frame,
+        mode: ParseMode,
+        limits: &ParseLimits,
+    ) -> Result<Self, ErrorCode> {
         assert!(matches!(frame.kind(), FrameKind::Settings));
 
         let mut settings = Settings::new();
         let mut buffer_reader = BufferReader::new(frame.payload());
+        let mut entries = 0;
 
         while buffer_reader.capacity() > 0 {
+            entries += 1;
+            if entries > limits.max_settings_entries() {
+                return Err(ErrorCode::ExcessiveLoad);
+            }
+
             let id = buffer_reader.get_varint().ok_or(ErrorCode::Frame)?;
             let value = buffer_reader.get_varint().ok_or(ErrorCode::Frame)?;
 
-            // TODO(bfesta): do we need to validate value?
+            let setting_id = match (SettingId::parse(id), mode) {
+                (Ok(setting_id), _) => setting_id,
+                (Err(ParseError::ReservedSetting), ParseMode::Strict) => {
+                    return Err(ErrorCode::Settings)
+                }
+                (Err(ParseError::ReservedSetting), ParseMode::Lenient) => continue,
+            };
+
+            if matches!(mode, ParseMode::Strict)
+                && setting_id.is_boolean()
+                && value.into_inner() > 1
+            {
+                return Err(ErrorCode::Settings);
+            }
 
-            match SettingId::parse(id) {
-                Ok(setting_id) => match settings.0.entry(setting_id) {
-                    hash_map::Entry::Vacant(slot) => {
+            match settings.0.entry(setting_id) {
+                hash_map::Entry::Vacant(slot) => {
+                    slot.insert(value);
+                }
+                hash_map::Entry::Occupied(mut slot) => match mode {
+                    ParseMode::Strict => return Err(ErrorCode::Settings),
+                    ParseMode::Lenient => {
                         slot.insert(value);
                     }
-                    hash_map::Entry::Occupied(_) => {
-                        return Err(ErrorCode::Settings);
-                    }
                 },
-                Err(ParseError::UnknownSetting) => {}
-                Err(ParseError::ReservedSetting) => return Err(ErrorCode::Settings),
             }
         }
 
@@ -169,6 +250,55 @@ impl Settings {
         self.0.get(&id).copied()
     }
 
+    /// Returns an iterator over all the settings, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (SettingId, VarInt)> + '_ {
+        self.0.iter().map(|(id, value)| (*id, *value))
+    }
+
+    /// Returns the number of settings.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no settings.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the [`Dialect`] spoken by the peer which sent these settings.
+    ///
+    /// Every known variant of the WebTransport and HTTP3 datagram settings is
+    /// accepted. When a peer advertises several of them, the most recent one
+    /// is taken (i.e., the session limit of [`WebTransportDraft::Draft07`] is
+    /// honored even if [`SettingId::EnableWebTransport`] is also present).
+    ///
+    /// Returns [`None`] if WebTransport or HTTP3 datagrams are not supported.
+    pub fn dialect(&self) -> Option<Dialect> {
+        let is_enabled = |id| self.get(id).map(VarInt::into_inner) == Some(1);
+
+        let webtransport = match self.get(SettingId::WebTransportMaxSessions) {
+            Some(max_sessions) if max_sessions.into_inner() > 0 => {
+                WebTransportDraft::Draft07 { max_sessions }
+            }
+            Some(_) => return None,
+            None if is_enabled(SettingId::EnableWebTransport) => WebTransportDraft::Draft02,
+            None => return None,
+        };
+
+        let datagrams = if is_enabled(SettingId::H3DatagramRfc) {
+            DatagramDraft::Rfc9297
+        } else if is_enabled(SettingId::H3Datagram) {
+            DatagramDraft::Draft04
+        } else {
+            return None;
+        };
+
+        Some(Dialect {
+            webtransport,
+            datagrams,
+        })
+    }
+
     fn new() -> Self {
         Self(HashMap::new())
     }
@@ -204,6 +334,70 @@ impl SettingsBuilder {
         self
     }
 
+    /// Advertises *WebTransport* and HTTP3 datagrams support with every known
+    /// variant of their settings, so that peers speaking any [`Dialect`]
+    /// recognize it.
+    ///
+    /// `max_sessions` is the number of concurrent sessions the peer may
+    /// establish (it must be greater than `0`), for peers of
+    /// [`WebTransportDraft::Draft07`].
+    pub fn enable_webtransport_compat(mut self, max_sessions: VarInt) -> Self {
+        debug_assert!(max_sessions.into_inner() > 0);
+
+        self = self.enable_webtransport().enable_h3_datagrams();
+        self.0
+             .0
+            .insert(SettingId::H3DatagramRfc, VarInt::from_u32(1));
+        self.0
+             .0
+            .insert(SettingId::WebTransportMaxSessions, max_sessions);
+        self
+    }
+
+    /// Sets the maximum size of a field section (e.g., headers) the peer can send.
+    pub fn max_field_section_size(mut self, value: VarInt) -> Self {
+        self.0 .0.insert(SettingId::MaxFieldSectionSize, value);
+        self
+    }
+
+    /// Adds a *grease* setting, which the peer must ignore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not a valid exercise (see [`SettingId::is_exercise`]).
+    pub fn grease(mut self, id: VarInt, value: VarInt) -> Self {
+        assert!(SettingId::is_exercise(id));
+        self.0 .0.insert(SettingId::Exercise(id), value);
+        self
+    }
+
+    /// Sets (or overrides) any setting.
+    ///
+    /// # Panics
+    ///
+    /// * Panics if `id` is [`SettingId::Exercise`] and its identifier
+    ///   is not a valid exercise (see [`SettingId::is_exercise`]).
+    /// * Panics if `id` is [`SettingId::Unknown`] and its identifier is
+    ///   reserved (HTTP/2 settings) or it belongs to another variant.
+    pub fn set(mut self, id: SettingId, value: VarInt) -> Self {
+        match id {
+            SettingId::Exercise(id) => assert!(SettingId::is_exercise(id)),
+            SettingId::Unknown(id) => {
+                assert!(matches!(SettingId::parse(id), Ok(SettingId::Unknown(_))))
+            }
+            _ => {}
+        }
+
+        self.0 .0.insert(id, value);
+        self
+    }
+
+    /// Sets (or overrides) all the settings of `settings`.
+    pub fn extend(mut self, settings: &Settings) -> Self {
+        self.0 .0.extend(settings.iter());
+        self
+    }
+
     /// Builds [`Settings`].
     pub fn build(self) -> Settings {
         self.0
@@ -217,5 +411,208 @@ mod setting_ids {
     pub const SETTINGS_MAX_FIELD_SECTION_SIZE: VarInt = VarInt::from_u32(0x06);
     pub const SETTINGS_QPACK_BLOCKED_STREAMS: VarInt = VarInt::from_u32(0x07);
     pub const SETTINGS_H3_DATAGRAM: VarInt = VarInt::from_u32(0xffd277);
+    pub const SETTINGS_H3_DATAGRAM_RFC: VarInt = VarInt::from_u32(0x33);
     pub const SETTINGS_ENABLE_WEBTRANSPORT: VarInt = VarInt::from_u32(0x2b603742);
+    pub const SETTINGS_WEBTRANSPORT_MAX_SESSIONS: VarInt = VarInt::from_u32(0xc671706a);
+}
+
+/// The variant of the WebTransport protocol spoken by a peer, derived from
+/// its SETTINGS (see [`Settings::dialect`]).
+///
+/// Browsers advertise WebTransport and HTTP3 datagrams with the settings of
+/// different drafts, depending on their release.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dialect {
+    webtransport: WebTransportDraft,
+    datagrams: DatagramDraft,
+}
+
+impl Dialect {
+    /// Returns how WebTransport support is advertised.
+    #[inline(always)]
+    pub fn webtransport(&self) -> WebTransportDraft {
+        self.webtransport
+    }
+
+    /// Returns how HTTP3 datagrams support is advertised.
+    #[inline(always)]
+    pub fn datagrams(&self) -> DatagramDraft {
+        self.datagrams
+    }
+
+    /// Returns the number of concurrent sessions the peer accepts, if it
+    /// advertises a limit.
+    #[inline(always)]
+    pub fn max_sessions(&self) -> Option<VarInt> {
+        match self.webtransport {
+            WebTransportDraft::Draft02 => None,
+            WebTransportDraft::Draft07 { max_sessions } => Some(max_sessions),
+        }
+    }
+}
+
+/// How a peer advertises WebTransport support.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WebTransportDraft {
+    /// With [`SettingId::EnableWebTransport`] (draft-02).
+    Draft02,
+
+    /// With [`SettingId::WebTransportMaxSessions`] (draft-07 and later).
+    Draft07 {
+        /// The number of concurrent sessions the peer accepts.
+        max_sessions: VarInt,
+    },
+}
+
+/// How a peer advertises HTTP3 datagrams support.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatagramDraft {
+    /// With [`SettingId::H3Datagram`] (draft-04 and later drafts).
+    Draft04,
+
+    /// With [`SettingId::H3DatagramRfc`] (RFC 9297).
+    Rfc9297,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_entries() {
+        let frame = Settings::builder()
+            .enable_webtransport()
+            .enable_h3_datagrams()
+            .grease(VarInt::from_u32(0x21), VarInt::from_u32(1))
+            .build()
+            .generate_frame();
+
+        let limits = ParseLimits::default().with_max_settings_entries(3);
+        assert!(Settings::with_frame_limits(&frame, ParseMode::Lenient, &limits).is_ok());
+
+        let limits = ParseLimits::default().with_max_settings_entries(2);
+        assert!(matches!(
+            Settings::with_frame_limits(&frame, ParseMode::Lenient, &limits),
+            Err(ErrorCode::ExcessiveLoad)
+        ));
+    }
+
+    #[test]
+    fn unknown_preserved() {
+        let settings = Settings::builder()
+            .enable_webtransport()
+            .set(
+                SettingId::Unknown(VarInt::from_u32(0x4242)),
+                VarInt::from_u32(7),
+            )
+            .grease(VarInt::from_u32(0x21), VarInt::from_u32(1))
+            .build();
+
+        for mode in [ParseMode::Strict, ParseMode::Lenient] {
+            let parsed = Settings::with_frame_mode(&settings.generate_frame(), mode).unwrap();
+
+            assert_eq!(parsed.len(), 3);
+            assert_eq!(
+                parsed.get(SettingId::Unknown(VarInt::from_u32(0x4242))),
+                Some(VarInt::from_u32(7))
+            );
+            assert_eq!(
+                parsed.get(SettingId::Exercise(VarInt::from_u32(0x21))),
+                Some(VarInt::from_u32(1))
+            );
+            assert_eq!(
+                parsed.get(SettingId::EnableWebTransport),
+                Some(VarInt::from_u32(1))
+            );
+        }
+    }
+
+    #[test]
+    fn extend_overrides() {
+        let overrides = Settings::builder()
+            .qpack_blocked_streams(VarInt::from_u32(8))
+            .build();
+
+        let settings = Settings::builder()
+            .qpack_blocked_streams(VarInt::from_u32(0))
+            .enable_h3_datagrams()
+            .extend(&overrides)
+            .build();
+
+        assert_eq!(
+            settings.get(SettingId::QPackBlockedStreams),
+            Some(VarInt::from_u32(8))
+        );
+        assert_eq!(
+            settings.get(SettingId::H3Datagram),
+            Some(VarInt::from_u32(1))
+        );
+    }
+
+    #[test]
+    fn dialects() {
+        let parse = |settings: Settings| {
+            Settings::with_frame(&settings.generate_frame())
+                .unwrap()
+                .dialect()
+        };
+
+        let draft02 = Settings::builder()
+            .enable_webtransport()
+            .enable_h3_datagrams()
+            .build();
+        assert_eq!(
+            parse(draft02),
+            Some(Dialect {
+                webtransport: WebTransportDraft::Draft02,
+                datagrams: DatagramDraft::Draft04,
+            })
+        );
+
+        let draft07 = Settings::builder()
+            .set(SettingId::WebTransportMaxSessions, VarInt::from_u32(4))
+            .set(SettingId::H3DatagramRfc, VarInt::from_u32(1))
+            .build();
+        let dialect = parse(draft07).unwrap();
+        assert_eq!(dialect.datagrams(), DatagramDraft::Rfc9297);
+        assert_eq!(dialect.max_sessions(), Some(VarInt::from_u32(4)));
+
+        let compat = Settings::builder()
+            .enable_webtransport_compat(VarInt::from_u32(1))
+            .build();
+        let dialect = parse(compat).unwrap();
+        assert_eq!(dialect.datagrams(), DatagramDraft::Rfc9297);
+        assert_eq!(dialect.max_sessions(), Some(VarInt::from_u32(1)));
+
+        let no_sessions = Settings::builder()
+            .enable_webtransport()
+            .enable_h3_datagrams()
+            .set(SettingId::WebTransportMaxSessions, VarInt::from_u32(0))
+            .build();
+        assert_eq!(parse(no_sessions), None);
+
+        let no_datagrams = Settings::builder().enable_webtransport().build();
+        assert_eq!(parse(no_datagrams), None);
+
+        let no_webtransport = Settings::builder().enable_h3_datagrams().build();
+        assert_eq!(parse(no_webtransport), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_unknown_reserved() {
+        Settings::builder().set(
+            SettingId::Unknown(VarInt::from_u32(0x02)),
+            VarInt::from_u32(0),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_unknown_known() {
+        Settings::builder().set(
+            SettingId::Unknown(VarInt::from_u32(0x07)),
+            VarInt::from_u32(0),
+        );
+    }
 }
@@ -6,6 +6,7 @@ use crate::bytes::EndOfBuffer;
 use crate::ids::InvalidSessionId;
 use crate::ids::SessionId;
 use crate::varint::VarInt;
+use std::fmt;
 
 #[cfg(feature = "async")]
 use crate::bytes::AsyncRead;
@@ -38,6 +39,37 @@ pub enum StreamHeaderReadAsyncError {
     IO(IoError),
 }
 
+impl fmt::Display for StreamHeaderReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamHeaderReadError::UnknownStream => write!(f, "unknown stream type"),
+            StreamHeaderReadError::InvalidSessionId => write!(f, "invalid session ID"),
+        }
+    }
+}
+
+impl std::error::Error for StreamHeaderReadError {}
+
+#[cfg(feature = "async")]
+impl fmt::Display for StreamHeaderReadAsyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamHeaderReadAsyncError::StreamHeader(_) => write!(f, "cannot parse stream header"),
+            StreamHeaderReadAsyncError::IO(_) => write!(f, "cannot read stream header"),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl std::error::Error for StreamHeaderReadAsyncError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamHeaderReadAsyncError::StreamHeader(error) => Some(error),
+            StreamHeaderReadAsyncError::IO(error) => Some(error),
+        }
+    }
+}
+
 #[cfg(feature = "async")]
 impl From<IoError> for StreamHeaderReadAsyncError {
     fn from(io_error: IoError) -> Self {
@@ -111,12 +143,35 @@ impl StreamHeader {
         Self::new(StreamKind::Control, None)
     }
 
+    /// Creates a new stream header of type [`StreamKind::QPackEncoder`].
+    #[inline(always)]
+    pub fn new_qpack_encoder() -> Self {
+        Self::new(StreamKind::QPackEncoder, None)
+    }
+
+    /// Creates a new stream header of type [`StreamKind::QPackDecoder`].
+    #[inline(always)]
+    pub fn new_qpack_decoder() -> Self {
+        Self::new(StreamKind::QPackDecoder, None)
+    }
+
     /// Creates a new stream header of type [`StreamKind::WebTransport`].
     #[inline(always)]
     pub fn new_webtransport(session_id: SessionId) -> Self {
         Self::new(StreamKind::WebTransport, Some(session_id))
     }
 
+    /// Creates a new stream header of type [`StreamKind::Exercise`] (i.e., a *grease* stream).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is not a valid exercise (see [`StreamKind::is_id_exercise`]).
+    #[inline(always)]
+    pub fn new_exercise(id: VarInt) -> Self {
+        assert!(StreamKind::is_id_exercise(id));
+        Self::new(StreamKind::Exercise(id), None)
+    }
+
     /// Reads a [`StreamHeader`] from a [`BytesReader`].
     ///
     /// It returns [`None`] if the `bytes_reader` does not contain enough bytes
@@ -322,6 +377,25 @@ mod tests {
         assert!(stream_header.session_id().is_none());
     }
 
+    #[test]
+    fn qpack() {
+        let stream_header = StreamHeader::new_qpack_encoder();
+        assert!(matches!(stream_header.kind(), StreamKind::QPackEncoder));
+        assert!(stream_header.session_id().is_none());
+
+        let stream_header = utils::assert_serde(stream_header);
+        assert!(matches!(stream_header.kind(), StreamKind::QPackEncoder));
+        assert!(stream_header.session_id().is_none());
+
+        let stream_header = StreamHeader::new_qpack_decoder();
+        assert!(matches!(stream_header.kind(), StreamKind::QPackDecoder));
+        assert!(stream_header.session_id().is_none());
+
+        let stream_header = utils::assert_serde(stream_header);
+        assert!(matches!(stream_header.kind(), StreamKind::QPackDecoder));
+        assert!(stream_header.session_id().is_none());
+    }
+
     #[test]
     fn webtransport() {
         let session_id = SessionId::try_from_varint(VarInt::from_u32(0)).unwrap();
@@ -349,6 +423,19 @@ mod tests {
         assert!(matches!(stream_header.session_id(), Some(x) if x == session_id));
     }
 
+    #[test]
+    fn exercise() {
+        let id = VarInt::from_u32(0x1f * 7 + 0x21);
+
+        let stream_header = StreamHeader::new_exercise(id);
+        assert!(matches!(stream_header.kind(), StreamKind::Exercise(x) if x == id));
+        assert!(stream_header.session_id().is_none());
+
+        let stream_header = utils::assert_serde(stream_header);
+        assert!(matches!(stream_header.kind(), StreamKind::Exercise(x) if x == id));
+        assert!(stream_header.session_id().is_none());
+    }
+
     #[test]
     fn read_eof() {
         let mut buffer = Vec::new();
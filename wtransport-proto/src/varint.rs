@@ -1,13 +1,60 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
 
 /// Error returned when constructing a [`VarInt`] from a value >= 2^62
 #[derive(Debug)]
 pub struct VarIntBoundsExceeded;
 
+impl fmt::Display for VarIntBoundsExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value exceeds the varint bounds (2^62 - 1)")
+    }
+}
+
+impl std::error::Error for VarIntBoundsExceeded {}
+
+/// Error returned when parsing a [`VarInt`] from a string.
+#[derive(Debug)]
+pub enum ParseVarIntError {
+    /// The string is not a valid non-negative integer.
+    Invalid(ParseIntError),
+
+    /// The integer is too large.
+    BoundsExceeded(VarIntBoundsExceeded),
+}
+
+impl fmt::Display for ParseVarIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseVarIntError::Invalid(_) => write!(f, "invalid varint"),
+            ParseVarIntError::BoundsExceeded(_) => write!(f, "varint out of bounds"),
+        }
+    }
+}
+
+impl std::error::Error for ParseVarIntError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseVarIntError::Invalid(error) => Some(error),
+            ParseVarIntError::BoundsExceeded(error) => Some(error),
+        }
+    }
+}
+
 /// QUIC variable-length integer.
 ///
 /// A non-negative integer value, less than 2^62.
+///
+/// With the `serde` feature, it is (de)serialized as a `u64` (deserialization
+/// fails for values out of bounds).
 #[derive(Default, Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "u64", into = "u64")
+)]
 pub struct VarInt(u64);
 
 impl VarInt {
@@ -47,6 +94,39 @@ impl VarInt {
         Self(value)
     }
 
+    /// Checked addition: returns [`None`] if the result exceeds [`Self::MAX`].
+    #[inline(always)]
+    pub const fn checked_add(self, other: Self) -> Option<Self> {
+        match self.0.checked_add(other.0) {
+            Some(value) if value <= Self::MAX.0 => Some(Self(value)),
+            _ => None,
+        }
+    }
+
+    /// Checked subtraction: returns [`None`] if the result is negative.
+    #[inline(always)]
+    pub const fn checked_sub(self, other: Self) -> Option<Self> {
+        match self.0.checked_sub(other.0) {
+            Some(value) => Some(Self(value)),
+            None => None,
+        }
+    }
+
+    /// Saturating addition: the result is capped at [`Self::MAX`].
+    #[inline(always)]
+    pub const fn saturating_add(self, other: Self) -> Self {
+        match self.checked_add(other) {
+            Some(value) => value,
+            None => Self::MAX,
+        }
+    }
+
+    /// Saturating subtraction: the result is capped at [`Self::MIN`].
+    #[inline(always)]
+    pub const fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
     /// Extract the integer value as `u64`.
     #[inline(always)]
     pub const fn into_inner(self) -> u64 {
@@ -71,6 +151,60 @@ impl VarInt {
         }
     }
 
+    /// Encodes the value as a variable-length integer in network byte-order.
+    ///
+    /// Returns the buffer and the number of bytes used (the encoding is
+    /// `buffer[..len]`). It can be used in const contexts.
+    ///
+    /// # Example
+    /// ```
+    /// use wtransport_proto::varint::VarInt;
+    ///
+    /// const ENCODED: ([u8; VarInt::MAX_SIZE], usize) = VarInt::from_u32(16383).encode_to_array();
+    ///
+    /// assert_eq!(&ENCODED.0[..ENCODED.1], &[0x7f, 0xff]);
+    /// ```
+    pub const fn encode_to_array(self) -> ([u8; Self::MAX_SIZE], usize) {
+        let size = self.size();
+        let value = self.0.to_be_bytes();
+        let mut buffer = [0; Self::MAX_SIZE];
+
+        let mut index = 0;
+        while index < size {
+            buffer[index] = value[Self::MAX_SIZE - size + index];
+            index += 1;
+        }
+
+        // The two most significant bits encode the length
+        buffer[0] |= (size.trailing_zeros() as u8) << 6;
+
+        (buffer, size)
+    }
+
+    /// Decodes a variable-length integer at the beginning of `bytes`.
+    ///
+    /// Returns the value and the number of bytes read, or [`None`] if `bytes`
+    /// is too short.
+    pub const fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        if bytes.is_empty() {
+            return None;
+        }
+
+        let size = Self::parse_size(bytes[0]);
+        if bytes.len() < size {
+            return None;
+        }
+
+        let mut value = (bytes[0] & 0x3f) as u64;
+        let mut index = 1;
+        while index < size {
+            value = (value << 8) | bytes[index] as u64;
+            index += 1;
+        }
+
+        Some((Self(value), size))
+    }
+
     /// Returns how long the variable-length integer is, given its first byte.
     pub const fn parse_size(first: u8) -> usize {
         match first >> 6 {
@@ -83,6 +217,47 @@ impl VarInt {
     }
 }
 
+/// Returns an iterator over the successive varints encoded in `bytes`.
+///
+/// The iteration stops at the end of `bytes` or at the first truncated varint
+/// (see [`DecodeIter::remaining`]).
+///
+/// # Example
+/// ```
+/// use wtransport_proto::varint::decode_iter;
+///
+/// let values = decode_iter(&[0x01, 0x40, 0x42]).map(|v| v.into_inner()).collect::<Vec<_>>();
+///
+/// assert_eq!(values, [1, 0x42]);
+/// ```
+pub fn decode_iter(bytes: &[u8]) -> DecodeIter<'_> {
+    DecodeIter(bytes)
+}
+
+/// Iterator over successive varints (see [`decode_iter`]).
+#[derive(Clone, Debug)]
+pub struct DecodeIter<'a>(&'a [u8]);
+
+impl<'a> DecodeIter<'a> {
+    /// Returns the bytes not decoded yet.
+    ///
+    /// Once the iteration is over, they are not empty if the last varint is truncated.
+    #[inline(always)]
+    pub fn remaining(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<'a> Iterator for DecodeIter<'a> {
+    type Item = VarInt;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (varint, size) = VarInt::decode(self.0)?;
+        self.0 = &self.0[size..];
+        Some(varint)
+    }
+}
+
 impl From<u8> for VarInt {
     #[inline(always)]
     fn from(value: u8) -> Self {
@@ -113,6 +288,15 @@ impl TryFrom<u64> for VarInt {
     }
 }
 
+impl TryFrom<usize> for VarInt {
+    type Error = VarIntBoundsExceeded;
+
+    #[inline(always)]
+    fn try_from(value: usize) -> Result<Self, Self::Error> {
+        Self::try_from_u64(u64::try_from(value).map_err(|_| VarIntBoundsExceeded)?)
+    }
+}
+
 impl From<VarInt> for u64 {
     #[inline]
     fn from(value: VarInt) -> Self {
@@ -120,6 +304,43 @@ impl From<VarInt> for u64 {
     }
 }
 
+impl PartialEq<u64> for VarInt {
+    #[inline(always)]
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<VarInt> for u64 {
+    #[inline(always)]
+    fn eq(&self, other: &VarInt) -> bool {
+        *self == other.0
+    }
+}
+
+impl PartialOrd<u64> for VarInt {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &u64) -> Option<Ordering> {
+        self.0.partial_cmp(other)
+    }
+}
+
+impl PartialOrd<VarInt> for u64 {
+    #[inline(always)]
+    fn partial_cmp(&self, other: &VarInt) -> Option<Ordering> {
+        self.partial_cmp(&other.0)
+    }
+}
+
+impl FromStr for VarInt {
+    type Err = ParseVarIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.parse::<u64>().map_err(ParseVarIntError::Invalid)?;
+        Self::try_from_u64(value).map_err(ParseVarIntError::BoundsExceeded)
+    }
+}
+
 impl fmt::Debug for VarInt {
     #[inline]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -165,6 +386,91 @@ mod tests {
         assert_eq!(VarInt::MAX.size(), VarInt::MAX_SIZE);
     }
 
+    #[test]
+    fn conversions() {
+        assert_eq!(VarInt::try_from(42_usize).unwrap(), 42_u64);
+        assert!(VarInt::try_from(VarInt::MAX.into_inner() + 1).is_err());
+        assert_eq!(u64::from(VarInt::MAX), VarInt::MAX.into_inner());
+
+        assert!(VarInt::from_u32(1) < 2_u64);
+        assert!(2_u64 > VarInt::from_u32(1));
+        assert_eq!(42_u64, VarInt::from_u32(42));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let one = VarInt::from_u32(1);
+
+        assert_eq!(one.checked_add(one), Some(VarInt::from_u32(2)));
+        assert_eq!(VarInt::MAX.checked_add(one), None);
+        assert_eq!(VarInt::MAX.saturating_add(one), VarInt::MAX);
+
+        assert_eq!(one.checked_sub(one), Some(VarInt::MIN));
+        assert_eq!(VarInt::MIN.checked_sub(one), None);
+        assert_eq!(VarInt::MIN.saturating_sub(one), VarInt::MIN);
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!("1337".parse::<VarInt>().unwrap(), 1337_u64);
+        assert_eq!(
+            VarInt::MAX.to_string().parse::<VarInt>().unwrap(),
+            VarInt::MAX
+        );
+
+        assert!(matches!(
+            "4611686018427387904".parse::<VarInt>(),
+            Err(ParseVarIntError::BoundsExceeded(_))
+        ));
+        assert!(matches!(
+            "-1".parse::<VarInt>(),
+            Err(ParseVarIntError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn encode_to_array() {
+        for value in [
+            0,
+            63,
+            64,
+            16383,
+            16384,
+            1073741823,
+            1073741824,
+            VarInt::MAX.0,
+        ] {
+            let varint = VarInt::try_from_u64(value).unwrap();
+            let (buffer, len) = varint.encode_to_array();
+
+            let mut expected = [0; VarInt::MAX_SIZE];
+            let mut writer = crate::bytes::BufferWriter::new(&mut expected);
+            crate::bytes::BytesWriter::put_varint(&mut writer, varint).unwrap();
+
+            assert_eq!(len, varint.size());
+            assert_eq!(&buffer[..len], writer.buffer_written());
+            assert_eq!(VarInt::decode(&buffer[..len]), Some((varint, len)));
+        }
+    }
+
+    #[test]
+    fn decode_iter() {
+        let mut bytes = Vec::new();
+        let values = [VarInt::MIN, VarInt::from_u32(1337), VarInt::MAX];
+
+        for varint in values {
+            let (buffer, len) = varint.encode_to_array();
+            bytes.extend_from_slice(&buffer[..len]);
+        }
+
+        assert!(super::decode_iter(&bytes).eq(values));
+        assert!(super::decode_iter(&[]).next().is_none());
+
+        let mut iter = super::decode_iter(&bytes[..bytes.len() - 1]);
+        assert_eq!(iter.by_ref().count(), 2);
+        assert_eq!(iter.remaining().len(), VarInt::MAX_SIZE - 1);
+    }
+
     #[test]
     fn parse() {
         assert_eq!(VarInt::parse_size(0xc2), 8);
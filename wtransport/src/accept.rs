@@ -0,0 +1,216 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use wtransport_proto::headers::Headers;
+
+/// A hook run on each session request, see [`ServerConfig::add_accept_hook`].
+///
+/// [`ServerConfig::add_accept_hook`]: crate::ServerConfig::add_accept_hook
+pub(crate) type AcceptHook = Arc<
+    dyn Fn(SessionRequest) -> Pin<Box<dyn Future<Output = Result<SessionRequest, Refusal>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// A WebTransport session request, as inspected by accept hooks before the
+/// session is accepted.
+///
+/// Hooks can read the request and add headers to the response, which is
+/// sent once all the hooks have let the request through.
+#[derive(Clone, Debug)]
+pub struct SessionRequest {
+    remote_address: SocketAddr,
+    headers: Headers,
+    response_headers: Vec<(String, String)>,
+}
+
+impl SessionRequest {
+    pub(crate) fn new(remote_address: SocketAddr, headers: Headers) -> Self {
+        Self {
+            remote_address,
+            headers,
+            response_headers: Vec::new(),
+        }
+    }
+
+    /// Returns the address of the client.
+    #[inline(always)]
+    pub fn remote_address(&self) -> SocketAddr {
+        self.remote_address
+    }
+
+    /// Returns the headers of the request.
+    #[inline(always)]
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the `:path` pseudo-header (e.g., `"/game?token=..."`).
+    #[inline(always)]
+    pub fn path(&self) -> Option<&str> {
+        self.headers.get(":path")
+    }
+
+    /// Returns the `:authority` pseudo-header.
+    #[inline(always)]
+    pub fn authority(&self) -> Option<&str> {
+        self.headers.get(":authority")
+    }
+
+    /// Returns the `origin` header.
+    #[inline(always)]
+    pub fn origin(&self) -> Option<&str> {
+        self.headers.get("origin")
+    }
+
+    /// Adds a header to the response.
+    ///
+    /// Pseudo-headers (e.g., `:status`) are ignored.
+    pub fn add_response_header<N, V>(&mut self, name: N, value: V)
+    where
+        N: ToString,
+        V: ToString,
+    {
+        let name = name.to_string();
+
+        if !name.starts_with(':') {
+            self.response_headers.push((name, value.to_string()));
+        }
+    }
+
+    /// Returns the headers added to the response so far.
+    pub fn response_headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.response_headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub(crate) fn into_response_headers(self) -> Vec<(String, String)> {
+        self.response_headers
+    }
+}
+
+/// The refusal of a session request by an accept hook.
+///
+/// The client receives a response with the status and headers of the refusal,
+/// rather than the session being just closed (e.g., a `503` with `retry-after`
+/// during maintenance).
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use wtransport::accept::Refusal;
+/// use wtransport::proto::Headers;
+///
+/// let headers = [("x-maintenance", "db-upgrade")].into_iter().collect::<Headers>();
+///
+/// let refusal = Refusal::service_unavailable()
+///     .with_retry_after(Duration::from_secs(30))
+///     .with_headers(&headers);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Refusal {
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl Refusal {
+    /// Creates a refusal with `status` (e.g., `403`).
+    ///
+    /// A refusal is an error response: values which are not a client (`4xx`)
+    /// or server (`5xx`) error status are replaced with `500`.
+    pub fn new(status: u16) -> Self {
+        let status = if (400..600).contains(&status) {
+            status
+        } else {
+            500
+        };
+
+        Self {
+            status,
+            headers: Vec::new(),
+        }
+    }
+
+    /// A `401 Unauthorized` refusal.
+    pub fn unauthorized() -> Self {
+        Self::new(401)
+    }
+
+    /// A `403 Forbidden` refusal.
+    pub fn forbidden() -> Self {
+        Self::new(403)
+    }
+
+    /// A `429 Too Many Requests` refusal.
+    pub fn too_many_requests() -> Self {
+        Self::new(429)
+    }
+
+    /// A `503 Service Unavailable` refusal (e.g., overload or maintenance).
+    pub fn service_unavailable() -> Self {
+        Self::new(503)
+    }
+
+    /// Creates a refusal with `status` and the response `headers`.
+    ///
+    /// See [`Self::new`] for the allowed `status` values.
+    /// Pseudo-headers (e.g., `:status`) in `headers` are ignored.
+    pub fn with_response(status: u16, headers: &Headers) -> Self {
+        Self::new(status).with_headers(headers)
+    }
+
+    /// Adds a header to the response (e.g., `retry-after`).
+    ///
+    /// Pseudo-headers (e.g., `:status`) are ignored.
+    pub fn with_header<N, V>(mut self, name: N, value: V) -> Self
+    where
+        N: ToString,
+        V: ToString,
+    {
+        let name = name.to_string();
+
+        if !name.starts_with(':') {
+            self.headers.push((name, value.to_string()));
+        }
+
+        self
+    }
+
+    /// Adds all the `headers` to the response.
+    ///
+    /// Pseudo-headers (e.g., `:status`) are ignored.
+    pub fn with_headers(self, headers: &Headers) -> Self {
+        headers.iter().fold(self, |refusal, (name, value)| {
+            refusal.with_header(name, value)
+        })
+    }
+
+    /// Adds the `retry-after` header, asking the client to wait `delay`
+    /// before retrying (rounded up to whole seconds).
+    pub fn with_retry_after(self, delay: Duration) -> Self {
+        let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+        self.with_header("retry-after", seconds)
+    }
+
+    /// Returns the response status.
+    #[inline(always)]
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Returns the headers added to the response (excluding `:status`).
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub(crate) fn response_headers(&self) -> Headers {
+        std::iter::once((":status".to_string(), self.status.to_string()))
+            .chain(self.headers.iter().cloned())
+            .collect()
+    }
+}
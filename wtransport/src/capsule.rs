@@ -0,0 +1,129 @@
+use crate::connection::Connection;
+use crate::error::ConnectionError;
+use std::borrow::Cow;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+use wtransport_proto::capsule::Capsule;
+use wtransport_proto::capsule::CapsuleKind;
+use wtransport_proto::varint::VarInt;
+
+type Encoder<T> = Arc<dyn Fn(&T) -> Vec<u8> + Send + Sync>;
+type Decoder<T> = Arc<dyn Fn(&[u8]) -> Option<T> + Send + Sync>;
+
+/// An application-defined capsule type.
+///
+/// It is made of a *codepoint* (the capsule type identifier) and the functions
+/// for encoding and decoding the capsule payload.
+///
+/// See [`Connection::register_capsule_type`].
+pub struct CapsuleType<T> {
+    codepoint: VarInt,
+    encode: Encoder<T>,
+    decode: Decoder<T>,
+}
+
+impl<T> CapsuleType<T> {
+    /// Creates a new capsule type.
+    ///
+    /// `decode` returns [`None`] if the payload is malformed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `codepoint` is a capsule type defined by WebTransport.
+    pub fn new<E, D>(codepoint: VarInt, encode: E, decode: D) -> Self
+    where
+        E: Fn(&T) -> Vec<u8> + Send + Sync + 'static,
+        D: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        assert!(
+            matches!(CapsuleKind::from_id(codepoint), CapsuleKind::Unknown(_)),
+            "Capsule type {codepoint} is reserved by WebTransport"
+        );
+
+        Self {
+            codepoint,
+            encode: Arc::new(encode),
+            decode: Arc::new(decode),
+        }
+    }
+
+    /// Returns the codepoint of the capsule type.
+    #[inline(always)]
+    pub fn codepoint(&self) -> VarInt {
+        self.codepoint
+    }
+}
+
+impl<T> Clone for CapsuleType<T> {
+    fn clone(&self) -> Self {
+        Self {
+            codepoint: self.codepoint,
+            encode: self.encode.clone(),
+            decode: self.decode.clone(),
+        }
+    }
+}
+
+/// Sends and receives capsules of a [`CapsuleType`] on the session stream.
+///
+/// It can be obtained with [`Connection::register_capsule_type`].
+/// It keeps the session alive.
+pub struct CapsuleChannel<T> {
+    capsule_type: CapsuleType<T>,
+    connection: Connection,
+    queue: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+}
+
+impl<T> CapsuleChannel<T> {
+    pub(crate) fn new(
+        capsule_type: CapsuleType<T>,
+        connection: Connection,
+        queue: mpsc::UnboundedReceiver<Vec<u8>>,
+    ) -> Self {
+        Self {
+            capsule_type,
+            connection,
+            queue: Mutex::new(queue),
+        }
+    }
+
+    /// Sends a capsule carrying `message`.
+    pub async fn send(&self, message: &T) -> Result<(), ConnectionError> {
+        let payload = (self.capsule_type.encode)(message);
+        let capsule = Capsule::new(
+            CapsuleKind::Unknown(self.capsule_type.codepoint),
+            Cow::Owned(payload),
+        );
+
+        self.connection.send_capsule(&capsule).await
+    }
+
+    /// Receives the next capsule.
+    ///
+    /// Capsules which cannot be decoded are discarded.
+    pub async fn receive(&self) -> Result<T, ConnectionError> {
+        let mut queue = self.queue.lock().await;
+
+        loop {
+            let payload = tokio::select! {
+                payload = queue.recv() => payload,
+                error = self.connection.closed() => return Err(error),
+            };
+
+            match payload {
+                Some(payload) => {
+                    if let Some(message) = (self.capsule_type.decode)(&payload) {
+                        return Ok(message);
+                    }
+                }
+                None => return Err(self.connection.closed().await),
+            }
+        }
+    }
+
+    /// Returns the [`CapsuleType`] of this channel.
+    pub fn capsule_type(&self) -> &CapsuleType<T> {
+        &self.capsule_type
+    }
+}
@@ -0,0 +1,304 @@
+use crate::error::MessageError;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use wtransport_proto::varint::VarInt;
+
+#[cfg(feature = "tokio-util")]
+use bytes::Buf;
+#[cfg(feature = "tokio-util")]
+use bytes::BufMut;
+#[cfg(feature = "tokio-util")]
+use bytes::Bytes;
+#[cfg(feature = "tokio-util")]
+use bytes::BytesMut;
+
+/// Default maximum size of a message.
+const DEFAULT_MAX_SIZE: usize = 64 * 1024;
+
+/// Length-prefixed messages over a stream.
+///
+/// Each message is encoded as its length (a QUIC variable-length integer)
+/// followed by its payload.
+///
+/// `T` is the underlying stream: a [`SendStream`](crate::SendStream) for
+/// sending only, a [`RecvStream`](crate::RecvStream) for receiving only, or a
+/// [`StreamCompat`](crate::stream::StreamCompat) for both.
+///
+/// Incoming messages larger than the maximum size (see [`Self::with_max_size`])
+/// are rejected before being buffered.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// use wtransport::codec::MessageStream;
+/// use wtransport::stream::StreamCompat;
+///
+/// # async fn run(connection: Connection) {
+/// let stream = StreamCompat::from(connection.open_bi().await.unwrap());
+/// let mut messages = MessageStream::new(stream).with_max_size(4096);
+///
+/// messages.send(b"ping").await.unwrap();
+/// let reply = messages.recv().await.unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MessageStream<T> {
+    stream: T,
+    max_size: usize,
+}
+
+impl<T> MessageStream<T> {
+    /// Default maximum size of a message.
+    pub const DEFAULT_MAX_SIZE: usize = DEFAULT_MAX_SIZE;
+
+    /// Wraps `stream`, with a maximum message size of [`Self::DEFAULT_MAX_SIZE`].
+    pub fn new(stream: T) -> Self {
+        Self {
+            stream,
+            max_size: Self::DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Sets the maximum size of a message (both sent and received).
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Returns the maximum size of a message.
+    #[inline(always)]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Returns a reference to the underlying stream.
+    #[inline(always)]
+    pub fn get_ref(&self) -> &T {
+        &self.stream
+    }
+
+    /// Returns the underlying stream.
+    pub fn into_inner(self) -> T {
+        self.stream
+    }
+}
+
+impl<T> MessageStream<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    /// Sends a message.
+    ///
+    /// It fails with [`MessageError::TooLarge`] (without writing anything) if
+    /// `message` is larger than the maximum size.
+    pub async fn send(&mut self, message: &[u8]) -> Result<(), MessageError> {
+        if message.len() > self.max_size {
+            return Err(MessageError::TooLarge);
+        }
+
+        let length = VarInt::try_from(message.len()).map_err(|_| MessageError::TooLarge)?;
+        let (prefix, prefix_len) = length.encode_to_array();
+
+        self.stream
+            .write_all(&prefix[..prefix_len])
+            .await
+            .map_err(MessageError::Io)?;
+
+        self.stream
+            .write_all(message)
+            .await
+            .map_err(MessageError::Io)
+    }
+
+    /// Serializes `message` as JSON and sends it.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub async fn send_json<M>(&mut self, message: &M) -> Result<(), MessageError>
+    where
+        M: serde::Serialize,
+    {
+        let message = serde_json::to_vec(message).map_err(MessageError::Json)?;
+        self.send(&message).await
+    }
+}
+
+impl<T> MessageStream<T>
+where
+    T: AsyncRead + Unpin,
+{
+    /// Receives the next message.
+    ///
+    /// Returns [`None`] if the peer finished the stream (between two messages).
+    /// It fails with [`MessageError::TooLarge`] if the announced length exceeds
+    /// the maximum size: the stream should be dropped in that case, as it is no
+    /// longer aligned to a message boundary.
+    pub async fn recv(&mut self) -> Result<Option<Vec<u8>>, MessageError> {
+        let mut prefix = [0; VarInt::MAX_SIZE];
+
+        match self.stream.read(&mut prefix[..1]).await {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(error) => return Err(MessageError::Io(error)),
+        }
+
+        let prefix_len = VarInt::parse_size(prefix[0]);
+        self.read_exact(&mut prefix[1..prefix_len]).await?;
+
+        let (length, _) = VarInt::decode(&prefix[..prefix_len]).expect("Complete varint");
+        let length = usize::try_from(length.into_inner())
+            .ok()
+            .filter(|&length| length <= self.max_size)
+            .ok_or(MessageError::TooLarge)?;
+
+        let mut message = vec![0; length];
+        self.read_exact(&mut message).await?;
+
+        Ok(Some(message))
+    }
+
+    /// Receives the next message and deserializes it from JSON.
+    ///
+    /// Returns [`None`] if the peer finished the stream (between two messages).
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub async fn recv_json<M>(&mut self) -> Result<Option<M>, MessageError>
+    where
+        M: serde::de::DeserializeOwned,
+    {
+        match self.recv().await? {
+            Some(message) => serde_json::from_slice(&message)
+                .map(Some)
+                .map_err(MessageError::Json),
+            None => Ok(None),
+        }
+    }
+
+    async fn read_exact(&mut self, buffer: &mut [u8]) -> Result<(), MessageError> {
+        match self.stream.read_exact(buffer).await {
+            Ok(_) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Err(MessageError::Truncated)
+            }
+            Err(error) => Err(MessageError::Io(error)),
+        }
+    }
+}
+
+/// [`tokio_util::codec`] counterpart of [`MessageStream`], with the same
+/// framing (so the two interoperate).
+///
+/// Both [`StreamCompat`](crate::stream::StreamCompat) and the stream halves
+/// ([`SendStream`](crate::SendStream), [`RecvStream`](crate::RecvStream)) can be
+/// framed with it, like `LengthDelimitedCodec` frames TCP streams.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// use bytes::Bytes;
+/// use futures::SinkExt;
+/// use futures::StreamExt;
+/// use tokio_util::codec::Framed;
+/// use wtransport::codec::MessageCodec;
+/// use wtransport::stream::StreamCompat;
+///
+/// # async fn run(connection: Connection) {
+/// let stream = StreamCompat::from(connection.open_bi().await.unwrap());
+/// let mut framed = Framed::new(stream, MessageCodec::new());
+///
+/// framed.send(Bytes::from_static(b"ping")).await.unwrap();
+/// let reply = framed.next().await.unwrap().unwrap();
+/// # }
+/// ```
+#[cfg(feature = "tokio-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+#[derive(Copy, Clone, Debug)]
+pub struct MessageCodec {
+    max_size: usize,
+}
+
+#[cfg(feature = "tokio-util")]
+impl MessageCodec {
+    /// Default maximum size of a message.
+    pub const DEFAULT_MAX_SIZE: usize = DEFAULT_MAX_SIZE;
+
+    /// Creates a codec, with a maximum message size of [`Self::DEFAULT_MAX_SIZE`].
+    pub fn new() -> Self {
+        Self {
+            max_size: Self::DEFAULT_MAX_SIZE,
+        }
+    }
+
+    /// Sets the maximum size of a message (both encoded and decoded).
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Returns the maximum size of a message.
+    #[inline(always)]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl Default for MessageCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl tokio_util::codec::Decoder for MessageCodec {
+    type Item = BytesMut;
+    type Error = MessageError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let Some((length, prefix_len)) = VarInt::decode(&src[..]) else {
+            return Ok(None);
+        };
+
+        let length = usize::try_from(length.into_inner())
+            .ok()
+            .filter(|&length| length <= self.max_size)
+            .ok_or(MessageError::TooLarge)?;
+
+        if src.len() < prefix_len + length {
+            src.reserve(prefix_len + length - src.len());
+            return Ok(None);
+        }
+
+        src.advance(prefix_len);
+        Ok(Some(src.split_to(length)))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(message) => Ok(Some(message)),
+            None if src.is_empty() => Ok(None),
+            None => Err(MessageError::Truncated),
+        }
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+impl tokio_util::codec::Encoder<Bytes> for MessageCodec {
+    type Error = MessageError;
+
+    fn encode(&mut self, message: Bytes, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if message.len() > self.max_size {
+            return Err(MessageError::TooLarge);
+        }
+
+        let length = VarInt::try_from(message.len()).map_err(|_| MessageError::TooLarge)?;
+        let (prefix, prefix_len) = length.encode_to_array();
+
+        dst.reserve(prefix_len + message.len());
+        dst.put_slice(&prefix[..prefix_len]);
+        dst.put_slice(&message);
+
+        Ok(())
+    }
+}
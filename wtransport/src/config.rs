@@ -1,27 +1,1613 @@
+use crate::accept::AcceptHook;
+use crate::accept::Refusal;
+use crate::accept::SessionRequest;
+use crate::http3::Request;
+use crate::marking::PacketMarking;
+use crate::metrics::Metrics;
+#[cfg(feature = "mock-network")]
+use crate::mock::MockNetwork;
+use crate::proxy::Proxy;
+use crate::proxy::ProxyCredentials;
+use crate::proxy::ProxyUrl;
+use crate::qlog::QlogSink;
+use crate::runtime::DriverSocket;
+use crate::runtime::Runtime;
 use crate::tls::Certificate;
+use crate::tls::ClientCertificate;
+#[cfg(feature = "dangerous-configuration")]
+use crate::tls::ServerHashVerification;
+use crate::tls::Sha256Digest;
 use quinn::ClientConfig as QuicClientConfig;
+use quinn::IdleTimeout;
+use quinn::MtuDiscoveryConfig;
 use quinn::ServerConfig as QuicServerConfig;
+use quinn::TransportConfig;
+use quinn::VarInt;
+use rustls::server::AllowAnyAuthenticatedClient;
+#[cfg(feature = "dangerous-configuration")]
+use rustls::server::ClientCertVerifier;
+use rustls::sign::CertifiedKey;
 use rustls::ClientConfig as TlsClientConfig;
 use rustls::RootCertStore;
 use rustls::ServerConfig as TlsServerConfig;
+use rustls::WantsServerCert;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::net::UdpSocket;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use tokio::runtime::Handle;
+use wtransport_proto::headers::Headers;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::ParseLimits;
 use wtransport_proto::WEBTRANSPORT_ALPN;
 
 /// Server configuration.
 ///
 /// Configuration can be created via [`ServerConfig::builder`] function.
 pub struct ServerConfig {
-    pub(crate) quic_config: QuicServerConfig,
-    pub(crate) bind_address: SocketAddr,
+    bind: Bind,
+    additional_binds: Vec<Bind>,
+    tls_config: Arc<TlsServerConfig>,
+    token_key: TokenKey,
+    pub(crate) address_validation: AddressValidation,
+    token_lifetime: Duration,
+    max_connections: u32,
+    pub(crate) connection_options: ConnectionOptions,
+    pub(crate) qlog: Option<Arc<dyn QlogSink>>,
+    pub(crate) metrics: Option<Arc<dyn Metrics>>,
+    pub(crate) handlers: ServerHandlers,
+    pub(crate) driver_runtime: DriverRuntime,
+    pub(crate) packet_marking: PacketMarking,
+    pub(crate) extra_settings: Arc<Settings>,
+    #[cfg(feature = "mock-network")]
+    mock_network: Option<MockNetwork>,
+}
+
+impl ServerConfig {
+    /// Default validity window of address validation tokens.
+    pub const DEFAULT_TOKEN_LIFETIME: Duration = Duration::from_secs(15);
+
+    /// Default maximum number of concurrent connections.
+    pub const DEFAULT_MAX_CONNECTIONS: u32 = 100_000;
+
+    /// Creates a builder to build up the server configuration.
+    ///
+    /// For more information, see the [`ServerConfigBuilder`] documentation.
+    pub fn builder() -> ServerConfigBuilder<WantsBindAddress> {
+        ServerConfigBuilder::default()
+    }
+
+    /// Sets the key used to issue and validate address validation tokens.
+    ///
+    /// By default, a random key is generated for each server. Configuring the same
+    /// key on all servers of a fleet makes tokens issued by one server accepted by
+    /// the others.
+    pub fn set_token_key(&mut self, token_key: TokenKey) -> &mut Self {
+        self.token_key = token_key;
+        self
+    }
+
+    /// Sets how long an address validation token remains valid after being issued.
+    ///
+    /// Default: [`ServerConfig::DEFAULT_TOKEN_LIFETIME`].
+    pub fn set_token_lifetime(&mut self, lifetime: Duration) -> &mut Self {
+        self.token_lifetime = lifetime;
+        self
+    }
+
+    /// Whether clients have to prove ownership of their address (via a *Retry* token)
+    /// before the server commits resources to the connection.
+    ///
+    /// Validating addresses costs clients a round-trip, but it protects the
+    /// server from spoofed handshakes (and from being used for amplification attacks).
+    ///
+    /// Default: [`AddressValidation::Disabled`].
+    pub fn set_address_validation(&mut self, address_validation: AddressValidation) -> &mut Self {
+        self.address_validation = address_validation;
+        self
+    }
+
+    /// Sets the maximum number of concurrent connections (handshakes in progress included).
+    ///
+    /// Further connection attempts are refused at the QUIC level: clients fail
+    /// with [`ConnectionError::ConnectionRefused`](crate::error::ConnectionError::ConnectionRefused).
+    ///
+    /// Default: [`ServerConfig::DEFAULT_MAX_CONNECTIONS`].
+    pub fn set_max_connections(&mut self, max_connections: u32) -> &mut Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Sets the maximum number of concurrent WebTransport sessions on a
+    /// single connection.
+    ///
+    /// Further session requests are rejected with a `429` response, and
+    /// [`Connection::accept_session`](crate::Connection::accept_session) fails with
+    /// [`RejectionReason::TooManySessions`](crate::error::RejectionReason::TooManySessions).
+    ///
+    /// Default: `None` (no limit).
+    pub fn set_max_sessions(&mut self, max_sessions: Option<usize>) -> &mut Self {
+        self.connection_options.max_sessions = max_sessions;
+        self
+    }
+
+    /// Sets the maximum amount of incoming datagram payload (in bytes) a
+    /// session can buffer while waiting for the application to receive it.
+    ///
+    /// Datagrams exceeding the limit are discarded (and accounted in
+    /// [`ConnectionStats::dropped_datagrams`](crate::connection::ConnectionStats::dropped_datagrams)).
+    /// Stream data is bounded by flow control instead (see
+    /// [`ServerConfig::set_max_unread_stream_data`]).
+    ///
+    /// Default: `None` (only the number of buffered datagrams is limited).
+    pub fn set_max_session_buffer(&mut self, max_bytes: Option<usize>) -> &mut Self {
+        self.connection_options.max_session_buffer = max_bytes;
+        self
+    }
+
+    /// Whether receive windows should grow automatically, based on the observed
+    /// round-trip time and throughput of each connection.
+    ///
+    /// Default: `true`.
+    ///
+    /// See [`ReceiveWindowConfig`] for more details.
+    pub fn set_receive_window_autotuning(&mut self, enabled: bool) -> &mut Self {
+        self.connection_options.receive_window.autotuning = enabled;
+        self
+    }
+
+    /// Sets the maximum size (in bytes) receive windows can grow up to
+    /// when auto-tuning is enabled.
+    ///
+    /// Default: [`ReceiveWindowConfig::DEFAULT_MAX_WINDOW`].
+    pub fn set_max_receive_window(&mut self, max_window: u32) -> &mut Self {
+        self.connection_options.receive_window.max_window = max_window;
+        self
+    }
+
+    /// Sets the maximum duration of inactivity before the connection is timed out.
+    ///
+    /// The effective timeout is the minimum between this value and the one
+    /// advertised by the peer. [`None`] disables the local timeout.
+    ///
+    /// Default: [`TransportOptions::DEFAULT_MAX_IDLE_TIMEOUT`].
+    pub fn set_max_idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.connection_options.transport.max_idle_timeout = timeout;
+        self
+    }
+
+    /// Sets the period of inactivity after which a keep-alive packet is sent.
+    ///
+    /// It should be lower than the idle timeout, in order to keep idle
+    /// connections open.
+    ///
+    /// Default: [`None`] (keep-alive disabled).
+    pub fn set_keep_alive_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.connection_options.transport.keep_alive_interval = interval;
+        self
+    }
+
+    /// Enables keep-alive packets whose interval adapts to the NAT timeouts
+    /// observed on the path.
+    ///
+    /// It works along with [`Self::set_keep_alive_interval`]: see
+    /// [`AdaptiveKeepAlive`] for more details.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_adaptive_keep_alive(
+        &mut self,
+        adaptive_keep_alive: Option<AdaptiveKeepAlive>,
+    ) -> &mut Self {
+        self.connection_options.adaptive_keep_alive = adaptive_keep_alive;
+        self
+    }
+
+    /// Sets a fixed size (in bytes) of the connection receive window.
+    ///
+    /// This disables receive window auto-tuning.
+    pub fn set_receive_window(&mut self, window: u32) -> &mut Self {
+        self.connection_options.transport.receive_window = Some(window);
+        self.connection_options.receive_window.autotuning = false;
+        self
+    }
+
+    /// Sets the size (in bytes) of the receive window of each stream.
+    ///
+    /// Default: [`ReceiveWindowConfig::max_window`] when auto-tuning is enabled,
+    /// the QUIC implementation default otherwise.
+    pub fn set_stream_receive_window(&mut self, window: u32) -> &mut Self {
+        self.connection_options.transport.stream_receive_window = Some(window);
+        self
+    }
+
+    /// Caps how much data (in bytes) is buffered for each receive stream the
+    /// application has not read yet, bounding the memory a slow consumer can pin.
+    ///
+    /// Once the cap is reached, no more flow-control credit is granted to
+    /// the peer on that stream until the application reads from it.
+    /// It prevails over [`Self::set_stream_receive_window`] and auto-tuning
+    /// (the stream receive window is set to the smaller value).
+    ///
+    /// Default: [`None`] (bounded by the stream receive window only).
+    pub fn set_max_unread_stream_data(&mut self, max_unread: Option<u32>) -> &mut Self {
+        self.connection_options.transport.max_unread_stream_data = max_unread;
+        self
+    }
+
+    /// Sets the maximum number of concurrent bi-directional streams the peer can open.
+    ///
+    /// Default: [`TransportOptions::DEFAULT_MAX_CONCURRENT_STREAMS`].
+    pub fn set_max_concurrent_bi_streams(&mut self, max_streams: u32) -> &mut Self {
+        self.connection_options.transport.max_concurrent_bi_streams = max_streams;
+        self
+    }
+
+    /// Sets the maximum number of concurrent uni-directional streams the peer can open.
+    ///
+    /// This does not include the streams HTTP3 itself requires (e.g., the control stream),
+    /// which are always allowed.
+    ///
+    /// Default: [`TransportOptions::DEFAULT_MAX_CONCURRENT_STREAMS`].
+    pub fn set_max_concurrent_uni_streams(&mut self, max_streams: u32) -> &mut Self {
+        self.connection_options.transport.max_concurrent_uni_streams = max_streams;
+        self
+    }
+
+    /// Sets the size (in bytes) of the outgoing datagram buffer.
+    ///
+    /// When the buffer is full, [`Connection::send_datagram`](crate::Connection::send_datagram)
+    /// discards the oldest datagrams, while
+    /// [`Connection::send_datagram_wait`](crate::Connection::send_datagram_wait) waits for room.
+    ///
+    /// Values below [`TransportOptions::MIN_DATAGRAM_SEND_BUFFER_SIZE`] are raised to it.
+    ///
+    /// Default: 1 MiB.
+    pub fn set_datagram_send_buffer_size(&mut self, size: usize) -> &mut Self {
+        self.connection_options.transport.datagram_send_buffer_size =
+            Some(size.max(TransportOptions::MIN_DATAGRAM_SEND_BUFFER_SIZE));
+        self
+    }
+
+    /// Enables or disables path MTU discovery (DPLPMTUD, RFC 8899).
+    ///
+    /// When enabled, larger UDP payloads are probed periodically, so that
+    /// datagrams up to the path MTU can be sent (see
+    /// [`Connection::path_mtu`](crate::Connection::path_mtu)). When disabled,
+    /// the initial MTU is used for the whole connection.
+    ///
+    /// Default: enabled.
+    pub fn set_mtu_discovery(&mut self, enabled: bool) -> &mut Self {
+        self.connection_options.transport.mtu_discovery = enabled;
+        self
+    }
+
+    /// Sets the UDP payload size (in bytes) used before any MTU is discovered.
+    ///
+    /// It should be raised only if the network is known to support larger
+    /// payloads: packets exceeding the path MTU are lost until the MTU is
+    /// discovered again. Values below [`TransportOptions::MIN_MTU`] are raised to it.
+    ///
+    /// Default: [`TransportOptions::MIN_MTU`].
+    pub fn set_initial_mtu(&mut self, mtu: u16) -> &mut Self {
+        self.connection_options.transport.initial_mtu = Some(mtu.max(TransportOptions::MIN_MTU));
+        self
+    }
+
+    /// Sets the largest UDP payload size (in bytes) probed by MTU discovery.
+    ///
+    /// Default: 1452 (Ethernet MTU minus IPv6 and UDP headers).
+    pub fn set_max_udp_payload_size(&mut self, size: u16) -> &mut Self {
+        self.connection_options.transport.max_udp_payload_size =
+            Some(size.max(TransportOptions::MIN_MTU));
+        self
+    }
+
+    /// Enables detection of unresponsive peers.
+    ///
+    /// Keep-alive PINGs are sent (at least) every heartbeat interval, and the
+    /// peer is considered silent when nothing is received for a number of them.
+    /// This detects failures much faster than the idle timeout.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_heartbeat(&mut self, heartbeat: Option<HeartbeatConfig>) -> &mut Self {
+        self.connection_options.heartbeat = heartbeat;
+        self
+    }
+
+    /// Restricts what clients may do in each accepted session.
+    ///
+    /// `resolver` is invoked with the headers of each session request
+    /// (e.g., `:path` and `origin`), so that different routes or origins
+    /// can be granted different permissions (see [`SessionPolicy`]).
+    ///
+    /// Default: every session is [`SessionPolicy::unrestricted`].
+    pub fn set_session_policy<F>(&mut self, resolver: F) -> &mut Self
+    where
+        F: Fn(&Headers) -> SessionPolicy + Send + Sync + 'static,
+    {
+        self.handlers.session_policy = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Serves plain HTTP3 requests (e.g., a `GET /health` probe) on the same
+    /// endpoint as WebTransport.
+    ///
+    /// `handler` is invoked with every request which is not a WebTransport
+    /// session request, from the task driving the connection: it must not
+    /// block (spawn a task to respond, see [`Request::respond`]).
+    ///
+    /// Default: such requests are rejected.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use wtransport::ServerConfig;
+    /// use wtransport::proto::Headers;
+    ///
+    /// # fn run(mut config: ServerConfig) {
+    /// config.set_request_handler(|request| {
+    ///     tokio::spawn(async move {
+    ///         let (status, body) = match request.path() {
+    ///             Some("/health") => (200, "ok"),
+    ///             _ => (404, "not found"),
+    ///         };
+    ///
+    ///         let headers = [("content-type", "text/plain")].into_iter().collect::<Headers>();
+    ///         let _ = request.respond(status, &headers, body.as_bytes()).await;
+    ///     });
+    /// });
+    /// # }
+    /// ```
+    pub fn set_request_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(Request) + Send + Sync + 'static,
+    {
+        self.handlers.request_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Adds a hook deciding whether session requests are accepted (e.g., for
+    /// authentication or rate limiting).
+    ///
+    /// `hook` is invoked with each WebTransport session request before it is
+    /// accepted, and it can asynchronously consult other services. It lets
+    /// the request through (possibly adding response headers, see
+    /// [`SessionRequest::add_response_header`]), or refuses it with a
+    /// [`Refusal`], whose response is sent to the client.
+    ///
+    /// Hooks form a chain: they run in the order they are added, each one
+    /// receiving the request let through by the previous one. The session is
+    /// accepted once all of them let the request through.
+    ///
+    /// While hooks run, the connection does not accept other sessions.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use wtransport::ServerConfig;
+    /// use wtransport::accept::Refusal;
+    ///
+    /// # fn run(mut config: ServerConfig) {
+    /// config.add_accept_hook(|mut request| async move {
+    ///     if request.path() != Some("/game") {
+    ///         return Err(Refusal::forbidden());
+    ///     }
+    ///
+    ///     request.add_response_header("x-region", "eu-west");
+    ///     Ok(request)
+    /// });
+    /// # }
+    /// ```
+    pub fn add_accept_hook<F, Fut>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(SessionRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<SessionRequest, Refusal>> + Send + 'static,
+    {
+        self.handlers
+            .accept_hooks
+            .push(Arc::new(move |request| Box::pin(hook(request))));
+        self
+    }
+
+    /// Spreads bursts of outgoing datagrams over `interval`, instead of
+    /// sending them all at once.
+    ///
+    /// For example, a game server sending a burst of updates every tick
+    /// (e.g., 60 ticks per second) can set `interval` to the tick duration,
+    /// so that the updates are smoothed across the frame. This avoids
+    /// inducing losses with micro-bursts.
+    ///
+    /// Datagrams sent when the pacing queue is full are discarded.
+    ///
+    /// When multiple sessions share the connection, the queued datagrams of
+    /// different sessions are sent round-robin, so that a session's burst
+    /// does not delay the others. Without pacing, datagrams are sent in order.
+    ///
+    /// Default: [`None`] (no pacing).
+    pub fn set_datagram_pacing(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.connection_options.datagram_pacing = interval;
+        self
+    }
+
+    /// Sets the *grease* sent to the peer on every connection.
+    ///
+    /// See [`Grease`] for more details.
+    ///
+    /// Default: [`Grease::all`].
+    pub fn set_grease(&mut self, grease: Grease) -> &mut Self {
+        self.connection_options.grease = grease;
+        self
+    }
+
+    /// Sets the maximum size (in bytes) of the encoded header blocks received
+    /// from the peer (requests, responses and trailers).
+    ///
+    /// Larger header blocks are not read: the connection is closed with
+    /// `H3_EXCESSIVE_LOAD`.
+    ///
+    /// Default: [`ParseLimits::DEFAULT_MAX_HEADER_BLOCK_SIZE`] (64 KiB).
+    pub fn set_max_header_block_size(&mut self, max_size: usize) -> &mut Self {
+        self.connection_options.parse_limits = self
+            .connection_options
+            .parse_limits
+            .with_max_header_block_size(max_size);
+        self
+    }
+
+    /// Sets the maximum number of fields in the header blocks received from
+    /// the peer.
+    ///
+    /// Header blocks with more fields close the connection with
+    /// `H3_EXCESSIVE_LOAD`.
+    ///
+    /// Default: [`ParseLimits::DEFAULT_MAX_HEADER_COUNT`].
+    pub fn set_max_header_count(&mut self, max_count: usize) -> &mut Self {
+        self.connection_options.parse_limits = self
+            .connection_options
+            .parse_limits
+            .with_max_header_count(max_count);
+        self
+    }
+
+    /// Sets the maximum number of entries in the SETTINGS frame of the peer.
+    ///
+    /// A larger SETTINGS frame closes the connection with `H3_EXCESSIVE_LOAD`.
+    ///
+    /// Default: [`ParseLimits::DEFAULT_MAX_SETTINGS_ENTRIES`].
+    pub fn set_max_settings_entries(&mut self, max_entries: usize) -> &mut Self {
+        self.connection_options.parse_limits = self
+            .connection_options
+            .parse_limits
+            .with_max_settings_entries(max_entries);
+        self
+    }
+
+    /// Sets how streams and datagrams of sessions not established yet are
+    /// buffered.
+    ///
+    /// See [`PendingSessionBuffer`] for more details.
+    pub fn set_pending_session_buffer(&mut self, buffer: PendingSessionBuffer) -> &mut Self {
+        self.connection_options.pending_sessions = buffer;
+        self
+    }
+
+    /// Sets the size (in bytes) of the memory pool outgoing datagrams are
+    /// encoded in, for each connection.
+    ///
+    /// The memory of sent datagrams is reused for the next ones, instead of
+    /// allocating a buffer for every datagram. Datagrams larger than the pool
+    /// are allocated separately. `0` disables pooling.
+    ///
+    /// Default: 64 KiB.
+    pub fn set_buffer_pool_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.connection_options.buffer_pool_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the session-level flow control granted to the peer.
+    ///
+    /// See [`SessionFlowControl`] for more details. The limits advertised by
+    /// the peer are always respected: opening streams and writing data wait
+    /// for credit (see [`BlockedLimit::Session`](crate::stream::BlockedLimit::Session)).
+    ///
+    /// Default: [`None`] (the peer is not limited at the session level).
+    pub fn set_session_flow_control(
+        &mut self,
+        flow_control: Option<SessionFlowControl>,
+    ) -> &mut Self {
+        self.connection_options.session_flow_control = flow_control;
+        self
+    }
+
+    /// Sets the priority outgoing streams are opened (or accepted) with.
+    ///
+    /// See [`SendStream::set_priority`](crate::SendStream::set_priority).
+    ///
+    /// Default: `0`.
+    pub fn set_default_stream_priority(&mut self, priority: i32) -> &mut Self {
+        self.connection_options.default_stream_priority = priority;
+        self
+    }
+
+    /// Records a [qlog](https://datatracker.ietf.org/doc/draft-ietf-quic-qlog-main-schema/)
+    /// trace of each connection into `sink`.
+    ///
+    /// Traces can be visualized with [qvis](https://qvis.quictools.info/).
+    /// See [`QlogSink`] for more details.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_qlog(&mut self, sink: Option<Arc<dyn QlogSink>>) -> &mut Self {
+        self.qlog = sink;
+        self
+    }
+
+    /// Reports the countable events of the endpoint's connections
+    /// (handshakes, sessions, streams, datagrams) to `metrics`.
+    ///
+    /// See [`Metrics`] for more details.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn Metrics>>) -> &mut Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Selects the runtime the endpoint driver (i.e., the task
+    /// sending and receiving UDP packets) and its timers run on.
+    ///
+    /// Latency-critical deployments can isolate packet processing from
+    /// application work with [`DriverRuntime::Dedicated`], or with
+    /// [`DriverRuntime::Handle`] pointing to a runtime whose worker threads
+    /// are set up by the application (e.g., pinned to specific CPUs).
+    ///
+    /// Default: [`DriverRuntime::Current`].
+    pub fn set_driver_runtime(&mut self, driver_runtime: DriverRuntime) -> &mut Self {
+        self.driver_runtime = driver_runtime;
+        self
+    }
+
+    /// Sets the DSCP (Differentiated Services Code Point) of outgoing
+    /// packets, for QoS classification on managed networks (e.g., `46` for
+    /// *Expedited Forwarding*). Only the 6 least significant bits are used.
+    ///
+    /// It applies to the sockets bound by the endpoint: not to proxy tunnels,
+    /// nor to sockets passed to [`Endpoint::rebind`](crate::Endpoint::rebind).
+    ///
+    /// Default: [`None`] (DSCP `0`, best effort).
+    pub fn set_dscp(&mut self, dscp: Option<u8>) -> &mut Self {
+        self.packet_marking.dscp = dscp.map(|dscp| dscp & 0b11_1111);
+        self
+    }
+
+    /// Enables or disables ECN (Explicit Congestion Notification) marking of
+    /// outgoing packets.
+    ///
+    /// When enabled, QUIC marks packets as ECN-capable as long as the path
+    /// delivers the marks correctly, so that routers can signal congestion
+    /// instead of dropping packets. Codepoints of received packets are
+    /// reported by [`ConnectionStats::ecn`](crate::connection::ConnectionStats::ecn)
+    /// in any case (where the platform supports it).
+    ///
+    /// Default: enabled.
+    pub fn set_ecn(&mut self, enabled: bool) -> &mut Self {
+        self.packet_marking.ecn = enabled;
+        self
+    }
+
+    /// Attaches the endpoint to an in-process network instead of a UDP socket.
+    ///
+    /// The bind address is virtual: it identifies the endpoint in `network`.
+    /// See [`MockNetwork`].
+    ///
+    /// Default: [`None`] (UDP socket).
+    #[cfg(feature = "mock-network")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mock-network")))]
+    pub fn set_mock_network(&mut self, network: Option<MockNetwork>) -> &mut Self {
+        self.mock_network = network;
+        self
+    }
+
+    /// Adds (or overrides) HTTP3 SETTINGS sent to the peer on every connection,
+    /// e.g., *grease* or experimental settings for interoperability testing.
+    ///
+    /// The defaults enable WebTransport and HTTP3 datagrams. QPACK settings
+    /// cannot be overridden: the dynamic table is not supported.
+    ///
+    /// The SETTINGS received from the peer are available with
+    /// [`Connection::peer_settings`](crate::Connection::peer_settings).
+    pub fn set_extra_settings(&mut self, settings: Settings) -> &mut Self {
+        self.extra_settings = Arc::new(settings);
+        self
+    }
+
+    /// Listens on `address` too, in addition to the bind address (e.g., on a
+    /// specific IPv4 address besides `[::]`).
+    ///
+    /// Connections from all the addresses are returned by the same
+    /// [`Endpoint::accept`](crate::Endpoint::accept).
+    /// [`Endpoint::local_addr`](crate::Endpoint::local_addr) reports the
+    /// bind address only: see [`Endpoint::local_addrs`](crate::Endpoint::local_addrs).
+    pub fn add_bind_address(&mut self, address: SocketAddr) -> &mut Self {
+        self.additional_binds.push(Bind::Address(address));
+        self
+    }
+
+    /// Accepts 0-RTT session requests (TLS early data) from clients resuming
+    /// a previous TLS session (see [`ClientConfig::set_early_data`]).
+    ///
+    /// Early data can be replayed by an attacker. However, sessions are
+    /// returned by [`Endpoint::accept`](crate::Endpoint::accept) only once the
+    /// handshake completes: a replayed session request never reaches the application.
+    ///
+    /// Default: `false`.
+    pub fn set_early_data(&mut self, enabled: bool) -> &mut Self {
+        self.connection_options.early_data = enabled;
+        self
+    }
+
+    /// Sets how many TLS session tickets are issued to each client.
+    ///
+    /// Tickets let clients resume their TLS session when reconnecting, which
+    /// skips the certificate exchange (and it is required for 0-RTT, see
+    /// [`ServerConfig::set_early_data`]). `0` disables session resumption.
+    ///
+    /// Default: `4`.
+    pub fn set_session_tickets(&mut self, count: usize) -> &mut Self {
+        Arc::make_mut(&mut self.tls_config).send_tls13_tickets = count;
+        self
+    }
+
+    /// Issues stateless session tickets, encrypted by `ticketer`
+    /// (e.g., [`rustls::Ticketer`]).
+    ///
+    /// By default, resumable sessions are kept in the server memory (and
+    /// tickets only refer to them). With stateless tickets, servers sharing
+    /// the same ticket keys can resume each other's sessions.
+    pub fn set_session_ticketer(
+        &mut self,
+        ticketer: Arc<dyn rustls::server::ProducesTickets>,
+    ) -> &mut Self {
+        Arc::make_mut(&mut self.tls_config).ticketer = ticketer;
+        self
+    }
+
+    /// The sockets to listen on: the bind address first, then the additional ones.
+    pub(crate) fn driver_sockets(&self) -> std::io::Result<Vec<DriverSocket>> {
+        std::iter::once(&self.bind)
+            .chain(&self.additional_binds)
+            .map(|bind| {
+                #[cfg(feature = "mock-network")]
+                if let Some(network) = &self.mock_network {
+                    return Ok(DriverSocket::Mock(network.clone(), bind.address()?));
+                }
+
+                bind.driver_socket()
+            })
+            .collect()
+    }
+
+    pub(crate) fn quic_config(&self) -> QuicServerConfig {
+        let tls_config = if self.connection_options.early_data {
+            // QUIC requires either no early data or an unlimited amount
+            let mut tls_config = (*self.tls_config).clone();
+            tls_config.max_early_data_size = u32::MAX;
+            Arc::new(tls_config)
+        } else {
+            self.tls_config.clone()
+        };
+
+        let mut quic_config = QuicServerConfig::new(tls_config, self.token_key.0.clone());
+
+        quic_config
+            .use_retry(matches!(
+                self.address_validation,
+                AddressValidation::Required
+            ))
+            .retry_token_lifetime(self.token_lifetime)
+            .concurrent_connections(self.max_connections)
+            .transport_config(Arc::new(self.connection_options.transport_config()));
+
+        quic_config
+    }
+}
+
+/// Secret key for issuing and validating address validation tokens.
+///
+/// See [`ServerConfig::set_token_key`].
+#[derive(Clone)]
+pub struct TokenKey(Arc<ring::hkdf::Prk>);
+
+impl TokenKey {
+    /// Creates a key derived from `secret`.
+    ///
+    /// `secret` should be a high-entropy value (e.g., 32 random bytes) shared
+    /// among all servers which should accept each other's tokens.
+    pub fn new(secret: &[u8]) -> Self {
+        Self(Arc::new(ring::hkdf::Prk::new_less_safe(
+            ring::hkdf::HKDF_SHA256,
+            secret,
+        )))
+    }
+
+    /// Creates a random key.
+    pub fn random() -> Self {
+        use ring::rand::SecureRandom;
+
+        let mut secret = [0; 64];
+        ring::rand::SystemRandom::new()
+            .fill(&mut secret)
+            .expect("System random generator failure");
+
+        Self::new(&secret)
+    }
+}
+
+/// Per-connection options, shared by server and client configurations.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct ConnectionOptions {
+    pub(crate) receive_window: ReceiveWindowConfig,
+    pub(crate) datagram_pacing: Option<Duration>,
+    pub(crate) transport: TransportOptions,
+    pub(crate) settings_wait: SettingsWait,
+    pub(crate) heartbeat: Option<HeartbeatConfig>,
+    pub(crate) adaptive_keep_alive: Option<AdaptiveKeepAlive>,
+    pub(crate) early_data: bool,
+    pub(crate) max_sessions: Option<usize>,
+    pub(crate) max_session_buffer: Option<usize>,
+    pub(crate) default_stream_priority: i32,
+    pub(crate) grease: Grease,
+    pub(crate) buffer_pool_capacity: Option<usize>,
+    pub(crate) session_flow_control: Option<SessionFlowControl>,
+    pub(crate) parse_limits: ParseLimits,
+    pub(crate) pending_sessions: PendingSessionBuffer,
+}
+
+impl ConnectionOptions {
+    fn transport_config(&self) -> TransportConfig {
+        let mut transport_config = TransportConfig::default();
+
+        self.receive_window.apply(&mut transport_config);
+        self.transport.apply(&mut transport_config);
+
+        // The stream receive window is the amount of unread data a stream can buffer
+        if let Some(max_unread) = self.transport.max_unread_stream_data {
+            let stream_window = self
+                .transport
+                .stream_receive_window
+                .or_else(|| {
+                    self.receive_window
+                        .autotuning()
+                        .then(|| self.receive_window.max_window())
+                })
+                .map_or(max_unread, |window| window.min(max_unread));
+
+            transport_config.stream_receive_window(VarInt::from_u32(stream_window));
+        }
+
+        // Keep-alive PINGs make a responsive peer send something every heartbeat interval
+        if let Some(heartbeat) = self.heartbeat {
+            let keep_alive_interval = self
+                .transport
+                .keep_alive_interval
+                .map_or(heartbeat.interval, |interval| {
+                    interval.min(heartbeat.interval)
+                });
+
+            transport_config.keep_alive_interval(Some(keep_alive_interval));
+        }
+
+        transport_config
+    }
+}
+
+/// Resolves the [`SessionPolicy`] of a session from its request headers.
+pub(crate) type SessionPolicyResolver = Arc<dyn Fn(&Headers) -> SessionPolicy + Send + Sync>;
+
+/// Handles the plain HTTP3 requests received by a server.
+pub(crate) type RequestHandler = Arc<dyn Fn(Request) + Send + Sync>;
+
+/// Application handlers of the requests received by a server.
+#[derive(Clone, Default)]
+pub(crate) struct ServerHandlers {
+    pub(crate) session_policy: Option<SessionPolicyResolver>,
+    pub(crate) request_handler: Option<RequestHandler>,
+    pub(crate) accept_hooks: Vec<AcceptHook>,
+}
+
+/// Resolves a host name and port into the addresses to connect to.
+pub(crate) type Resolver = Arc<
+    dyn Fn(String, u16) -> Pin<Box<dyn Future<Output = std::io::Result<Vec<SocketAddr>>> + Send>>
+        + Send
+        + Sync,
+>;
+
+/// Permissions granted to the peer in a session.
+///
+/// Streams and datagrams not permitted are rejected as soon as they are
+/// received: streams are stopped and datagrams are discarded.
+///
+/// See [`ServerConfig::set_session_policy`].
+#[derive(Copy, Clone, Debug)]
+pub struct SessionPolicy {
+    datagrams: bool,
+    max_bi_streams: Option<u64>,
+    max_uni_streams: Option<u64>,
+    read_only: bool,
+}
+
+impl SessionPolicy {
+    /// Creates a policy with no restrictions.
+    pub fn unrestricted() -> Self {
+        Self {
+            datagrams: true,
+            max_bi_streams: None,
+            max_uni_streams: None,
+            read_only: false,
+        }
+    }
+
+    /// Whether the peer may send datagrams.
+    pub fn with_datagrams(mut self, allowed: bool) -> Self {
+        self.datagrams = allowed;
+        self
+    }
+
+    /// Sets the maximum number of bi-directional streams the peer may open
+    /// in the session (in total, not concurrently).
+    ///
+    /// [`None`] means no limit.
+    pub fn with_max_bi_streams(mut self, max_streams: Option<u64>) -> Self {
+        self.max_bi_streams = max_streams;
+        self
+    }
+
+    /// Sets the maximum number of uni-directional streams the peer may open
+    /// in the session (in total, not concurrently).
+    ///
+    /// [`None`] means no limit.
+    pub fn with_max_uni_streams(mut self, max_streams: Option<u64>) -> Self {
+        self.max_uni_streams = max_streams;
+        self
+    }
+
+    /// Whether the peer may only receive data, in which case all the streams
+    /// and datagrams it sends are rejected regardless of the other permissions.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Whether the peer may send datagrams.
+    #[inline(always)]
+    pub fn datagrams(&self) -> bool {
+        self.datagrams && !self.read_only
+    }
+
+    /// The maximum number of bi-directional streams the peer may open.
+    #[inline(always)]
+    pub fn max_bi_streams(&self) -> Option<u64> {
+        if self.read_only {
+            Some(0)
+        } else {
+            self.max_bi_streams
+        }
+    }
+
+    /// The maximum number of uni-directional streams the peer may open.
+    #[inline(always)]
+    pub fn max_uni_streams(&self) -> Option<u64> {
+        if self.read_only {
+            Some(0)
+        } else {
+            self.max_uni_streams
+        }
+    }
+
+    /// Whether the peer may only receive data.
+    #[inline(always)]
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        Self::unrestricted()
+    }
+}
+
+/// Session-level flow control windows granted to the peer.
+///
+/// The peer is granted credit with `WT_MAX_STREAMS` and `WT_MAX_DATA`
+/// capsules: each window is the number of streams (or bytes, on all the
+/// streams of the session) the peer may open (or send) beyond what the
+/// application has consumed. Credit is extended as the application accepts
+/// streams and reads data. A peer exceeding its credit makes the session fail
+/// with `WEBTRANSPORT_FLOW_CONTROL_ERROR`.
+///
+/// This prevents a single session from starving the others sharing the
+/// same connection.
+///
+/// See [`ServerConfig::set_session_flow_control`].
+#[derive(Copy, Clone, Debug)]
+pub struct SessionFlowControl {
+    max_bi_streams: u64,
+    max_uni_streams: u64,
+    max_data: u64,
+}
+
+impl SessionFlowControl {
+    /// Creates windows of `max_bi_streams` and `max_uni_streams` streams,
+    /// and of `max_data` bytes.
+    pub fn new(max_bi_streams: u64, max_uni_streams: u64, max_data: u64) -> Self {
+        Self {
+            max_bi_streams,
+            max_uni_streams,
+            max_data,
+        }
+    }
+
+    /// The window of bi-directional streams.
+    #[inline(always)]
+    pub fn max_bi_streams(&self) -> u64 {
+        self.max_bi_streams
+    }
+
+    /// The window of uni-directional streams.
+    #[inline(always)]
+    pub fn max_uni_streams(&self) -> u64 {
+        self.max_uni_streams
+    }
+
+    /// The window of data (in bytes).
+    #[inline(always)]
+    pub fn max_data(&self) -> u64 {
+        self.max_data
+    }
+}
+
+/// Buffering of the streams and datagrams of sessions not established yet.
+///
+/// The peer can open streams and send datagrams of a session before the
+/// session is established locally (e.g., racing the server's response, or
+/// the session request itself). They are buffered until the session is
+/// established, for up to [`Self::timeout`] and for up to
+/// [`Self::max_sessions`] sessions at once.
+/// Streams exceeding the limits are rejected with
+/// `WEBTRANSPORT_BUFFERED_STREAM_REJECTED`, and datagrams are discarded.
+///
+/// Session IDs which are not client-initiated bidirectional stream IDs, or
+/// (on the client) which do not refer to a session requested by the client,
+/// are never buffered: the connection is closed with `H3_ID_ERROR`.
+///
+/// See [`ServerConfig::set_pending_session_buffer`].
+#[derive(Copy, Clone, Debug)]
+pub struct PendingSessionBuffer {
+    timeout: Duration,
+    max_sessions: usize,
+}
+
+impl PendingSessionBuffer {
+    /// Default time streams and datagrams are buffered for.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Default maximum number of sessions buffering at once.
+    pub const DEFAULT_MAX_SESSIONS: usize = 16;
+
+    /// Creates the default buffering.
+    pub fn new() -> Self {
+        Self {
+            timeout: Self::DEFAULT_TIMEOUT,
+            max_sessions: Self::DEFAULT_MAX_SESSIONS,
+        }
+    }
+
+    /// Sets how long streams and datagrams are buffered, waiting for their
+    /// session.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum number of sessions buffering at once (`0` disables
+    /// buffering).
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions;
+        self
+    }
+
+    /// The time streams and datagrams are buffered for.
+    #[inline(always)]
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// The maximum number of sessions buffering at once.
+    #[inline(always)]
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+}
+
+impl Default for PendingSessionBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Limit of the data sent by a session, as a token bucket.
+///
+/// It covers the application data of the session: the bytes written on its
+/// streams and the payloads of its datagrams (framing excluded). It prevents
+/// a session from saturating the uplink shared with the other sessions
+/// (e.g., of a multi-tenant server).
+///
+/// Up to [`Self::burst`] bytes can be sent at once; then sending proceeds
+/// at [`Self::bytes_per_sec`]. Stream writes wait for the bucket to refill,
+/// while datagrams exceeding the limit are discarded (see
+/// [`Connection::send_datagram_wait`] to wait instead).
+///
+/// See [`Connection::set_send_rate_limit`].
+///
+/// [`Connection::send_datagram_wait`]: crate::Connection::send_datagram_wait
+/// [`Connection::set_send_rate_limit`]: crate::Connection::set_send_rate_limit
+#[derive(Copy, Clone, Debug)]
+pub struct SendRateLimit {
+    bytes_per_sec: u64,
+    burst: u64,
+}
+
+impl SendRateLimit {
+    /// Default burst, as the time of sending at the limit rate.
+    pub const DEFAULT_BURST_DURATION: Duration = Duration::from_millis(100);
+
+    /// Minimum default burst (in bytes), so that low rates still allow a
+    /// full-sized packet at once.
+    pub const MIN_DEFAULT_BURST: u64 = 16 * 1024;
+
+    /// Limits the rate to `bytes_per_sec`, with a burst of
+    /// [`Self::DEFAULT_BURST_DURATION`] of data (at least
+    /// [`Self::MIN_DEFAULT_BURST`] bytes).
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let burst = (bytes_per_sec as f64 * Self::DEFAULT_BURST_DURATION.as_secs_f64()) as u64;
+
+        Self {
+            bytes_per_sec,
+            burst: burst.max(Self::MIN_DEFAULT_BURST),
+        }
+    }
+
+    /// Sets the maximum number of bytes sent at once.
+    pub fn with_burst(mut self, burst: u64) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// The sustained rate, in bytes per second.
+    #[inline(always)]
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// The maximum number of bytes sent at once.
+    #[inline(always)]
+    pub fn burst(&self) -> u64 {
+        self.burst
+    }
+}
+
+/// Racing of the addresses of a host name
+/// ([Happy Eyeballs, RFC 8305](https://www.rfc-editor.org/rfc/rfc8305)).
+///
+/// When a host name resolves to both IPv6 and IPv4 addresses, the families
+/// are interleaved (starting from the preferred one) and the addresses are
+/// attempted in turn: a new attempt starts when the previous one fails, or
+/// after the stagger delay. The first established connection wins.
+///
+/// See [`ClientConfig::set_happy_eyeballs`].
+#[derive(Copy, Clone, Debug)]
+pub struct HappyEyeballs {
+    stagger: Duration,
+    preferred_family: AddressFamily,
+}
+
+impl HappyEyeballs {
+    /// Default delay between connection attempts (as recommended by RFC 8305).
+    pub const DEFAULT_STAGGER: Duration = Duration::from_millis(250);
+
+    /// Sets the delay before starting the next attempt, while the previous
+    /// ones are still in progress.
+    pub fn with_stagger(mut self, stagger: Duration) -> Self {
+        self.stagger = stagger;
+        self
+    }
+
+    /// Sets the address family attempted first.
+    pub fn with_preferred_family(mut self, family: AddressFamily) -> Self {
+        self.preferred_family = family;
+        self
+    }
+
+    /// The delay between connection attempts.
+    #[inline(always)]
+    pub fn stagger(&self) -> Duration {
+        self.stagger
+    }
+
+    /// The address family attempted first.
+    #[inline(always)]
+    pub fn preferred_family(&self) -> AddressFamily {
+        self.preferred_family
+    }
+
+    /// Orders `addresses` for racing, interleaving the two families.
+    pub(crate) fn order<I>(&self, addresses: I) -> Vec<SocketAddr>
+    where
+        I: IntoIterator<Item = SocketAddr>,
+    {
+        let (preferred, other): (Vec<_>, Vec<_>) = addresses
+            .into_iter()
+            .partition(|address| AddressFamily::of(address) == self.preferred_family);
+
+        let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+        let mut preferred = preferred.into_iter();
+        let mut other = other.into_iter();
+
+        loop {
+            match (preferred.next(), other.next()) {
+                (None, None) => return ordered,
+                (first, second) => ordered.extend(first.into_iter().chain(second)),
+            }
+        }
+    }
+}
+
+impl Default for HappyEyeballs {
+    /// IPv6 first, with [`Self::DEFAULT_STAGGER`].
+    fn default() -> Self {
+        Self {
+            stagger: Self::DEFAULT_STAGGER,
+            preferred_family: AddressFamily::Ipv6,
+        }
+    }
+}
+
+/// An IP address family.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddressFamily {
+    /// IPv6.
+    Ipv6,
+
+    /// IPv4.
+    Ipv4,
+}
+
+impl AddressFamily {
+    fn of(address: &SocketAddr) -> Self {
+        match address {
+            SocketAddr::V6(_) => AddressFamily::Ipv6,
+            SocketAddr::V4(_) => AddressFamily::Ipv4,
+        }
+    }
+}
+
+/// Keep-alive adaptation to NAT timeouts.
+///
+/// Keep-alive packets are sent every interval, starting from the maximum one.
+/// Whenever the peer address changes (i.e., a NAT rebinding is observed,
+/// likely because the binding timed out) the interval is halved, down to the
+/// minimum one. After a long enough period with no rebinding, the interval
+/// grows back, up to the maximum one.
+///
+/// This keeps mobile sessions alive without a fixed aggressive ping rate.
+///
+/// See [`ServerConfig::set_adaptive_keep_alive`].
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveKeepAlive {
+    min_interval: Duration,
+    max_interval: Duration,
+}
+
+impl AdaptiveKeepAlive {
+    /// Creates an adaptive keep-alive within the interval bounds.
+    ///
+    /// Bounds are swapped if `min_interval` is greater than `max_interval`.
+    pub fn new(min_interval: Duration, max_interval: Duration) -> Self {
+        Self {
+            min_interval: min_interval.min(max_interval),
+            max_interval: max_interval.max(min_interval),
+        }
+    }
+
+    /// The lower bound of the keep-alive interval.
+    #[inline(always)]
+    pub fn min_interval(&self) -> Duration {
+        self.min_interval
+    }
+
+    /// The upper bound of the keep-alive interval.
+    #[inline(always)]
+    pub fn max_interval(&self) -> Duration {
+        self.max_interval
+    }
+}
+
+/// *Grease* sent to the peer ([RFC 9114, section 9](https://www.rfc-editor.org/rfc/rfc9114#section-9)).
+///
+/// Peers must ignore unknown protocol elements: sending reserved ones, with
+/// random identifiers, detects early the peers which do not.
+///
+/// See [`ServerConfig::set_grease`].
+#[derive(Copy, Clone, Debug)]
+pub struct Grease {
+    settings: bool,
+    frames: bool,
+    streams: bool,
+}
+
+impl Grease {
+    /// Grease of every kind.
+    pub fn all() -> Self {
+        Self {
+            settings: true,
+            frames: true,
+            streams: true,
+        }
+    }
+
+    /// No grease.
+    pub fn none() -> Self {
+        Self {
+            settings: false,
+            frames: false,
+            streams: false,
+        }
+    }
+
+    /// Whether a reserved setting (with a random value) is added to the SETTINGS.
+    pub fn with_settings(mut self, enabled: bool) -> Self {
+        self.settings = enabled;
+        self
+    }
+
+    /// Whether reserved frames (with a random payload) are sent on the control
+    /// stream: after the SETTINGS and before a GOAWAY.
+    pub fn with_frames(mut self, enabled: bool) -> Self {
+        self.frames = enabled;
+        self
+    }
+
+    /// Whether a uni-directional stream of reserved type is opened on every connection.
+    pub fn with_streams(mut self, enabled: bool) -> Self {
+        self.streams = enabled;
+        self
+    }
+
+    /// Whether a reserved setting is sent.
+    #[inline(always)]
+    pub fn settings(&self) -> bool {
+        self.settings
+    }
+
+    /// Whether reserved frames are sent.
+    #[inline(always)]
+    pub fn frames(&self) -> bool {
+        self.frames
+    }
+
+    /// Whether a stream of reserved type is opened.
+    #[inline(always)]
+    pub fn streams(&self) -> bool {
+        self.streams
+    }
+}
+
+impl Default for Grease {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// When clients have to validate their address before the handshake.
+///
+/// See [`ServerConfig::set_address_validation`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum AddressValidation {
+    /// Addresses are not validated.
+    #[default]
+    Disabled,
+
+    /// Every client has to validate its address.
+    Required,
+
+    /// Clients have to validate their address while the endpoint is under
+    /// load, i.e., while it has at least `open_connections` connections
+    /// (handshakes in progress included).
+    ///
+    /// The load is sampled periodically, so short bursts can exceed the threshold.
+    Adaptive {
+        /// The number of connections from which addresses are validated.
+        open_connections: usize,
+    },
+}
+
+/// The runtime the endpoint driver runs on.
+///
+/// See [`ServerConfig::set_driver_runtime`].
+#[derive(Clone, Debug, Default)]
+pub enum DriverRuntime {
+    /// The runtime the endpoint is created from.
+    ///
+    /// Creating the endpoint outside a tokio runtime fails, unless the
+    /// `async-std` feature is enabled (the async-std runtime is used then).
+    #[default]
+    Current,
+
+    /// The runtime of the given handle (with the `tokio` feature).
+    #[cfg(feature = "tokio")]
+    Handle(Handle),
+
+    /// A *current-thread* runtime on a dedicated OS thread, created for the endpoint.
+    ///
+    /// Each endpoint gets its own thread, so multiple endpoints (e.g., one per
+    /// shard) do not compete with each other nor with the application tasks.
+    /// The thread stops when the endpoint is dropped: connections must not
+    /// outlive their endpoint.
+    ///
+    /// It requires the `tokio` feature.
+    #[cfg(feature = "tokio")]
+    Dedicated,
+
+    /// A custom [`Runtime`] (e.g., the async-std one, see [`crate::runtime`]).
+    Custom(Arc<dyn Runtime>),
+}
+
+/// Peer liveness detection.
+///
+/// See [`ServerConfig::set_heartbeat`].
+#[derive(Copy, Clone, Debug)]
+pub struct HeartbeatConfig {
+    interval: Duration,
+    max_misses: u32,
+    close_on_silence: bool,
+}
+
+impl HeartbeatConfig {
+    /// Default number of missed intervals before the peer is considered silent.
+    pub const DEFAULT_MAX_MISSES: u32 = 3;
+
+    /// Creates a heartbeat checking the peer every `interval`.
+    ///
+    /// The peer is considered silent after [`Self::DEFAULT_MAX_MISSES`] intervals
+    /// without receiving anything from it.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            max_misses: Self::DEFAULT_MAX_MISSES,
+            close_on_silence: false,
+        }
+    }
+
+    /// Sets the number of consecutive intervals without receiving anything
+    /// after which the peer is considered silent.
+    pub fn with_max_misses(mut self, max_misses: u32) -> Self {
+        self.max_misses = max_misses.max(1);
+        self
+    }
+
+    /// Whether the connection should be closed as soon as the peer is silent.
+    ///
+    /// Otherwise, silence is only notified via [`Connection::peer_silent`](crate::Connection::peer_silent).
+    pub fn with_close_on_silence(mut self, close_on_silence: bool) -> Self {
+        self.close_on_silence = close_on_silence;
+        self
+    }
+
+    /// The interval between checks.
+    #[inline(always)]
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// The number of missed intervals before the peer is considered silent.
+    #[inline(always)]
+    pub fn max_misses(&self) -> u32 {
+        self.max_misses
+    }
+
+    /// Whether the connection is closed when the peer is silent.
+    #[inline(always)]
+    pub fn close_on_silence(&self) -> bool {
+        self.close_on_silence
+    }
+}
+
+/// How long a client connection attempt waits for the server's HTTP3 SETTINGS.
+///
+/// The server's SETTINGS advertise whether it supports WebTransport. Waiting
+/// for them gives certainty the session can be established, at the cost of
+/// (up to) an additional round-trip before [`Connecting`](crate::connection::Connecting)
+/// resolves.
+///
+/// See [`ClientConfig::set_settings_wait`].
+#[derive(Copy, Clone, Debug, Default)]
+pub enum SettingsWait {
+    /// The connection is established only after the server's SETTINGS are
+    /// received and validated for WebTransport support.
+    #[default]
+    Required,
+
+    /// The connection is established as soon as SETTINGS are received, or
+    /// once `max_wait` has elapsed, whichever comes first.
+    ///
+    /// SETTINGS received later are still validated: if the server turns out
+    /// not to support WebTransport, the connection is closed.
+    /// [`Duration::ZERO`] does not wait at all.
+    Optimistic {
+        /// The maximum time to wait for SETTINGS.
+        max_wait: Duration,
+    },
+}
+
+/// QUIC transport parameters.
+///
+/// See, for example, [`ServerConfig::set_max_idle_timeout`].
+#[derive(Copy, Clone, Debug)]
+pub struct TransportOptions {
+    max_idle_timeout: Option<Duration>,
+    keep_alive_interval: Option<Duration>,
+    receive_window: Option<u32>,
+    stream_receive_window: Option<u32>,
+    max_unread_stream_data: Option<u32>,
+    max_concurrent_bi_streams: u32,
+    max_concurrent_uni_streams: u32,
+    datagram_send_buffer_size: Option<usize>,
+    mtu_discovery: bool,
+    initial_mtu: Option<u16>,
+    max_udp_payload_size: Option<u16>,
+}
+
+impl TransportOptions {
+    /// Default maximum duration of inactivity.
+    pub const DEFAULT_MAX_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Default maximum number of concurrent streams (for each direction).
+    pub const DEFAULT_MAX_CONCURRENT_STREAMS: u32 = 100;
+
+    /// Default size of the outgoing datagram buffer.
+    pub const DEFAULT_DATAGRAM_SEND_BUFFER_SIZE: usize = 1024 * 1024;
+
+    /// Minimum size of the outgoing datagram buffer, so that it can
+    /// always hold (at least) a datagram of the maximum size.
+    pub const MIN_DATAGRAM_SEND_BUFFER_SIZE: usize = 65536;
+
+    /// Minimum UDP payload size, which every QUIC path must support.
+    pub const MIN_MTU: u16 = 1200;
+
+    /// Number of uni-directional streams the peer opens for HTTP3
+    /// (control, QPACK encoder and QPACK decoder streams).
+    const H3_UNI_STREAMS: u32 = 3;
+
+    /// Returns the size (in bytes) of the outgoing datagram buffer.
+    pub(crate) fn datagram_send_buffer_size(&self) -> usize {
+        self.datagram_send_buffer_size
+            .unwrap_or(Self::DEFAULT_DATAGRAM_SEND_BUFFER_SIZE)
+    }
+
+    fn apply(&self, transport_config: &mut TransportConfig) {
+        transport_config.datagram_send_buffer_size(self.datagram_send_buffer_size());
+
+        let max_idle_timeout = self.max_idle_timeout.map(|timeout| {
+            IdleTimeout::try_from(timeout).unwrap_or_else(|_| IdleTimeout::from(VarInt::MAX))
+        });
+
+        transport_config
+            .max_idle_timeout(max_idle_timeout)
+            .keep_alive_interval(self.keep_alive_interval)
+            .max_concurrent_bidi_streams(VarInt::from_u32(self.max_concurrent_bi_streams))
+            .max_concurrent_uni_streams(VarInt::from_u32(
+                self.max_concurrent_uni_streams
+                    .saturating_add(Self::H3_UNI_STREAMS),
+            ));
+
+        if let Some(receive_window) = self.receive_window {
+            transport_config.receive_window(VarInt::from_u32(receive_window));
+        }
+
+        if let Some(stream_receive_window) = self.stream_receive_window {
+            transport_config.stream_receive_window(VarInt::from_u32(stream_receive_window));
+        }
+
+        if let Some(initial_mtu) = self.initial_mtu {
+            transport_config.initial_mtu(initial_mtu);
+        }
+
+        let mtu_discovery = self.mtu_discovery.then(|| {
+            let mut mtu_discovery = MtuDiscoveryConfig::default();
+            if let Some(max_udp_payload_size) = self.max_udp_payload_size {
+                mtu_discovery.upper_bound(max_udp_payload_size);
+            }
+            mtu_discovery
+        });
+
+        transport_config.mtu_discovery_config(mtu_discovery);
+    }
+}
+
+impl Default for TransportOptions {
+    fn default() -> Self {
+        Self {
+            max_idle_timeout: Some(Self::DEFAULT_MAX_IDLE_TIMEOUT),
+            keep_alive_interval: None,
+            receive_window: None,
+            stream_receive_window: None,
+            max_unread_stream_data: None,
+            max_concurrent_bi_streams: Self::DEFAULT_MAX_CONCURRENT_STREAMS,
+            max_concurrent_uni_streams: Self::DEFAULT_MAX_CONCURRENT_STREAMS,
+            datagram_send_buffer_size: None,
+            mtu_discovery: true,
+            initial_mtu: None,
+            max_udp_payload_size: None,
+        }
+    }
+}
+
+/// Receive flow-control configuration.
+///
+/// When auto-tuning is enabled, the connection receive window starts at
+/// [`ReceiveWindowConfig::INITIAL_WINDOW`] and it is doubled every time the
+/// peer is able to fill (most of) it within a round-trip time, up to the
+/// configured maximum. That is, the window follows the bandwidth-delay
+/// product of the link, similarly to TCP receive buffer auto-tuning.
+///
+/// As QUIC does not allow shrinking stream windows, per-stream windows are
+/// set to the maximum directly: the memory committed for buffering is
+/// bounded by the (tuned) connection window anyway.
+#[derive(Copy, Clone, Debug)]
+pub struct ReceiveWindowConfig {
+    autotuning: bool,
+    max_window: u32,
+}
+
+impl ReceiveWindowConfig {
+    /// Initial size (in bytes) of the connection receive window.
+    pub const INITIAL_WINDOW: u32 = 1_250_000;
+
+    /// Default maximum size (in bytes) of receive windows.
+    pub const DEFAULT_MAX_WINDOW: u32 = 16 * 1024 * 1024;
+
+    /// Whether auto-tuning is enabled.
+    #[inline(always)]
+    pub fn autotuning(&self) -> bool {
+        self.autotuning
+    }
+
+    /// The maximum size (in bytes) of receive windows.
+    #[inline(always)]
+    pub fn max_window(&self) -> u32 {
+        self.max_window
+    }
+
+    pub(crate) fn initial_window(&self) -> u32 {
+        Self::INITIAL_WINDOW.min(self.max_window)
+    }
+
+    fn apply(&self, transport_config: &mut TransportConfig) {
+        if self.autotuning {
+            transport_config
+                .stream_receive_window(VarInt::from_u32(self.max_window))
+                .receive_window(VarInt::from_u32(self.initial_window()));
+        }
+    }
 }
 
-impl ServerConfig {
-    /// Creates a builder to build up the server configuration.
-    ///
-    /// For more information, see the [`ServerConfigBuilder`] documentation.
-    pub fn builder() -> ServerConfigBuilder<WantsBindAddress> {
-        ServerConfigBuilder::default()
+impl Default for ReceiveWindowConfig {
+    fn default() -> Self {
+        Self {
+            autotuning: true,
+            max_window: Self::DEFAULT_MAX_WINDOW,
+        }
     }
 }
 
@@ -45,33 +1631,136 @@ impl ServerConfigBuilder<WantsBindAddress> {
     /// Sets the binding (local) socket address for the endpoint.
     pub fn with_bind_address(self, address: SocketAddr) -> ServerConfigBuilder<WantsCertificate> {
         ServerConfigBuilder(WantsCertificate {
-            bind_address: address,
+            bind: Bind::Address(address),
+            client_auth: ClientAuth::Disabled,
+            key_log: false,
+        })
+    }
+
+    /// Uses an already-bound UDP socket for the endpoint, instead of binding one.
+    ///
+    /// This allows sockets with custom options (e.g., `SO_REUSEPORT`, DSCP marking),
+    /// or passed by the service manager (e.g., systemd socket activation).
+    pub fn with_socket(self, socket: UdpSocket) -> ServerConfigBuilder<WantsCertificate> {
+        ServerConfigBuilder(WantsCertificate {
+            bind: Bind::Socket(socket),
+            client_auth: ClientAuth::Disabled,
+            key_log: false,
         })
     }
 }
 
 impl ServerConfigBuilder<WantsCertificate> {
+    /// Requires clients to present a TLS certificate issued by one of
+    /// `roots` (mutual TLS).
+    ///
+    /// The certificates presented by the peer can be then inspected with
+    /// [`Connection::peer_certificates`](crate::Connection::peer_certificates).
+    ///
+    /// See [`ClientConfigBuilder::with_client_certificate`].
+    pub fn with_client_auth_roots(mut self, roots: RootCertStore) -> Self {
+        self.0.client_auth = ClientAuth::Roots(roots);
+        self
+    }
+
+    /// Requests (and verifies) a TLS certificate from clients with a custom verifier.
+    ///
+    /// See [`Self::with_client_auth_roots`].
+    #[cfg(feature = "dangerous-configuration")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dangerous-configuration")))]
+    pub fn with_client_cert_verifier(
+        mut self,
+        client_cert_verifier: Arc<dyn ClientCertVerifier>,
+    ) -> Self {
+        self.0.client_auth = ClientAuth::Verifier(client_cert_verifier);
+        self
+    }
+
+    /// Logs the TLS secrets of connections into the file named by the
+    /// `SSLKEYLOGFILE` environment variable (if set).
+    ///
+    /// This allows decrypting captured QUIC traffic, e.g., with Wireshark.
+    /// **Note**: it defeats the confidentiality of connections. Use only for debugging.
+    pub fn with_key_log(mut self) -> Self {
+        self.0.key_log = true;
+        self
+    }
+
     /// Sets the TLS certificate the server will present to incoming
     /// WebTransport connections.
     pub fn with_certificate(self, certificate: Certificate) -> ServerConfig {
-        let tls_config = Self::build_tls_config(certificate);
-        let quic_config = QuicServerConfig::with_crypto(Arc::new(tls_config));
+        let tls_config = Self::tls_config_builder(self.0.client_auth.clone())
+            .with_single_cert(certificate.certificates, certificate.key)
+            .unwrap(); // TODO(bfesta): handle this error
+
+        self.build(tls_config)
+    }
+
+    /// Uses a custom TLS configuration, instead of building one from a certificate.
+    ///
+    /// This allows, for example, custom cipher suites, OCSP stapling or a custom
+    /// certificate resolver. `tls_config` must support *TLS 1.3*, as required by QUIC.
+    ///
+    /// **Note**: ALPN protocols of `tls_config` are overridden, as WebTransport
+    /// connections are always negotiated as HTTP3.
+    pub fn with_custom_tls(self, tls_config: TlsServerConfig) -> ServerConfig {
+        self.build(tls_config)
+    }
+
+    /// Sets a resolver choosing the TLS certificate to present to each incoming
+    /// connection (e.g., based on the SNI of the client hello).
+    ///
+    /// This allows hosting multiple domains on the same endpoint. See, for example,
+    /// [`rustls::server::ResolvesServerCertUsingSni`] and [`Certificate::into_certified_key`].
+    pub fn with_certificate_resolver(self, resolver: Arc<dyn ResolvesServerCert>) -> ServerConfig {
+        let tls_config =
+            Self::tls_config_builder(self.0.client_auth.clone()).with_cert_resolver(resolver);
+
+        self.build(tls_config)
+    }
+
+    fn build(self, mut tls_config: TlsServerConfig) -> ServerConfig {
+        tls_config.alpn_protocols = [WEBTRANSPORT_ALPN.to_vec()].to_vec();
+
+        if self.0.key_log {
+            tls_config.key_log = Arc::new(KeyLogFile::new());
+        }
 
         ServerConfig {
-            quic_config,
-            bind_address: self.0.bind_address,
+            bind: self.0.bind,
+            additional_binds: Vec::new(),
+            tls_config: Arc::new(tls_config),
+            token_key: TokenKey::random(),
+            address_validation: AddressValidation::default(),
+            token_lifetime: ServerConfig::DEFAULT_TOKEN_LIFETIME,
+            max_connections: ServerConfig::DEFAULT_MAX_CONNECTIONS,
+            connection_options: ConnectionOptions::default(),
+            qlog: None,
+            metrics: None,
+            handlers: ServerHandlers::default(),
+            driver_runtime: DriverRuntime::default(),
+            packet_marking: PacketMarking::default(),
+            extra_settings: Arc::new(Settings::builder().build()),
+            #[cfg(feature = "mock-network")]
+            mock_network: None,
         }
     }
 
-    fn build_tls_config(certificate: Certificate) -> TlsServerConfig {
-        let mut config = TlsServerConfig::builder()
-            .with_safe_defaults()
-            .with_no_client_auth()
-            .with_single_cert(certificate.certificates, certificate.key)
-            .unwrap(); // TODO(bfesta): handle this error
+    fn tls_config_builder(
+        client_auth: ClientAuth,
+    ) -> ConfigBuilder<TlsServerConfig, WantsServerCert> {
+        let builder = TlsServerConfig::builder().with_safe_defaults();
 
-        config.alpn_protocols = [WEBTRANSPORT_ALPN.to_vec()].to_vec();
-        config
+        match client_auth {
+            ClientAuth::Disabled => builder.with_no_client_auth(),
+            ClientAuth::Roots(roots) => {
+                builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            }
+            #[cfg(feature = "dangerous-configuration")]
+            ClientAuth::Verifier(client_cert_verifier) => {
+                builder.with_client_cert_verifier(client_cert_verifier)
+            }
+        }
     }
 }
 
@@ -79,8 +1768,19 @@ impl ServerConfigBuilder<WantsCertificate> {
 ///
 /// Configuration can be created via [`ClientConfig::builder`] function.
 pub struct ClientConfig {
-    pub(crate) quic_config: QuicClientConfig,
-    pub(crate) bind_address: SocketAddr,
+    bind: Bind,
+    tls_config: Arc<TlsClientConfig>,
+    pub(crate) connection_options: ConnectionOptions,
+    pub(crate) qlog: Option<Arc<dyn QlogSink>>,
+    pub(crate) metrics: Option<Arc<dyn Metrics>>,
+    pub(crate) driver_runtime: DriverRuntime,
+    pub(crate) packet_marking: PacketMarking,
+    pub(crate) extra_settings: Arc<Settings>,
+    pub(crate) happy_eyeballs: HappyEyeballs,
+    pub(crate) resolver: Option<Resolver>,
+    proxy: Option<Proxy>,
+    #[cfg(feature = "mock-network")]
+    mock_network: Option<MockNetwork>,
 }
 
 impl ClientConfig {
@@ -90,6 +1790,499 @@ impl ClientConfig {
     pub fn builder() -> ClientConfigBuilder<WantsBindAddress> {
         ClientConfigBuilder::default()
     }
+
+    /// Whether receive windows should grow automatically, based on the observed
+    /// round-trip time and throughput of each connection.
+    ///
+    /// Default: `true`.
+    ///
+    /// See [`ReceiveWindowConfig`] for more details.
+    pub fn set_receive_window_autotuning(&mut self, enabled: bool) -> &mut Self {
+        self.connection_options.receive_window.autotuning = enabled;
+        self
+    }
+
+    /// Sets the maximum size (in bytes) receive windows can grow up to
+    /// when auto-tuning is enabled.
+    ///
+    /// Default: [`ReceiveWindowConfig::DEFAULT_MAX_WINDOW`].
+    pub fn set_max_receive_window(&mut self, max_window: u32) -> &mut Self {
+        self.connection_options.receive_window.max_window = max_window;
+        self
+    }
+
+    /// Sets the maximum duration of inactivity before the connection is timed out.
+    ///
+    /// The effective timeout is the minimum between this value and the one
+    /// advertised by the peer. [`None`] disables the local timeout.
+    ///
+    /// Default: [`TransportOptions::DEFAULT_MAX_IDLE_TIMEOUT`].
+    pub fn set_max_idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.connection_options.transport.max_idle_timeout = timeout;
+        self
+    }
+
+    /// Sets the period of inactivity after which a keep-alive packet is sent.
+    ///
+    /// It should be lower than the idle timeout, in order to keep idle
+    /// connections open.
+    ///
+    /// Default: [`None`] (keep-alive disabled).
+    pub fn set_keep_alive_interval(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.connection_options.transport.keep_alive_interval = interval;
+        self
+    }
+
+    /// Enables keep-alive packets whose interval adapts to the NAT timeouts
+    /// observed on the path.
+    ///
+    /// It works along with [`Self::set_keep_alive_interval`]: see
+    /// [`AdaptiveKeepAlive`] for more details.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_adaptive_keep_alive(
+        &mut self,
+        adaptive_keep_alive: Option<AdaptiveKeepAlive>,
+    ) -> &mut Self {
+        self.connection_options.adaptive_keep_alive = adaptive_keep_alive;
+        self
+    }
+
+    /// Sets a fixed size (in bytes) of the connection receive window.
+    ///
+    /// This disables receive window auto-tuning.
+    pub fn set_receive_window(&mut self, window: u32) -> &mut Self {
+        self.connection_options.transport.receive_window = Some(window);
+        self.connection_options.receive_window.autotuning = false;
+        self
+    }
+
+    /// Sets the size (in bytes) of the receive window of each stream.
+    ///
+    /// Default: [`ReceiveWindowConfig::max_window`] when auto-tuning is enabled,
+    /// the QUIC implementation default otherwise.
+    pub fn set_stream_receive_window(&mut self, window: u32) -> &mut Self {
+        self.connection_options.transport.stream_receive_window = Some(window);
+        self
+    }
+
+    /// Caps how much data (in bytes) is buffered for each receive stream the
+    /// application has not read yet, bounding the memory a slow consumer can pin.
+    ///
+    /// Once the cap is reached, no more flow-control credit is granted to
+    /// the peer on that stream until the application reads from it.
+    /// It prevails over [`Self::set_stream_receive_window`] and auto-tuning
+    /// (the stream receive window is set to the smaller value).
+    ///
+    /// Default: [`None`] (bounded by the stream receive window only).
+    pub fn set_max_unread_stream_data(&mut self, max_unread: Option<u32>) -> &mut Self {
+        self.connection_options.transport.max_unread_stream_data = max_unread;
+        self
+    }
+
+    /// Sets the maximum number of concurrent bi-directional streams the peer can open.
+    ///
+    /// Default: [`TransportOptions::DEFAULT_MAX_CONCURRENT_STREAMS`].
+    pub fn set_max_concurrent_bi_streams(&mut self, max_streams: u32) -> &mut Self {
+        self.connection_options.transport.max_concurrent_bi_streams = max_streams;
+        self
+    }
+
+    /// Sets the maximum number of concurrent uni-directional streams the peer can open.
+    ///
+    /// This does not include the streams HTTP3 itself requires (e.g., the control stream),
+    /// which are always allowed.
+    ///
+    /// Default: [`TransportOptions::DEFAULT_MAX_CONCURRENT_STREAMS`].
+    pub fn set_max_concurrent_uni_streams(&mut self, max_streams: u32) -> &mut Self {
+        self.connection_options.transport.max_concurrent_uni_streams = max_streams;
+        self
+    }
+
+    /// Sets the size (in bytes) of the outgoing datagram buffer.
+    ///
+    /// When the buffer is full, [`Connection::send_datagram`](crate::Connection::send_datagram)
+    /// discards the oldest datagrams, while
+    /// [`Connection::send_datagram_wait`](crate::Connection::send_datagram_wait) waits for room.
+    ///
+    /// Values below [`TransportOptions::MIN_DATAGRAM_SEND_BUFFER_SIZE`] are raised to it.
+    ///
+    /// Default: 1 MiB.
+    pub fn set_datagram_send_buffer_size(&mut self, size: usize) -> &mut Self {
+        self.connection_options.transport.datagram_send_buffer_size =
+            Some(size.max(TransportOptions::MIN_DATAGRAM_SEND_BUFFER_SIZE));
+        self
+    }
+
+    /// Enables or disables path MTU discovery (DPLPMTUD, RFC 8899).
+    ///
+    /// When enabled, larger UDP payloads are probed periodically, so that
+    /// datagrams up to the path MTU can be sent (see
+    /// [`Connection::path_mtu`](crate::Connection::path_mtu)). When disabled,
+    /// the initial MTU is used for the whole connection.
+    ///
+    /// Default: enabled.
+    pub fn set_mtu_discovery(&mut self, enabled: bool) -> &mut Self {
+        self.connection_options.transport.mtu_discovery = enabled;
+        self
+    }
+
+    /// Sets the UDP payload size (in bytes) used before any MTU is discovered.
+    ///
+    /// It should be raised only if the network is known to support larger
+    /// payloads: packets exceeding the path MTU are lost until the MTU is
+    /// discovered again. Values below [`TransportOptions::MIN_MTU`] are raised to it.
+    ///
+    /// Default: [`TransportOptions::MIN_MTU`].
+    pub fn set_initial_mtu(&mut self, mtu: u16) -> &mut Self {
+        self.connection_options.transport.initial_mtu = Some(mtu.max(TransportOptions::MIN_MTU));
+        self
+    }
+
+    /// Sets the largest UDP payload size (in bytes) probed by MTU discovery.
+    ///
+    /// Default: 1452 (Ethernet MTU minus IPv6 and UDP headers).
+    pub fn set_max_udp_payload_size(&mut self, size: u16) -> &mut Self {
+        self.connection_options.transport.max_udp_payload_size =
+            Some(size.max(TransportOptions::MIN_MTU));
+        self
+    }
+
+    /// Enables detection of unresponsive peers.
+    ///
+    /// Keep-alive PINGs are sent (at least) every heartbeat interval, and the
+    /// peer is considered silent when nothing is received for a number of them.
+    /// This detects failures much faster than the idle timeout.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_heartbeat(&mut self, heartbeat: Option<HeartbeatConfig>) -> &mut Self {
+        self.connection_options.heartbeat = heartbeat;
+        self
+    }
+
+    /// Spreads bursts of outgoing datagrams over `interval`, instead of
+    /// sending them all at once.
+    ///
+    /// For example, a game server sending a burst of updates every tick
+    /// (e.g., 60 ticks per second) can set `interval` to the tick duration,
+    /// so that the updates are smoothed across the frame. This avoids
+    /// inducing losses with micro-bursts.
+    ///
+    /// Datagrams sent when the pacing queue is full are discarded.
+    ///
+    /// When multiple sessions share the connection, the queued datagrams of
+    /// different sessions are sent round-robin, so that a session's burst
+    /// does not delay the others. Without pacing, datagrams are sent in order.
+    ///
+    /// Default: [`None`] (no pacing).
+    pub fn set_datagram_pacing(&mut self, interval: Option<Duration>) -> &mut Self {
+        self.connection_options.datagram_pacing = interval;
+        self
+    }
+
+    /// Sets the *grease* sent to the peer on every connection.
+    ///
+    /// See [`Grease`] for more details.
+    ///
+    /// Default: [`Grease::all`].
+    pub fn set_grease(&mut self, grease: Grease) -> &mut Self {
+        self.connection_options.grease = grease;
+        self
+    }
+
+    /// Sets the maximum size (in bytes) of the encoded header blocks received
+    /// from the peer (requests, responses and trailers).
+    ///
+    /// Larger header blocks are not read: the connection is closed with
+    /// `H3_EXCESSIVE_LOAD`.
+    ///
+    /// Default: [`ParseLimits::DEFAULT_MAX_HEADER_BLOCK_SIZE`] (64 KiB).
+    pub fn set_max_header_block_size(&mut self, max_size: usize) -> &mut Self {
+        self.connection_options.parse_limits = self
+            .connection_options
+            .parse_limits
+            .with_max_header_block_size(max_size);
+        self
+    }
+
+    /// Sets the maximum number of fields in the header blocks received from
+    /// the peer.
+    ///
+    /// Header blocks with more fields close the connection with
+    /// `H3_EXCESSIVE_LOAD`.
+    ///
+    /// Default: [`ParseLimits::DEFAULT_MAX_HEADER_COUNT`].
+    pub fn set_max_header_count(&mut self, max_count: usize) -> &mut Self {
+        self.connection_options.parse_limits = self
+            .connection_options
+            .parse_limits
+            .with_max_header_count(max_count);
+        self
+    }
+
+    /// Sets the maximum number of entries in the SETTINGS frame of the peer.
+    ///
+    /// A larger SETTINGS frame closes the connection with `H3_EXCESSIVE_LOAD`.
+    ///
+    /// Default: [`ParseLimits::DEFAULT_MAX_SETTINGS_ENTRIES`].
+    pub fn set_max_settings_entries(&mut self, max_entries: usize) -> &mut Self {
+        self.connection_options.parse_limits = self
+            .connection_options
+            .parse_limits
+            .with_max_settings_entries(max_entries);
+        self
+    }
+
+    /// Sets how streams and datagrams of sessions not established yet are
+    /// buffered.
+    ///
+    /// See [`PendingSessionBuffer`] for more details.
+    pub fn set_pending_session_buffer(&mut self, buffer: PendingSessionBuffer) -> &mut Self {
+        self.connection_options.pending_sessions = buffer;
+        self
+    }
+
+    /// Sets the size (in bytes) of the memory pool outgoing datagrams are
+    /// encoded in, for each connection.
+    ///
+    /// The memory of sent datagrams is reused for the next ones, instead of
+    /// allocating a buffer for every datagram. Datagrams larger than the pool
+    /// are allocated separately. `0` disables pooling.
+    ///
+    /// Default: 64 KiB.
+    pub fn set_buffer_pool_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.connection_options.buffer_pool_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the session-level flow control granted to the peer.
+    ///
+    /// See [`SessionFlowControl`] for more details. The limits advertised by
+    /// the peer are always respected: opening streams and writing data wait
+    /// for credit (see [`BlockedLimit::Session`](crate::stream::BlockedLimit::Session)).
+    ///
+    /// Default: [`None`] (the peer is not limited at the session level).
+    pub fn set_session_flow_control(
+        &mut self,
+        flow_control: Option<SessionFlowControl>,
+    ) -> &mut Self {
+        self.connection_options.session_flow_control = flow_control;
+        self
+    }
+
+    /// Sets the priority outgoing streams are opened (or accepted) with.
+    ///
+    /// See [`SendStream::set_priority`](crate::SendStream::set_priority).
+    ///
+    /// Default: `0`.
+    pub fn set_default_stream_priority(&mut self, priority: i32) -> &mut Self {
+        self.connection_options.default_stream_priority = priority;
+        self
+    }
+
+    /// Sets whether connecting waits for the server's SETTINGS before
+    /// the connection is established.
+    ///
+    /// Default: [`SettingsWait::Required`].
+    pub fn set_settings_wait(&mut self, settings_wait: SettingsWait) -> &mut Self {
+        self.connection_options.settings_wait = settings_wait;
+        self
+    }
+
+    /// Enables 0-RTT: when reconnecting to a server, the session is requested
+    /// as TLS early data, saving a round-trip.
+    ///
+    /// This applies when the TLS session of a previous connection to the same
+    /// server can be resumed, and the server accepts early data (see
+    /// [`ServerConfig::set_early_data`]). The server's SETTINGS are then
+    /// validated in background (as with [`SettingsWait::Optimistic`]).
+    /// If the server rejects the early data, a new connection is transparently
+    /// made, with a regular handshake.
+    ///
+    /// See [`Connection::is_0rtt`](crate::Connection::is_0rtt).
+    ///
+    /// Default: `false`.
+    pub fn set_early_data(&mut self, enabled: bool) -> &mut Self {
+        self.connection_options.early_data = enabled;
+        self
+    }
+
+    /// Sets where TLS sessions are stored, for resuming them when
+    /// reconnecting to the same server (see [`ServerConfig::set_session_tickets`]).
+    ///
+    /// Custom storage (e.g., shared by multiple endpoints) can be provided
+    /// with [`rustls::client::Resumption::store`], implementing
+    /// [`rustls::client::ClientSessionStore`].
+    /// [`rustls::client::Resumption::disabled`] disables session resumption.
+    ///
+    /// Default: in-memory storage of up to 256 sessions, per endpoint.
+    pub fn set_session_resumption(&mut self, resumption: rustls::client::Resumption) -> &mut Self {
+        Arc::make_mut(&mut self.tls_config).resumption = resumption;
+        self
+    }
+
+    /// Records a [qlog](https://datatracker.ietf.org/doc/draft-ietf-quic-qlog-main-schema/)
+    /// trace of each connection into `sink`.
+    ///
+    /// Traces can be visualized with [qvis](https://qvis.quictools.info/).
+    /// See [`QlogSink`] for more details.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_qlog(&mut self, sink: Option<Arc<dyn QlogSink>>) -> &mut Self {
+        self.qlog = sink;
+        self
+    }
+
+    /// Reports the countable events of the endpoint's connections
+    /// (handshakes, sessions, streams, datagrams) to `metrics`.
+    ///
+    /// See [`Metrics`] for more details.
+    ///
+    /// Default: [`None`] (disabled).
+    pub fn set_metrics(&mut self, metrics: Option<Arc<dyn Metrics>>) -> &mut Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Selects the runtime the endpoint driver (i.e., the task
+    /// sending and receiving UDP packets) and its timers run on.
+    ///
+    /// Latency-critical deployments can isolate packet processing from
+    /// application work with [`DriverRuntime::Dedicated`], or with
+    /// [`DriverRuntime::Handle`] pointing to a runtime whose worker threads
+    /// are set up by the application (e.g., pinned to specific CPUs).
+    ///
+    /// Default: [`DriverRuntime::Current`].
+    pub fn set_driver_runtime(&mut self, driver_runtime: DriverRuntime) -> &mut Self {
+        self.driver_runtime = driver_runtime;
+        self
+    }
+
+    /// Sets the DSCP (Differentiated Services Code Point) of outgoing
+    /// packets, for QoS classification on managed networks (e.g., `46` for
+    /// *Expedited Forwarding*). Only the 6 least significant bits are used.
+    ///
+    /// It applies to the sockets bound by the endpoint: not to proxy tunnels,
+    /// nor to sockets passed to [`Endpoint::rebind`](crate::Endpoint::rebind).
+    ///
+    /// Default: [`None`] (DSCP `0`, best effort).
+    pub fn set_dscp(&mut self, dscp: Option<u8>) -> &mut Self {
+        self.packet_marking.dscp = dscp.map(|dscp| dscp & 0b11_1111);
+        self
+    }
+
+    /// Enables or disables ECN (Explicit Congestion Notification) marking of
+    /// outgoing packets.
+    ///
+    /// When enabled, QUIC marks packets as ECN-capable as long as the path
+    /// delivers the marks correctly, so that routers can signal congestion
+    /// instead of dropping packets. Codepoints of received packets are
+    /// reported by [`ConnectionStats::ecn`](crate::connection::ConnectionStats::ecn)
+    /// in any case (where the platform supports it).
+    ///
+    /// Default: enabled.
+    pub fn set_ecn(&mut self, enabled: bool) -> &mut Self {
+        self.packet_marking.ecn = enabled;
+        self
+    }
+
+    /// Attaches the endpoint to an in-process network instead of a UDP socket.
+    ///
+    /// The bind address is virtual: it identifies the endpoint in `network`.
+    /// See [`MockNetwork`].
+    ///
+    /// Default: [`None`] (UDP socket).
+    #[cfg(feature = "mock-network")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mock-network")))]
+    pub fn set_mock_network(&mut self, network: Option<MockNetwork>) -> &mut Self {
+        self.mock_network = network;
+        self
+    }
+
+    /// Adds (or overrides) HTTP3 SETTINGS sent to the peer on every connection,
+    /// e.g., *grease* or experimental settings for interoperability testing.
+    ///
+    /// The defaults enable WebTransport and HTTP3 datagrams. QPACK settings
+    /// cannot be overridden: the dynamic table is not supported.
+    ///
+    /// The SETTINGS received from the peer are available with
+    /// [`Connection::peer_settings`](crate::Connection::peer_settings).
+    pub fn set_extra_settings(&mut self, settings: Settings) -> &mut Self {
+        self.extra_settings = Arc::new(settings);
+        self
+    }
+
+    /// Sets how the addresses of a host name are raced by
+    /// [`Endpoint::connect_host`](crate::Endpoint::connect_host).
+    ///
+    /// Default: [`HappyEyeballs::default`].
+    pub fn set_happy_eyeballs(&mut self, happy_eyeballs: HappyEyeballs) -> &mut Self {
+        self.happy_eyeballs = happy_eyeballs;
+        self
+    }
+
+    /// Resolves host names with `resolver` in
+    /// [`Endpoint::connect_host`](crate::Endpoint::connect_host), instead of
+    /// the system resolver.
+    ///
+    /// `resolver` is invoked with the host name and the port, and returns the
+    /// addresses to connect to (e.g., looked up with DNS-over-HTTPS or a
+    /// service-discovery system). An error, or no address, fails the connection
+    /// with [`ConnectionError::UnresolvedHost`](crate::error::ConnectionError::UnresolvedHost).
+    ///
+    /// Default: the system resolver (queried on a blocking thread).
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use wtransport::ClientConfig;
+    /// # fn run(mut config: ClientConfig) {
+    /// config.set_resolver(|host, port| async move {
+    ///     match host.as_str() {
+    ///         "game.internal" => Ok(vec![([10, 0, 0, 7], port).into()]),
+    ///         _ => Err(std::io::ErrorKind::NotFound.into()),
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn set_resolver<F, Fut>(&mut self, resolver: F) -> &mut Self
+    where
+        F: Fn(String, u16) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::io::Result<Vec<SocketAddr>>> + Send + 'static,
+    {
+        self.resolver = Some(Arc::new(move |host, port| Box::pin(resolver(host, port))));
+        self
+    }
+
+    pub(crate) fn driver_socket(&self) -> std::io::Result<DriverSocket> {
+        #[cfg(feature = "mock-network")]
+        if let Some(network) = &self.mock_network {
+            return Ok(DriverSocket::Mock(network.clone(), self.bind.address()?));
+        }
+
+        if let Some(proxy) = &self.proxy {
+            return Ok(DriverSocket::Proxy(
+                proxy.clone(),
+                self.tls_config.clone(),
+                self.bind.udp_socket()?,
+            ));
+        }
+
+        self.bind.driver_socket()
+    }
+
+    pub(crate) fn quic_config(&self) -> QuicClientConfig {
+        let tls_config = if self.connection_options.early_data {
+            let mut tls_config = (*self.tls_config).clone();
+            tls_config.enable_early_data = true;
+            Arc::new(tls_config)
+        } else {
+            self.tls_config.clone()
+        };
+
+        let mut quic_config = QuicClientConfig::new(tls_config);
+        quic_config.transport_config(Arc::new(self.connection_options.transport_config()));
+        quic_config
+    }
 }
 
 /// Client builder configuration.
@@ -110,38 +2303,137 @@ impl ClientConfigBuilder<WantsBindAddress> {
     /// Sets the binding (local) socket address for the endpoint.
     pub fn with_bind_address(self, address: SocketAddr) -> ClientConfigBuilder<WantsRootStore> {
         ClientConfigBuilder(WantsRootStore {
-            bind_address: address,
+            bind: Bind::Address(address),
+            client_certificate: None,
+            key_log: false,
+            proxy: None,
+        })
+    }
+
+    /// Uses an already-bound UDP socket for the endpoint, instead of binding one.
+    ///
+    /// See [`ServerConfigBuilder::with_socket`].
+    pub fn with_socket(self, socket: UdpSocket) -> ClientConfigBuilder<WantsRootStore> {
+        ClientConfigBuilder(WantsRootStore {
+            bind: Bind::Socket(socket),
+            client_certificate: None,
+            key_log: false,
+            proxy: None,
         })
     }
 }
 
 impl ClientConfigBuilder<WantsRootStore> {
+    /// Sets the TLS certificate the client will present when the server
+    /// requests client authentication (mutual TLS).
+    ///
+    /// It returns [`Err`] if the private key type is not supported.
+    ///
+    /// See [`ServerConfigBuilder::with_client_auth_roots`].
+    pub fn with_client_certificate(
+        mut self,
+        certificate: Certificate,
+    ) -> Result<Self, rustls::Error> {
+        self.0.client_certificate = Some(Arc::new(certificate.into_certified_key()?));
+        Ok(self)
+    }
+
+    /// Logs the TLS secrets of connections into the file named by the
+    /// `SSLKEYLOGFILE` environment variable (if set).
+    ///
+    /// This allows decrypting captured QUIC traffic, e.g., with Wireshark.
+    /// **Note**: it defeats the confidentiality of connections. Use only for debugging.
+    pub fn with_key_log(mut self) -> Self {
+        self.0.key_log = true;
+        self
+    }
+
+    /// Connects to servers through an HTTP proxy, tunneling QUIC packets with
+    /// *CONNECT-UDP* ([RFC 9298](https://www.rfc-editor.org/rfc/rfc9298)).
+    ///
+    /// The proxy is reached with HTTP/3 from the bind address of the endpoint,
+    /// and its certificate is validated like the ones of servers. `credentials`
+    /// (if any) are sent in the `Proxy-Authorization` header of every tunnel request.
+    ///
+    /// The proxy host is resolved when the endpoint is created
+    /// (see [`Endpoint::client`](crate::Endpoint::client)). **Note**: after
+    /// [`Endpoint::rebind`](crate::Endpoint::rebind), packets are sent directly
+    /// through the new socket, bypassing the proxy.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use std::net::Ipv4Addr;
+    /// # use std::net::SocketAddr;
+    /// use wtransport::proxy::ProxyCredentials;
+    /// use wtransport::ClientConfig;
+    ///
+    /// let config = ClientConfig::builder()
+    ///     .with_bind_address(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0))
+    ///     .with_proxy(
+    ///         "https://proxy.example.com".parse().unwrap(),
+    ///         Some(ProxyCredentials::basic("user", "password")),
+    ///     )
+    ///     .with_native_certs();
+    /// ```
+    pub fn with_proxy(mut self, url: ProxyUrl, credentials: Option<ProxyCredentials>) -> Self {
+        self.0.proxy = Some(Proxy { url, credentials });
+        self
+    }
+
     /// Loads local (native) root certificate for server validation.
-    pub fn with_native_certs(self) -> ClientConfig {
-        let tls_config = Self::build_tls_config(Self::native_cert_store());
-        let quic_config = QuicClientConfig::new(Arc::new(tls_config));
+    pub fn with_native_certs(mut self) -> ClientConfig {
+        let tls_config = self.build_tls_config(Self::native_cert_store());
+        self.build(tls_config)
+    }
 
-        ClientConfig {
-            quic_config,
-            bind_address: self.0.bind_address,
-        }
+    /// Trusts the server only if it presents a certificate whose SHA-256
+    /// digest is among `hashes`, instead of validating it against root CAs.
+    ///
+    /// This is the equivalent of the `serverCertificateHashes` option of the
+    /// WebTransport browser API, and it applies the same constraints: the
+    /// certificate must be *X.509v3*, with an *ECDSA* public key, and its
+    /// validity period must include the current time and be at most two weeks long.
+    ///
+    /// See [`Certificate::hashes`].
+    #[cfg(feature = "dangerous-configuration")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dangerous-configuration")))]
+    pub fn with_server_certificate_hashes<I>(mut self, hashes: I) -> ClientConfig
+    where
+        I: IntoIterator<Item = Sha256Digest>,
+    {
+        let mut tls_config = self.build_tls_config(RootCertStore::empty());
+        tls_config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(ServerHashVerification::new(
+                hashes.into_iter().collect(),
+            )));
+
+        self.build(tls_config)
+    }
+
+    /// Uses a custom TLS configuration, instead of building one from a root store.
+    ///
+    /// This allows, for example, custom cipher suites or a custom server certificate
+    /// verifier. `tls_config` must support *TLS 1.3*, as required by QUIC.
+    ///
+    /// **Note**: ALPN protocols of `tls_config` are overridden, as WebTransport
+    /// connections are always negotiated as HTTP3. The client certificate set with
+    /// [`Self::with_client_certificate`] (if any) is ignored: client authentication is
+    /// part of `tls_config`.
+    pub fn with_custom_tls(self, tls_config: TlsClientConfig) -> ClientConfig {
+        self.build(tls_config)
     }
 
     /// Skip certificate server validation.
     #[cfg(feature = "dangerous-configuration")]
     #[cfg_attr(docsrs, doc(cfg(feature = "dangerous-configuration")))]
-    pub fn with_no_cert_validation(self) -> ClientConfig {
-        let mut tls_config = Self::build_tls_config(RootCertStore::empty());
+    pub fn with_no_cert_validation(mut self) -> ClientConfig {
+        let mut tls_config = self.build_tls_config(RootCertStore::empty());
         tls_config
             .dangerous()
             .set_certificate_verifier(Arc::new(dangerous_configuration::NoServerVerification));
 
-        let quic_config = QuicClientConfig::new(Arc::new(tls_config));
-
-        ClientConfig {
-            quic_config,
-            bind_address: self.0.bind_address,
-        }
+        self.build(tls_config)
     }
 
     fn native_cert_store() -> RootCertStore {
@@ -159,17 +2451,44 @@ impl ClientConfigBuilder<WantsRootStore> {
         root_store
     }
 
-    fn build_tls_config(root_store: RootCertStore) -> TlsClientConfig {
-        let mut config = TlsClientConfig::builder()
+    fn build(self, mut tls_config: TlsClientConfig) -> ClientConfig {
+        tls_config.alpn_protocols = [WEBTRANSPORT_ALPN.to_vec()].to_vec();
+
+        if self.0.key_log {
+            tls_config.key_log = Arc::new(KeyLogFile::new());
+        }
+
+        ClientConfig {
+            bind: self.0.bind,
+            tls_config: Arc::new(tls_config),
+            connection_options: ConnectionOptions::default(),
+            qlog: None,
+            metrics: None,
+            driver_runtime: DriverRuntime::default(),
+            packet_marking: PacketMarking::default(),
+            extra_settings: Arc::new(Settings::builder().build()),
+            happy_eyeballs: HappyEyeballs::default(),
+            resolver: None,
+            proxy: self.0.proxy,
+            #[cfg(feature = "mock-network")]
+            mock_network: None,
+        }
+    }
+
+    fn build_tls_config(&mut self, root_store: RootCertStore) -> TlsClientConfig {
+        let builder = TlsClientConfig::builder()
             .with_safe_default_cipher_suites()
             .with_safe_default_kx_groups()
             .with_safe_default_protocol_versions()
             .expect("Safe protocols should not error")
-            .with_root_certificates(root_store)
-            .with_no_client_auth();
+            .with_root_certificates(root_store);
 
-        config.alpn_protocols = [WEBTRANSPORT_ALPN.to_vec()].to_vec();
-        config
+        match self.0.client_certificate.take() {
+            Some(certified_key) => {
+                builder.with_client_cert_resolver(Arc::new(ClientCertificate::new(certified_key)))
+            }
+            None => builder.with_no_client_auth(),
+        }
     }
 }
 
@@ -188,14 +2507,59 @@ impl Default for ClientConfigBuilder<WantsBindAddress> {
 /// Config builder state where the caller must supply binding address.
 pub struct WantsBindAddress {}
 
+/// How the endpoint gets its UDP socket.
+enum Bind {
+    Address(SocketAddr),
+    Socket(UdpSocket),
+}
+
+impl Bind {
+    #[cfg(feature = "mock-network")]
+    fn address(&self) -> std::io::Result<SocketAddr> {
+        match self {
+            Bind::Address(address) => Ok(*address),
+            Bind::Socket(socket) => socket.local_addr(),
+        }
+    }
+
+    fn udp_socket(&self) -> std::io::Result<UdpSocket> {
+        match self {
+            Bind::Address(address) => UdpSocket::bind(address),
+            Bind::Socket(socket) => socket.try_clone(),
+        }
+    }
+
+    fn driver_socket(&self) -> std::io::Result<DriverSocket> {
+        match self {
+            Bind::Address(address) => Ok(DriverSocket::Udp(*address)),
+            // The configuration only lends the socket: the endpoint gets its own handle
+            Bind::Socket(socket) => Ok(DriverSocket::Bound(socket.try_clone()?)),
+        }
+    }
+}
+
 /// Config builder state where the caller must supply TLS certificate.
 pub struct WantsCertificate {
-    bind_address: SocketAddr,
+    bind: Bind,
+    client_auth: ClientAuth,
+    key_log: bool,
+}
+
+/// How the server authenticates clients.
+#[derive(Clone)]
+enum ClientAuth {
+    Disabled,
+    Roots(RootCertStore),
+    #[cfg(feature = "dangerous-configuration")]
+    Verifier(Arc<dyn ClientCertVerifier>),
 }
 
 /// Config builder state where the caller must supply TLS root store.
 pub struct WantsRootStore {
-    bind_address: SocketAddr,
+    bind: Bind,
+    client_certificate: Option<Arc<CertifiedKey>>,
+    key_log: bool,
+    proxy: Option<Proxy>,
 }
 
 #[cfg(feature = "dangerous-configuration")]
@@ -1,100 +1,579 @@
+use crate::accept::SessionRequest;
+use crate::capsule::CapsuleChannel;
+use crate::capsule::CapsuleType;
+use crate::config::ConnectionOptions;
+use crate::config::SendRateLimit;
+use crate::config::ServerHandlers;
+use crate::config::SessionPolicy;
+use crate::config::SettingsWait;
 use crate::datagram::Datagram;
+use crate::datagram::DatagramChannel;
+use crate::datagram::DatagramChannels;
+use crate::datagram::DatagramOptions;
+use crate::datagram::Datagrams;
+use crate::endpoint::ConnectionTracker;
+use crate::engine::demux::SessionQueues;
 use crate::engine::session::Session;
+use crate::engine::worker::WorkerError;
 use crate::engine::Engine;
 use crate::error::ConnectionError;
 use crate::error::DatagramError;
+use crate::error::ExportKeyingMaterialError;
+use crate::error::H3Error;
+use crate::error::RegisterError;
+use crate::error::RejectionReason;
+use crate::error::SendDeadlineError;
+use crate::error::StreamError;
+use crate::error::TimeoutError;
+use crate::events::Events;
+use crate::extension::ExtensionFrameChannel;
+use crate::qlog::QlogSink;
+use crate::qlog::QlogTrace;
+use crate::qlog::VantagePoint;
+use crate::runtime;
 use crate::stream::RecvStream;
 use crate::stream::SendStream;
+use crate::trace;
+use futures_core::Stream;
+use std::future::pending;
+use std::future::poll_fn;
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Weak;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
+use wtransport_proto::capsule::Capsule;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::FrameKind;
+use wtransport_proto::headers::Headers;
 use wtransport_proto::ids::SessionId;
+use wtransport_proto::settings::Dialect;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::varint::VarInt;
+
+/// Delivery guarantee of a message sent with [`Connection::send_message`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Reliability {
+    /// The message is sent as a datagram if it fits, hence it might be lost.
+    BestEffort,
+
+    /// The message is always sent on a stream, hence it is delivered reliably.
+    Reliable,
+}
+
+/// Statistics of a connection, see [`Connection::stats`].
+///
+/// Statistics refer to the whole QUIC connection, hence they are shared
+/// among the sessions pooled on it.
+#[derive(Copy, Clone, Debug)]
+pub struct ConnectionStats {
+    pub(crate) rtt: Duration,
+    pub(crate) congestion_window: u64,
+    pub(crate) congestion_events: u64,
+    pub(crate) sent_packets: u64,
+    pub(crate) lost_packets: u64,
+    pub(crate) lost_bytes: u64,
+    pub(crate) sent_bytes: u64,
+    pub(crate) received_bytes: u64,
+    pub(crate) dropped_datagrams: u64,
+    pub(crate) opened_bi_streams: u64,
+    pub(crate) opened_uni_streams: u64,
+    pub(crate) accepted_bi_streams: u64,
+    pub(crate) accepted_uni_streams: u64,
+    pub(crate) reserved_streams: u64,
+    pub(crate) reserved_frames: u64,
+    pub(crate) ecn: EcnCounts,
+}
+
+impl ConnectionStats {
+    /// The current best estimate of the round-trip time.
+    #[inline(always)]
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+
+    /// The current congestion window, in bytes.
+    #[inline(always)]
+    pub fn congestion_window(&self) -> u64 {
+        self.congestion_window
+    }
+
+    /// The number of congestion events.
+    #[inline(always)]
+    pub fn congestion_events(&self) -> u64 {
+        self.congestion_events
+    }
+
+    /// The number of packets sent.
+    #[inline(always)]
+    pub fn sent_packets(&self) -> u64 {
+        self.sent_packets
+    }
+
+    /// The number of packets declared lost.
+    #[inline(always)]
+    pub fn lost_packets(&self) -> u64 {
+        self.lost_packets
+    }
+
+    /// The number of bytes declared lost.
+    #[inline(always)]
+    pub fn lost_bytes(&self) -> u64 {
+        self.lost_bytes
+    }
+
+    /// The number of bytes sent over UDP (including headers and retransmissions).
+    #[inline(always)]
+    pub fn sent_bytes(&self) -> u64 {
+        self.sent_bytes
+    }
+
+    /// The number of bytes received over UDP.
+    #[inline(always)]
+    pub fn received_bytes(&self) -> u64 {
+        self.received_bytes
+    }
+
+    /// The number of application datagrams discarded locally.
+    ///
+    /// This includes incoming datagrams not delivered because their session
+    /// was gone or its queue was full, and outgoing paced datagrams discarded
+    /// (see [`ServerConfig::set_datagram_pacing`](crate::ServerConfig::set_datagram_pacing)).
+    #[inline(always)]
+    pub fn dropped_datagrams(&self) -> u64 {
+        self.dropped_datagrams
+    }
+
+    /// The number of bi-directional streams opened.
+    #[inline(always)]
+    pub fn opened_bi_streams(&self) -> u64 {
+        self.opened_bi_streams
+    }
+
+    /// The number of uni-directional streams opened.
+    #[inline(always)]
+    pub fn opened_uni_streams(&self) -> u64 {
+        self.opened_uni_streams
+    }
+
+    /// The number of bi-directional streams accepted.
+    #[inline(always)]
+    pub fn accepted_bi_streams(&self) -> u64 {
+        self.accepted_bi_streams
+    }
+
+    /// The number of uni-directional streams accepted.
+    #[inline(always)]
+    pub fn accepted_uni_streams(&self) -> u64 {
+        self.accepted_uni_streams
+    }
+
+    /// The number of uni-directional streams of reserved type received.
+    ///
+    /// Reserved (*grease*) types exercise the peer's handling of unknown
+    /// types: these streams are discarded.
+    #[inline(always)]
+    pub fn reserved_streams(&self) -> u64 {
+        self.reserved_streams
+    }
+
+    /// The number of frames of reserved type received (and discarded).
+    #[inline(always)]
+    pub fn reserved_frames(&self) -> u64 {
+        self.reserved_frames
+    }
+
+    /// The ECN codepoints of the packets received from the peer.
+    #[inline(always)]
+    pub fn ecn(&self) -> EcnCounts {
+        self.ecn
+    }
+}
+
+/// Number of packets received with each ECN codepoint, see [`ConnectionStats::ecn`].
+///
+/// Packets without ECN marking (or received where the platform does not
+/// report the codepoint) are not counted.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EcnCounts {
+    pub(crate) ect0: u64,
+    pub(crate) ect1: u64,
+    pub(crate) ce: u64,
+}
+
+impl EcnCounts {
+    /// The number of packets marked ECT(0).
+    #[inline(always)]
+    pub fn ect0(&self) -> u64 {
+        self.ect0
+    }
+
+    /// The number of packets marked ECT(1).
+    #[inline(always)]
+    pub fn ect1(&self) -> u64 {
+        self.ect1
+    }
+
+    /// The number of packets marked CE (congestion experienced).
+    #[inline(always)]
+    pub fn ce(&self) -> u64 {
+        self.ce
+    }
+}
 
 /// [`Future`] for an in-progress connection attempt.
-pub struct Connecting(
-    Pin<Box<dyn Future<Output = Result<Connection, ConnectionError>> + Send + Sync>>,
-);
+pub struct Connecting {
+    remote_address: SocketAddr,
+    future: Pin<Box<dyn Future<Output = Result<Connection, ConnectionError>> + Send + Sync>>,
+}
+
+/// Opens a new QUIC connection to the same server, when a 0-RTT attempt is rejected.
+pub(crate) type Reconnect =
+    Box<dyn FnOnce() -> Result<quinn::Connecting, ConnectionError> + Send + Sync>;
 
 impl Connecting {
-    pub(crate) fn new(is_server: bool, quic_connecting: quinn::Connecting) -> Self {
-        if is_server {
-            Self(Box::pin(async {
-                Self::connect_as_server(quic_connecting).await
-            }))
-        } else {
-            Self(Box::pin(async {
-                Self::connect_as_client(quic_connecting).await
-            }))
+    pub(crate) fn server(
+        quic_connecting: quinn::Connecting,
+        connection_options: ConnectionOptions,
+        qlog: Option<Arc<dyn QlogSink>>,
+        handlers: ServerHandlers,
+        tracker: Arc<ConnectionTracker>,
+        extra_settings: Arc<Settings>,
+    ) -> Self {
+        let remote_address = quic_connecting.remote_address();
+        let metrics = tracker.metrics().clone();
+
+        let future = Box::pin(trace::connection_span(
+            async move {
+                let result = Self::connect_as_server(
+                    quic_connecting,
+                    connection_options,
+                    qlog,
+                    handlers,
+                    tracker,
+                    extra_settings,
+                )
+                .await;
+
+                if let Err(error) = &result {
+                    trace::connection_failed(error);
+                    metrics.handshake_failed(error.into());
+                }
+
+                result
+            },
+            "server",
+            remote_address,
+        ));
+
+        Self {
+            remote_address,
+            future,
+        }
+    }
+
+    /// A client connection attempt.
+    ///
+    /// With `reconnect`, the session is requested in 0-RTT (if a TLS session
+    /// can be resumed), falling back to a new connection if the server rejects it.
+    pub(crate) fn client(
+        quic_connecting: quinn::Connecting,
+        reconnect: Option<Reconnect>,
+        connection_options: ConnectionOptions,
+        qlog: Option<Arc<dyn QlogSink>>,
+        tracker: Arc<ConnectionTracker>,
+        extra_settings: Arc<Settings>,
+    ) -> Self {
+        let remote_address = quic_connecting.remote_address();
+        let metrics = tracker.metrics().clone();
+
+        let future = Box::pin(trace::connection_span(
+            async move {
+                let result = Self::connect_as_client(
+                    quic_connecting,
+                    reconnect,
+                    connection_options,
+                    qlog,
+                    tracker,
+                    extra_settings,
+                )
+                .await;
+
+                if let Err(error) = &result {
+                    trace::connection_failed(error);
+                    metrics.handshake_failed(error.into());
+                }
+
+                result
+            },
+            "client",
+            remote_address,
+        ));
+
+        Self {
+            remote_address,
+            future,
         }
     }
 
+    /// Waits for the connection to be established up to `timeout`.
+    ///
+    /// On [`TimeoutError::Elapsed`], the connection attempt is abandoned.
+    pub async fn with_timeout(
+        self,
+        timeout: Duration,
+    ) -> Result<Connection, TimeoutError<ConnectionError>> {
+        Connection::timeout(timeout, self).await
+    }
+
+    /// Returns the peer's UDP address.
+    ///
+    /// On *server* endpoints, this allows to filter incoming connection
+    /// attempts (e.g., by dropping them) before the handshake completes.
+    #[inline(always)]
+    pub fn remote_address(&self) -> SocketAddr {
+        self.remote_address
+    }
+
     async fn connect_as_server(
         quic_connecting: quinn::Connecting,
+        connection_options: ConnectionOptions,
+        qlog: Option<Arc<dyn QlogSink>>,
+        handlers: ServerHandlers,
+        tracker: Arc<ConnectionTracker>,
+        extra_settings: Arc<Settings>,
     ) -> Result<Connection, ConnectionError> {
         let quic_connection = quic_connecting.await?;
-        let engine = Engine::new(quic_connection.clone());
+        Self::check_handshake_fail_point(&quic_connection)?;
+        trace::handshake_completed(&quic_connection);
+        tracker.metrics().handshake_completed();
+        let qlog = Self::open_qlog(qlog, VantagePoint::Server, &quic_connection);
+        let engine = Arc::new(Engine::new(
+            quic_connection.clone(),
+            connection_options,
+            qlog,
+            handlers,
+            extra_settings,
+            tracker.metrics().clone(),
+            VantagePoint::Server,
+        ));
+        tracker.track(&engine);
 
-        let _remote_settings = engine.remote_settings().await.map_err(|worker_error| {
+        let remote_settings = engine.remote_settings().await.map_err(|worker_error| {
             ConnectionError::close_worker_error(worker_error, &quic_connection)
         })?;
+        trace::settings_received();
 
-        // TODO(bfesta): validate settings
+        // Plain HTTP3 clients (served by the request handler) need not support WebTransport
+        if engine.request_handler().is_none() {
+            Self::check_peer_settings(
+                &remote_settings,
+                &quic_connection,
+                "Client does not support WebTransport",
+            )?;
+        }
 
-        let session = engine
-            .accept_session()
-            .await
-            .map_err(|worker_error| {
-                ConnectionError::close_worker_error(worker_error, &quic_connection)
-            })?
-            .accept()
-            .await
-            .map_err(|session_error| {
-                ConnectionError::close_session_error(session_error, &quic_connection)
-            })?;
+        let (session, policy) =
+            Connection::accept_session_stream(&quic_connection, &engine).await?;
+        trace::session_established(session.id());
 
-        Ok(Connection {
+        Ok(Connection::new(
             quic_connection,
             engine,
             session,
-        })
+            policy,
+            false,
+        ))
     }
 
     async fn connect_as_client(
         quic_connecting: quinn::Connecting,
+        reconnect: Option<Reconnect>,
+        connection_options: ConnectionOptions,
+        qlog: Option<Arc<dyn QlogSink>>,
+        tracker: Arc<ConnectionTracker>,
+        extra_settings: Arc<Settings>,
     ) -> Result<Connection, ConnectionError> {
-        let quic_connection = quic_connecting.await?;
-        let engine = Engine::new(quic_connection.clone());
+        // 0-RTT is possible only if the TLS session of a previous connection can be resumed
+        let zero_rtt = match reconnect {
+            Some(reconnect) => quic_connecting
+                .into_0rtt()
+                .map(|(quic_connection, accepted)| (quic_connection, accepted, reconnect)),
+            None => Err(quic_connecting),
+        };
 
-        let _remote_settings = engine.remote_settings().await.map_err(|worker_error| {
-            ConnectionError::close_worker_error(worker_error, &quic_connection)
-        })?;
+        let (quic_connection, accepted, reconnect) = match zero_rtt {
+            Ok(zero_rtt) => zero_rtt,
+            Err(quic_connecting) => {
+                return Self::establish_as_client(
+                    quic_connecting.await?,
+                    false,
+                    connection_options,
+                    qlog,
+                    tracker,
+                    extra_settings,
+                )
+                .await;
+            }
+        };
 
-        // TODO(bfesta): validate settings
+        // The server's SETTINGS cannot arrive before the handshake completes:
+        // in 0-RTT they are validated in background
+        let early_options = ConnectionOptions {
+            settings_wait: SettingsWait::Optimistic {
+                max_wait: Duration::ZERO,
+            },
+            ..connection_options
+        };
 
-        let session = engine
-            .connect_session()
-            .await
-            .map_err(|worker_error| {
-                ConnectionError::close_worker_error(worker_error, &quic_connection)
-            })?
-            .request()
-            .await
-            .map_err(|session_error| {
-                ConnectionError::close_session_error(session_error, &quic_connection)
-            })?
-            .confirm()
-            .await
-            .map_err(|session_error| {
-                ConnectionError::close_session_error(session_error, &quic_connection)
-            })?;
+        let mut establish = Box::pin(Self::establish_as_client(
+            quic_connection,
+            true,
+            early_options,
+            qlog.clone(),
+            tracker.clone(),
+            extra_settings.clone(),
+        ));
+
+        tokio::pin!(accepted);
+
+        // A rejection makes the session request fail as well: it must be detected first
+        let accepted = tokio::select! {
+            biased;
+            accepted = &mut accepted => accepted,
+            result = &mut establish => return result,
+        };
+
+        if accepted {
+            return establish.await;
+        }
+
+        // The early data has been discarded by the server: the attempt is
+        // abandoned (closing its connection) in favor of a new connection
+        drop(establish);
+
+        Self::establish_as_client(
+            reconnect()?.await?,
+            false,
+            connection_options,
+            qlog,
+            tracker,
+            extra_settings,
+        )
+        .await
+    }
+
+    /// Sets up HTTP3 and requests the session on a QUIC connection.
+    async fn establish_as_client(
+        quic_connection: quinn::Connection,
+        early_data: bool,
+        connection_options: ConnectionOptions,
+        qlog: Option<Arc<dyn QlogSink>>,
+        tracker: Arc<ConnectionTracker>,
+        extra_settings: Arc<Settings>,
+    ) -> Result<Connection, ConnectionError> {
+        Self::check_handshake_fail_point(&quic_connection)?;
+        trace::handshake_completed(&quic_connection);
+        tracker.metrics().handshake_completed();
+        let qlog = Self::open_qlog(qlog, VantagePoint::Client, &quic_connection);
+        let engine = Arc::new(Engine::new(
+            quic_connection.clone(),
+            connection_options,
+            qlog,
+            ServerHandlers::default(),
+            extra_settings,
+            tracker.metrics().clone(),
+            VantagePoint::Client,
+        ));
+        tracker.track(&engine);
+
+        let validation = Self::validate_server_settings(engine.clone(), quic_connection.clone());
 
-        Ok(Connection {
+        match connection_options.settings_wait {
+            SettingsWait::Required => validation.await?,
+            SettingsWait::Optimistic { max_wait } => {
+                // Validation goes on in background (closing the connection on failure)
+                // if SETTINGS do not arrive in time.
+                let mut validation = runtime::spawn(trace::in_current_span(validation));
+                if let Ok(result) = runtime::timeout(max_wait, &mut validation).await {
+                    result.expect("Settings validation cannot panic")?;
+                }
+            }
+        }
+
+        let session = Connection::open_session_stream(&quic_connection, &engine).await?;
+        trace::session_established(session.id());
+
+        Ok(Connection::new(
             quic_connection,
             engine,
             session,
+            SessionPolicy::unrestricted(),
+            early_data,
+        ))
+    }
+
+    /// Closes the connection if a failure is injected at [`FailPoint::Handshake`].
+    ///
+    /// [`FailPoint::Handshake`]: crate::failpoints::FailPoint::Handshake
+    fn check_handshake_fail_point(
+        quic_connection: &quinn::Connection,
+    ) -> Result<(), ConnectionError> {
+        if fail_point!(Handshake) {
+            quic_connection.close(quinn::VarInt::from_u32(0), b"Injected failure");
+            return Err(ConnectionError::QuicError);
+        }
+
+        Ok(())
+    }
+
+    fn open_qlog(
+        sink: Option<Arc<dyn QlogSink>>,
+        vantage_point: VantagePoint,
+        quic_connection: &quinn::Connection,
+    ) -> Option<Arc<QlogTrace>> {
+        sink.and_then(|sink| {
+            QlogTrace::new(&*sink, vantage_point, quic_connection.remote_address())
         })
+        .map(Arc::new)
+    }
+
+    /// Waits for the server's SETTINGS and checks they advertise WebTransport support.
+    async fn validate_server_settings(
+        engine: Arc<Engine>,
+        quic_connection: quinn::Connection,
+    ) -> Result<(), ConnectionError> {
+        let remote_settings = engine.remote_settings().await.map_err(|worker_error| {
+            ConnectionError::close_worker_error(worker_error, &quic_connection)
+        })?;
+        trace::settings_received();
+
+        Self::check_peer_settings(
+            &remote_settings,
+            &quic_connection,
+            "Server does not support WebTransport",
+        )
+    }
+
+    /// Closes the connection with `H3_SETTINGS_ERROR` (and `reason`) if the
+    /// peer's SETTINGS do not enable WebTransport (and HTTP3 datagrams).
+    fn check_peer_settings(
+        remote_settings: &Settings,
+        quic_connection: &quinn::Connection,
+        reason: &str,
+    ) -> Result<(), ConnectionError> {
+        if remote_settings.dialect().is_some() {
+            Ok(())
+        } else {
+            Err(ConnectionError::close_worker_error(
+                WorkerError::LocalClosed(H3Error::new(ErrorCode::Settings, reason)),
+                quic_connection,
+            ))
+        }
     }
 }
 
@@ -102,98 +581,1017 @@ impl Future for Connecting {
     type Output = Result<Connection, ConnectionError>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Future::poll(self.0.as_mut(), cx)
+        Future::poll(self.future.as_mut(), cx)
     }
 }
 
 /// A WebTransport session connection.
-pub struct Connection {
+///
+/// This handle is cheaply cloneable: all clones refer to the same underlying
+/// session. The session is closed when the last (strong) handle is dropped.
+///
+/// Multiple sessions can be pooled over the same QUIC connection
+/// (see [`Connection::accept_session`] and [`Connection::open_session`]).
+/// The QUIC connection is closed when all its sessions are dropped.
+///
+/// See [`Connection::downgrade`] for obtaining a handle which does not keep
+/// the session alive.
+#[derive(Clone)]
+pub struct Connection(Arc<ConnectionInner>);
+
+struct ConnectionInner {
     quic_connection: quinn::Connection,
-    engine: Engine,
+    engine: Arc<Engine>,
     session: Session,
+    queues: SessionQueues,
+    datagram_channels: DatagramChannels,
+    early_data: bool,
+}
+
+impl Drop for ConnectionInner {
+    fn drop(&mut self) {
+        self.engine.metrics().session_closed();
+    }
 }
 
 impl Connection {
-    /// Accepts the next uni-directional stream.
+    /// Application error code of the streams reset by [`Self::send_with_deadline`]
+    /// when the deadline expires.
+    pub const EXPIRED_ERROR_CODE: u32 = 0;
+
+    /// Lower bound of the sampling period of [`Self::max_datagram_size_changed`].
+    const MIN_DATAGRAM_SIZE_SAMPLING: Duration = Duration::from_millis(50);
+
+    /// Lower bound of the sampling period of [`Self::rtt_changed`].
+    const MIN_RTT_SAMPLING: Duration = Duration::from_millis(10);
+
+    /// Sampling period of the peer's address in [`Self::path_changed`].
+    const PATH_SAMPLING: Duration = Duration::from_millis(100);
+
+    fn new(
+        quic_connection: quinn::Connection,
+        engine: Arc<Engine>,
+        session: Session,
+        policy: SessionPolicy,
+        early_data: bool,
+    ) -> Self {
+        let queues = engine.register_session(session.id(), policy);
+        engine.metrics().session_opened();
+
+        if let Some(flow_control) = engine.session_flow_control() {
+            session.credit().grant(flow_control);
+        }
+
+        Self(Arc::new(ConnectionInner {
+            quic_connection,
+            engine,
+            session,
+            queues,
+            datagram_channels: DatagramChannels::new(),
+            early_data,
+        }))
+    }
+
+    /// Accepts the next WebTransport session on the same QUIC connection.
+    ///
+    /// The returned [`Connection`] is an independent session sharing the
+    /// underlying QUIC connection with `self`.
+    ///
+    /// This is meaningful only for *server* endpoints, where the peer
+    /// can issue multiple session requests.
+    pub async fn accept_session(&self) -> Result<Connection, ConnectionError> {
+        let (session, policy) =
+            Self::accept_session_stream(&self.0.quic_connection, &self.0.engine).await?;
+
+        Ok(Connection::new(
+            self.0.quic_connection.clone(),
+            self.0.engine.clone(),
+            session,
+            policy,
+            false,
+        ))
+    }
+
+    /// Opens a new WebTransport session on the same QUIC connection.
+    ///
+    /// The returned [`Connection`] is an independent session sharing the
+    /// underlying QUIC connection with `self`, avoiding a new handshake.
+    ///
+    /// This is meaningful only for *client* endpoints.
+    pub async fn open_session(&self) -> Result<Connection, ConnectionError> {
+        let session = Self::open_session_stream(&self.0.quic_connection, &self.0.engine).await?;
+
+        Ok(Connection::new(
+            self.0.quic_connection.clone(),
+            self.0.engine.clone(),
+            session,
+            SessionPolicy::unrestricted(),
+            false,
+        ))
+    }
+
+    /// Accepts the next bi-directional stream.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no stream is lost (the next call returns it).
     pub async fn accept_bi(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
-        let wtstream = self.engine.accept_bi().await.map_err(|worker_error| {
-            ConnectionError::close_worker_error(worker_error, &self.quic_connection)
-        })?;
+        self.accept_bi_impl().await
+    }
+
+    /// Accepts the next bi-directional stream, waiting up to `timeout`.
+    ///
+    /// On [`TimeoutError::Elapsed`], no stream is lost: the next incoming
+    /// stream is returned by the following call.
+    pub async fn accept_bi_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(SendStream, RecvStream), TimeoutError<ConnectionError>> {
+        Self::timeout(timeout, self.accept_bi_impl()).await
+    }
+
+    async fn accept_bi_impl(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
+        let wtstream = self
+            .with_session(async {
+                self.0
+                    .engine
+                    .accept_bi(&self.0.queues)
+                    .await
+                    .map_err(|worker_error| {
+                        ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+                    })
+            })
+            .await?;
 
         let raw_stream = wtstream.raw();
+        let closed_notice = self.0.engine.stream_closed_notice(raw_stream.1.id());
+        trace::stream_opened(self.0.session.id(), raw_stream.1.id(), false);
+        self.0.engine.metrics().stream_opened();
+        self.0.session.credit().on_accepted(true);
 
-        Ok((SendStream::new(raw_stream.0), RecvStream::new(raw_stream.1)))
+        Ok((
+            SendStream::new(
+                raw_stream.0,
+                self.0.engine.write_progress(),
+                self.0.engine.default_stream_priority(),
+                self.0.session.credit().clone(),
+                self.0.session.send_rate().clone(),
+                closed_notice.clone(),
+            ),
+            RecvStream::new(raw_stream.1, self.0.session.credit().clone(), closed_notice),
+        ))
     }
 
-    /// Accepts the next bi-directional stream.
+    /// Accepts the next uni-directional stream.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no stream is lost (the next call returns it).
     pub async fn accept_uni(&self) -> Result<RecvStream, ConnectionError> {
-        let wtstream = self.engine.accept_uni().await.map_err(|worker_error| {
-            ConnectionError::close_worker_error(worker_error, &self.quic_connection)
-        })?;
+        self.accept_uni_impl().await
+    }
+
+    /// Accepts the next uni-directional stream, waiting up to `timeout`.
+    ///
+    /// On [`TimeoutError::Elapsed`], no stream is lost: the next incoming
+    /// stream is returned by the following call.
+    pub async fn accept_uni_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<RecvStream, TimeoutError<ConnectionError>> {
+        Self::timeout(timeout, self.accept_uni_impl()).await
+    }
+
+    async fn accept_uni_impl(&self) -> Result<RecvStream, ConnectionError> {
+        let wtstream = self
+            .with_session(async {
+                self.0
+                    .engine
+                    .accept_uni(&self.0.queues)
+                    .await
+                    .map_err(|worker_error| {
+                        ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+                    })
+            })
+            .await?;
 
         let raw_stream = wtstream.raw();
+        let closed_notice = self.0.engine.stream_closed_notice(raw_stream.id());
+        trace::stream_opened(self.0.session.id(), raw_stream.id(), false);
+        self.0.engine.metrics().stream_opened();
+        self.0.session.credit().on_accepted(false);
 
-        Ok(RecvStream::new(raw_stream))
+        Ok(RecvStream::new(
+            raw_stream,
+            self.0.session.credit().clone(),
+            closed_notice,
+        ))
     }
 
-    /// Initiates a new outgoing unidirectional stream.
+    /// Initiates a new outgoing bidirectional stream.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, the stream being opened (if any) is reset, so that the
+    /// peer never observes a partially written stream header.
     pub async fn open_bi(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
-        let wtstream = self
-            .engine
-            .open_bi(self.session.id())
+        let stream = self.open_bi_until(None).await?;
+        Ok(stream.expect("Opening without deadline"))
+    }
+
+    /// Initiates a new outgoing bi-directional stream, waiting up to `timeout`
+    /// for the peer to grant one.
+    ///
+    /// On [`TimeoutError::Elapsed`], no stream has been opened.
+    pub async fn open_bi_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<(SendStream, RecvStream), TimeoutError<ConnectionError>> {
+        let deadline = Instant::now() + timeout;
+
+        self.open_bi_until(Some(deadline))
             .await
-            .map_err(|worker_error| {
-                ConnectionError::close_worker_error(worker_error, &self.quic_connection)
-            })?;
+            .map_err(TimeoutError::Failed)?
+            .ok_or(TimeoutError::Elapsed)
+    }
+
+    async fn open_bi_until(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Option<(SendStream, RecvStream)>, ConnectionError> {
+        let wtstream = self
+            .with_session(async {
+                if !self.wait_open_credit(true, deadline).await {
+                    return Ok(None);
+                }
+
+                self.0
+                    .engine
+                    .open_bi(self.0.session.id(), deadline)
+                    .await
+                    .map_err(|worker_error| {
+                        ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+                    })
+            })
+            .await?;
+
+        let Some(wtstream) = wtstream else {
+            return Ok(None);
+        };
 
         let raw_stream = wtstream.raw();
+        let closed_notice = self.0.engine.stream_closed_notice(raw_stream.0.id());
+        trace::stream_opened(self.0.session.id(), raw_stream.0.id(), true);
+        self.0.engine.metrics().stream_opened();
 
-        Ok((SendStream::new(raw_stream.0), RecvStream::new(raw_stream.1)))
+        Ok(Some((
+            SendStream::new(
+                raw_stream.0,
+                self.0.engine.write_progress(),
+                self.0.engine.default_stream_priority(),
+                self.0.session.credit().clone(),
+                self.0.session.send_rate().clone(),
+                closed_notice.clone(),
+            ),
+            RecvStream::new(raw_stream.1, self.0.session.credit().clone(), closed_notice),
+        )))
     }
 
-    /// Initiates a new outgoing bidirectional stream.
+    /// Initiates a new outgoing unidirectional stream.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, the stream being opened (if any) is reset, so that the
+    /// peer never observes a partially written stream header.
     pub async fn open_uni(&self) -> Result<SendStream, ConnectionError> {
-        let wtstream = self
-            .engine
-            .open_uni(self.session.id())
+        let stream = self.open_uni_until(None).await?;
+        Ok(stream.expect("Opening without deadline"))
+    }
+
+    /// Initiates a new outgoing uni-directional stream, waiting up to `timeout`
+    /// for the peer to grant one.
+    ///
+    /// On [`TimeoutError::Elapsed`], no stream has been opened.
+    pub async fn open_uni_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<SendStream, TimeoutError<ConnectionError>> {
+        let deadline = Instant::now() + timeout;
+
+        self.open_uni_until(Some(deadline))
             .await
-            .map_err(|worker_error| {
-                ConnectionError::close_worker_error(worker_error, &self.quic_connection)
-            })?;
+            .map_err(TimeoutError::Failed)?
+            .ok_or(TimeoutError::Elapsed)
+    }
+
+    async fn open_uni_until(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Option<SendStream>, ConnectionError> {
+        let wtstream = self
+            .with_session(async {
+                if !self.wait_open_credit(false, deadline).await {
+                    return Ok(None);
+                }
+
+                self.0
+                    .engine
+                    .open_uni(self.0.session.id(), deadline)
+                    .await
+                    .map_err(|worker_error| {
+                        ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+                    })
+            })
+            .await?;
+
+        let Some(wtstream) = wtstream else {
+            return Ok(None);
+        };
 
         let raw_stream = wtstream.raw();
+        let closed_notice = self.0.engine.stream_closed_notice(raw_stream.id());
+        trace::stream_opened(self.0.session.id(), raw_stream.id(), true);
+        self.0.engine.metrics().stream_opened();
+
+        Ok(Some(SendStream::new(
+            raw_stream,
+            self.0.engine.write_progress(),
+            self.0.engine.default_stream_priority(),
+            self.0.session.credit().clone(),
+            self.0.session.send_rate().clone(),
+            closed_notice,
+        )))
+    }
 
-        Ok(SendStream::new(raw_stream))
+    /// Receives an application datagram, waiting up to `timeout`.
+    ///
+    /// On [`TimeoutError::Elapsed`], no datagram is lost.
+    pub async fn receive_datagram_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Datagram, TimeoutError<DatagramError>> {
+        Self::timeout(timeout, self.receive_datagram()).await
     }
 
     /// Receives an application datagram.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no datagram is lost.
     pub async fn receive_datagram(&self) -> Result<Datagram, DatagramError> {
-        self.engine
-            .receive_datagram(self.session.id())
-            .await
-            .map_err(|worker_error| {
-                ConnectionError::close_worker_error(worker_error, &self.quic_connection);
-                DatagramError::ConnectionClosed
-            })
+        self.with_session(async {
+            self.0
+                .engine
+                .receive_datagram(&self.0.queues)
+                .await
+                .map_err(|worker_error| {
+                    ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+                })
+        })
+        .await
+        .map_err(|_connection_error| DatagramError::ConnectionClosed)
+    }
+
+    /// Receives all the application datagrams currently queued (up to `max`),
+    /// appending them to `datagrams`.
+    ///
+    /// It waits until at least one datagram is available, then returns the
+    /// number of datagrams appended, without waiting for more. If `max` is
+    /// `0`, it returns immediately.
+    ///
+    /// At high packet rates, this is cheaper than calling
+    /// [`Self::receive_datagram`] for each datagram.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no datagram is lost.
+    pub async fn receive_datagrams(
+        &self,
+        datagrams: &mut Vec<Datagram>,
+        max: usize,
+    ) -> Result<usize, DatagramError> {
+        self.with_session(async {
+            self.0
+                .engine
+                .receive_datagrams(&self.0.queues, datagrams, max)
+                .await
+                .map_err(|worker_error| {
+                    ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+                })
+        })
+        .await
+        .map_err(|_connection_error| DatagramError::ConnectionClosed)
     }
 
     /// Sends an application datagram.
+    ///
+    /// Empty datagrams are allowed: the peer receives a [`Datagram`] with no payload.
+    ///
+    /// Datagrams larger than [`Self::max_datagram_size`] are never sent (nor
+    /// truncated): [`DatagramError::TooLarge`] is returned instead. Likewise,
+    /// [`DatagramError::RateLimited`] is returned for datagrams exceeding the
+    /// send rate limit (see [`Self::set_send_rate_limit`]).
     pub fn send_datagram<D>(&self, data: D) -> Result<(), DatagramError>
     where
         D: AsRef<[u8]>,
     {
-        self.engine.send_datagram(data.as_ref(), self.session.id())
-    }
+        let data = data.as_ref();
+        self.charge_datagram(data.len())?;
 
-    /// Waits for the connection to be closed for any reason.
-    pub async fn closed(&self) {
-        let _ = self.quic_connection.closed().await;
+        self.0.engine.send_datagram(data, self.0.session.id())
     }
 
-    /// Returns the WebTransport session identifier.
+    /// Sends several application datagrams, in order.
+    ///
+    /// It stops at the first datagram that cannot be sent, returning its
+    /// error: the datagrams before it have been sent. See [`Self::send_datagram`].
+    pub fn send_datagrams<I, D>(&self, datagrams: I) -> Result<(), DatagramError>
+    where
+        I: IntoIterator<Item = D>,
+        D: AsRef<[u8]>,
+    {
+        let session_id = self.0.session.id();
+
+        datagrams.into_iter().try_for_each(|data| {
+            let data = data.as_ref();
+            self.charge_datagram(data.len())?;
+
+            self.0.engine.send_datagram(data, session_id)
+        })
+    }
+
+    /// Sends an application datagram with a priority and a drop policy.
+    ///
+    /// Unlike [`Self::send_datagram`], datagrams go through a queue: while the
+    /// connection is congested, the ones with higher priority are sent first,
+    /// and the drop policy decides what to discard when the queue is full
+    /// (e.g., a new game state replacing the stale one).
+    /// Datagram pacing (if configured) does not apply to them.
+    pub fn send_datagram_with<D>(
+        &self,
+        data: D,
+        options: DatagramOptions,
+    ) -> Result<(), DatagramError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let data = data.as_ref();
+        self.charge_datagram(data.len())?;
+
+        self.0
+            .engine
+            .send_datagram_with(data, self.0.session.id(), options)
+    }
+
+    /// Sends an application datagram, waiting for room in the outgoing buffer.
+    ///
+    /// Unlike [`Self::send_datagram`], which discards the oldest buffered datagrams
+    /// when the congestion controller cannot keep up, this applies backpressure to
+    /// the caller (see [`ServerConfig::set_datagram_send_buffer_size`]). If no room
+    /// is freed within a second, the oldest buffered datagrams are discarded
+    /// as well. With datagram pacing, it waits for room in the pacing queue. With a
+    /// send rate limit (see [`Self::set_send_rate_limit`]), it waits for the
+    /// limit to allow the datagram.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, the datagram has not been sent.
+    ///
+    /// [`ServerConfig::set_datagram_send_buffer_size`]: crate::ServerConfig::set_datagram_send_buffer_size
+    pub async fn send_datagram_wait<D>(&self, data: D) -> Result<(), DatagramError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let data = data.as_ref();
+        self.0
+            .engine
+            .check_datagram_payload(self.0.session.id(), data.len())?;
+        self.0.session.send_rate().consume_wait(data.len()).await;
+
+        self.0
+            .engine
+            .send_datagram_wait(data, self.0.session.id())
+            .await
+    }
+
+    /// Sends a message which is worth delivering only within `ttl`
+    /// (*partially reliable* delivery, e.g., a media frame).
+    ///
+    /// If the message fits in a datagram (see [`Self::max_datagram_size`]) and
+    /// the send rate limit allows it, it is sent as a datagram: delivery is
+    /// unreliable, and the deadline is not tracked. Otherwise it is sent on a
+    /// new uni-directional stream: if
+    /// the peer does not acknowledge the whole message before the deadline,
+    /// the stream is reset with [`Self::EXPIRED_ERROR_CODE`] and the message
+    /// is abandoned ([`DeadlineDelivery::Expired`]).
+    ///
+    /// The receiver reads the message either as a datagram, or as the whole
+    /// content of a uni-directional stream.
+    pub async fn send_with_deadline<D>(
+        &self,
+        data: D,
+        ttl: Duration,
+    ) -> Result<DeadlineDelivery, SendDeadlineError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let data = data.as_ref();
+        let deadline = Instant::now() + ttl;
+
+        if self.try_send_as_datagram(data) {
+            return Ok(DeadlineDelivery::Datagram);
+        }
+
+        let mut stream = match runtime::timeout_at(deadline, self.open_uni()).await {
+            Ok(stream) => stream.map_err(SendDeadlineError::Connection)?,
+            Err(_elapsed) => return Ok(DeadlineDelivery::Expired),
+        };
+
+        let delivery = runtime::timeout_at(deadline, async {
+            stream.write_all(data).await?;
+            stream.finish().await
+        })
+        .await;
+
+        match delivery {
+            Ok(result) => result
+                .map(|()| DeadlineDelivery::Stream)
+                .map_err(SendDeadlineError::Stream),
+            Err(_elapsed) => {
+                stream.reset(Self::EXPIRED_ERROR_CODE);
+                Ok(DeadlineDelivery::Expired)
+            }
+        }
+    }
+
+    /// Sends a message as a datagram, if it fits (see [`Self::max_datagram_size`]).
+    ///
+    /// It returns `false` if the message has not been sent (e.g., the path MTU
+    /// just shrunk, or the send rate limit is exceeded): the caller falls back
+    /// to a stream.
+    fn try_send_as_datagram(&self, data: &[u8]) -> bool {
+        let fits = matches!(self.max_datagram_size(), Some(max_size) if data.len() <= max_size);
+        fits && self.send_datagram(data).is_ok()
+    }
+
+    /// Returns the maximum payload size of a datagram which can be currently sent
+    /// with [`Self::send_datagram`].
+    ///
+    /// It returns [`None`] if datagrams are not supported by the peer.
+    /// **Note**: the value depends on the path MTU, and it may change over time.
+    pub fn max_datagram_size(&self) -> Option<usize> {
+        let header_size = Datagram::header_size(self.0.session.id());
+
+        self.0
+            .quic_connection
+            .max_datagram_size()
+            .map(|max_size| max_size.saturating_sub(header_size))
+    }
+
+    /// Limits the data sent by this session (stream data and datagrams), or
+    /// removes the limit with [`None`].
+    ///
+    /// The limit can be adjusted at any time, also while writes are waiting
+    /// for it. It applies to this session only: the other sessions pooled on
+    /// the same connection are not affected. Datagrams exceeding the limit
+    /// are not sent: [`DatagramError::RateLimited`] is returned instead.
+    ///
+    /// See [`SendRateLimit`] for more details.
+    pub fn set_send_rate_limit(&self, limit: Option<SendRateLimit>) {
+        self.0.session.send_rate().set(limit);
+    }
+
+    /// Returns the current limit of the data sent by this session
+    /// (see [`Self::set_send_rate_limit`]).
+    pub fn send_rate_limit(&self) -> Option<SendRateLimit> {
+        self.0.session.send_rate().limit()
+    }
+
+    /// Charges a datagram of `len` bytes to the send rate limit.
+    ///
+    /// Datagrams which cannot be sent anyway (e.g., too large) are not charged.
+    fn charge_datagram(&self, len: usize) -> Result<(), DatagramError> {
+        self.0
+            .engine
+            .check_datagram_payload(self.0.session.id(), len)?;
+
+        if self.0.session.send_rate().try_consume(len) {
+            Ok(())
+        } else {
+            Err(DatagramError::RateLimited)
+        }
+    }
+
+    /// Returns the current path MTU: the largest UDP payload (in bytes) which
+    /// can be sent to the peer.
+    ///
+    /// It starts at the initial MTU, and it grows as larger payloads are
+    /// discovered to get through (see [`ServerConfig::set_mtu_discovery`]).
+    ///
+    /// [`ServerConfig::set_mtu_discovery`]: crate::ServerConfig::set_mtu_discovery
+    pub fn path_mtu(&self) -> u16 {
+        self.0.quic_connection.stats().path.current_mtu
+    }
+
+    /// Waits for the value returned by [`Self::max_datagram_size`] to change,
+    /// and returns the new value.
+    ///
+    /// **Note**: the value is sampled periodically (about every round-trip time),
+    /// hence changes are observed with a small delay.
+    ///
+    /// If the connection is closed, this future never completes:
+    /// use it along with [`Self::closed`].
+    pub async fn max_datagram_size_changed(&self) -> Option<usize> {
+        let current = self.max_datagram_size();
+
+        loop {
+            let period = self
+                .0
+                .quic_connection
+                .rtt()
+                .max(Self::MIN_DATAGRAM_SIZE_SAMPLING);
+
+            tokio::select! {
+                _ = runtime::sleep(period) => {}
+                _ = self.0.quic_connection.closed() => return pending().await,
+            }
+
+            let max_size = self.max_datagram_size();
+            if max_size != current {
+                return max_size;
+            }
+        }
+    }
+
+    /// Sends a message, choosing the transport according to `reliability`.
+    ///
+    /// With [`Reliability::BestEffort`], the message is sent as a datagram when it fits
+    /// (see [`Self::max_datagram_size`]) and the send rate limit allows it, otherwise
+    /// it is sent on a dedicated uni-directional stream (finished right after the message). With
+    /// [`Reliability::Reliable`], the stream is always used.
+    ///
+    /// Receivers need to read messages from both datagrams and incoming uni-directional
+    /// streams (where the message is the whole content of the stream).
+    pub async fn send_message<D>(
+        &self,
+        data: D,
+        reliability: Reliability,
+    ) -> Result<(), StreamError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let data = data.as_ref();
+
+        if let Reliability::BestEffort = reliability {
+            if self.try_send_as_datagram(data) {
+                return Ok(());
+            }
+        }
+
+        let mut stream = self
+            .open_uni()
+            .await
+            .map_err(|_| StreamError::ConnectionClosed)?;
+
+        stream.write_all(data).await?;
+        stream.finish().await
+    }
+
+    /// Returns a [`Stream`] of incoming bi-directional streams.
+    ///
+    /// It is equivalent to calling [`Self::accept_bi`] in a loop. The stream
+    /// yields the error which terminated the connection, and then it ends.
+    pub fn incoming_bi_streams(&self) -> IncomingBiStreams {
+        IncomingBiStreams(Incoming::new(self.clone(), |connection| {
+            Box::pin(async move { connection.accept_bi().await })
+        }))
+    }
+
+    /// Returns a [`Stream`] of incoming uni-directional streams.
+    ///
+    /// It is equivalent to calling [`Self::accept_uni`] in a loop. The stream
+    /// yields the error which terminated the connection, and then it ends.
+    pub fn incoming_uni_streams(&self) -> IncomingUniStreams {
+        IncomingUniStreams(Incoming::new(self.clone(), |connection| {
+            Box::pin(async move { connection.accept_uni().await })
+        }))
+    }
+
+    /// Returns a [`Stream`] of incoming application datagrams.
+    ///
+    /// It is equivalent to calling [`Self::receive_datagram`] in a loop. The stream
+    /// yields the error which terminated the connection, and then it ends.
+    pub fn incoming_datagrams(&self) -> IncomingDatagrams {
+        IncomingDatagrams(Incoming::new(self.clone(), |connection| {
+            Box::pin(async move { connection.receive_datagram().await })
+        }))
+    }
+
+    /// Returns the application datagrams as a [`Stream`] of payloads and a
+    /// [`Sink`](futures_sink::Sink) of payloads to send.
+    ///
+    /// See [`Datagrams`].
+    pub fn datagrams(&self) -> Datagrams {
+        Datagrams::new(self.clone())
+    }
+
+    /// Receives an application datagram tagged with `channel`.
+    ///
+    /// Datagrams tagged with a different channel are buffered, so that they can be
+    /// retrieved by another call to this method. Datagrams with no valid channel
+    /// tag are discarded.
+    ///
+    /// **Note**: this method should not be mixed with [`Self::receive_datagram`]
+    /// on the same connection, as the latter does not process channel tags.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no datagram is lost.
+    pub async fn receive_datagram_on(
+        &self,
+        channel: DatagramChannel,
+    ) -> Result<Datagram, DatagramError> {
+        loop {
+            let notified = self.0.datagram_channels.notify().notified();
+
+            if let Some(dgram) = self.0.datagram_channels.pop(channel) {
+                return Ok(dgram);
+            }
+
+            tokio::select! {
+                dgram = self.receive_datagram() => {
+                    if let Some((dgram_channel, dgram)) = dgram?.into_channel() {
+                        if dgram_channel == channel {
+                            return Ok(dgram);
+                        }
+
+                        self.0.datagram_channels.push(dgram_channel, dgram);
+                    }
+                }
+                _ = notified => {}
+            }
+        }
+    }
+
+    /// Sends an application datagram tagged with `channel`.
+    ///
+    /// The peer can receive it with [`Self::receive_datagram_on`].
+    /// As for [`Self::send_datagram`], `data` can be empty.
+    pub fn send_datagram_on<D>(
+        &self,
+        channel: DatagramChannel,
+        data: D,
+    ) -> Result<(), DatagramError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let data = data.as_ref();
+        self.charge_datagram(data.len())?;
+
+        self.0
+            .engine
+            .send_datagram_on(channel, data, self.0.session.id())
+    }
+
+    /// Closes the session with an application error code and a reason.
+    ///
+    /// The peer observes `error_code` and `reason` from [`Self::closed`] (or
+    /// as [`ConnectionError::ConnectionClosed`] on pending operations).
+    ///
+    /// `reason` is truncated to 1024 bytes (the limit imposed by the protocol).
+    ///
+    /// The underlying QUIC connection is not closed, as other sessions
+    /// might be using it.
+    pub async fn close(&self, error_code: u32, reason: &str) {
+        self.0.session.close(error_code, reason, None).await;
+    }
+
+    /// Closes the session like [`Self::close`], additionally sending
+    /// `trailers` (e.g., a final status or a checksum) as trailing headers
+    /// on the session stream.
+    ///
+    /// Trailers must not contain pseudo-headers (i.e., names starting with `:`).
+    ///
+    /// The peer observes them from [`Self::trailers`].
+    pub async fn close_with_trailers(&self, error_code: u32, reason: &str, trailers: &Headers) {
+        self.0
+            .session
+            .close(error_code, reason, Some(trailers))
+            .await;
+    }
+
+    /// Returns the trailing headers the peer sent on the session stream
+    /// (see [`Self::close_with_trailers`]).
+    ///
+    /// Trailers are received at the session termination: this returns [`None`]
+    /// until then, or if the peer did not send any.
+    pub fn trailers(&self) -> Option<Headers> {
+        self.0.session.trailers()
+    }
+
+    /// Registers an application-defined capsule type for this session.
+    ///
+    /// Capsules of that type received on the session stream are delivered
+    /// by the returned [`CapsuleChannel`], which can also send them.
+    /// Capsules of types not registered are skipped.
+    ///
+    /// It returns [`RegisterError::AlreadyRegistered`] if the capsule type
+    /// has been already registered.
+    pub fn register_capsule_type<T>(
+        &self,
+        capsule_type: CapsuleType<T>,
+    ) -> Result<CapsuleChannel<T>, RegisterError> {
+        let queue = self
+            .0
+            .session
+            .register_capsule(capsule_type.codepoint())
+            .ok_or(RegisterError::AlreadyRegistered)?;
+
+        Ok(CapsuleChannel::new(capsule_type, self.clone(), queue))
+    }
+
+    /// Registers an HTTP3 extension frame type for the underlying connection.
+    ///
+    /// Frames of that type received on the peer's control stream are delivered
+    /// by the returned [`ExtensionFrameChannel`], which can also send them on
+    /// the local control stream.
+    /// Frames of types not registered (including the ones received before the
+    /// registration) are skipped.
+    ///
+    /// The control streams are shared by all the sessions of the underlying
+    /// QUIC connection.
+    ///
+    /// It fails if `frame_type` is defined by HTTP3 or WebTransport
+    /// ([`RegisterError::Predefined`]), if it is reserved for grease or by
+    /// HTTP/2 ([`RegisterError::Reserved`]), or if it has been already
+    /// registered ([`RegisterError::AlreadyRegistered`]).
+    pub fn register_extension_frame_type(
+        &self,
+        frame_type: VarInt,
+    ) -> Result<ExtensionFrameChannel, RegisterError> {
+        if FrameKind::is_id_exercise(frame_type) || FrameKind::is_id_reserved(frame_type) {
+            return Err(RegisterError::Reserved);
+        }
+
+        if !FrameKind::is_id_unknown(frame_type) {
+            return Err(RegisterError::Predefined);
+        }
+
+        let queue = self
+            .0
+            .engine
+            .register_extension_frame(frame_type)
+            .ok_or(RegisterError::AlreadyRegistered)?;
+
+        Ok(ExtensionFrameChannel::new(frame_type, self.clone(), queue))
+    }
+
+    pub(crate) fn send_extension_frame(&self, frame_type: VarInt, payload: Vec<u8>) -> bool {
+        self.0.engine.send_extension_frame(frame_type, payload)
+    }
+
+    pub(crate) async fn send_capsule(&self, capsule: &Capsule<'_>) -> Result<(), ConnectionError> {
+        if self.0.session.send_capsule(capsule).await {
+            Ok(())
+        } else {
+            Err(self.closed().await)
+        }
+    }
+
+    /// Asks the peer to gracefully shut down the session.
+    ///
+    /// The peer is notified via [`Self::draining`], it should stop opening
+    /// new streams and close the session once ongoing transfers are completed.
+    /// The session is not closed: existing streams keep working.
+    pub async fn drain(&self) {
+        self.0.session.drain().await;
+    }
+
+    /// Waits for the peer to ask for a graceful shutdown of the session.
+    ///
+    /// See [`Self::drain`].
+    ///
+    /// If the session is closed before any request, this future never completes:
+    /// use it along with [`Self::closed`].
+    pub async fn draining(&self) {
+        self.0.session.draining().await;
+    }
+
+    /// Returns the HTTP3 SETTINGS received from the peer, including
+    /// unknown and *grease* ones.
+    ///
+    /// It returns [`None`] if they have not been received yet (this is
+    /// possible for clients only, see [`SettingsWait::Optimistic`]).
+    pub fn peer_settings(&self) -> Option<Settings> {
+        self.0.engine.peer_settings()
+    }
+
+    /// Returns the WebTransport [`Dialect`] spoken by the peer (i.e., which
+    /// drafts its SETTINGS follow), once they have been received.
+    ///
+    /// It is [`None`] if SETTINGS have not been received yet, or if they do
+    /// not advertise WebTransport (see [`Settings::dialect`]).
+    pub fn peer_dialect(&self) -> Option<Dialect> {
+        self.peer_settings().as_ref().and_then(Settings::dialect)
+    }
+
+    /// Waits for the peer to announce the shutdown of the underlying QUIC
+    /// connection, with an HTTP3 GOAWAY frame (e.g., a server performing
+    /// [`Endpoint::graceful_shutdown`]).
+    ///
+    /// Existing sessions (and their streams) keep working, but new sessions
+    /// must not be opened on this connection: they would be rejected.
+    /// Clients should open new sessions on a new connection instead.
+    ///
+    /// If the connection is closed before any announcement, this future never
+    /// completes: use it along with [`Self::closed`].
+    ///
+    /// [`Endpoint::graceful_shutdown`]: crate::Endpoint::graceful_shutdown
+    pub async fn peer_going_away(&self) {
+        self.0.engine.remote_goaway().await;
+    }
+
+    /// Waits for the connection to be closed for any reason.
+    ///
+    /// It returns the reason of the closure. In particular, if the peer closed
+    /// the session, [`ConnectionError::ConnectionClosed`] carries the error code
+    /// and the reason sent by the peer.
+    pub async fn closed(&self) -> ConnectionError {
+        tokio::select! {
+            termination = self.0.session.terminated() => termination.into(),
+            quic_error = self.0.quic_connection.closed() => quic_error.into(),
+        }
+    }
+
+    /// Returns the reason the connection has been closed, or [`None`] if
+    /// it is still open.
+    ///
+    /// Unlike [`Self::closed`], this does not wait: the reason is the same
+    /// [`Self::closed`] resolves with.
+    pub fn close_reason(&self) -> Option<ConnectionError> {
+        self.0
+            .session
+            .termination()
+            .map(ConnectionError::from)
+            .or_else(|| {
+                self.0
+                    .quic_connection
+                    .close_reason()
+                    .map(ConnectionError::from)
+            })
+    }
+
+    /// Waits for the peer to go silent, i.e., nothing has been received from it
+    /// for a while (see [`ServerConfig::set_heartbeat`]).
+    ///
+    /// If the heartbeat is disabled, or the connection is closed before
+    /// the peer goes silent, this never completes.
+    ///
+    /// [`ServerConfig::set_heartbeat`]: crate::ServerConfig::set_heartbeat
+    pub async fn peer_silent(&self) {
+        self.0.engine.peer_silent().await;
+    }
+
+    /// Returns the WebTransport session identifier.
     #[inline(always)]
     pub fn session_id(&self) -> SessionId {
-        self.session.id()
+        self.0.session.id()
+    }
+
+    /// Returns the headers of the session request.
+    ///
+    /// It returns [`None`] on *client* endpoints.
+    #[inline(always)]
+    pub fn request_headers(&self) -> Option<&Headers> {
+        self.0.session.request_headers()
+    }
+
+    /// Returns the `:path` of the session request (e.g., `"/game/lobby"`).
+    ///
+    /// It returns [`None`] on *client* endpoints.
+    #[inline(always)]
+    pub fn request_path(&self) -> Option<&str> {
+        self.request_headers()?.get(":path")
+    }
+
+    /// Returns `true` if the session was requested in 0-RTT, and the server
+    /// accepted the early data (saving a round-trip).
+    ///
+    /// It is always `false` for *server* endpoints, and for client sessions
+    /// which fell back to a regular handshake (see [`ClientConfig::set_early_data`]).
+    ///
+    /// [`ClientConfig::set_early_data`]: crate::ClientConfig::set_early_data
+    #[inline(always)]
+    pub fn is_0rtt(&self) -> bool {
+        self.0.early_data
     }
 
     /// Returns the peer's UDP address.
@@ -202,6 +1600,475 @@ impl Connection {
     /// during connection.
     #[inline(always)]
     pub fn remote_address(&self) -> SocketAddr {
-        self.quic_connection.remote_address()
+        self.0.quic_connection.remote_address()
+    }
+
+    /// Returns the server name requested by the client (SNI), if any.
+    pub fn server_name(&self) -> Option<String> {
+        self.handshake_data()?.server_name
+    }
+
+    /// Returns the application protocol negotiated during the handshake (ALPN).
+    pub fn alpn_protocol(&self) -> Option<Vec<u8>> {
+        self.handshake_data()?.protocol
+    }
+
+    /// Checks the peer is alive, returning the round-trip time of the check.
+    ///
+    /// It sends an ack-eliciting packet, and completes when the peer
+    /// acknowledges it. Along with a timeout, it detects connections which
+    /// died silently (e.g., whose NAT binding expired) sooner than the idle
+    /// timeout. For periodic checks, see [`ServerConfig::set_heartbeat`].
+    ///
+    /// [`ServerConfig::set_heartbeat`]: crate::ServerConfig::set_heartbeat
+    pub async fn ping(&self) -> Result<Duration, ConnectionError> {
+        self.with_session(async {
+            self.0.engine.ping().await.map_err(|worker_error| {
+                ConnectionError::close_worker_error(worker_error, &self.0.quic_connection)
+            })
+        })
+        .await
+    }
+
+    /// Returns the current estimate of the round-trip time of the path.
+    #[inline(always)]
+    pub fn rtt(&self) -> Duration {
+        self.0.quic_connection.rtt()
+    }
+
+    /// Waits for the round-trip time estimate to change by at least
+    /// `min_change` (in either direction), and returns the new estimate.
+    ///
+    /// **Note**: the estimate is sampled about every round-trip time.
+    ///
+    /// If the connection is closed, this future never completes:
+    /// use it along with [`Self::closed`].
+    pub async fn rtt_changed(&self, min_change: Duration) -> Duration {
+        let current = self.rtt();
+
+        loop {
+            let period = self.rtt().max(Self::MIN_RTT_SAMPLING);
+
+            tokio::select! {
+                _ = runtime::sleep(period) => {}
+                _ = self.0.quic_connection.closed() => return pending().await,
+            }
+
+            let rtt = self.rtt();
+            let change = rtt.max(current) - rtt.min(current);
+            if change >= min_change && change > Duration::ZERO {
+                return rtt;
+            }
+        }
+    }
+
+    /// Waits for the network path of the connection to change, and returns
+    /// the (possibly new) peer's address.
+    ///
+    /// The path changes when:
+    ///  * the peer migrates to a new address (as observed by *server* endpoints);
+    ///  * the local endpoint is rebound to a new socket (see
+    ///    [`Endpoint::rebind`](crate::Endpoint::rebind)).
+    ///
+    /// **Note**: the peer's address is sampled periodically, hence migrations
+    /// are observed with a small delay.
+    ///
+    /// If the connection is closed, this future never completes:
+    /// use it along with [`Self::closed`].
+    pub async fn path_changed(&self) -> SocketAddr {
+        let current = self.remote_address();
+        let rebound = self.0.engine.wait_rebound();
+        tokio::pin!(rebound);
+
+        loop {
+            tokio::select! {
+                _ = runtime::sleep(Self::PATH_SAMPLING) => {}
+                () = &mut rebound => return self.remote_address(),
+                _ = self.0.quic_connection.closed() => return pending().await,
+            }
+
+            let remote_address = self.remote_address();
+            if remote_address != current {
+                return remote_address;
+            }
+        }
+    }
+
+    /// Returns the current statistics of the connection.
+    ///
+    /// Each call samples the live values, so it can be polled periodically
+    /// (e.g., for dashboards or adaptive bitrate decisions).
+    pub fn stats(&self) -> ConnectionStats {
+        self.0.engine.stats()
+    }
+
+    /// Subscribes to the events of the connection (see [`ConnectionEvent`]).
+    ///
+    /// Only events occurring after the subscription are yielded. Each call
+    /// returns an independent subscription.
+    ///
+    /// **Note**: events refer to the whole QUIC connection, hence they are
+    /// shared by all the sessions pooled on it (see [`Self::accept_session`]
+    /// and [`Self::open_session`]).
+    ///
+    /// If the subscriber does not keep up, the oldest events are discarded
+    /// and [`ConnectionEvent::Lagged`] is yielded instead.
+    ///
+    /// [`ConnectionEvent`]: crate::events::ConnectionEvent
+    /// [`ConnectionEvent::Lagged`]: crate::events::ConnectionEvent::Lagged
+    pub fn events(&self) -> Events {
+        self.0.engine.events()
+    }
+
+    /// Returns the certificate chain presented by the peer, where each
+    /// certificate is *DER-encoded* *X.509* (end-entity certificate first).
+    ///
+    /// For *server* endpoints, this is [`None`] unless clients are requested to
+    /// authenticate (see [`ServerConfigBuilder::with_client_auth_roots`])
+    /// and the client presented a certificate.
+    ///
+    /// [`ServerConfigBuilder::with_client_auth_roots`]: crate::config::ServerConfigBuilder::with_client_auth_roots
+    pub fn peer_certificates(&self) -> Option<Vec<Vec<u8>>> {
+        let peer_identity = self.0.quic_connection.peer_identity()?;
+        let certificates = peer_identity.downcast::<Vec<rustls::Certificate>>().ok()?;
+
+        Some(
+            certificates
+                .into_iter()
+                .map(|certificate| certificate.0)
+                .collect(),
+        )
+    }
+
+    /// Derives keying material from the TLS session of the connection
+    /// (see [RFC 5705](https://www.rfc-editor.org/rfc/rfc5705)), filling `output`.
+    ///
+    /// Both peers derive the same material from the same `label` and `context`,
+    /// so it can bind an out-of-band channel to this connection.
+    ///
+    /// **Note**: sessions sharing the same QUIC connection (see
+    /// [`Self::accept_session`]) derive the same material. Include the
+    /// [`Self::session_id`] in `context` for per-session values.
+    pub fn export_keying_material(
+        &self,
+        label: &[u8],
+        context: &[u8],
+        output: &mut [u8],
+    ) -> Result<(), ExportKeyingMaterialError> {
+        self.0
+            .quic_connection
+            .export_keying_material(output, label, context)
+            .map_err(|_| ExportKeyingMaterialError)
+    }
+
+    fn handshake_data(&self) -> Option<quinn::crypto::rustls::HandshakeData> {
+        self.0
+            .quic_connection
+            .handshake_data()?
+            .downcast::<quinn::crypto::rustls::HandshakeData>()
+            .ok()
+            .map(|handshake_data| *handshake_data)
+    }
+
+    /// Runs a cancel-safe operation up to `timeout`.
+    async fn timeout<F, T, E>(timeout: Duration, future: F) -> Result<T, TimeoutError<E>>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        match runtime::timeout(timeout, future).await {
+            Ok(result) => result.map_err(TimeoutError::Failed),
+            Err(_elapsed) => Err(TimeoutError::Elapsed),
+        }
+    }
+
+    /// Waits for the peer to grant a new stream in the session (session flow
+    /// control), up to `deadline` (if any).
+    ///
+    /// Returns `false` if the deadline expires.
+    async fn wait_open_credit(&self, bidirectional: bool, deadline: Option<Instant>) -> bool {
+        let credit = poll_fn(|cx| self.0.session.credit().poll_open(cx, bidirectional));
+
+        match deadline {
+            Some(deadline) => runtime::timeout_at(deadline, credit).await.is_ok(),
+            None => {
+                credit.await;
+                true
+            }
+        }
+    }
+
+    /// Runs `future` unless the session is terminated in the meantime.
+    async fn with_session<F, T>(&self, future: F) -> Result<T, ConnectionError>
+    where
+        F: Future<Output = Result<T, ConnectionError>>,
+    {
+        tokio::select! {
+            result = future => result,
+            termination = self.0.session.terminated() => Err(termination.into()),
+        }
+    }
+
+    async fn accept_session_stream(
+        quic_connection: &quinn::Connection,
+        engine: &Engine,
+    ) -> Result<(Session, SessionPolicy), ConnectionError> {
+        let session_request = loop {
+            let session_request = engine.accept_session().await.map_err(|worker_error| {
+                ConnectionError::close_worker_error(worker_error, quic_connection)
+            })?;
+
+            match engine.request_handler() {
+                Some(handler) if !session_request.is_webtransport() => {
+                    handler(session_request.into_request());
+                }
+                _ => break session_request,
+            }
+        };
+
+        let session_request = session_request.validate().await.map_err(|session_error| {
+            ConnectionError::close_session_error(session_error, quic_connection)
+        })?;
+
+        if !engine.admit_session() {
+            let session_error = session_request
+                .reject(RejectionReason::TooManySessions)
+                .await;
+            return Err(ConnectionError::close_session_error(
+                session_error,
+                quic_connection,
+            ));
+        }
+
+        let mut hook_request = SessionRequest::new(
+            quic_connection.remote_address(),
+            session_request.headers().clone(),
+        );
+
+        for hook in engine.accept_hooks() {
+            hook_request = match hook(hook_request).await {
+                Ok(hook_request) => hook_request,
+                Err(refusal) => {
+                    let session_error = session_request.refuse(refusal).await;
+                    return Err(ConnectionError::close_session_error(
+                        session_error,
+                        quic_connection,
+                    ));
+                }
+            };
+        }
+
+        let policy = engine.session_policy(session_request.headers());
+        let response_headers = hook_request.into_response_headers();
+        let session = session_request
+            .accept(response_headers)
+            .await
+            .map_err(|session_error| {
+                ConnectionError::close_session_error(session_error, quic_connection)
+            })?;
+
+        Ok((session, policy))
+    }
+
+    async fn open_session_stream(
+        quic_connection: &quinn::Connection,
+        engine: &Engine,
+    ) -> Result<Session, ConnectionError> {
+        engine
+            .connect_session()
+            .await
+            .map_err(|worker_error| {
+                ConnectionError::close_worker_error(worker_error, quic_connection)
+            })?
+            .request()
+            .await
+            .map_err(|session_error| {
+                ConnectionError::close_session_error(session_error, quic_connection)
+            })?
+            .confirm()
+            .await
+            .map_err(|session_error| {
+                ConnectionError::close_session_error(session_error, quic_connection)
+            })
+    }
+
+    /// Creates a [`WeakConnection`] handle to this connection.
+    ///
+    /// A weak handle does not keep the session alive: once all [`Connection`]
+    /// handles are dropped, the session is closed and [`WeakConnection::upgrade`]
+    /// returns [`None`].
+    pub fn downgrade(&self) -> WeakConnection {
+        WeakConnection(Arc::downgrade(&self.0))
+    }
+
+    /// Creates an [`OpenStreams`] handle to this connection.
+    ///
+    /// It can be cloned and passed to other tasks, which can then open streams
+    /// and send datagrams, but neither accept incoming ones nor close the session.
+    pub fn opener(&self) -> OpenStreams {
+        OpenStreams(self.downgrade())
+    }
+}
+
+/// A cloneable handle for opening streams and sending datagrams.
+///
+/// It can be created with [`Connection::opener`].
+///
+/// Like [`WeakConnection`], it does not keep the session alive: once all
+/// [`Connection`] handles are dropped, operations fail with
+/// [`ConnectionError::LocallyClosed`] (or [`DatagramError::ConnectionClosed`]).
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// # async fn run(connection: Connection) {
+/// let opener = connection.opener();
+///
+/// tokio::spawn(async move {
+///     let mut stream = opener.open_uni().await.unwrap();
+///     stream.write_all(b"hello").await.unwrap();
+/// });
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct OpenStreams(WeakConnection);
+
+impl OpenStreams {
+    /// Initiates a new outgoing bidirectional stream
+    /// (see [`Connection::open_bi`]).
+    pub async fn open_bi(&self) -> Result<(SendStream, RecvStream), ConnectionError> {
+        self.connection()?.open_bi().await
+    }
+
+    /// Initiates a new outgoing unidirectional stream
+    /// (see [`Connection::open_uni`]).
+    pub async fn open_uni(&self) -> Result<SendStream, ConnectionError> {
+        self.connection()?.open_uni().await
+    }
+
+    /// Sends an application datagram (see [`Connection::send_datagram`]).
+    pub fn send_datagram<D>(&self, data: D) -> Result<(), DatagramError>
+    where
+        D: AsRef<[u8]>,
+    {
+        self.connection()
+            .map_err(|_connection_error| DatagramError::ConnectionClosed)?
+            .send_datagram(data)
+    }
+
+    fn connection(&self) -> Result<Connection, ConnectionError> {
+        self.0.upgrade().ok_or(ConnectionError::LocallyClosed)
+    }
+}
+
+/// How a message of [`Connection::send_with_deadline`] has been handled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeadlineDelivery {
+    /// The message has been sent as a datagram (delivery is not guaranteed).
+    Datagram,
+
+    /// The message has been sent on a stream, and acknowledged by the peer
+    /// before the deadline.
+    Stream,
+
+    /// The deadline expired before the message was acknowledged: it has been
+    /// abandoned.
+    Expired,
+}
+
+/// A weak handle to a [`Connection`].
+///
+/// It can be created with [`Connection::downgrade`].
+#[derive(Clone)]
+pub struct WeakConnection(Weak<ConnectionInner>);
+
+impl WeakConnection {
+    /// Attempts to upgrade to a [`Connection`] handle.
+    ///
+    /// Returns [`None`] if the connection has been already dropped.
+    pub fn upgrade(&self) -> Option<Connection> {
+        self.0.upgrade().map(Connection)
+    }
+}
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Drives a *receive* operation of a [`Connection`] repeatedly.
+///
+/// The pending operation is kept across polls, so that no item is lost
+/// when the consumer is not ready.
+struct Incoming<T, E> {
+    connection: Connection,
+    receive: fn(Connection) -> BoxFuture<Result<T, E>>,
+    pending: Option<BoxFuture<Result<T, E>>>,
+    terminated: bool,
+}
+
+impl<T, E> Incoming<T, E> {
+    fn new(connection: Connection, receive: fn(Connection) -> BoxFuture<Result<T, E>>) -> Self {
+        Self {
+            connection,
+            receive,
+            pending: None,
+            terminated: false,
+        }
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<T, E>>> {
+        if self.terminated {
+            return Poll::Ready(None);
+        }
+
+        let pending = self
+            .pending
+            .get_or_insert_with(|| (self.receive)(self.connection.clone()));
+
+        let result = match pending.as_mut().poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending,
+        };
+
+        self.pending = None;
+        self.terminated = result.is_err();
+
+        Poll::Ready(Some(result))
+    }
+}
+
+/// [`Stream`] of incoming bi-directional streams.
+///
+/// Created by [`Connection::incoming_bi_streams`].
+pub struct IncomingBiStreams(Incoming<(SendStream, RecvStream), ConnectionError>);
+
+impl Stream for IncomingBiStreams {
+    type Item = Result<(SendStream, RecvStream), ConnectionError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next(cx)
+    }
+}
+
+/// [`Stream`] of incoming uni-directional streams.
+///
+/// Created by [`Connection::incoming_uni_streams`].
+pub struct IncomingUniStreams(Incoming<RecvStream, ConnectionError>);
+
+impl Stream for IncomingUniStreams {
+    type Item = Result<RecvStream, ConnectionError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next(cx)
+    }
+}
+
+/// [`Stream`] of incoming application datagrams.
+///
+/// Created by [`Connection::incoming_datagrams`].
+pub struct IncomingDatagrams(Incoming<Datagram, DatagramError>);
+
+impl Stream for IncomingDatagrams {
+    type Item = Result<Datagram, DatagramError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next(cx)
     }
 }
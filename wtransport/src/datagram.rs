@@ -1,50 +1,137 @@
+use crate::connection::IncomingDatagrams;
+use crate::engine::buffer_pool::BufferPool;
+use crate::error::DatagramError;
 use crate::error::H3Error;
+use crate::Connection;
 use bytes::Bytes;
+use futures_core::Stream;
+use futures_sink::Sink;
+use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::ops::Deref;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::ready;
+use std::task::Context;
+use std::task::Poll;
+use tokio::sync::Notify;
+use wtransport_proto::bytes::BufferReader;
+use wtransport_proto::bytes::BufferWriter;
+use wtransport_proto::bytes::BytesReader;
+use wtransport_proto::bytes::BytesWriter;
 use wtransport_proto::datagram::Datagram as H3Datagram;
 use wtransport_proto::ids::QStreamId;
 use wtransport_proto::ids::SessionId;
+use wtransport_proto::varint::VarInt;
 
 /// An application Datagram.
 pub struct Datagram {
     quic_dgram: Bytes,
     payload_offset: usize,
+    session_id: SessionId,
 }
 
 impl Datagram {
-    pub(crate) fn read(session_id: SessionId, quic_dgram: Bytes) -> Result<Option<Self>, H3Error> {
-        let h3dgram = H3Datagram::read(&quic_dgram)
-            .map_err(|h3_code| H3Error::new(h3_code, "Invalid datagram"))?;
-
-        let stream_id = h3dgram.qstream_id().into_stream_id();
-        if session_id.session_stream() != stream_id {
-            return Ok(None);
-        }
+    pub(crate) fn read(quic_dgram: Bytes) -> Result<Self, H3Error> {
+        let h3dgram = H3Datagram::read(&quic_dgram).map_err(|read_error| {
+            H3Error::new(
+                read_error.error_code(),
+                format!("Invalid datagram: {read_error}"),
+            )
+        })?;
 
+        let session_id = h3dgram.qstream_id().into_session_id();
         let payload_offset = quic_dgram.len() - h3dgram.payload().len();
 
-        Ok(Some(Self {
+        Ok(Self {
             quic_dgram,
             payload_offset,
-        }))
+            session_id,
+        })
     }
 
-    pub(crate) fn write(session_id: SessionId, payload: &[u8]) -> Self {
-        let h3dgram = H3Datagram::new(QStreamId::from_session_id(session_id), payload);
+    /// Returns the size of the HTTP3 datagram header for `session_id`.
+    pub(crate) fn header_size(session_id: SessionId) -> usize {
+        H3Datagram::new(QStreamId::from_session_id(session_id), &[]).write_size()
+    }
 
-        let mut buffer = vec![0; h3dgram.write_size()].into_boxed_slice();
-        h3dgram.write(&mut buffer).expect("Preallocated capacity");
+    pub(crate) fn write(buffer_pool: &BufferPool, session_id: SessionId, payload: &[u8]) -> Self {
+        let h3dgram = H3Datagram::new(QStreamId::from_session_id(session_id), payload);
 
-        let quic_dgram = Bytes::from(buffer);
+        let quic_dgram = buffer_pool.encode(h3dgram.write_size(), |buffer| {
+            h3dgram.write(buffer).expect("Preallocated capacity");
+        });
 
         let payload_offset = quic_dgram.len() - payload.len();
 
         Self {
             quic_dgram,
             payload_offset,
+            session_id,
+        }
+    }
+
+    pub(crate) fn write_on_channel(
+        buffer_pool: &BufferPool,
+        session_id: SessionId,
+        channel: DatagramChannel,
+        payload: &[u8],
+    ) -> Self {
+        let qstream_id = QStreamId::from_session_id(session_id).into_varint();
+        let channel_id = channel.into_varint();
+        let header_size = qstream_id.size() + channel_id.size();
+
+        // The channel tag is encoded in place, as part of the HTTP3 datagram payload
+        let quic_dgram = buffer_pool.encode(header_size + payload.len(), |buffer| {
+            let mut buffer_writer = BufferWriter::new(buffer);
+            buffer_writer
+                .put_varint(qstream_id)
+                .expect("Preallocated capacity");
+            buffer_writer
+                .put_varint(channel_id)
+                .expect("Preallocated capacity");
+            buffer_writer
+                .put_bytes(payload)
+                .expect("Preallocated capacity");
+        });
+
+        Self {
+            quic_dgram,
+            payload_offset: qstream_id.size(),
+            session_id,
         }
     }
 
+    /// Splits the application channel prefix from the payload.
+    ///
+    /// Returns [`None`] if the payload does not start with a valid channel tag.
+    pub(crate) fn into_channel(self) -> Option<(DatagramChannel, Self)> {
+        let mut buffer_reader = BufferReader::new(&self);
+        let channel = DatagramChannel::try_from_varint(buffer_reader.get_varint()?)?;
+        let prefix_len = buffer_reader.offset();
+
+        Some((
+            channel,
+            Self {
+                quic_dgram: self.quic_dgram,
+                payload_offset: self.payload_offset + prefix_len,
+                session_id: self.session_id,
+            },
+        ))
+    }
+
+    /// Returns the [`SessionId`] this datagram is associated with.
+    #[inline(always)]
+    pub fn session_id(&self) -> SessionId {
+        self.session_id
+    }
+
+    /// Returns the payload (without copying it).
+    #[inline(always)]
+    pub fn payload(&self) -> Bytes {
+        self.quic_dgram.slice(self.payload_offset..)
+    }
+
     #[inline(always)]
     pub(crate) fn into_quic_bytes(self) -> Bytes {
         self.quic_dgram
@@ -59,3 +146,402 @@ impl Deref for Datagram {
         &self.quic_dgram[self.payload_offset..]
     }
 }
+
+/// An application channel tag for datagrams.
+///
+/// Channels allow multiplexing several kinds of unreliable messages over the
+/// same session. The channel is encoded as a *varint* prefix (1 or 2 bytes)
+/// on the datagram payload; thus, values are limited to [`DatagramChannel::MAX`].
+///
+/// See [`Connection::send_datagram_on`](crate::Connection::send_datagram_on) and
+/// [`Connection::receive_datagram_on`](crate::Connection::receive_datagram_on).
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DatagramChannel(u16);
+
+impl DatagramChannel {
+    /// The largest channel value (encoded in two bytes).
+    pub const MAX: u16 = 16383;
+
+    /// Creates a new channel tag.
+    ///
+    /// Returns [`None`] if `value` is greater than [`DatagramChannel::MAX`].
+    #[inline(always)]
+    pub const fn new(value: u16) -> Option<Self> {
+        if value <= Self::MAX {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the integer value of the channel.
+    #[inline(always)]
+    pub const fn value(self) -> u16 {
+        self.0
+    }
+
+    #[inline(always)]
+    fn into_varint(self) -> VarInt {
+        VarInt::from(self.0)
+    }
+
+    fn try_from_varint(varint: VarInt) -> Option<Self> {
+        u16::try_from(varint.into_inner()).ok().and_then(Self::new)
+    }
+}
+
+/// What to discard when the outgoing datagram queue is full
+/// (see [`DatagramOptions`]).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DatagramDropPolicy {
+    /// The oldest queued datagram is discarded (among the ones with the lowest
+    /// priority, if not higher than the new datagram's).
+    DropOldest,
+
+    /// The new datagram is discarded.
+    DropNewest,
+
+    /// A queued datagram with the same key is replaced by the new one, which
+    /// takes its place in the queue (e.g., the latest state of a game object
+    /// supersedes the stale one). Without such a datagram, it behaves as
+    /// [`DatagramDropPolicy::DropOldest`].
+    ReplaceByKey(u64),
+}
+
+/// Options of an outgoing datagram
+/// (see [`Connection::send_datagram_with`](crate::Connection::send_datagram_with)).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DatagramOptions {
+    pub(crate) priority: i32,
+    pub(crate) drop_policy: DatagramDropPolicy,
+}
+
+impl DatagramOptions {
+    /// Priority `0`, with the [`DatagramDropPolicy::DropOldest`] policy.
+    pub const fn new() -> Self {
+        Self {
+            priority: 0,
+            drop_policy: DatagramDropPolicy::DropOldest,
+        }
+    }
+
+    /// Sets the priority: while the connection is congested, queued datagrams
+    /// with higher priority are sent first.
+    pub const fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the policy applied when the queue is full.
+    pub const fn with_drop_policy(mut self, drop_policy: DatagramDropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+}
+
+impl Default for DatagramOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Buffers received datagrams per application channel.
+pub(crate) struct DatagramChannels {
+    queues: Mutex<HashMap<DatagramChannel, VecDeque<Datagram>>>,
+    notify: Notify,
+}
+
+impl DatagramChannels {
+    /// Maximum number of datagrams buffered for each channel.
+    ///
+    /// When the limit is reached, the oldest datagram is discarded.
+    const MAX_QUEUE_SIZE: usize = 64;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            queues: Mutex::new(HashMap::new()),
+            notify: Notify::new(),
+        }
+    }
+
+    pub(crate) fn pop(&self, channel: DatagramChannel) -> Option<Datagram> {
+        self.queues
+            .lock()
+            .unwrap()
+            .get_mut(&channel)
+            .and_then(|queue| queue.pop_front())
+    }
+
+    pub(crate) fn push(&self, channel: DatagramChannel, datagram: Datagram) {
+        {
+            let mut queues = self.queues.lock().unwrap();
+            let queue = queues.entry(channel).or_default();
+
+            if queue.len() >= Self::MAX_QUEUE_SIZE {
+                queue.pop_front();
+            }
+
+            queue.push_back(datagram);
+        }
+
+        self.notify.notify_waiters();
+    }
+
+    #[inline(always)]
+    pub(crate) fn notify(&self) -> &Notify {
+        &self.notify
+    }
+}
+
+/// The application datagrams of a session, as a [`Stream`] of received
+/// payloads and a [`Sink`] of payloads to send.
+///
+/// Created by [`Connection::datagrams`]. It plugs datagrams into
+/// `futures`-based pipelines (e.g., with `StreamExt::forward`).
+///
+/// Sending never waits: as with [`Connection::send_datagram`], the oldest
+/// buffered datagrams are discarded when the congestion controller cannot
+/// keep up. The stream ends when the connection is closed.
+///
+/// With the `serde` feature, values can be exchanged instead of payloads
+/// (see [`Self::typed`]).
+pub struct Datagrams {
+    connection: Connection,
+    incoming: IncomingDatagrams,
+}
+
+impl Datagrams {
+    pub(crate) fn new(connection: Connection) -> Self {
+        Self {
+            incoming: connection.incoming_datagrams(),
+            connection,
+        }
+    }
+
+    /// Exchanges values of type `T`, each serialized (as JSON) in a datagram.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn typed<T>(self) -> TypedDatagrams<T> {
+        TypedDatagrams {
+            datagrams: self,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns the connection the datagrams belong to.
+    #[inline(always)]
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl Stream for Datagrams {
+    type Item = Bytes;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match ready!(Pin::new(&mut self.incoming).poll_next(cx)) {
+            Some(Ok(dgram)) => Poll::Ready(Some(dgram.payload())),
+            Some(Err(_)) | None => Poll::Ready(None),
+        }
+    }
+}
+
+impl<D> Sink<D> for Datagrams
+where
+    D: AsRef<[u8]>,
+{
+    type Error = DatagramError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: D) -> Result<(), Self::Error> {
+        self.connection.send_datagram(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// [`Datagrams`] carrying values of type `T`, serialized as JSON.
+///
+/// Created by [`Datagrams::typed`]. Received datagrams which cannot be
+/// deserialized are yielded as errors, without ending the stream.
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub struct TypedDatagrams<T> {
+    datagrams: Datagrams,
+    _marker: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TypedDatagrams<T> {
+    /// Returns the underlying [`Datagrams`].
+    pub fn into_inner(self) -> Datagrams {
+        self.datagrams
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Stream for TypedDatagrams<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    type Item = Result<T, serde_json::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let payload = ready!(Pin::new(&mut self.datagrams).poll_next(cx));
+        Poll::Ready(payload.map(|payload| serde_json::from_slice(&payload)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T> Sink<T> for TypedDatagrams<T>
+where
+    T: serde::Serialize,
+{
+    type Error = crate::error::TypedDatagramError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let payload = serde_json::to_vec(&item).map_err(crate::error::TypedDatagramError::Json)?;
+
+        self.datagrams
+            .connection
+            .send_datagram(payload)
+            .map_err(crate::error::TypedDatagramError::Datagram)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+    use wtransport_proto::ids::StreamId;
+
+    fn session_id() -> SessionId {
+        SessionId::try_from_session_stream(StreamId::new(VarInt::from_u32(4))).unwrap()
+    }
+
+    fn channel(value: u16) -> DatagramChannel {
+        DatagramChannel::new(value).unwrap()
+    }
+
+    /// Encodes a datagram on `channel`, as received by the peer.
+    fn on_channel(channel: DatagramChannel, payload: &[u8]) -> Datagram {
+        let buffer_pool = BufferPool::new(BufferPool::DEFAULT_CAPACITY);
+        let dgram = Datagram::write_on_channel(&buffer_pool, session_id(), channel, payload);
+
+        Datagram::read(dgram.into_quic_bytes()).unwrap()
+    }
+
+    #[test]
+    fn channel_prefix() {
+        for (value, prefix_size) in [(0, 1), (63, 1), (64, 2), (DatagramChannel::MAX, 2)] {
+            let dgram = on_channel(channel(value), b"payload");
+            assert_eq!(dgram.len(), prefix_size + b"payload".len());
+
+            let (dgram_channel, dgram) = dgram.into_channel().unwrap();
+            assert_eq!(dgram_channel.value(), value);
+            assert_eq!(&dgram[..], b"payload");
+            assert_eq!(dgram.session_id(), session_id());
+        }
+    }
+
+    #[test]
+    fn channel_prefix_empty_payload() {
+        let (dgram_channel, dgram) = on_channel(channel(7), b"").into_channel().unwrap();
+
+        assert_eq!(dgram_channel, channel(7));
+        assert!(dgram.is_empty());
+    }
+
+    #[test]
+    fn channel_bound() {
+        assert!(DatagramChannel::new(DatagramChannel::MAX).is_some());
+        assert!(DatagramChannel::new(DatagramChannel::MAX + 1).is_none());
+        assert!(DatagramChannel::new(u16::MAX).is_none());
+    }
+
+    #[test]
+    fn invalid_channel_prefix() {
+        let buffer_pool = BufferPool::new(BufferPool::DEFAULT_CAPACITY);
+        let (prefix, prefix_size) =
+            VarInt::from_u32(u32::from(DatagramChannel::MAX) + 1).encode_to_array();
+
+        // A prefix beyond the channel bound
+        let dgram = Datagram::write(&buffer_pool, session_id(), &prefix[..prefix_size]);
+        let dgram = Datagram::read(dgram.into_quic_bytes()).unwrap();
+        assert!(dgram.into_channel().is_none());
+
+        // No prefix at all
+        let dgram = Datagram::write(&buffer_pool, session_id(), b"");
+        let dgram = Datagram::read(dgram.into_quic_bytes()).unwrap();
+        assert!(dgram.into_channel().is_none());
+    }
+
+    #[test]
+    fn channels_are_separated() {
+        let channels = DatagramChannels::new();
+
+        channels.push(channel(1), on_channel(channel(1), b"a"));
+        channels.push(channel(2), on_channel(channel(2), b"b"));
+        channels.push(channel(1), on_channel(channel(1), b"c"));
+
+        assert_eq!(&channels.pop(channel(2)).unwrap()[..], b"b");
+        assert!(channels.pop(channel(2)).is_none());
+        assert_eq!(&channels.pop(channel(1)).unwrap()[..], b"a");
+        assert_eq!(&channels.pop(channel(1)).unwrap()[..], b"c");
+        assert!(channels.pop(channel(3)).is_none());
+    }
+
+    #[test]
+    fn queue_overflow() {
+        let channels = DatagramChannels::new();
+
+        for index in 0..=DatagramChannels::MAX_QUEUE_SIZE {
+            let payload = index.to_string();
+            channels.push(channel(1), on_channel(channel(1), payload.as_bytes()));
+        }
+
+        // The oldest datagram is discarded
+        let payloads = std::iter::from_fn(|| channels.pop(channel(1)))
+            .map(|dgram| String::from_utf8(dgram.to_vec()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(payloads.len(), DatagramChannels::MAX_QUEUE_SIZE);
+        assert_eq!(payloads.first().unwrap(), "1");
+        assert_eq!(
+            payloads.last().unwrap(),
+            &DatagramChannels::MAX_QUEUE_SIZE.to_string()
+        );
+    }
+
+    #[test]
+    fn push_wakes_waiters() {
+        let channels = DatagramChannels::new();
+        let notified = channels.notify().notified();
+
+        channels.push(channel(1), on_channel(channel(1), b"a"));
+
+        assert!(notified.now_or_never().is_some());
+        assert!(channels.notify().notified().now_or_never().is_none());
+    }
+}
@@ -1,10 +1,42 @@
+use crate::config::AddressValidation;
 use crate::config::ClientConfig;
+use crate::config::ConnectionOptions;
+use crate::config::HappyEyeballs;
+use crate::config::Resolver;
 use crate::config::ServerConfig;
+use crate::config::ServerHandlers;
 use crate::connection::Connecting;
+use crate::connection::Reconnect;
+use crate::engine::Engine;
 use crate::error::ConnectionError;
+use crate::marking::EcnCounters;
+use crate::metrics::Metrics;
+use crate::metrics::NoMetrics;
+use crate::qlog::QlogSink;
+use crate::runtime;
+use crate::runtime::DedicatedThread;
+use crate::runtime::EndpointDriver;
+use crate::runtime::Runtime;
+use crate::runtime::Task;
+use crate::trace;
+use crate::Connection;
 use quinn::Endpoint as QuicEndpoint;
+use std::future::poll_fn;
+use std::future::Future;
 use std::marker::PhantomData;
 use std::net::SocketAddr;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::task::Poll;
+use std::time::Duration;
+use tokio::sync::watch;
+use wtransport_proto::discovery::AltSvc;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::settings::Settings;
 
 /// Type of endpoint accepting multiple WebTransport connections.
 pub struct Server;
@@ -18,58 +50,571 @@ pub struct Client;
 /// * For creating a client: [`Endpoint::client`].
 pub struct Endpoint<Side> {
     endpoint: QuicEndpoint,
+    additional_endpoints: Vec<QuicEndpoint>,
+    connection_options: ConnectionOptions,
+    qlog: Option<Arc<dyn QlogSink>>,
+    handlers: ServerHandlers,
+    tracker: Arc<ConnectionTracker>,
+    extra_settings: Arc<Settings>,
+    happy_eyeballs: HappyEyeballs,
+    resolver: Option<Resolver>,
+    _retry_monitor: Option<RetryMonitor>,
+    _dedicated: Vec<DedicatedThread>,
     _marker: PhantomData<Side>,
 }
 
+impl<Side> Endpoint<Side> {
+    /// Returns the local socket address the endpoint is bound to.
+    ///
+    /// This is useful when the endpoint is bound to port `0`, in order
+    /// to discover the port assigned by the operating system.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.endpoint.local_addr()
+    }
+
+    /// Returns all the local socket addresses the endpoint is bound to:
+    /// the one of [`Endpoint::local_addr`] first, then the additional ones
+    /// (see [`ServerConfig::add_bind_address`]).
+    pub fn local_addrs(&self) -> std::io::Result<Vec<SocketAddr>> {
+        self.quic_endpoints()
+            .map(|endpoint| endpoint.local_addr())
+            .collect()
+    }
+
+    /// Closes all the connections of the endpoint immediately.
+    ///
+    /// Pending operations fail with [`ConnectionError::LocallyClosed`]
+    /// and no new connections are accepted nor opened.
+    ///
+    /// Close signals are delivered to peers asynchronously: see
+    /// [`Endpoint::wait_idle`] for waiting until they are.
+    pub fn close(&self, error_code: u32, reason: &[u8]) {
+        for endpoint in self.quic_endpoints() {
+            endpoint.close(error_code.into(), reason);
+        }
+    }
+
+    /// Waits for all the connections of the endpoint to be fully drained.
+    ///
+    /// A connection is drained when its close signal has been delivered
+    /// to the peer (or the draining period has expired), even if all the
+    /// handles to it have been already dropped.
+    ///
+    /// Awaiting this before exiting the process (or before binding a new
+    /// endpoint on the same address) ensures peers are notified of the
+    /// closure, instead of waiting for their idle timeout.
+    ///
+    /// This does not close any connection: see [`Endpoint::close`].
+    pub async fn wait_idle(&self) {
+        for endpoint in self.quic_endpoints() {
+            endpoint.wait_idle().await;
+        }
+    }
+
+    /// Gracefully shuts down the endpoint.
+    ///
+    /// 1. An HTTP3 GOAWAY frame is sent on every connection: peers stop
+    ///    opening new sessions (see [`Connection::peer_going_away`]), and new
+    ///    session requests are rejected.
+    /// 2. No new connections are accepted (nor opened): [`Endpoint::accept`]
+    ///    returns [`None`].
+    /// 3. Existing sessions are given up to `grace_period` to drain, i.e., for
+    ///    their connections to be closed by either side.
+    /// 4. The endpoint is closed (see [`Endpoint::close`]) and all close
+    ///    signals are delivered (see [`Endpoint::wait_idle`]).
+    ///
+    /// [`Connection::peer_going_away`]: crate::Connection::peer_going_away
+    pub async fn graceful_shutdown(&self, grace_period: Duration) {
+        self.tracker.shut_down();
+        for endpoint in self.quic_endpoints() {
+            endpoint.set_server_config(None);
+        }
+
+        let quic_connections = self.tracker.quic_connections();
+        let drained = async {
+            for quic_connection in quic_connections {
+                quic_connection.closed().await;
+            }
+        };
+
+        let _ = runtime::timeout(grace_period, drained).await;
+
+        let error_code = quinn::VarInt::from_u64(ErrorCode::NoError.to_code().into_inner())
+            .expect("HTTP3 error code is a valid varint");
+
+        for endpoint in self.quic_endpoints() {
+            endpoint.close(error_code, b"");
+        }
+        self.wait_idle().await;
+    }
+
+    /// The QUIC endpoints, one per socket (the one of the bind address first).
+    fn quic_endpoints(&self) -> impl Iterator<Item = &QuicEndpoint> {
+        std::iter::once(&self.endpoint).chain(&self.additional_endpoints)
+    }
+}
+
 impl Endpoint<Server> {
     /// Constructs a *server* endpoint.
     pub fn server(server_config: ServerConfig) -> std::io::Result<Self> {
-        let quic_config = server_config.quic_config;
-        let bind_address = server_config.bind_address;
+        let quic_config = server_config.quic_config();
+
+        let mut endpoints = Vec::new();
+        let mut dedicated = Vec::new();
+        let mut driver_runtime = None;
+
+        let ecn_counters = Arc::new(EcnCounters::default());
+
+        for driver_socket in server_config.driver_sockets()? {
+            let driver = EndpointDriver::bind(driver_socket, &server_config.driver_runtime)?;
+            driver_runtime.get_or_insert_with(|| driver.runtime().clone());
+            let (endpoint, dedicated_thread) = driver.into_endpoint(
+                Some(quic_config.clone()),
+                server_config.packet_marking,
+                ecn_counters.clone(),
+            )?;
+
+            endpoints.push(endpoint);
+            dedicated.extend(dedicated_thread);
+        }
+
+        let tracker = Arc::new(ConnectionTracker::new(
+            server_config.metrics.clone(),
+            ecn_counters,
+        ));
 
-        let endpoint = QuicEndpoint::server(quic_config, bind_address)?;
+        let retry_monitor = match server_config.address_validation {
+            AddressValidation::Adaptive { open_connections } => Some(RetryMonitor::new(
+                driver_runtime
+                    .as_deref()
+                    .expect("The bind address is always present"),
+                endpoints.clone(),
+                quic_config,
+                open_connections,
+                tracker.clone(),
+            )),
+            AddressValidation::Disabled | AddressValidation::Required => None,
+        };
+
+        let mut endpoints = endpoints.into_iter();
+        let endpoint = endpoints
+            .next()
+            .expect("The bind address is always present");
 
         Ok(Self {
             endpoint,
+            additional_endpoints: endpoints.collect(),
+            connection_options: server_config.connection_options,
+            qlog: server_config.qlog,
+            handlers: server_config.handlers,
+            extra_settings: server_config.extra_settings,
+            happy_eyeballs: HappyEyeballs::default(),
+            resolver: None,
+            tracker,
+            _retry_monitor: retry_monitor,
+            _dedicated: dedicated,
             _marker: PhantomData,
         })
     }
 
+    /// Returns the advertisement of this endpoint for QUIC discovery, i.e.,
+    /// its port and ALPN.
+    ///
+    /// It produces `Alt-Svc` header values (e.g., for the HTTP/1.1 or HTTP/2
+    /// server of the same origin) and `HTTPS` DNS record parameters.
+    /// See [`AltSvc`].
+    pub fn alt_svc(&self) -> std::io::Result<AltSvc> {
+        Ok(AltSvc::new(self.local_addr()?.port()))
+    }
+
     /// Get the next incoming connection attempt from a client.
     ///
-    /// Returns `None` if the endpoint has been closed.
+    /// Returns `None` if the endpoint has been closed, or it is shutting down
+    /// (see [`Endpoint::graceful_shutdown`]).
     pub async fn accept(&self) -> Option<Connecting> {
-        self.endpoint
-            .accept()
-            .await
-            .map(|quic_connecting| Connecting::new(true, quic_connecting))
+        let quic_connecting = tokio::select! {
+            quic_connecting = self.accept_quic() => quic_connecting?,
+            () = self.tracker.shutting_down() => return None,
+        };
+
+        trace::incoming_connection(quic_connecting.remote_address());
+
+        Some(Connecting::server(
+            quic_connecting,
+            self.connection_options,
+            self.qlog.clone(),
+            self.handlers.clone(),
+            self.tracker.clone(),
+            self.extra_settings.clone(),
+        ))
+    }
+
+    /// Waits for an incoming QUIC connection on any of the sockets.
+    ///
+    /// Returns `None` once all the sockets are closed.
+    async fn accept_quic(&self) -> Option<quinn::Connecting> {
+        // Accepting is cancel-safe: pending connections stay queued in their endpoint
+        let mut accepts = self
+            .quic_endpoints()
+            .map(|endpoint| Some(Box::pin(endpoint.accept())))
+            .collect::<Vec<_>>();
+
+        std::future::poll_fn(|cx| {
+            for slot in accepts.iter_mut() {
+                let Some(accept) = slot else {
+                    continue;
+                };
+
+                match accept.as_mut().poll(cx) {
+                    Poll::Ready(Some(quic_connecting)) => {
+                        return Poll::Ready(Some(quic_connecting))
+                    }
+                    Poll::Ready(None) => *slot = None,
+                    Poll::Pending => {}
+                }
+            }
+
+            if accepts.iter().all(Option::is_none) {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
     }
 }
 
 impl Endpoint<Client> {
     /// Constructs a *client* endpoint.
     pub fn client(client_config: ClientConfig) -> std::io::Result<Self> {
-        let quic_config = client_config.quic_config;
-        let bind_address = client_config.bind_address;
+        let quic_config = client_config.quic_config();
 
-        let mut endpoint = QuicEndpoint::client(bind_address)?;
+        let driver = EndpointDriver::bind(
+            client_config.driver_socket()?,
+            &client_config.driver_runtime,
+        )?;
+        let ecn_counters = Arc::new(EcnCounters::default());
+        let (mut endpoint, dedicated) =
+            driver.into_endpoint(None, client_config.packet_marking, ecn_counters.clone())?;
         endpoint.set_default_client_config(quic_config);
 
         Ok(Self {
             endpoint,
+            additional_endpoints: Vec::new(),
+            connection_options: client_config.connection_options,
+            qlog: client_config.qlog,
+            handlers: ServerHandlers::default(),
+            extra_settings: client_config.extra_settings,
+            happy_eyeballs: client_config.happy_eyeballs,
+            resolver: client_config.resolver,
+            tracker: Arc::new(ConnectionTracker::new(client_config.metrics, ecn_counters)),
+            _retry_monitor: None,
+            _dedicated: dedicated.into_iter().collect(),
             _marker: PhantomData,
         })
     }
 
+    /// Rebinds the endpoint to a new UDP socket (e.g., when the device switches
+    /// from Wi-Fi to cellular).
+    ///
+    /// Open connections migrate to the new local address: their sessions
+    /// survive the change, and the server follows the migration automatically.
+    /// Connections are notified via [`Connection::path_changed`].
+    ///
+    /// [`Connection::path_changed`]: crate::Connection::path_changed
+    pub fn rebind(&self, socket: UdpSocket) -> std::io::Result<()> {
+        self.endpoint.rebind(socket)?;
+        self.tracker.rebound();
+        Ok(())
+    }
+
     /// Connects to a remote endpoint.
     ///
     /// `server_name` must be covered by the certificate presented by the server.
+    ///
+    /// Fails with [`ConnectionError::LocallyClosed`] if the endpoint
+    /// has been closed (see [`Endpoint::close`]), or it is shutting down
+    /// (see [`Endpoint::graceful_shutdown`]).
     pub fn connect(
         &self,
         remote_address: SocketAddr,
         server_name: &str,
     ) -> Result<Connecting, ConnectionError> {
-        let quic_connecting = self.endpoint.connect(remote_address, server_name).unwrap();
-        Ok(Connecting::new(false, quic_connecting))
+        if self.tracker.is_shutting_down() {
+            return Err(ConnectionError::LocallyClosed);
+        }
+
+        let quic_connecting = Self::quic_connect(&self.endpoint, remote_address, server_name)?;
+
+        let reconnect = self.connection_options.early_data.then(|| {
+            let endpoint = self.endpoint.clone();
+            let server_name = server_name.to_string();
+
+            Box::new(move || Self::quic_connect(&endpoint, remote_address, &server_name))
+                as Reconnect
+        });
+
+        Ok(Connecting::client(
+            quic_connecting,
+            reconnect,
+            self.connection_options,
+            self.qlog.clone(),
+            self.tracker.clone(),
+            self.extra_settings.clone(),
+        ))
+    }
+
+    /// Connects to a remote endpoint given its host name.
+    ///
+    /// `host` is resolved (see [`ClientConfig::set_resolver`]), and its
+    /// addresses are raced as configured with
+    /// [`ClientConfig::set_happy_eyeballs`]: the first connection established
+    /// is returned, and the other attempts are abandoned. `host` is also the
+    /// server name (see [`Endpoint::connect`]).
+    ///
+    /// Fails with [`ConnectionError::UnresolvedHost`] if `host` has no address,
+    /// otherwise with the error of the last failed attempt.
+    pub async fn connect_host(&self, host: &str, port: u16) -> Result<Connection, ConnectionError> {
+        let addresses = self
+            .resolve(host, port)
+            .await
+            .map_err(|_| ConnectionError::UnresolvedHost)?;
+
+        let mut addresses = self.happy_eyeballs.order(addresses).into_iter();
+        if addresses.as_slice().is_empty() {
+            return Err(ConnectionError::UnresolvedHost);
+        }
+
+        let stagger_duration = self.happy_eyeballs.stagger();
+        let mut stagger = runtime::sleep(stagger_duration);
+
+        let mut attempts = Vec::<Connecting>::new();
+        let mut last_error = None;
+
+        poll_fn(|cx| loop {
+            // The next attempt starts when the stagger delay elapses, or
+            // right away if no attempt is in progress
+            let start_next = attempts.is_empty() || stagger.as_mut().poll(cx).is_ready();
+
+            if start_next {
+                if let Some(address) = addresses.next() {
+                    stagger = runtime::sleep(stagger_duration);
+
+                    match self.connect(address, host) {
+                        Ok(connecting) => attempts.push(connecting),
+                        Err(connection_error) => {
+                            last_error = Some(connection_error);
+                            continue;
+                        }
+                    }
+                }
+            }
+
+            let mut index = 0;
+            while index < attempts.len() {
+                match Pin::new(&mut attempts[index]).poll(cx) {
+                    Poll::Ready(Ok(connection)) => return Poll::Ready(Ok(connection)),
+                    Poll::Ready(Err(connection_error)) => {
+                        attempts.swap_remove(index);
+                        last_error = Some(connection_error);
+                    }
+                    Poll::Pending => index += 1,
+                }
+            }
+
+            if !attempts.is_empty() {
+                return Poll::Pending;
+            }
+
+            if addresses.as_slice().is_empty() {
+                return Poll::Ready(Err(last_error
+                    .take()
+                    .expect("At least one attempt has failed")));
+            }
+        })
+        .await
+    }
+
+    async fn resolve(&self, host: &str, port: u16) -> std::io::Result<Vec<SocketAddr>> {
+        if let Some(resolver) = &self.resolver {
+            return resolver(host.to_string(), port).await;
+        }
+
+        // The system resolver blocks: keep it off the runtime threads
+        let host = host.to_string();
+        runtime::spawn_blocking(move || {
+            (host, port)
+                .to_socket_addrs()
+                .map(|addresses| addresses.collect())
+        })
+        .await
+        .unwrap_or_else(|| Err(std::io::ErrorKind::Other.into()))
+    }
+
+    fn quic_connect(
+        endpoint: &QuicEndpoint,
+        remote_address: SocketAddr,
+        server_name: &str,
+    ) -> Result<quinn::Connecting, ConnectionError> {
+        endpoint
+            .connect(remote_address, server_name)
+            .map_err(|connect_error| match connect_error {
+                quinn::ConnectError::EndpointStopping => ConnectionError::LocallyClosed,
+                _ => ConnectionError::QuicError,
+            })
+    }
+}
+
+/// Turns address validation on and off according to the load of a server
+/// (see [`AddressValidation::Adaptive`]).
+struct RetryMonitor {
+    task: Task<()>,
+}
+
+impl RetryMonitor {
+    /// How often the number of open connections is sampled.
+    const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+    fn new(
+        driver_runtime: &dyn Runtime,
+        endpoints: Vec<QuicEndpoint>,
+        quic_config: quinn::ServerConfig,
+        open_connections: usize,
+        tracker: Arc<ConnectionTracker>,
+    ) -> Self {
+        let task = runtime::spawn_on(
+            driver_runtime,
+            Self::run(endpoints, quic_config, open_connections, tracker),
+        );
+        Self { task }
+    }
+
+    async fn run(
+        endpoints: Vec<QuicEndpoint>,
+        mut quic_config: quinn::ServerConfig,
+        threshold: usize,
+        tracker: Arc<ConnectionTracker>,
+    ) {
+        let mut retry = false;
+
+        loop {
+            // The server config is removed on shutdown: it must not be restored
+            if tracker.is_shutting_down() {
+                return;
+            }
+
+            let open_connections = endpoints
+                .iter()
+                .map(QuicEndpoint::open_connections)
+                .sum::<usize>();
+
+            if (open_connections >= threshold) != retry {
+                retry = !retry;
+                quic_config.use_retry(retry);
+
+                for endpoint in &endpoints {
+                    endpoint.set_server_config(Some(quic_config.clone()));
+                }
+            }
+
+            runtime::sleep(Self::SAMPLE_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for RetryMonitor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Tracks the connections of an endpoint, for the graceful shutdown.
+pub(crate) struct ConnectionTracker {
+    engines: Mutex<Vec<Weak<Engine>>>,
+    shutdown: watch::Sender<bool>,
+    metrics: Arc<dyn Metrics>,
+    ecn_counters: Arc<EcnCounters>,
+}
+
+impl ConnectionTracker {
+    fn new(metrics: Option<Arc<dyn Metrics>>, ecn_counters: Arc<EcnCounters>) -> Self {
+        Self {
+            engines: Mutex::new(Vec::new()),
+            shutdown: watch::channel(false).0,
+            metrics: metrics.unwrap_or_else(|| Arc::new(NoMetrics)),
+            ecn_counters,
+        }
+    }
+
+    /// The metrics of the endpoint's connections.
+    pub(crate) fn metrics(&self) -> &Arc<dyn Metrics> {
+        &self.metrics
+    }
+
+    /// Tracks a new connection.
+    ///
+    /// If the endpoint is shutting down, the connection is immediately notified.
+    pub(crate) fn track(&self, engine: &Arc<Engine>) {
+        let mut engines = self.engines.lock().expect("Mutex poisoned");
+        engines.retain(|engine| engine.strong_count() > 0);
+        engines.push(Arc::downgrade(engine));
+
+        let live = engines
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|engine| engine.quic_connection().remote_address())
+            .collect::<Vec<_>>();
+        self.ecn_counters.retain(&live);
+        engine.set_ecn_counters(self.ecn_counters.clone());
+
+        if self.is_shutting_down() {
+            engine.goaway();
+        }
+    }
+
+    /// Starts the shutdown: a GOAWAY is sent on every live connection.
+    fn shut_down(&self) {
+        let engines = self.engines.lock().expect("Mutex poisoned");
+        self.shutdown.send_replace(true);
+
+        for engine in engines.iter().filter_map(Weak::upgrade) {
+            engine.goaway();
+        }
+    }
+
+    /// Notifies every live connection that the endpoint has been rebound.
+    fn rebound(&self) {
+        let engines = self.engines.lock().expect("Mutex poisoned");
+
+        for engine in engines.iter().filter_map(Weak::upgrade) {
+            engine.rebound();
+        }
+    }
+
+    fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    async fn shutting_down(&self) {
+        let mut shutdown = self.shutdown.subscribe();
+
+        // The sender is owned by `self`: it cannot be dropped while waiting
+        while !*shutdown.borrow_and_update() {
+            let _ = shutdown.changed().await;
+        }
+    }
+
+    /// The QUIC connections still alive.
+    ///
+    /// Only QUIC handles are returned: they do not keep engines (and so
+    /// connections) alive once the application drops them.
+    fn quic_connections(&self) -> Vec<quinn::Connection> {
+        self.engines
+            .lock()
+            .expect("Mutex poisoned")
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|engine| engine.quic_connection().clone())
+            .collect()
     }
 }
@@ -0,0 +1,50 @@
+use bytes::Bytes;
+use bytes::BytesMut;
+use std::sync::Mutex;
+
+/// Memory shared by the outgoing datagrams of a connection.
+///
+/// Datagrams are encoded in a single arena and handed out as [`Bytes`]
+/// views into it. Once all the datagrams of an arena chunk have been sent
+/// (i.e., their views dropped), its memory is reclaimed for the next ones,
+/// instead of allocating a buffer for each datagram.
+///
+/// A capacity of `0` disables pooling: each datagram gets its own buffer.
+pub(crate) struct BufferPool {
+    arena: Mutex<BytesMut>,
+    capacity: usize,
+}
+
+impl BufferPool {
+    pub(crate) const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            arena: Mutex::new(BytesMut::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    /// Returns a buffer of `len` bytes, filled by `fill`.
+    pub(crate) fn encode<F>(&self, len: usize, fill: F) -> Bytes
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        if len > self.capacity {
+            let mut buffer = vec![0; len];
+            fill(&mut buffer);
+            return Bytes::from(buffer);
+        }
+
+        let mut arena = self.arena.lock().expect("Mutex poisoned");
+
+        // It reuses the arena memory if no view into it is alive anymore
+        if arena.capacity() < len {
+            arena.reserve(self.capacity);
+        }
+
+        arena.resize(len, 0);
+        fill(&mut arena[..]);
+        arena.split().freeze()
+    }
+}
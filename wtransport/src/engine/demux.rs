@@ -0,0 +1,519 @@
+use crate::config::PendingSessionBuffer;
+use crate::config::SessionPolicy;
+use crate::datagram::Datagram;
+use crate::engine::stream::BiRemote;
+use crate::engine::stream::Stream;
+use crate::engine::stream::UniRemote;
+use crate::engine::stream::Wt;
+use crate::error::H3Error;
+use crate::metrics::Metrics;
+use crate::qlog::VantagePoint;
+use crate::runtime;
+use crate::trace;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex as AsyncMutex;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::ids::SessionId;
+
+/// Dispatches incoming WebTransport streams and datagrams to their sessions.
+///
+/// Streams and datagrams might be received before the associated session
+/// is established (or claimed). In that case, they are buffered in a
+/// *pending* slot, up to [`PendingSessionBuffer::max_sessions`] sessions.
+/// Slots not claimed within [`PendingSessionBuffer::timeout`] are discarded:
+/// their streams are rejected with `WEBTRANSPORT_BUFFERED_STREAM_REJECTED`.
+///
+/// On the client, sessions are only the ones it requested (see
+/// [`Demux::request`]): referring to other IDs is a protocol violation.
+///
+/// Buffered datagrams are limited in number and, optionally, in size
+/// (bytes of payload per session).
+pub(crate) struct Demux {
+    state: Mutex<DemuxState>,
+    dropped_datagrams: AtomicU64,
+    max_session_buffer: Option<usize>,
+    pending_sessions: PendingSessionBuffer,
+    metrics: Arc<dyn Metrics>,
+}
+
+impl Demux {
+    const STREAMS_QUEUE_SIZE: usize = 1024;
+    const DATAGRAMS_QUEUE_SIZE: usize = 256;
+
+    pub(crate) fn new(
+        max_session_buffer: Option<usize>,
+        pending_sessions: PendingSessionBuffer,
+        side: VantagePoint,
+        metrics: Arc<dyn Metrics>,
+    ) -> Self {
+        Self {
+            state: Mutex::new(DemuxState {
+                sessions: HashMap::new(),
+                closed: ClosedSessions::default(),
+                requested: (side == VantagePoint::Client).then(HashSet::new),
+                terminated: false,
+            }),
+            dropped_datagrams: AtomicU64::new(0),
+            max_session_buffer,
+            pending_sessions,
+            metrics,
+        }
+    }
+
+    /// Records a session requested by the client, so that the server can
+    /// refer to it.
+    pub(crate) fn request(&self, session_id: SessionId) {
+        if let Some(requested) = self.state.lock().unwrap().requested.as_mut() {
+            requested.insert(session_id);
+        }
+    }
+
+    /// Claims the incoming queues of a session.
+    ///
+    /// Data buffered for the session before this call is preserved.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the session has been already claimed.
+    pub(crate) fn claim(
+        self: &Arc<Self>,
+        session_id: SessionId,
+        policy: SessionPolicy,
+    ) -> SessionQueues {
+        let mut state = self.state.lock().unwrap();
+
+        let receivers = if state.terminated {
+            SessionSlot::new().1
+        } else {
+            state
+                .sessions
+                .entry(session_id)
+                .or_insert_with(|| {
+                    let (slot, receivers) = SessionSlot::new();
+                    SessionSlot {
+                        receivers: Some(receivers),
+                        ..slot
+                    }
+                })
+                .receivers
+                .take()
+                .expect("Session already claimed")
+        };
+
+        SessionQueues {
+            session_id,
+            bi: AsyncMutex::new(receivers.bi),
+            uni: AsyncMutex::new(receivers.uni),
+            dgram: AsyncMutex::new(receivers.dgram),
+            buffered: receivers.buffered,
+            policy,
+            accepted_bi: AtomicU64::new(0),
+            accepted_uni: AtomicU64::new(0),
+            demux: self.clone(),
+        }
+    }
+
+    /// Dispatches an incoming bi-directional stream.
+    ///
+    /// It fails if the stream refers to a session that cannot exist.
+    pub(crate) fn route_bi(self: &Arc<Self>, stream: Stream<BiRemote, Wt>) -> Result<(), H3Error> {
+        let session_id = stream.session_id();
+        self.validate(session_id)?;
+
+        match self.with_slot(session_id, |slot| slot.bi.clone()) {
+            Ok(sender) => {
+                if let Err(mpsc::error::TrySendError::Full(stream))
+                | Err(mpsc::error::TrySendError::Closed(stream)) = sender.try_send(stream)
+                {
+                    stream.stop(ErrorCode::BufferedStreamRejected.to_code());
+                }
+            }
+            Err(error_code) => stream.stop(error_code.to_code()),
+        }
+
+        Ok(())
+    }
+
+    /// Dispatches an incoming uni-directional stream.
+    ///
+    /// It fails if the stream refers to a session that cannot exist.
+    pub(crate) fn route_uni(
+        self: &Arc<Self>,
+        stream: Stream<UniRemote, Wt>,
+    ) -> Result<(), H3Error> {
+        let session_id = stream.session_id();
+        self.validate(session_id)?;
+
+        match self.with_slot(session_id, |slot| slot.uni.clone()) {
+            Ok(sender) => {
+                if let Err(mpsc::error::TrySendError::Full(stream))
+                | Err(mpsc::error::TrySendError::Closed(stream)) = sender.try_send(stream)
+                {
+                    stream.stop(ErrorCode::BufferedStreamRejected.to_code());
+                }
+            }
+            Err(error_code) => stream.stop(error_code.to_code()),
+        }
+
+        Ok(())
+    }
+
+    /// Dispatches an incoming datagram.
+    ///
+    /// It fails if the datagram refers to a session that cannot exist.
+    pub(crate) fn route_datagram(self: &Arc<Self>, datagram: Datagram) -> Result<(), H3Error> {
+        let session_id = datagram.session_id();
+        self.validate(session_id)?;
+
+        // Datagrams are unreliable: if there is no room, just discard them
+        let (sender, buffered) = match self.with_slot(session_id, |slot| {
+            (slot.dgram.clone(), slot.buffered.clone())
+        }) {
+            Ok(slot) => slot,
+            Err(ErrorCode::SessionGone) => {
+                self.drop_datagram("session gone");
+                return Ok(());
+            }
+            Err(_) => {
+                self.drop_datagram("too many pending sessions");
+                return Ok(());
+            }
+        };
+
+        let size = datagram.len();
+
+        let admitted = buffered
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |buffered| match self
+                .max_session_buffer
+            {
+                Some(max) if buffered + size > max => None,
+                _ => Some(buffered + size),
+            })
+            .is_ok();
+
+        if !admitted {
+            self.drop_datagram("session receive buffer full");
+        } else if sender.try_send(datagram).is_err() {
+            buffered.fetch_sub(size, Ordering::Relaxed);
+            self.drop_datagram("session receive queue full");
+        }
+
+        Ok(())
+    }
+
+    /// Number of incoming datagrams discarded because their session
+    /// was gone or its queue was full.
+    pub(crate) fn dropped_datagrams(&self) -> u64 {
+        self.dropped_datagrams.load(Ordering::Relaxed)
+    }
+
+    /// Number of sessions claimed and not released yet.
+    pub(crate) fn claimed_sessions(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .sessions
+            .values()
+            .filter(|slot| slot.receivers.is_none())
+            .count()
+    }
+
+    /// Drops all the queues, so that waiting sessions are notified.
+    ///
+    /// This is called when the connection terminates.
+    pub(crate) fn terminate(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.terminated = true;
+        state.sessions.clear();
+    }
+
+    /// Checks that `session_id` can refer to a session: on the client, it
+    /// must be one of the requested sessions.
+    fn validate(&self, session_id: SessionId) -> Result<(), H3Error> {
+        match &self.state.lock().unwrap().requested {
+            Some(requested) if !requested.contains(&session_id) => Err(H3Error::new(
+                ErrorCode::Id,
+                "Session ID not referring to a session request",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn with_slot<F, T>(self: &Arc<Self>, session_id: SessionId, f: F) -> Result<T, ErrorCode>
+    where
+        F: FnOnce(&SessionSlot) -> T,
+    {
+        let mut state = self.state.lock().unwrap();
+
+        if state.terminated || state.closed.contains(&session_id) {
+            return Err(ErrorCode::SessionGone);
+        }
+
+        if !state.sessions.contains_key(&session_id) {
+            let num_pending = state
+                .sessions
+                .values()
+                .filter(|slot| slot.receivers.is_some())
+                .count();
+
+            if num_pending >= self.pending_sessions.max_sessions() {
+                return Err(ErrorCode::BufferedStreamRejected);
+            }
+
+            let (slot, receivers) = SessionSlot::new();
+            let created = slot.created;
+            state.sessions.insert(
+                session_id,
+                SessionSlot {
+                    receivers: Some(receivers),
+                    ..slot
+                },
+            );
+
+            let demux = Arc::downgrade(self);
+            let timeout = self.pending_sessions.timeout();
+            runtime::spawn(async move {
+                runtime::sleep(timeout).await;
+                if let Some(demux) = Weak::upgrade(&demux) {
+                    demux.expire(session_id, created);
+                }
+            });
+        }
+
+        Ok(f(state
+            .sessions
+            .get(&session_id)
+            .expect("Slot has been just inserted")))
+    }
+
+    /// Discards the slot of `session_id` created at `created`, if still
+    /// not claimed.
+    fn expire(&self, session_id: SessionId, created: Instant) {
+        let slot = {
+            let mut state = self.state.lock().unwrap();
+
+            match state.sessions.get(&session_id) {
+                Some(slot) if slot.receivers.is_some() && slot.created == created => {
+                    state.sessions.remove(&session_id)
+                }
+                _ => None,
+            }
+        };
+
+        let Some(mut receivers) = slot.and_then(|slot| slot.receivers) else {
+            return;
+        };
+
+        while let Ok(stream) = receivers.bi.try_recv() {
+            stream.stop(ErrorCode::BufferedStreamRejected.to_code());
+        }
+
+        while let Ok(stream) = receivers.uni.try_recv() {
+            stream.stop(ErrorCode::BufferedStreamRejected.to_code());
+        }
+
+        while receivers.dgram.try_recv().is_ok() {
+            self.drop_datagram("session not established in time");
+        }
+    }
+
+    /// Accounts an incoming datagram discarded for `reason`.
+    fn drop_datagram(&self, reason: &'static str) {
+        trace::datagram_dropped(reason);
+        self.metrics.datagram_dropped();
+        self.dropped_datagrams.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn release(&self, session_id: SessionId) {
+        let mut state = self.state.lock().unwrap();
+        state.sessions.remove(&session_id);
+        state.closed.insert(session_id);
+    }
+}
+
+/// Incoming queues of a single session.
+///
+/// Dropping this releases the session: further streams and datagrams
+/// associated with it are rejected.
+pub(crate) struct SessionQueues {
+    session_id: SessionId,
+    pub(super) bi: AsyncMutex<mpsc::Receiver<Stream<BiRemote, Wt>>>,
+    pub(super) uni: AsyncMutex<mpsc::Receiver<Stream<UniRemote, Wt>>>,
+    pub(super) dgram: AsyncMutex<mpsc::Receiver<Datagram>>,
+    buffered: Arc<AtomicUsize>,
+    policy: SessionPolicy,
+    accepted_bi: AtomicU64,
+    accepted_uni: AtomicU64,
+    demux: Arc<Demux>,
+}
+
+impl SessionQueues {
+    /// Checks whether the session policy allows one more incoming bi-directional stream.
+    ///
+    /// If so, the stream is accounted.
+    pub(super) fn admit_bi(&self) -> bool {
+        Self::admit(&self.accepted_bi, self.policy.max_bi_streams())
+    }
+
+    /// Checks whether the session policy allows one more incoming uni-directional stream.
+    ///
+    /// If so, the stream is accounted.
+    pub(super) fn admit_uni(&self) -> bool {
+        Self::admit(&self.accepted_uni, self.policy.max_uni_streams())
+    }
+
+    /// Accounts a datagram taken out of the queue.
+    pub(super) fn unbuffer_datagram(&self, datagram: &Datagram) {
+        self.buffered.fetch_sub(datagram.len(), Ordering::Relaxed);
+    }
+
+    /// Checks whether the session policy allows incoming datagrams.
+    pub(super) fn admit_datagram(&self) -> bool {
+        self.policy.datagrams()
+    }
+
+    fn admit(accepted: &AtomicU64, max_streams: Option<u64>) -> bool {
+        accepted
+            .fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |accepted| match max_streams {
+                    Some(max_streams) if accepted >= max_streams => None,
+                    _ => Some(accepted + 1),
+                },
+            )
+            .is_ok()
+    }
+}
+
+impl Drop for SessionQueues {
+    fn drop(&mut self) {
+        self.demux.release(self.session_id);
+    }
+}
+
+struct DemuxState {
+    sessions: HashMap<SessionId, SessionSlot>,
+    closed: ClosedSessions,
+    /// The sessions requested so far (only on the client).
+    requested: Option<HashSet<SessionId>>,
+    terminated: bool,
+}
+
+/// The most recently released sessions, whose streams and datagrams are
+/// rejected.
+///
+/// It is bounded: data for sessions released long before is buffered as for
+/// a session not established yet (and discarded on its expiry).
+#[derive(Default)]
+struct ClosedSessions {
+    ids: HashSet<SessionId>,
+    order: VecDeque<SessionId>,
+}
+
+impl ClosedSessions {
+    const MAX_SIZE: usize = 1024;
+
+    fn insert(&mut self, session_id: SessionId) {
+        if !self.ids.insert(session_id) {
+            return;
+        }
+
+        self.order.push_back(session_id);
+
+        if self.order.len() > Self::MAX_SIZE {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+
+    fn contains(&self, session_id: &SessionId) -> bool {
+        self.ids.contains(session_id)
+    }
+}
+
+struct SessionSlot {
+    bi: mpsc::Sender<Stream<BiRemote, Wt>>,
+    uni: mpsc::Sender<Stream<UniRemote, Wt>>,
+    dgram: mpsc::Sender<Datagram>,
+    buffered: Arc<AtomicUsize>,
+    receivers: Option<SessionReceivers>,
+    created: Instant,
+}
+
+impl SessionSlot {
+    fn new() -> (Self, SessionReceivers) {
+        let bi = mpsc::channel(Demux::STREAMS_QUEUE_SIZE);
+        let uni = mpsc::channel(Demux::STREAMS_QUEUE_SIZE);
+        let dgram = mpsc::channel(Demux::DATAGRAMS_QUEUE_SIZE);
+        let buffered = Arc::new(AtomicUsize::new(0));
+
+        (
+            Self {
+                bi: bi.0,
+                uni: uni.0,
+                dgram: dgram.0,
+                buffered: buffered.clone(),
+                receivers: None,
+                created: Instant::now(),
+            },
+            SessionReceivers {
+                bi: bi.1,
+                uni: uni.1,
+                dgram: dgram.1,
+                buffered,
+            },
+        )
+    }
+}
+
+struct SessionReceivers {
+    bi: mpsc::Receiver<Stream<BiRemote, Wt>>,
+    uni: mpsc::Receiver<Stream<UniRemote, Wt>>,
+    dgram: mpsc::Receiver<Datagram>,
+    buffered: Arc<AtomicUsize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wtransport_proto::ids::StreamId;
+    use wtransport_proto::varint::VarInt;
+
+    fn session_id(index: u32) -> SessionId {
+        SessionId::try_from_session_stream(StreamId::new(VarInt::from_u32(index * 4))).unwrap()
+    }
+
+    #[test]
+    fn closed_sessions_bounded() {
+        let mut closed = ClosedSessions::default();
+
+        for index in 0..ClosedSessions::MAX_SIZE as u32 {
+            closed.insert(session_id(index));
+        }
+
+        // Duplicates do not take room
+        closed.insert(session_id(0));
+        assert!(closed.contains(&session_id(0)));
+        assert_eq!(closed.order.len(), ClosedSessions::MAX_SIZE);
+
+        // The oldest one is forgotten
+        closed.insert(session_id(ClosedSessions::MAX_SIZE as u32));
+        assert!(!closed.contains(&session_id(0)));
+        assert!(closed.contains(&session_id(1)));
+        assert!(closed.contains(&session_id(ClosedSessions::MAX_SIZE as u32)));
+        assert_eq!(closed.ids.len(), ClosedSessions::MAX_SIZE);
+        assert_eq!(closed.order.len(), ClosedSessions::MAX_SIZE);
+    }
+}
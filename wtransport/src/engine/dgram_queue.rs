@@ -0,0 +1,371 @@
+use crate::datagram::DatagramDropPolicy;
+use crate::datagram::DatagramOptions;
+use crate::metrics::Metrics;
+use crate::runtime;
+use crate::runtime::Task;
+use crate::trace;
+use bytes::Bytes;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Outgoing datagrams ordered by priority, with drop policies.
+///
+/// Datagrams are handed to QUIC by a dedicated task as soon as there is
+/// room in its outgoing buffer: they wait in this queue only while the
+/// connection is congested, where the highest priority is sent first (and
+/// datagrams of the same priority in order).
+pub(super) struct DatagramQueue {
+    entries: Arc<Mutex<Entries>>,
+    notify: Arc<Notify>,
+    dropped: Arc<AtomicU64>,
+    metrics: Arc<dyn Metrics>,
+    task: Task<()>,
+}
+
+impl DatagramQueue {
+    /// Maximum number of datagrams waiting in the queue.
+    const MAX_SIZE: usize = 256;
+
+    /// Interval between checks of the outgoing buffer space of QUIC.
+    const BUFFER_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    pub(super) fn new(quic_connection: quinn::Connection, metrics: Arc<dyn Metrics>) -> Self {
+        let entries = Arc::new(Mutex::new(Entries::default()));
+        let notify = Arc::new(Notify::new());
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        let task = runtime::spawn(Self::run(
+            quic_connection,
+            entries.clone(),
+            notify.clone(),
+            dropped.clone(),
+            metrics.clone(),
+        ));
+
+        Self {
+            entries,
+            notify,
+            dropped,
+            metrics,
+            task,
+        }
+    }
+
+    /// Queues a datagram, applying the drop policy of `options` if the queue is full.
+    pub(super) fn push(&self, dgram: Bytes, options: DatagramOptions) {
+        let discarded = self
+            .entries
+            .lock()
+            .expect("Mutex poisoned")
+            .push(dgram, options);
+
+        if discarded {
+            self.on_dropped();
+        }
+
+        self.notify.notify_one();
+    }
+
+    /// Number of datagrams discarded by the drop policies, or rejected by QUIC.
+    pub(super) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn on_dropped(&self) {
+        trace::datagram_dropped("discarded by queue policy");
+        self.metrics.datagram_dropped();
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    async fn run(
+        quic_connection: quinn::Connection,
+        entries: Arc<Mutex<Entries>>,
+        notify: Arc<Notify>,
+        dropped: Arc<AtomicU64>,
+        metrics: Arc<dyn Metrics>,
+    ) {
+        loop {
+            let next = entries.lock().expect("Mutex poisoned").pop();
+
+            let Some(dgram) = next else {
+                notify.notified().await;
+                continue;
+            };
+
+            // No notification is available when buffer space is freed: poll it
+            while quic_connection.datagram_send_buffer_space() < dgram.len() {
+                runtime::sleep(Self::BUFFER_POLL_INTERVAL).await;
+            }
+
+            match quic_connection.send_datagram(dgram) {
+                Ok(()) => {}
+                Err(quinn::SendDatagramError::ConnectionLost(_)) => return,
+                Err(_) => {
+                    trace::datagram_dropped("rejected by QUIC");
+                    metrics.datagram_dropped();
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DatagramQueue {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    entries: Vec<Entry>,
+    next_seq: u64,
+}
+
+struct Entry {
+    dgram: Bytes,
+    priority: i32,
+    seq: u64,
+    key: Option<u64>,
+}
+
+impl Entries {
+    /// Adds a datagram, returning whether a datagram has been discarded.
+    fn push(&mut self, dgram: Bytes, options: DatagramOptions) -> bool {
+        let key = match options.drop_policy {
+            DatagramDropPolicy::ReplaceByKey(key) => Some(key),
+            DatagramDropPolicy::DropOldest | DatagramDropPolicy::DropNewest => None,
+        };
+
+        // The stale datagram is replaced in place, keeping its turn
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|entry| key.is_some() && entry.key == key)
+        {
+            entry.dgram = dgram;
+            entry.priority = options.priority;
+            return true;
+        }
+
+        let mut discarded = false;
+
+        if self.entries.len() >= DatagramQueue::MAX_SIZE {
+            if let DatagramDropPolicy::DropNewest = options.drop_policy {
+                return true;
+            }
+
+            // The oldest datagram of the lowest priority, unless it is more
+            // important than the new one
+            let victim = self
+                .entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| (entry.priority, entry.seq))
+                .map(|(index, entry)| (index, entry.priority))
+                .expect("Queue is full");
+
+            if victim.1 > options.priority {
+                return true;
+            }
+
+            self.entries.remove(victim.0);
+            discarded = true;
+        }
+
+        self.entries.push(Entry {
+            dgram,
+            priority: options.priority,
+            seq: self.next_seq,
+            key,
+        });
+        self.next_seq += 1;
+
+        discarded
+    }
+
+    /// Removes the datagram to send next: the oldest of the highest priority.
+    fn pop(&mut self) -> Option<Bytes> {
+        let index = self
+            .entries
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, entry)| (entry.priority, std::cmp::Reverse(entry.seq)))
+            .map(|(index, _)| index)?;
+
+        Some(self.entries.remove(index).dgram)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(entries: &mut Entries, dgram: &str, priority: i32, policy: DatagramDropPolicy) -> bool {
+        entries.push(
+            Bytes::from(dgram.to_string()),
+            DatagramOptions::new()
+                .with_priority(priority)
+                .with_drop_policy(policy),
+        )
+    }
+
+    /// Fills the queue with datagrams `"0"`, `"1"`, ... of `priority`.
+    fn fill(entries: &mut Entries, priority: i32) {
+        while entries.entries.len() < DatagramQueue::MAX_SIZE {
+            let dgram = entries.entries.len().to_string();
+            assert!(!push(
+                entries,
+                &dgram,
+                priority,
+                DatagramDropPolicy::DropOldest
+            ));
+        }
+    }
+
+    fn pop_all(entries: &mut Entries) -> Vec<Bytes> {
+        std::iter::from_fn(|| entries.pop()).collect()
+    }
+
+    #[test]
+    fn priority() {
+        let mut entries = Entries::default();
+
+        for (dgram, priority) in [("a", 0), ("b", 5), ("c", 0), ("d", 5), ("e", -1)] {
+            assert!(!push(
+                &mut entries,
+                dgram,
+                priority,
+                DatagramDropPolicy::DropOldest
+            ));
+        }
+
+        assert_eq!(pop_all(&mut entries), ["b", "d", "a", "c", "e"]);
+        assert!(entries.pop().is_none());
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let mut entries = Entries::default();
+        fill(&mut entries, 0);
+
+        assert!(push(&mut entries, "new", 0, DatagramDropPolicy::DropOldest));
+
+        let dgrams = pop_all(&mut entries);
+        assert_eq!(dgrams.len(), DatagramQueue::MAX_SIZE);
+        assert_eq!(dgrams[0], "1");
+        assert_eq!(dgrams[DatagramQueue::MAX_SIZE - 1], "new");
+    }
+
+    #[test]
+    fn drop_oldest_lowest_priority() {
+        let mut entries = Entries::default();
+        assert!(!push(
+            &mut entries,
+            "high",
+            1,
+            DatagramDropPolicy::DropOldest
+        ));
+        fill(&mut entries, 0);
+
+        // The oldest datagram of the lowest priority is discarded
+        assert!(push(&mut entries, "new", 0, DatagramDropPolicy::DropOldest));
+
+        let dgrams = pop_all(&mut entries);
+        assert_eq!(dgrams[0], "high");
+        assert_eq!(dgrams[1], "2");
+        assert_eq!(dgrams[DatagramQueue::MAX_SIZE - 1], "new");
+
+        // A datagram less important than all the queued ones is discarded
+        let mut entries = Entries::default();
+        fill(&mut entries, 1);
+
+        assert!(push(&mut entries, "low", 0, DatagramDropPolicy::DropOldest));
+
+        let dgrams = pop_all(&mut entries);
+        assert_eq!(dgrams.len(), DatagramQueue::MAX_SIZE);
+        assert!(!dgrams.contains(&Bytes::from("low")));
+    }
+
+    #[test]
+    fn drop_newest() {
+        let mut entries = Entries::default();
+        fill(&mut entries, 0);
+
+        assert!(push(&mut entries, "new", 1, DatagramDropPolicy::DropNewest));
+
+        let dgrams = pop_all(&mut entries);
+        assert_eq!(dgrams.len(), DatagramQueue::MAX_SIZE);
+        assert_eq!(dgrams[0], "0");
+        assert!(!dgrams.contains(&Bytes::from("new")));
+    }
+
+    #[test]
+    fn replace_by_key() {
+        let mut entries = Entries::default();
+
+        assert!(!push(
+            &mut entries,
+            "a",
+            0,
+            DatagramDropPolicy::ReplaceByKey(7)
+        ));
+        assert!(!push(
+            &mut entries,
+            "b",
+            0,
+            DatagramDropPolicy::ReplaceByKey(8)
+        ));
+        assert!(!push(&mut entries, "c", 0, DatagramDropPolicy::DropOldest));
+
+        // Replaced in place, keeping its turn
+        assert!(push(
+            &mut entries,
+            "a2",
+            0,
+            DatagramDropPolicy::ReplaceByKey(7)
+        ));
+        assert_eq!(entries.entries.len(), 3);
+
+        // Replaced with a new priority
+        assert!(push(
+            &mut entries,
+            "b2",
+            1,
+            DatagramDropPolicy::ReplaceByKey(8)
+        ));
+
+        assert_eq!(pop_all(&mut entries), ["b2", "a2", "c"]);
+    }
+
+    #[test]
+    fn replace_by_key_full() {
+        let mut entries = Entries::default();
+        fill(&mut entries, 0);
+
+        // No datagram to replace: the oldest one is discarded
+        assert!(push(
+            &mut entries,
+            "a",
+            0,
+            DatagramDropPolicy::ReplaceByKey(7)
+        ));
+        assert_eq!(entries.entries.len(), DatagramQueue::MAX_SIZE);
+
+        assert!(push(
+            &mut entries,
+            "a2",
+            0,
+            DatagramDropPolicy::ReplaceByKey(7)
+        ));
+        assert_eq!(entries.entries.len(), DatagramQueue::MAX_SIZE);
+
+        let dgrams = pop_all(&mut entries);
+        assert_eq!(dgrams[0], "1");
+        assert_eq!(dgrams[DatagramQueue::MAX_SIZE - 1], "a2");
+    }
+}
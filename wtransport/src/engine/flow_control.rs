@@ -0,0 +1,441 @@
+use crate::config::ReceiveWindowConfig;
+use crate::config::SessionFlowControl;
+use crate::runtime;
+use crate::runtime::Task;
+use quinn::VarInt;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::Notify;
+use wtransport_proto::capsule::CapsuleKind;
+use wtransport_proto::capsule::FlowControlCapsule;
+
+/// Grows the connection receive window following the bandwidth-delay product.
+///
+/// Every round-trip time, the amount of data received from the peer is measured.
+/// If the peer was able to send more than half the current window within that
+/// interval, the window is likely limiting the throughput: it is doubled
+/// (or set to twice the estimated bandwidth-delay product, if larger).
+///
+/// The window never shrinks and it is capped to [`ReceiveWindowConfig::max_window`].
+pub(super) struct ReceiveWindowTuner {
+    quic_connection: quinn::Connection,
+    window: u32,
+    max_window: u32,
+}
+
+impl ReceiveWindowTuner {
+    /// Lower bound of the sampling interval, so that very small RTTs
+    /// (e.g., loopback) do not make the tuner spin.
+    const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+    pub(super) fn new(quic_connection: quinn::Connection, config: ReceiveWindowConfig) -> Self {
+        Self {
+            quic_connection,
+            window: config.initial_window(),
+            max_window: config.max_window(),
+        }
+    }
+
+    pub(super) fn run(self) -> Task<()> {
+        runtime::spawn(self.run_impl())
+    }
+
+    async fn run_impl(mut self) {
+        let mut last_rx_bytes = self.quic_connection.stats().udp_rx.bytes;
+        let mut last_sample = Instant::now();
+
+        while self.window < self.max_window {
+            let interval = self.quic_connection.rtt().max(Self::MIN_INTERVAL);
+
+            tokio::select! {
+                _ = runtime::sleep(interval) => {}
+                _ = self.quic_connection.closed() => return,
+            }
+
+            let rx_bytes = self.quic_connection.stats().udp_rx.bytes;
+            let now = Instant::now();
+
+            let received = rx_bytes.saturating_sub(last_rx_bytes);
+            let elapsed = now.duration_since(last_sample);
+            let rtt = self.quic_connection.rtt();
+
+            last_rx_bytes = rx_bytes;
+            last_sample = now;
+
+            if received * 2 < self.window as u64 {
+                continue;
+            }
+
+            let bdp = (received as f64 * rtt.as_secs_f64() / elapsed.as_secs_f64()) as u64;
+
+            self.window = (self.window as u64 * 2)
+                .max(bdp * 2)
+                .min(self.max_window as u64) as u32;
+
+            self.quic_connection
+                .set_receive_window(VarInt::from_u32(self.window));
+        }
+    }
+}
+
+/// Session-level flow control (`WT_MAX_STREAMS` and `WT_MAX_DATA` capsules).
+///
+/// On the send side, the limits of the peer apply once it advertises them:
+/// until then, the session is not limited (the peer does not use session
+/// flow control). Opening a stream or writing data beyond a limit waits for
+/// more credit, notifying the peer with a `WT_*_BLOCKED` capsule.
+///
+/// On the receive side, credit is granted only if configured (see
+/// [`SessionFlowControl`]). It is extended as the application accepts streams
+/// and reads data, once half of the window has been used. A peer exceeding
+/// the granted credit violates the protocol.
+///
+/// Capsules to send are collected here, and written on the session stream
+/// by the session (see [`Self::updated`]).
+pub(crate) struct SessionCredit {
+    state: Mutex<CreditState>,
+    updates: Notify,
+}
+
+impl SessionCredit {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: Mutex::new(CreditState::default()),
+            updates: Notify::new(),
+        }
+    }
+
+    /// Grants the initial credit to the peer, enforcing it from now on.
+    pub(crate) fn grant(&self, config: SessionFlowControl) {
+        let mut state = self.state.lock().expect("Mutex poisoned");
+
+        let receive = ReceiveCredit {
+            bi: Grant::new(config.max_bi_streams()),
+            uni: Grant::new(config.max_uni_streams()),
+            data: Grant::new(config.max_data()),
+        };
+
+        state.queue(CapsuleKind::WtMaxStreamsBidi, receive.bi.max);
+        state.queue(CapsuleKind::WtMaxStreamsUni, receive.uni.max);
+        state.queue(CapsuleKind::WtMaxData, receive.data.max);
+        state.receive = Some(receive);
+
+        self.updates.notify_one();
+    }
+
+    /// Processes a flow control capsule received from the peer.
+    pub(crate) fn on_capsule(&self, capsule: FlowControlCapsule) {
+        let mut state = self.state.lock().expect("Mutex poisoned");
+        let max = capsule.value().into_inner();
+
+        let raised = match capsule.kind() {
+            CapsuleKind::WtMaxStreamsBidi => state.send.bi.raise(max),
+            CapsuleKind::WtMaxStreamsUni => state.send.uni.raise(max),
+            CapsuleKind::WtMaxData => state.send.data.raise(max),
+            // The peer is waiting for credit: it is extended as the application consumes
+            _ => false,
+        };
+
+        if raised {
+            state.wake_waiters();
+        }
+    }
+
+    /// Takes credit for opening a stream, waiting for the peer to grant it.
+    pub(crate) fn poll_open(&self, cx: &mut Context<'_>, bidirectional: bool) -> Poll<()> {
+        let mut guard = self.state.lock().expect("Mutex poisoned");
+        let state = &mut *guard;
+
+        let (limit, blocked_kind) = if bidirectional {
+            (&mut state.send.bi, CapsuleKind::WtStreamsBlockedBidi)
+        } else {
+            (&mut state.send.uni, CapsuleKind::WtStreamsBlockedUni)
+        };
+
+        if limit.available() > 0 {
+            limit.used += 1;
+            return Poll::Ready(());
+        }
+
+        if state.closed {
+            return Poll::Ready(());
+        }
+
+        let blocked_at = limit.blocked();
+        if let Some(max) = blocked_at {
+            state.queue(blocked_kind, max);
+            self.updates.notify_one();
+        }
+
+        state.wait(cx);
+        Poll::Pending
+    }
+
+    /// Reserves credit for writing up to `amount` bytes, waiting for the
+    /// peer to grant some.
+    ///
+    /// The reservation may be smaller than `amount` (but not empty, unless
+    /// `amount` is zero).
+    pub(crate) fn poll_reserve(
+        self: &Arc<Self>,
+        cx: &mut Context<'_>,
+        amount: usize,
+    ) -> Poll<Reservation> {
+        let mut state = self.state.lock().expect("Mutex poisoned");
+
+        let available = state.send.data.available();
+        if available > 0 || amount == 0 || state.closed {
+            let amount = if state.closed {
+                amount
+            } else {
+                amount.min(available.try_into().unwrap_or(usize::MAX))
+            };
+
+            state.send.data.used += amount as u64;
+
+            return Poll::Ready(Reservation {
+                credit: self.clone(),
+                amount,
+            });
+        }
+
+        if let Some(max) = state.send.data.blocked() {
+            state.queue(CapsuleKind::WtDataBlocked, max);
+            self.updates.notify_one();
+        }
+
+        state.wait(cx);
+        Poll::Pending
+    }
+
+    /// Whether writing is blocked by the session data limit of the peer.
+    pub(crate) fn is_data_blocked(&self) -> bool {
+        let state = self.state.lock().expect("Mutex poisoned");
+        !state.closed && state.send.data.available() == 0
+    }
+
+    /// Accounts a stream accepted by the application.
+    pub(crate) fn on_accepted(&self, bidirectional: bool) {
+        let mut guard = self.state.lock().expect("Mutex poisoned");
+        let state = &mut *guard;
+
+        let Some(receive) = state.receive.as_mut() else {
+            return;
+        };
+
+        let (grant, kind) = if bidirectional {
+            (&mut receive.bi, CapsuleKind::WtMaxStreamsBidi)
+        } else {
+            (&mut receive.uni, CapsuleKind::WtMaxStreamsUni)
+        };
+
+        let extended = grant.consume(1);
+        state.on_consumed(kind, extended);
+        self.updates.notify_one();
+    }
+
+    /// Accounts `amount` bytes read by the application.
+    pub(crate) fn on_read(&self, amount: usize) {
+        if amount == 0 {
+            return;
+        }
+
+        let mut guard = self.state.lock().expect("Mutex poisoned");
+        let state = &mut *guard;
+
+        let Some(receive) = state.receive.as_mut() else {
+            return;
+        };
+
+        let extended = receive.data.consume(amount as u64);
+        state.on_consumed(CapsuleKind::WtMaxData, extended);
+        self.updates.notify_one();
+    }
+
+    /// Waits for capsules to send (or for a violation of the granted credit).
+    pub(crate) async fn updated(&self) {
+        self.updates.notified().await
+    }
+
+    /// Takes the capsules to send, and whether the peer exceeded the granted credit.
+    pub(crate) fn take_updates(&self) -> (Vec<FlowControlCapsule>, bool) {
+        let mut state = self.state.lock().expect("Mutex poisoned");
+        (std::mem::take(&mut state.outgoing), state.violated)
+    }
+
+    /// Stops limiting the send side, as the session is terminated.
+    ///
+    /// Pending operations are woken, so that they fail on the terminated session.
+    pub(crate) fn close(&self) {
+        let mut state = self.state.lock().expect("Mutex poisoned");
+        state.closed = true;
+        state.wake_waiters();
+    }
+}
+
+/// Credit for writing data, taken with [`SessionCredit::poll_reserve`].
+///
+/// Dropping the reservation returns the credit not committed.
+pub(crate) struct Reservation {
+    credit: Arc<SessionCredit>,
+    amount: usize,
+}
+
+impl Reservation {
+    /// The number of bytes which can be written.
+    #[inline(always)]
+    pub(crate) fn amount(&self) -> usize {
+        self.amount
+    }
+
+    /// Keeps the credit of the `written` bytes, returning the rest.
+    pub(crate) fn commit(mut self, written: usize) {
+        self.amount -= written.min(self.amount);
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        if self.amount == 0 {
+            return;
+        }
+
+        let mut state = self.credit.state.lock().expect("Mutex poisoned");
+        state.send.data.used -= self.amount as u64;
+        state.wake_waiters();
+    }
+}
+
+#[derive(Default)]
+struct CreditState {
+    send: SendCredit,
+    receive: Option<ReceiveCredit>,
+    outgoing: Vec<FlowControlCapsule>,
+    waiters: Vec<Waker>,
+    violated: bool,
+    closed: bool,
+}
+
+impl CreditState {
+    fn queue(&mut self, kind: CapsuleKind, value: u64) {
+        let value = wtransport_proto::varint::VarInt::try_from_u64(value)
+            .unwrap_or(wtransport_proto::varint::VarInt::MAX);
+
+        self.outgoing.push(FlowControlCapsule::new(kind, value));
+    }
+
+    fn on_consumed(&mut self, kind: CapsuleKind, extended: Result<Option<u64>, CreditExceeded>) {
+        match extended {
+            Ok(Some(max)) => self.queue(kind, max),
+            Ok(None) => {}
+            Err(CreditExceeded) => self.violated = true,
+        }
+    }
+
+    fn wait(&mut self, cx: &mut Context<'_>) {
+        if !self.waiters.iter().any(|waker| waker.will_wake(cx.waker())) {
+            self.waiters.push(cx.waker().clone());
+        }
+    }
+
+    fn wake_waiters(&mut self) {
+        for waker in self.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+#[derive(Default)]
+struct SendCredit {
+    bi: Limit,
+    uni: Limit,
+    data: Limit,
+}
+
+/// A limit advertised by the peer, and its usage.
+#[derive(Default)]
+struct Limit {
+    max: Option<u64>,
+    used: u64,
+    blocked_at: Option<u64>,
+}
+
+impl Limit {
+    fn available(&self) -> u64 {
+        match self.max {
+            Some(max) => max.saturating_sub(self.used),
+            None => u64::MAX,
+        }
+    }
+
+    /// Raises the limit, returning whether it changed (limits never decrease).
+    fn raise(&mut self, max: u64) -> bool {
+        if matches!(self.max, Some(current) if current >= max) {
+            return false;
+        }
+
+        self.max = Some(max);
+        true
+    }
+
+    /// Returns the limit to report as blocking (only once for each limit).
+    fn blocked(&mut self) -> Option<u64> {
+        let max = self.max?;
+
+        if self.blocked_at == Some(max) {
+            return None;
+        }
+
+        self.blocked_at = Some(max);
+        Some(max)
+    }
+}
+
+struct ReceiveCredit {
+    bi: Grant,
+    uni: Grant,
+    data: Grant,
+}
+
+/// Credit granted to the peer, and its usage.
+struct Grant {
+    max: u64,
+    used: u64,
+    window: u64,
+}
+
+struct CreditExceeded;
+
+impl Grant {
+    fn new(window: u64) -> Self {
+        Self {
+            max: window,
+            used: 0,
+            window,
+        }
+    }
+
+    /// Accounts `amount` of consumed credit.
+    ///
+    /// Returns the new limit if the credit has been extended.
+    fn consume(&mut self, amount: u64) -> Result<Option<u64>, CreditExceeded> {
+        self.used = self.used.saturating_add(amount);
+
+        if self.used > self.max {
+            return Err(CreditExceeded);
+        }
+
+        let max = self.used.saturating_add(self.window);
+        if (self.max - self.used).saturating_mul(2) <= self.window && max > self.max {
+            self.max = max;
+            return Ok(Some(max));
+        }
+
+        Ok(None)
+    }
+}
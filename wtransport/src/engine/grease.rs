@@ -0,0 +1,65 @@
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use wtransport_proto::varint::VarInt;
+
+/// Returns a random reserved identifier (`0x1f * N + 0x21`), valid for
+/// *grease* frame types, stream types and settings.
+pub(crate) fn reserved_id() -> VarInt {
+    let n = u64::from(random_u32());
+    VarInt::try_from_u64(0x1f * n + 0x21).expect("Reserved identifier fits a varint")
+}
+
+/// Returns a random value, for *grease* settings.
+pub(crate) fn random_value() -> VarInt {
+    VarInt::from_u32(random_u32())
+}
+
+/// Returns a random payload (up to 8 bytes), for *grease* frames.
+pub(crate) fn random_payload() -> Vec<u8> {
+    let mut bytes = [0; 9];
+    fill(&mut bytes);
+
+    let len = usize::from(bytes[0] % 9);
+    bytes[1..=len].to_vec()
+}
+
+/// Number of reserved (*grease*) streams and frames received on a connection.
+///
+/// They carry no meaning: they are discarded, without affecting the connection.
+#[derive(Default)]
+pub(crate) struct ReservedCounts {
+    streams: AtomicU64,
+    frames: AtomicU64,
+}
+
+impl ReservedCounts {
+    pub(crate) fn stream_discarded(&self) {
+        self.streams.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn frame_discarded(&self) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn streams(&self) -> u64 {
+        self.streams.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn frames(&self) -> u64 {
+        self.frames.load(Ordering::Relaxed)
+    }
+}
+
+fn random_u32() -> u32 {
+    let mut bytes = [0; 4];
+    fill(&mut bytes);
+    u32::from_le_bytes(bytes)
+}
+
+fn fill(bytes: &mut [u8]) {
+    SystemRandom::new()
+        .fill(bytes)
+        .expect("System random generator failure");
+}
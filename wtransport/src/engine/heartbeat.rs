@@ -0,0 +1,90 @@
+use crate::config::HeartbeatConfig;
+use crate::runtime;
+use crate::runtime::Task;
+use quinn::VarInt;
+use std::future::pending;
+use tokio::sync::watch;
+use wtransport_proto::error::ErrorCode;
+
+/// Detects when the peer goes silent.
+///
+/// Every interval, the number of UDP datagrams received from the peer is
+/// sampled: as keep-alive PINGs are sent at least as often as the heartbeat
+/// interval, a responsive peer always acknowledges something. After
+/// [`HeartbeatConfig::max_misses`] consecutive intervals without any incoming
+/// packet, the peer is considered silent.
+pub(super) struct Heartbeat {
+    silent: watch::Receiver<bool>,
+    task: Task<()>,
+}
+
+impl Heartbeat {
+    pub(super) fn new(quic_connection: quinn::Connection, config: HeartbeatConfig) -> Self {
+        let (sender, silent) = watch::channel(false);
+        let task = runtime::spawn(Self::run(quic_connection, config, sender));
+
+        Self { silent, task }
+    }
+
+    /// Waits for the peer to be silent.
+    ///
+    /// If the connection is closed in the meantime, this never completes.
+    pub(super) async fn silent(&self) {
+        let mut receiver = self.silent.clone();
+
+        loop {
+            if *receiver.borrow() {
+                return;
+            }
+
+            if receiver.changed().await.is_err() {
+                return pending().await;
+            }
+        }
+    }
+
+    async fn run(
+        quic_connection: quinn::Connection,
+        config: HeartbeatConfig,
+        silent: watch::Sender<bool>,
+    ) {
+        let mut last_rx_datagrams = quic_connection.stats().udp_rx.datagrams;
+        let mut misses = 0;
+
+        loop {
+            tokio::select! {
+                _ = runtime::sleep(config.interval()) => {}
+                _ = quic_connection.closed() => return,
+            }
+
+            let rx_datagrams = quic_connection.stats().udp_rx.datagrams;
+
+            if rx_datagrams == last_rx_datagrams {
+                misses += 1;
+            } else {
+                misses = 0;
+            }
+
+            last_rx_datagrams = rx_datagrams;
+
+            silent.send_if_modified(|silent| {
+                let is_silent = misses >= config.max_misses();
+                std::mem::replace(silent, is_silent) != is_silent
+            });
+
+            if misses >= config.max_misses() && config.close_on_silence() {
+                let code = VarInt::from_u64(ErrorCode::NoError.to_code().into_inner())
+                    .expect("HTTP3 error code fits a varint");
+
+                quic_connection.close(code, b"Peer unresponsive");
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
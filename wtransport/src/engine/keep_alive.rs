@@ -0,0 +1,65 @@
+use crate::config::AdaptiveKeepAlive;
+use crate::runtime;
+use std::net::SocketAddr;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Schedules keep-alive packets, adapting their interval to NAT timeouts.
+///
+/// A change of the peer address is taken as evidence the NAT binding expired:
+/// the interval is halved. After [`KeepAliveScheduler::GROWTH_TICKS`] keep-alives
+/// without any rebinding, the interval grows by half.
+pub(super) struct KeepAliveScheduler {
+    config: AdaptiveKeepAlive,
+    interval: Duration,
+    deadline: Instant,
+    remote_address: SocketAddr,
+    stable_ticks: u32,
+}
+
+impl KeepAliveScheduler {
+    /// Number of keep-alives without rebinding before the interval grows.
+    const GROWTH_TICKS: u32 = 10;
+
+    pub(super) fn new(config: AdaptiveKeepAlive, quic_connection: &quinn::Connection) -> Self {
+        let interval = config.max_interval();
+
+        Self {
+            config,
+            interval,
+            deadline: Instant::now() + interval,
+            remote_address: quic_connection.remote_address(),
+            stable_ticks: 0,
+        }
+    }
+
+    /// Waits for the next keep-alive to be sent, updating the interval.
+    ///
+    /// If `scheduler` is [`None`], this never completes.
+    pub(super) async fn tick(scheduler: Option<&mut Self>, quic_connection: &quinn::Connection) {
+        let scheduler = match scheduler {
+            Some(scheduler) => scheduler,
+            None => return std::future::pending().await,
+        };
+
+        runtime::sleep_until(scheduler.deadline).await;
+
+        let remote_address = quic_connection.remote_address();
+
+        if remote_address != scheduler.remote_address {
+            scheduler.remote_address = remote_address;
+            scheduler.stable_ticks = 0;
+            scheduler.interval = (scheduler.interval / 2).max(scheduler.config.min_interval());
+        } else {
+            scheduler.stable_ticks += 1;
+
+            if scheduler.stable_ticks >= Self::GROWTH_TICKS {
+                scheduler.stable_ticks = 0;
+                scheduler.interval =
+                    (scheduler.interval * 3 / 2).min(scheduler.config.max_interval());
+            }
+        }
+
+        scheduler.deadline = Instant::now() + scheduler.interval;
+    }
+}
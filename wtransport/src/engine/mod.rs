@@ -1,4 +1,22 @@
+use crate::accept::AcceptHook;
+use crate::config::ConnectionOptions;
+use crate::config::RequestHandler;
+use crate::config::ServerHandlers;
+use crate::config::SessionFlowControl;
+use crate::config::SessionPolicy;
+use crate::connection::ConnectionStats;
 use crate::datagram::Datagram;
+use crate::datagram::DatagramChannel;
+use crate::datagram::DatagramOptions;
+use crate::engine::buffer_pool::BufferPool;
+use crate::engine::demux::Demux;
+use crate::engine::demux::SessionQueues;
+use crate::engine::dgram_queue::DatagramQueue;
+use crate::engine::flow_control::ReceiveWindowTuner;
+use crate::engine::grease;
+use crate::engine::grease::ReservedCounts;
+use crate::engine::heartbeat::Heartbeat;
+use crate::engine::pacer::DatagramPacer;
 use crate::engine::session::SessionLocalRequest;
 use crate::engine::session::SessionRemoteRequest;
 use crate::engine::stream::BiLocal;
@@ -6,16 +24,43 @@ use crate::engine::stream::BiRemote;
 use crate::engine::stream::Stream;
 use crate::engine::stream::UniLocal;
 use crate::engine::stream::UniRemote;
+use crate::engine::stream::WriteProgress;
 use crate::engine::stream::Wt;
+use crate::engine::worker::ExtensionFrameQueues;
+use crate::engine::worker::ExtensionFrames;
 use crate::engine::worker::Worker;
 use crate::engine::worker::WorkerError;
 use crate::engine::worker::WorkerHandler;
+use crate::engine::worker::WorkerOptions;
 use crate::error::DatagramError;
+use crate::error::H3Error;
+use crate::error::StreamError;
+use crate::events::ConnectionEvent;
+use crate::events::EventMetrics;
+use crate::events::EventSender;
+use crate::events::Events;
+use crate::events::StreamClosedNotice;
+use crate::marking::EcnCounters;
+use crate::metrics::Metrics;
+use crate::qlog::QlogTrace;
+use crate::qlog::VantagePoint;
+use crate::runtime;
+use crate::runtime::Task;
+use crate::trace;
 use quinn::VarInt;
+use std::future::Future;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tokio::sync::watch;
 use tokio::sync::Mutex;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::headers::Headers;
 use wtransport_proto::ids::SessionId;
+use wtransport_proto::ids::StreamId;
 use wtransport_proto::settings::Settings;
 use wtransport_proto::stream::StreamHeader;
 
@@ -23,35 +68,149 @@ pub(crate) struct Engine {
     quic_connection: quinn::Connection,
     worker_handle: Mutex<WorkerHandler>,
     settings_channel: Mutex<watch::Receiver<Option<Settings>>>,
-    bi_streams_channel: Mutex<mpsc::Receiver<Stream<BiRemote, Wt>>>,
-    uni_streams_channel: Mutex<mpsc::Receiver<Stream<UniRemote, Wt>>>,
+    peer_settings: watch::Receiver<Option<Settings>>,
     session_streams_channel: Mutex<mpsc::Receiver<SessionRemoteRequest>>,
+    demux: Arc<Demux>,
+    window_tuner: Option<Task<()>>,
+    datagram_pacer: Option<DatagramPacer>,
+    datagram_queue: DatagramQueue,
+    datagram_send_buffer_size: usize,
+    buffer_pool: BufferPool,
+    heartbeat: Option<Heartbeat>,
+    write_progress: Arc<WriteProgress>,
+    qlog: Option<Arc<QlogTrace>>,
+    handlers: ServerHandlers,
+    max_sessions: Option<usize>,
+    default_stream_priority: i32,
+    session_flow_control: Option<SessionFlowControl>,
+    stream_counts: StreamCounts,
+    reserved_counts: Arc<ReservedCounts>,
+    parse_limits: wtransport_proto::ParseLimits,
+    goaway_request: watch::Sender<bool>,
+    remote_goaway: watch::Receiver<Option<wtransport_proto::varint::VarInt>>,
+    rebinds: watch::Sender<u64>,
+    ecn_counters: std::sync::Mutex<Option<Arc<EcnCounters>>>,
+    extension_frames: ExtensionFrameQueues,
+    extension_frames_out: mpsc::UnboundedSender<(wtransport_proto::varint::VarInt, Vec<u8>)>,
+    metrics: Arc<dyn Metrics>,
+    events: EventSender,
+    event_monitor: std::sync::Mutex<Option<Task<()>>>,
 }
 
 impl Engine {
-    pub fn new(quic_connection: quinn::Connection) -> Self {
+    /// Initial interval between checks of the outgoing datagram buffer space,
+    /// when waiting to send a datagram. It doubles at every check, up to the RTT.
+    const DATAGRAM_BUFFER_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Maximum wait for room in the outgoing datagram buffer: then, the oldest
+    /// buffered datagrams are discarded instead (as [`Self::send_datagram`] does).
+    const DATAGRAM_BUFFER_MAX_WAIT: Duration = Duration::from_secs(1);
+
+    /// Sampling period of the peer's address, for [`ConnectionEvent::PathMigrated`].
+    const PATH_SAMPLING: Duration = Duration::from_millis(100);
+
+    pub fn new(
+        quic_connection: quinn::Connection,
+        connection_options: ConnectionOptions,
+        qlog: Option<Arc<QlogTrace>>,
+        handlers: ServerHandlers,
+        local_settings: Arc<Settings>,
+        metrics: Arc<dyn Metrics>,
+        side: VantagePoint,
+    ) -> Self {
+        let events = EventSender::new();
+        let metrics: Arc<dyn Metrics> = Arc::new(EventMetrics::new(metrics, events.clone()));
         let settings_channel = watch::channel(None);
-        let bi_streams_channel = mpsc::channel(1024);
-        let uni_streams_channel = mpsc::channel(1024);
         let session_streams_channel = mpsc::channel(1);
+        let demux = Arc::new(Demux::new(
+            connection_options.max_session_buffer,
+            connection_options.pending_sessions,
+            side,
+            metrics.clone(),
+        ));
+        let goaway_request = watch::channel(false);
+        let remote_goaway = watch::channel(None);
+        let extension_frames = ExtensionFrameQueues::default();
+        let extension_frames_out = mpsc::unbounded_channel();
+        let reserved_counts = Arc::new(ReservedCounts::default());
 
         let worker = Worker::new(
             quic_connection.clone(),
             settings_channel.0,
-            bi_streams_channel.0,
-            uni_streams_channel.0,
             session_streams_channel.0,
+            demux.clone(),
+            goaway_request.1,
+            remote_goaway.0,
+            WorkerOptions {
+                qlog: qlog.clone(),
+                adaptive_keep_alive: connection_options.adaptive_keep_alive,
+                grease: connection_options.grease,
+                local_settings,
+                max_sessions: Self::advertised_max_sessions(connection_options.max_sessions),
+                extension_frames: ExtensionFrames {
+                    incoming: extension_frames.clone(),
+                    outgoing: extension_frames_out.1,
+                },
+                reserved_counts: reserved_counts.clone(),
+                parse_limits: connection_options.parse_limits,
+                events: events.clone(),
+            },
         );
 
         let worker_handle = WorkerHandler::run_worker(worker);
 
+        let receive_window = connection_options.receive_window;
+        let window_tuner = receive_window
+            .autotuning()
+            .then(|| ReceiveWindowTuner::new(quic_connection.clone(), receive_window).run());
+
+        let datagram_pacer = connection_options
+            .datagram_pacing
+            .map(|interval| DatagramPacer::new(quic_connection.clone(), interval, metrics.clone()));
+
+        let datagram_queue = DatagramQueue::new(quic_connection.clone(), metrics.clone());
+
+        let heartbeat = connection_options
+            .heartbeat
+            .map(|config| Heartbeat::new(quic_connection.clone(), config));
+
+        let write_progress = Arc::new(WriteProgress::new(quic_connection.clone()));
+
         Self {
             quic_connection,
             worker_handle: Mutex::new(worker_handle),
+            peer_settings: settings_channel.1.clone(),
             settings_channel: Mutex::new(settings_channel.1),
-            bi_streams_channel: Mutex::new(bi_streams_channel.1),
-            uni_streams_channel: Mutex::new(uni_streams_channel.1),
             session_streams_channel: Mutex::new(session_streams_channel.1),
+            demux,
+            window_tuner,
+            datagram_pacer,
+            datagram_queue,
+            datagram_send_buffer_size: connection_options.transport.datagram_send_buffer_size(),
+            buffer_pool: BufferPool::new(
+                connection_options
+                    .buffer_pool_capacity
+                    .unwrap_or(BufferPool::DEFAULT_CAPACITY),
+            ),
+            heartbeat,
+            write_progress,
+            qlog,
+            handlers,
+            max_sessions: connection_options.max_sessions,
+            default_stream_priority: connection_options.default_stream_priority,
+            session_flow_control: connection_options.session_flow_control,
+            stream_counts: StreamCounts::default(),
+            reserved_counts,
+            parse_limits: connection_options.parse_limits,
+            goaway_request: goaway_request.0,
+            remote_goaway: remote_goaway.1,
+            rebinds: watch::channel(0).0,
+            ecn_counters: std::sync::Mutex::new(None),
+            extension_frames,
+            extension_frames_out: extension_frames_out.0,
+            metrics,
+            events,
+            event_monitor: std::sync::Mutex::new(None),
         }
     }
 
@@ -68,6 +227,11 @@ impl Engine {
         }
     }
 
+    /// The SETTINGS received from the peer, if already arrived.
+    pub fn peer_settings(&self) -> Option<Settings> {
+        self.peer_settings.borrow().clone()
+    }
+
     pub async fn accept_session(&self) -> Result<SessionRemoteRequest, WorkerError> {
         let mut lock = self.session_streams_channel.lock().await;
         match lock.recv().await {
@@ -82,78 +246,659 @@ impl Engine {
             None => return Err(self.worker_result().await),
         };
 
-        Ok(SessionLocalRequest::new(stream))
+        self.demux.request(
+            SessionId::try_from_session_stream(stream.id())
+                .expect("Client opens bidirectional streams"),
+        );
+
+        Ok(SessionLocalRequest::new(
+            stream,
+            self.reserved_counts.clone(),
+            self.parse_limits,
+            self.events.clone(),
+        ))
     }
 
-    pub async fn accept_bi(&self) -> Result<Stream<BiRemote, Wt>, WorkerError> {
-        let mut lock = self.bi_streams_channel.lock().await;
-        match lock.recv().await {
-            Some(stream) => Ok(stream),
-            None => Err(self.worker_result().await),
+    /// The priority of new outgoing streams.
+    pub fn default_stream_priority(&self) -> i32 {
+        self.default_stream_priority
+    }
+
+    /// The session-level flow control granted to the peer, if configured.
+    pub fn session_flow_control(&self) -> Option<SessionFlowControl> {
+        self.session_flow_control
+    }
+
+    /// Write progress shared among the streams of the connection.
+    pub fn write_progress(&self) -> Arc<WriteProgress> {
+        self.write_progress.clone()
+    }
+
+    /// Resolves the policy of an incoming session from its request headers.
+    pub fn session_policy(&self, headers: &Headers) -> SessionPolicy {
+        self.handlers
+            .session_policy
+            .as_ref()
+            .map_or_else(SessionPolicy::unrestricted, |resolver| resolver(headers))
+    }
+
+    /// The limit of concurrent sessions advertised to the peer (at least `1`).
+    fn advertised_max_sessions(max_sessions: Option<usize>) -> wtransport_proto::varint::VarInt {
+        let max_sessions = max_sessions.map_or(u32::MAX, |max_sessions| {
+            u32::try_from(max_sessions).unwrap_or(u32::MAX)
+        });
+
+        wtransport_proto::varint::VarInt::from_u32(max_sessions.max(1))
+    }
+
+    /// The handler of plain HTTP3 requests, if any.
+    pub fn request_handler(&self) -> Option<&RequestHandler> {
+        self.handlers.request_handler.as_ref()
+    }
+
+    /// The hooks deciding whether session requests are accepted.
+    pub fn accept_hooks(&self) -> &[AcceptHook] {
+        &self.handlers.accept_hooks
+    }
+
+    /// Checks whether one more session can be established on the connection.
+    pub fn admit_session(&self) -> bool {
+        self.max_sessions.map_or(true, |max_sessions| {
+            self.demux.claimed_sessions() < max_sessions
+        })
+    }
+
+    /// Claims the incoming streams and datagrams of an established session.
+    ///
+    /// Incoming streams and datagrams not allowed by `policy` are rejected.
+    pub fn register_session(&self, session_id: SessionId, policy: SessionPolicy) -> SessionQueues {
+        self.demux.claim(session_id, policy)
+    }
+
+    pub async fn accept_bi(
+        &self,
+        queues: &SessionQueues,
+    ) -> Result<Stream<BiRemote, Wt>, WorkerError> {
+        if fail_point!(StreamAccept) {
+            return Err(Self::injected_failure(ErrorCode::StreamCreation));
+        }
+
+        let mut lock = queues.bi.lock().await;
+        loop {
+            match lock.recv().await {
+                Some(stream) if queues.admit_bi() => {
+                    self.stream_counts
+                        .accepted_bi
+                        .fetch_add(1, Ordering::Relaxed);
+                    self.stream_opened(stream.id(), false);
+                    return Ok(stream);
+                }
+                Some(stream) => {
+                    trace::stream_rejected(stream.id());
+                    stream.stop(ErrorCode::BufferedStreamRejected.to_code())
+                }
+                None => return Err(self.worker_result().await),
+            }
         }
     }
 
-    pub async fn accept_uni(&self) -> Result<Stream<UniRemote, Wt>, WorkerError> {
-        let mut lock = self.uni_streams_channel.lock().await;
-        match lock.recv().await {
-            Some(stream) => Ok(stream),
-            None => Err(self.worker_result().await),
+    pub async fn accept_uni(
+        &self,
+        queues: &SessionQueues,
+    ) -> Result<Stream<UniRemote, Wt>, WorkerError> {
+        if fail_point!(StreamAccept) {
+            return Err(Self::injected_failure(ErrorCode::StreamCreation));
+        }
+
+        let mut lock = queues.uni.lock().await;
+        loop {
+            match lock.recv().await {
+                Some(stream) if queues.admit_uni() => {
+                    self.stream_counts
+                        .accepted_uni
+                        .fetch_add(1, Ordering::Relaxed);
+                    self.stream_opened(stream.id(), false);
+                    return Ok(stream);
+                }
+                Some(stream) => {
+                    trace::stream_rejected(stream.id());
+                    stream.stop(ErrorCode::BufferedStreamRejected.to_code())
+                }
+                None => return Err(self.worker_result().await),
+            }
         }
     }
 
-    pub async fn open_bi(&self, session_id: SessionId) -> Result<Stream<BiLocal, Wt>, WorkerError> {
-        let stream = match Stream::open_bi(&self.quic_connection).await {
-            Some(stream) => stream,
-            None => return Err(self.worker_result().await),
+    /// Opens a stream, giving up (with [`None`]) if the peer does not grant
+    /// one before `deadline`.
+    ///
+    /// The deadline applies only to the wait for a stream: once opened, its
+    /// header is written anyway, so that an expired attempt never leaves a
+    /// partial header on the wire.
+    pub async fn open_bi(
+        &self,
+        session_id: SessionId,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Stream<BiLocal, Wt>>, WorkerError> {
+        let stream = match Self::until(deadline, Stream::open_bi(&self.quic_connection)).await {
+            Some(Some(stream)) => stream,
+            Some(None) => return Err(self.worker_result().await),
+            None => return Ok(None),
         };
 
         match stream.upgrade().upgrade(session_id).await {
-            Ok(stream) => Ok(stream),
+            Ok(stream) => {
+                if let Some(qlog) = self.qlog.as_deref() {
+                    qlog.stream_opened(stream.id(), true);
+                }
+
+                self.stream_counts.opened_bi.fetch_add(1, Ordering::Relaxed);
+                self.stream_opened(stream.id(), true);
+                Ok(Some(stream))
+            }
             Err(_) => Err(self.worker_result().await),
         }
     }
 
-    pub async fn open_uni(
-        &self,
-        session_id: SessionId,
-    ) -> Result<Stream<UniLocal, Wt>, WorkerError> {
+    /// Elicits an acknowledgement from the peer, returning the round-trip time.
+    ///
+    /// A uni-directional stream of reserved type (which the peer ignores) is
+    /// opened and finished: the peer acknowledges its data, or stops it.
+    pub async fn ping(&self) -> Result<Duration, WorkerError> {
         let stream = match Stream::open_uni(&self.quic_connection).await {
             Some(stream) => stream,
             None => return Err(self.worker_result().await),
         };
 
+        // Opening might wait for the peer to grant more streams: not part of the round trip
+        let start = Instant::now();
+
+        let mut stream = match stream
+            .upgrade(StreamHeader::new_exercise(grease::reserved_id()))
+            .await
+        {
+            Ok(stream) => stream,
+            Err(_) => return Err(self.worker_result().await),
+        };
+
+        match stream.finish().await {
+            Ok(()) | Err(StreamError::Stopped) => Ok(start.elapsed()),
+            Err(StreamError::ConnectionClosed) => Err(self.worker_result().await),
+        }
+    }
+
+    /// Opens a stream, giving up (with [`None`]) if the peer does not grant
+    /// one before `deadline` (see [`Self::open_bi`]).
+    pub async fn open_uni(
+        &self,
+        session_id: SessionId,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Stream<UniLocal, Wt>>, WorkerError> {
+        let stream = match Self::until(deadline, Stream::open_uni(&self.quic_connection)).await {
+            Some(Some(stream)) => stream,
+            Some(None) => return Err(self.worker_result().await),
+            None => return Ok(None),
+        };
+
         match stream
             .upgrade(StreamHeader::new_webtransport(session_id))
             .await
         {
-            Ok(stream) => Ok(stream.upgrade()),
+            Ok(stream) => {
+                if let Some(qlog) = self.qlog.as_deref() {
+                    qlog.stream_opened(stream.id(), true);
+                    qlog.stream_type_set(stream.id(), stream.header().kind(), true);
+                }
+
+                self.stream_counts
+                    .opened_uni
+                    .fetch_add(1, Ordering::Relaxed);
+                self.stream_opened(stream.id(), true);
+                Ok(Some(stream.upgrade()))
+            }
             Err(_) => Err(self.worker_result().await),
         }
     }
 
-    pub async fn receive_datagram(&self, session_id: SessionId) -> Result<Datagram, WorkerError> {
+    pub async fn receive_datagram(&self, queues: &SessionQueues) -> Result<Datagram, WorkerError> {
+        let mut lock = queues.dgram.lock().await;
         loop {
-            let quic_dgram = match self.quic_connection.read_datagram().await {
-                Ok(quic_dgram) => quic_dgram,
-                Err(_) => return Err(self.worker_result().await),
+            match lock.recv().await {
+                Some(dgram) => {
+                    queues.unbuffer_datagram(&dgram);
+
+                    if queues.admit_datagram() {
+                        return Ok(dgram);
+                    }
+                }
+                None => return Err(self.worker_result().await),
+            }
+        }
+    }
+
+    /// Waits for at least one datagram, then appends to `dgrams` all the
+    /// queued ones (up to `max`), without waiting further.
+    pub async fn receive_datagrams(
+        &self,
+        queues: &SessionQueues,
+        dgrams: &mut Vec<Datagram>,
+        max: usize,
+    ) -> Result<usize, WorkerError> {
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let mut lock = queues.dgram.lock().await;
+        let mut received = 0;
+
+        while received == 0 {
+            let Some(dgram) = lock.recv().await else {
+                return Err(self.worker_result().await);
             };
 
-            if let Some(dgram) =
-                Datagram::read(session_id, quic_dgram).map_err(WorkerError::LocalClosed)?
-            {
-                return Ok(dgram);
+            received += Self::admit_datagram(queues, dgram, dgrams);
+
+            while received < max {
+                let Ok(dgram) = lock.try_recv() else {
+                    break;
+                };
+
+                received += Self::admit_datagram(queues, dgram, dgrams);
             }
         }
+
+        Ok(received)
+    }
+
+    /// Accounts a dequeued datagram, returning `1` if it is kept.
+    fn admit_datagram(
+        queues: &SessionQueues,
+        dgram: Datagram,
+        dgrams: &mut Vec<Datagram>,
+    ) -> usize {
+        queues.unbuffer_datagram(&dgram);
+
+        if queues.admit_datagram() {
+            dgrams.push(dgram);
+            1
+        } else {
+            0
+        }
     }
 
     pub fn send_datagram(&self, data: &[u8], session_id: SessionId) -> Result<(), DatagramError> {
-        let dgram = Datagram::write(session_id, data);
+        self.send_quic_datagram(Datagram::write(&self.buffer_pool, session_id, data))
+    }
+
+    pub fn send_datagram_on(
+        &self,
+        channel: DatagramChannel,
+        data: &[u8],
+        session_id: SessionId,
+    ) -> Result<(), DatagramError> {
+        self.send_quic_datagram(Datagram::write_on_channel(
+            &self.buffer_pool,
+            session_id,
+            channel,
+            data,
+        ))
+    }
+
+    /// Sends a datagram through the priority queue (bypassing pacing).
+    pub fn send_datagram_with(
+        &self,
+        data: &[u8],
+        session_id: SessionId,
+        options: DatagramOptions,
+    ) -> Result<(), DatagramError> {
+        if fail_point!(DatagramSend) {
+            return Err(DatagramError::Protocol);
+        }
+
+        let dgram = Datagram::write(&self.buffer_pool, session_id, data).into_quic_bytes();
+
+        self.check_datagram(dgram.len())?;
+        self.datagram_queue.push(dgram, options);
+
+        self.metrics.datagram_sent();
+        Ok(())
+    }
+
+    /// Sends a datagram waiting for room in the outgoing buffer (or in the
+    /// pacing queue), instead of discarding older datagrams.
+    pub async fn send_datagram_wait(
+        &self,
+        data: &[u8],
+        session_id: SessionId,
+    ) -> Result<(), DatagramError> {
+        if fail_point!(DatagramSend) {
+            return Err(DatagramError::Protocol);
+        }
+
+        let dgram = Datagram::write(&self.buffer_pool, session_id, data).into_quic_bytes();
+
+        self.check_datagram(dgram.len())?;
+
+        match &self.datagram_pacer {
+            Some(datagram_pacer) => datagram_pacer
+                .send_wait(session_id, dgram)
+                .await
+                .map_err(|_| DatagramError::ConnectionClosed)?,
+            None => {
+                // The buffer would never have room for it
+                if dgram.len() > self.datagram_send_buffer_size {
+                    return Err(DatagramError::TooLarge);
+                }
+
+                // No notification is available when buffer space is freed: poll it,
+                // backing off as space is freed by acknowledgments (within an RTT)
+                let deadline = Instant::now() + Self::DATAGRAM_BUFFER_MAX_WAIT;
+                let mut interval = Self::DATAGRAM_BUFFER_POLL_INTERVAL;
+
+                while self.quic_connection.datagram_send_buffer_space() < dgram.len()
+                    && Instant::now() < deadline
+                {
+                    runtime::sleep(interval).await;
+                    self.check_datagram(dgram.len())?;
+
+                    let max_interval = self
+                        .quic_connection
+                        .rtt()
+                        .max(Self::DATAGRAM_BUFFER_POLL_INTERVAL);
+                    interval = (interval * 2).min(max_interval);
+                }
+
+                self.quic_connection.send_datagram(dgram)?;
+            }
+        }
+
+        self.metrics.datagram_sent();
+        Ok(())
+    }
+
+    fn send_quic_datagram(&self, dgram: Datagram) -> Result<(), DatagramError> {
+        if fail_point!(DatagramSend) {
+            return Err(DatagramError::Protocol);
+        }
 
-        self.quic_connection
-            .send_datagram(dgram.into_quic_bytes())?;
+        let session_id = dgram.session_id();
+        let dgram = dgram.into_quic_bytes();
+
+        match &self.datagram_pacer {
+            Some(datagram_pacer) => {
+                // Paced datagrams are sent asynchronously: errors are detected in advance
+                self.check_datagram(dgram.len())?;
+                datagram_pacer.send(session_id, dgram);
+            }
+            None => {
+                self.quic_connection.send_datagram(dgram)?;
+            }
+        }
+
+        self.metrics.datagram_sent();
         Ok(())
     }
 
+    /// Runs `future` up to `deadline` (if any), returning [`None`] if it expires.
+    async fn until<F>(deadline: Option<Instant>, future: F) -> Option<F::Output>
+    where
+        F: Future,
+    {
+        match deadline {
+            Some(deadline) => runtime::timeout_at(deadline, future).await.ok(),
+            None => Some(future.await),
+        }
+    }
+
+    /// Checks a datagram with a payload of `size` bytes can be currently
+    /// sent on `session_id`.
+    pub fn check_datagram_payload(
+        &self,
+        session_id: SessionId,
+        size: usize,
+    ) -> Result<(), DatagramError> {
+        self.check_datagram(Datagram::header_size(session_id) + size)
+    }
+
+    /// Checks a datagram of `size` bytes can be currently sent.
+    fn check_datagram(&self, size: usize) -> Result<(), DatagramError> {
+        if self.quic_connection.close_reason().is_some() {
+            return Err(DatagramError::ConnectionClosed);
+        }
+
+        match self.quic_connection.max_datagram_size() {
+            Some(max_size) if size <= max_size => Ok(()),
+            Some(_) => Err(DatagramError::TooLarge),
+            None => Err(DatagramError::UnsupportedByPeer),
+        }
+    }
+
+    /// Waits for the peer to go silent, as detected by the heartbeat.
+    ///
+    /// If the heartbeat is disabled, this never completes.
+    pub async fn peer_silent(&self) {
+        match &self.heartbeat {
+            Some(heartbeat) => heartbeat.silent().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Asks the worker to send a GOAWAY frame on the control stream.
+    ///
+    /// New session requests are rejected from then on.
+    pub fn goaway(&self) {
+        self.goaway_request.send_replace(true);
+    }
+
+    /// Waits for the peer to send a GOAWAY frame.
+    ///
+    /// If the connection is closed before that, this never completes.
+    pub async fn remote_goaway(&self) {
+        let mut remote_goaway = self.remote_goaway.clone();
+
+        loop {
+            if remote_goaway.borrow_and_update().is_some() {
+                return;
+            }
+
+            if remote_goaway.changed().await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    /// Registers an extension frame type, so that received frames of that type
+    /// on the control stream are delivered to the returned queue.
+    ///
+    /// Returns [`None`] if the frame type is already registered.
+    pub fn register_extension_frame(
+        &self,
+        frame_type: wtransport_proto::varint::VarInt,
+    ) -> Option<mpsc::UnboundedReceiver<Vec<u8>>> {
+        let mut extension_frames = self.extension_frames.lock().unwrap();
+
+        if extension_frames.contains_key(&frame_type) {
+            return None;
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        extension_frames.insert(frame_type, sender);
+
+        Some(receiver)
+    }
+
+    /// Sends an extension frame on the local control stream.
+    ///
+    /// Returns `false` if the worker is terminated.
+    pub fn send_extension_frame(
+        &self,
+        frame_type: wtransport_proto::varint::VarInt,
+        payload: Vec<u8>,
+    ) -> bool {
+        self.extension_frames_out
+            .send((frame_type, payload))
+            .is_ok()
+    }
+
+    /// Notifies that the local endpoint has been bound to a new socket.
+    pub fn rebound(&self) {
+        self.rebinds.send_modify(|rebinds| *rebinds += 1);
+    }
+
+    /// Waits for the local endpoint to be bound to a new socket.
+    pub async fn wait_rebound(&self) {
+        let mut rebinds = self.rebinds.subscribe();
+
+        // The sender is owned by `self`: it cannot be dropped while waiting
+        let _ = rebinds.changed().await;
+    }
+
+    /// The underlying QUIC connection.
+    pub fn quic_connection(&self) -> &quinn::Connection {
+        &self.quic_connection
+    }
+
+    pub fn metrics(&self) -> &dyn Metrics {
+        &*self.metrics
+    }
+
+    /// Subscribes to the events of the connection.
+    ///
+    /// The first subscription starts the task observing the events that
+    /// are not reported by other components (settings, GOAWAY, migrations).
+    pub fn events(&self) -> Events {
+        let events = self.events.subscribe();
+
+        let mut event_monitor = self.event_monitor.lock().expect("Mutex poisoned");
+        if event_monitor.is_none() {
+            *event_monitor = Some(runtime::spawn(Self::monitor_events(
+                self.quic_connection.clone(),
+                self.peer_settings.clone(),
+                self.remote_goaway.clone(),
+                self.rebinds.subscribe(),
+                self.events.clone(),
+            )));
+        }
+
+        events
+    }
+
+    /// Returns the notice emitting [`ConnectionEvent::StreamClosed`] once the
+    /// handles of the stream are dropped.
+    pub fn stream_closed_notice(&self, stream_id: StreamId) -> Arc<StreamClosedNotice> {
+        StreamClosedNotice::new(stream_id, self.events.clone())
+    }
+
+    fn stream_opened(&self, stream_id: StreamId, local: bool) {
+        self.events
+            .emit(|| ConnectionEvent::StreamOpened { stream_id, local });
+    }
+
+    /// Emits the events of received settings and GOAWAY, and of path
+    /// migrations, until the connection is closed.
+    ///
+    /// Only changes after the subscription are reported.
+    async fn monitor_events(
+        quic_connection: quinn::Connection,
+        mut peer_settings: watch::Receiver<Option<Settings>>,
+        mut remote_goaway: watch::Receiver<Option<wtransport_proto::varint::VarInt>>,
+        mut rebinds: watch::Receiver<u64>,
+        events: EventSender,
+    ) {
+        peer_settings.borrow_and_update();
+        remote_goaway.borrow_and_update();
+
+        let mut settings_open = true;
+        let mut goaway_open = true;
+        let mut rebinds_open = true;
+        let mut remote_address = quic_connection.remote_address();
+
+        loop {
+            tokio::select! {
+                result = peer_settings.changed(), if settings_open => {
+                    settings_open = result.is_ok();
+
+                    if let Some(settings) = peer_settings.borrow_and_update().clone() {
+                        events.emit(|| ConnectionEvent::SettingsReceived(settings));
+                    }
+                }
+                result = remote_goaway.changed(), if goaway_open => {
+                    goaway_open = result.is_ok();
+
+                    if let Some(id) = *remote_goaway.borrow_and_update() {
+                        events.emit(|| ConnectionEvent::GoAwayReceived { id });
+                    }
+                }
+                result = rebinds.changed(), if rebinds_open => {
+                    rebinds_open = result.is_ok();
+
+                    if rebinds_open {
+                        remote_address = quic_connection.remote_address();
+                        events.emit(|| ConnectionEvent::PathMigrated { remote_address });
+                    }
+                }
+                () = runtime::sleep(Self::PATH_SAMPLING) => {
+                    let current = quic_connection.remote_address();
+
+                    if current != remote_address {
+                        remote_address = current;
+                        events.emit(|| ConnectionEvent::PathMigrated { remote_address });
+                    }
+                }
+                _ = quic_connection.closed() => return,
+            }
+        }
+    }
+
+    /// Samples the current statistics of the connection.
+    pub fn stats(&self) -> ConnectionStats {
+        let quic_stats = self.quic_connection.stats();
+
+        let dropped_datagrams = self.demux.dropped_datagrams()
+            + self.datagram_queue.dropped()
+            + self
+                .datagram_pacer
+                .as_ref()
+                .map_or(0, |datagram_pacer| datagram_pacer.dropped());
+
+        ConnectionStats {
+            rtt: quic_stats.path.rtt,
+            congestion_window: quic_stats.path.cwnd,
+            congestion_events: quic_stats.path.congestion_events,
+            sent_packets: quic_stats.path.sent_packets,
+            lost_packets: quic_stats.path.lost_packets,
+            lost_bytes: quic_stats.path.lost_bytes,
+            sent_bytes: quic_stats.udp_tx.bytes,
+            received_bytes: quic_stats.udp_rx.bytes,
+            dropped_datagrams,
+            opened_bi_streams: self.stream_counts.opened_bi.load(Ordering::Relaxed),
+            opened_uni_streams: self.stream_counts.opened_uni.load(Ordering::Relaxed),
+            accepted_bi_streams: self.stream_counts.accepted_bi.load(Ordering::Relaxed),
+            accepted_uni_streams: self.stream_counts.accepted_uni.load(Ordering::Relaxed),
+            reserved_streams: self.reserved_counts.streams(),
+            reserved_frames: self.reserved_counts.frames(),
+            ecn: self
+                .ecn_counters
+                .lock()
+                .expect("Mutex poisoned")
+                .as_ref()
+                .map(|ecn_counters| ecn_counters.get(self.quic_connection.remote_address()))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Reports the ECN codepoints received by the endpoint in the statistics.
+    pub fn set_ecn_counters(&self, ecn_counters: Arc<EcnCounters>) {
+        *self.ecn_counters.lock().expect("Mutex poisoned") = Some(ecn_counters);
+    }
+
+    /// The error reported for a failure injected at a fail point.
+    ///
+    /// As for real errors, the connection is closed with `h3code`.
+    fn injected_failure(h3code: ErrorCode) -> WorkerError {
+        WorkerError::LocalClosed(H3Error::new(h3code, "Injected failure"))
+    }
+
     async fn worker_result(&self) -> WorkerError {
         let mut lock = self.worker_handle.lock().await;
         lock.result().await
@@ -163,10 +908,40 @@ impl Engine {
 impl Drop for Engine {
     fn drop(&mut self) {
         self.quic_connection.close(VarInt::from_u32(0), b"");
+
+        let stats = self.quic_connection.stats();
+        self.metrics
+            .connection_closed(stats.udp_tx.bytes, stats.udp_rx.bytes);
+
+        if let Some(window_tuner) = &self.window_tuner {
+            window_tuner.abort();
+        }
+
+        if let Some(event_monitor) = self.event_monitor.get_mut().expect("Mutex poisoned") {
+            event_monitor.abort();
+        }
         // TODO(bfesta): if not mutex-ed maybe we should abort the worker
     }
 }
 
+/// Number of WebTransport streams opened and accepted on a connection.
+#[derive(Default)]
+struct StreamCounts {
+    opened_bi: AtomicU64,
+    opened_uni: AtomicU64,
+    accepted_bi: AtomicU64,
+    accepted_uni: AtomicU64,
+}
+
+pub(crate) mod buffer_pool;
+pub(crate) mod demux;
+pub(crate) mod dgram_queue;
+pub(crate) mod flow_control;
+pub(crate) mod grease;
+pub(crate) mod heartbeat;
+pub(crate) mod keep_alive;
+pub(crate) mod pacer;
+pub(crate) mod rate_limit;
 pub(crate) mod session;
 pub(crate) mod stream;
 pub(crate) mod worker;
@@ -0,0 +1,154 @@
+use crate::metrics::Metrics;
+use crate::runtime;
+use crate::runtime::Task;
+use crate::trace;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use wtransport_proto::ids::SessionId;
+
+/// Spreads bursts of outgoing datagrams over a time interval.
+///
+/// Datagrams are queued and sent by a dedicated task. All the datagrams
+/// queued at the beginning of a burst are evenly spaced across the interval;
+/// datagrams queued in the meantime are part of the next burst.
+///
+/// Within a burst, datagrams of different sessions are sent round-robin
+/// (see [`DatagramPacer::round_robin`]), so that a session's burst does not
+/// delay the datagrams of the other sessions on the same connection.
+pub(super) struct DatagramPacer {
+    queue: mpsc::Sender<(SessionId, Bytes)>,
+    dropped: Arc<AtomicU64>,
+    metrics: Arc<dyn Metrics>,
+    task: Task<()>,
+}
+
+impl DatagramPacer {
+    const QUEUE_SIZE: usize = 1024;
+
+    pub(super) fn new(
+        quic_connection: quinn::Connection,
+        interval: Duration,
+        metrics: Arc<dyn Metrics>,
+    ) -> Self {
+        let (queue, receiver) = mpsc::channel(Self::QUEUE_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let task = runtime::spawn(Self::run(
+            quic_connection,
+            receiver,
+            interval,
+            dropped.clone(),
+            metrics.clone(),
+        ));
+
+        Self {
+            queue,
+            dropped,
+            metrics,
+            task,
+        }
+    }
+
+    /// Queues a datagram for sending.
+    ///
+    /// If the queue is full, the datagram is discarded.
+    pub(super) fn send(&self, session_id: SessionId, dgram: Bytes) {
+        if self.queue.try_send((session_id, dgram)).is_err() {
+            trace::datagram_dropped("pacing queue full");
+            self.metrics.datagram_dropped();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Queues a datagram for sending, waiting if the queue is full.
+    ///
+    /// It fails if the pacing task is terminated (i.e., the connection is closed).
+    pub(super) async fn send_wait(
+        &self,
+        session_id: SessionId,
+        dgram: Bytes,
+    ) -> Result<(), mpsc::error::SendError<(SessionId, Bytes)>> {
+        self.queue.send((session_id, dgram)).await
+    }
+
+    /// Number of datagrams discarded, either because the queue was full
+    /// or because they could not be sent when their turn came.
+    pub(super) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    async fn run(
+        quic_connection: quinn::Connection,
+        mut receiver: mpsc::Receiver<(SessionId, Bytes)>,
+        interval: Duration,
+        dropped: Arc<AtomicU64>,
+        metrics: Arc<dyn Metrics>,
+    ) {
+        let mut burst = Vec::new();
+
+        while let Some(dgram) = receiver.recv().await {
+            burst.push(dgram);
+            while let Ok(dgram) = receiver.try_recv() {
+                burst.push(dgram);
+            }
+
+            let start = Instant::now();
+            let spacing = interval / burst.len() as u32;
+
+            for (index, dgram) in Self::round_robin(&mut burst).into_iter().enumerate() {
+                // Deadlines are absolute, so that timer granularity does not stretch the burst
+                runtime::sleep_until(start + spacing * index as u32).await;
+
+                match quic_connection.send_datagram(dgram) {
+                    Ok(()) => {}
+                    Err(quinn::SendDatagramError::ConnectionLost(_)) => return,
+                    Err(_) => {
+                        trace::datagram_dropped("rejected by QUIC");
+                        metrics.datagram_dropped();
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl DatagramPacer {
+    /// Drains a burst, ordering its datagrams round-robin across sessions.
+    ///
+    /// The order of the datagrams of the same session is preserved.
+    fn round_robin(burst: &mut Vec<(SessionId, Bytes)>) -> Vec<Bytes> {
+        let total = burst.len();
+
+        // Sessions sharing a connection are few: a linear lookup is enough
+        let mut sessions: Vec<(SessionId, VecDeque<Bytes>)> = Vec::new();
+        for (session_id, dgram) in burst.drain(..) {
+            match sessions.iter_mut().find(|(id, _)| *id == session_id) {
+                Some((_, queue)) => queue.push_back(dgram),
+                None => sessions.push((session_id, VecDeque::from([dgram]))),
+            }
+        }
+
+        let mut ordered = Vec::with_capacity(total);
+        while ordered.len() < total {
+            for (_, queue) in sessions.iter_mut() {
+                if let Some(dgram) = queue.pop_front() {
+                    ordered.push(dgram);
+                }
+            }
+        }
+
+        ordered
+    }
+}
+
+impl Drop for DatagramPacer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,263 @@
+use crate::config::SendRateLimit;
+use crate::runtime;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Token bucket limiting the data a session sends (stream data and datagrams).
+///
+/// The bucket holds up to [`SendRateLimit::burst`] bytes and it is refilled
+/// at [`SendRateLimit::bytes_per_sec`]. Stream writes are allowed as long as
+/// the bucket is not empty and they are charged for the bytes actually
+/// written, so concurrent writes may overdraw it (the debt delays the
+/// following ones).
+///
+/// The limit can be changed at any time: waits are capped to
+/// [`Self::MAX_WAIT`], so that a raised limit applies promptly.
+pub(crate) struct SendRateLimiter {
+    bucket: Mutex<Option<Bucket>>,
+}
+
+struct Bucket {
+    limit: SendRateLimit,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl SendRateLimiter {
+    /// Maximum time before checking the bucket again.
+    const MAX_WAIT: Duration = Duration::from_millis(50);
+
+    pub(crate) fn new() -> Self {
+        Self {
+            bucket: Mutex::new(None),
+        }
+    }
+
+    /// Sets (or removes) the limit.
+    ///
+    /// A new limit starts with a full bucket; an adjusted one keeps the
+    /// current tokens (capped to the new burst).
+    pub(crate) fn set(&self, limit: Option<SendRateLimit>) {
+        self.set_at(limit, Instant::now());
+    }
+
+    fn set_at(&self, limit: Option<SendRateLimit>, now: Instant) {
+        let mut bucket = self.bucket.lock().expect("Rate limiter lock poisoned");
+
+        *bucket = limit.map(|limit| {
+            let tokens = match bucket.take() {
+                Some(mut bucket) => {
+                    bucket.refill(now);
+                    bucket.tokens.min(limit.burst() as f64)
+                }
+                None => limit.burst() as f64,
+            };
+
+            Bucket {
+                limit,
+                tokens,
+                refilled: now,
+            }
+        });
+    }
+
+    /// Returns the current limit.
+    pub(crate) fn limit(&self) -> Option<SendRateLimit> {
+        self.bucket
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .as_ref()
+            .map(|bucket| bucket.limit)
+    }
+
+    /// Returns how many of `amount` bytes of stream data can be written now,
+    /// or when to check again.
+    ///
+    /// Written bytes must be charged with [`Self::consume`].
+    pub(crate) fn available(&self, amount: usize) -> Result<usize, Instant> {
+        self.available_at(amount, Instant::now())
+    }
+
+    fn available_at(&self, amount: usize, now: Instant) -> Result<usize, Instant> {
+        let mut bucket = self.bucket.lock().expect("Rate limiter lock poisoned");
+        let Some(bucket) = bucket.as_mut() else {
+            return Ok(amount);
+        };
+
+        bucket.refill(now);
+
+        if bucket.tokens >= 1.0 {
+            Ok(amount.min(bucket.tokens as usize))
+        } else {
+            Err(bucket.ready_at(now, 1.0))
+        }
+    }
+
+    /// Charges `amount` bytes to the bucket.
+    pub(crate) fn consume(&self, amount: usize) {
+        if let Some(bucket) = self
+            .bucket
+            .lock()
+            .expect("Rate limiter lock poisoned")
+            .as_mut()
+        {
+            bucket.tokens -= amount as f64;
+        }
+    }
+
+    /// Charges a datagram of `amount` bytes, if the bucket allows it.
+    pub(crate) fn try_consume(&self, amount: usize) -> bool {
+        self.try_consume_at(amount, Instant::now()).is_ok()
+    }
+
+    /// Charges a datagram of `amount` bytes, waiting for the bucket to allow it.
+    pub(crate) async fn consume_wait(&self, amount: usize) {
+        while let Err(ready_at) = self.try_consume_at(amount, Instant::now()) {
+            runtime::sleep_until(ready_at).await;
+        }
+    }
+
+    /// Charges a datagram, or returns when to check again.
+    ///
+    /// Datagrams larger than the burst are allowed once the bucket is full.
+    fn try_consume_at(&self, amount: usize, now: Instant) -> Result<(), Instant> {
+        let mut bucket = self.bucket.lock().expect("Rate limiter lock poisoned");
+        let Some(bucket) = bucket.as_mut() else {
+            return Ok(());
+        };
+
+        bucket.refill(now);
+
+        let needed = (amount as f64).min(bucket.limit.burst() as f64);
+        if bucket.tokens >= needed {
+            bucket.tokens -= amount as f64;
+            Ok(())
+        } else {
+            Err(bucket.ready_at(now, needed))
+        }
+    }
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * self.limit.bytes_per_sec() as f64)
+            .min(self.limit.burst() as f64);
+        self.refilled = now;
+    }
+
+    /// Returns when the bucket will hold `needed` tokens (waiting at most
+    /// [`SendRateLimiter::MAX_WAIT`]).
+    fn ready_at(&self, now: Instant, needed: f64) -> Instant {
+        let wait = (needed - self.tokens) / self.limit.bytes_per_sec().max(1) as f64;
+
+        now + Duration::from_secs_f64(wait.min(SendRateLimiter::MAX_WAIT.as_secs_f64()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// 1000 bytes per second, with a burst of 100 bytes.
+    fn limiter(now: Instant) -> SendRateLimiter {
+        let limiter = SendRateLimiter::new();
+        limiter.set_at(Some(SendRateLimit::new(1000).with_burst(100)), now);
+        limiter
+    }
+
+    #[test]
+    fn unlimited() {
+        let limiter = SendRateLimiter::new();
+
+        assert!(limiter.limit().is_none());
+        assert_eq!(limiter.available(1 << 20), Ok(1 << 20));
+        assert!(limiter.try_consume(1 << 20));
+    }
+
+    #[test]
+    fn burst() {
+        let now = Instant::now();
+        let limiter = limiter(now);
+
+        assert_eq!(limiter.available_at(1000, now), Ok(100));
+        assert_eq!(limiter.try_consume_at(60, now), Ok(()));
+        assert_eq!(limiter.available_at(1000, now), Ok(40));
+
+        // 20 bytes missing
+        assert_eq!(limiter.try_consume_at(60, now), Err(now + 20 * MS));
+
+        limiter.consume(40);
+        assert_eq!(limiter.available_at(1, now), Err(now + MS));
+    }
+
+    #[test]
+    fn refill() {
+        let now = Instant::now();
+        let limiter = limiter(now);
+
+        assert_eq!(limiter.try_consume_at(100, now), Ok(()));
+        assert!(limiter.try_consume_at(50, now + 40 * MS).is_err());
+        assert_eq!(limiter.try_consume_at(50, now + 60 * MS), Ok(()));
+
+        // Refills up to the burst
+        assert_eq!(limiter.available_at(1000, now + 10_000 * MS), Ok(100));
+    }
+
+    #[test]
+    fn oversize_datagram() {
+        let now = Instant::now();
+        let limiter = limiter(now);
+
+        limiter.consume(10);
+        assert!(limiter.try_consume_at(150, now).is_err());
+
+        // Allowed on a full bucket, leaving a debt
+        assert_eq!(limiter.try_consume_at(150, now + 10 * MS), Ok(()));
+        assert!(limiter.available_at(1, now + 10 * MS).is_err());
+        assert_eq!(limiter.available_at(1000, now + 110 * MS), Ok(50));
+    }
+
+    #[test]
+    fn adjust() {
+        let now = Instant::now();
+        let limiter = limiter(now);
+
+        // Lowered burst: tokens are capped
+        limiter.set_at(Some(SendRateLimit::new(1000).with_burst(10)), now);
+        assert_eq!(limiter.limit().map(|limit| limit.burst()), Some(10));
+        assert_eq!(limiter.available_at(1000, now), Ok(10));
+
+        // Raised burst: tokens are kept
+        limiter.set_at(Some(SendRateLimit::new(1000).with_burst(1000)), now);
+        assert_eq!(limiter.available_at(1000, now), Ok(10));
+
+        limiter.set_at(None, now);
+        assert!(limiter.limit().is_none());
+        assert_eq!(limiter.available_at(1000, now), Ok(1000));
+    }
+
+    #[test]
+    fn max_wait() {
+        let now = Instant::now();
+        let limiter = SendRateLimiter::new();
+        limiter.set_at(Some(SendRateLimit::new(1).with_burst(1)), now);
+
+        assert_eq!(limiter.available_at(5, now), Ok(1));
+        limiter.consume(1);
+
+        // One second to wait, capped
+        assert_eq!(
+            limiter.available_at(5, now),
+            Err(now + SendRateLimiter::MAX_WAIT)
+        );
+        assert_eq!(
+            limiter.try_consume_at(1, now),
+            Err(now + SendRateLimiter::MAX_WAIT)
+        );
+    }
+}
@@ -1,43 +1,121 @@
+use crate::accept::Refusal;
+use crate::engine::flow_control::SessionCredit;
+use crate::engine::grease::ReservedCounts;
+use crate::engine::rate_limit::SendRateLimiter;
 use crate::engine::stream::Bi;
 use crate::engine::stream::BiLocal;
 use crate::engine::stream::BiRemote;
 use crate::engine::stream::FrameReadError;
 use crate::engine::stream::FrameWriteError;
-use crate::engine::stream::Raw;
+use crate::engine::stream::QuicRecvStream;
+use crate::engine::stream::QuicSendStream;
 use crate::engine::stream::Stream;
+use crate::engine::stream::FRAME_PARSE_MODE;
 use crate::engine::stream::H3;
 use crate::error::H3Error;
+use crate::error::RejectionReason;
+use crate::error::SessionRejected;
+use crate::events::ConnectionEvent;
+use crate::events::EventSender;
+use crate::http3::Request;
+use crate::runtime;
+use crate::runtime::Task;
+use std::collections::HashMap;
+use std::future::pending;
+use std::sync::Arc;
+use std::sync::Mutex as SyncMutex;
+use tokio::sync::mpsc;
+use tokio::sync::watch;
+use tokio::sync::Mutex;
+use wtransport_proto::bytes::BufferReader;
+use wtransport_proto::bytes::BytesReader;
+use wtransport_proto::capsule::Capsule;
+use wtransport_proto::capsule::CapsuleKind;
+use wtransport_proto::capsule::CloseWebTransportSession;
+use wtransport_proto::capsule::DrainWebTransportSession;
+use wtransport_proto::capsule::FlowControlCapsule;
 use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::Frame;
 use wtransport_proto::frame::FrameKind;
 use wtransport_proto::headers::Headers;
 use wtransport_proto::ids::SessionId;
+use wtransport_proto::varint::VarInt;
+use wtransport_proto::ParseLimits;
+use wtransport_proto::ParseMode;
 
 #[derive(Debug)]
 pub(crate) enum SessionError {
     LocalClosed(H3Error),
     RemoteClosed,
+    Rejected(SessionRejected),
 }
 
 pub(crate) struct SessionRemoteRequest {
     stream: Stream<BiRemote, H3>,
     headers: Headers,
+    reserved_counts: Arc<ReservedCounts>,
+    parse_limits: ParseLimits,
+    events: EventSender,
 }
 
 impl SessionRemoteRequest {
-    pub(super) fn new(stream: Stream<BiRemote, H3>, headers: Headers) -> Self {
-        Self { stream, headers }
+    pub(super) fn new(
+        stream: Stream<BiRemote, H3>,
+        headers: Headers,
+        reserved_counts: Arc<ReservedCounts>,
+        parse_limits: ParseLimits,
+        events: EventSender,
+    ) -> Self {
+        Self {
+            stream,
+            headers,
+            reserved_counts,
+            parse_limits,
+            events,
+        }
+    }
+
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Whether this is a WebTransport session request (an extended CONNECT).
+    ///
+    /// Other requests are plain HTTP3 requests (see [`Self::into_request`]).
+    pub fn is_webtransport(&self) -> bool {
+        self.headers.get(":method") == Some("CONNECT")
+            && self.headers.get(":protocol") == Some("webtransport")
+    }
+
+    pub fn into_request(self) -> Request {
+        Request::new(self.stream, self.headers)
     }
 
-    pub async fn accept(mut self) -> Result<Session, SessionError> {
+    /// Checks the request is a valid WebTransport session request,
+    /// rejecting it otherwise.
+    pub async fn validate(self) -> Result<Self, SessionError> {
+        match Self::validate_headers(&self.headers) {
+            Ok(()) => Ok(self),
+            Err(reason) => Err(self.reject(reason).await),
+        }
+    }
+
+    /// Accepts the session, adding `extra_headers` to the response.
+    ///
+    /// The request is expected to be validated (see [`Self::validate`]).
+    pub async fn accept(
+        mut self,
+        extra_headers: Vec<(String, String)>,
+    ) -> Result<Session, SessionError> {
         let response_headers = [
             (":status", "200"),
             ("sec-webtransport-http3-draft", "draft02"),
         ]
         .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .chain(extra_headers)
         .collect::<Headers>();
 
-        Self::validate_headers(self.headers)?;
-
         self.stream
             .write_frame(response_headers.generate_frame(self.stream.id()))
             .await
@@ -45,45 +123,159 @@ impl SessionRemoteRequest {
                 SessionError::with_frame_write_err(frame_write_error, "Unable to accept SESSION")
             })?;
 
-        Ok(Session(self.stream.normalize()))
+        Ok(Session::new(
+            self.stream.normalize(),
+            Some(self.headers),
+            self.reserved_counts,
+            self.parse_limits,
+            self.events,
+        ))
+    }
+
+    /// Notifies the peer about the rejection.
+    ///
+    /// Malformed requests are treated as stream errors, otherwise
+    /// a `400` (or `429` for [`RejectionReason::TooManySessions`]) response is sent.
+    pub async fn reject(mut self, reason: RejectionReason) -> SessionError {
+        let status = match reason {
+            RejectionReason::Malformed => None,
+            RejectionReason::MethodNotAllowed
+            | RejectionReason::UnsupportedProtocol
+            | RejectionReason::InvalidScheme
+            | RejectionReason::InvalidOrigin => Some(400),
+            RejectionReason::TooManySessions => Some(429),
+            RejectionReason::Refused { status } => Some(status),
+        };
+
+        let response_headers = match status {
+            None => {
+                self.stream.abort(ErrorCode::Message.to_code());
+                None
+            }
+            Some(status) => {
+                let response_headers = [(":status", status)].into_iter().collect::<Headers>();
+                self.respond_and_finish(&response_headers).await;
+                Some(response_headers)
+            }
+        };
+
+        SessionError::Rejected(SessionRejected::new(reason, self.headers, response_headers))
+    }
+
+    /// Notifies the peer about the refusal by an accept hook.
+    pub async fn refuse(mut self, refusal: Refusal) -> SessionError {
+        let response_headers = refusal.response_headers();
+        self.respond_and_finish(&response_headers).await;
+
+        SessionError::Rejected(SessionRejected::new(
+            RejectionReason::Refused {
+                status: refusal.status(),
+            },
+            self.headers,
+            Some(response_headers),
+        ))
     }
 
-    fn validate_headers(headers: Headers) -> Result<(), SessionError> {
-        let method = headers.get(":method").unwrap_or_default();
+    async fn respond_and_finish(&mut self, response_headers: &Headers) {
+        if self
+            .stream
+            .write_frame(response_headers.generate_frame(self.stream.id()))
+            .await
+            .is_ok()
+        {
+            let _ = self.stream.finish().await;
+        }
+    }
+
+    fn validate_headers(headers: &Headers) -> Result<(), RejectionReason> {
+        let (method, scheme) = match (headers.get(":method"), headers.get(":scheme")) {
+            (Some(method), Some(scheme)) => (method, scheme),
+            _ => return Err(RejectionReason::Malformed),
+        };
+
         if method != "CONNECT" {
-            return Err(SessionError::LocalClosed(H3Error::new(
-                ErrorCode::Message,
-                "Method not supported",
-            )));
+            return Err(RejectionReason::MethodNotAllowed);
         }
 
-        let protocol = headers.get(":protocol").unwrap_or_default();
-        if protocol != "webtransport" {
-            return Err(SessionError::LocalClosed(H3Error::new(
-                ErrorCode::Message,
-                "Protocol not supported",
-            )));
+        if headers.get(":protocol") != Some("webtransport") {
+            return Err(RejectionReason::UnsupportedProtocol);
         }
 
-        let scheme = headers.get(":scheme").unwrap_or_default();
         if scheme != "https" {
-            return Err(SessionError::LocalClosed(H3Error::new(
-                ErrorCode::Message,
-                "Protocol not supported",
-            )));
+            return Err(RejectionReason::InvalidScheme);
+        }
+
+        if let Some(origin) = headers.get("origin") {
+            if !Self::is_valid_origin(origin) {
+                return Err(RejectionReason::InvalidOrigin);
+            }
         }
 
         Ok(())
     }
+
+    /// Checks `origin` is a serialized origin (`scheme://host[:port]` or `null`).
+    fn is_valid_origin(origin: &str) -> bool {
+        if origin == "null" {
+            return true;
+        }
+
+        let (scheme, host_port) = match origin.split_once("://") {
+            Some(split) => split,
+            None => return false,
+        };
+
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+        // IPv6 literals are enclosed in brackets and they contain colons
+        let (host, port) = if host_port.starts_with('[') {
+            match host_port.split_once(']') {
+                Some((host, "")) => (host, None),
+                Some((host, port)) => match port.strip_prefix(':') {
+                    Some(port) => (host, Some(port)),
+                    None => return false,
+                },
+                None => return false,
+            }
+        } else {
+            match host_port.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (host_port, None),
+            }
+        };
+
+        let valid_host = !host.is_empty()
+            && !host.contains(|c: char| matches!(c, '/' | '?' | '#' | '@') || c.is_whitespace());
+
+        let valid_port = port.map_or(true, |port| port.parse::<u16>().is_ok());
+
+        valid_scheme && valid_host && valid_port
+    }
 }
 
 pub(crate) struct SessionLocalRequest {
     stream: Stream<BiLocal, H3>,
+    reserved_counts: Arc<ReservedCounts>,
+    parse_limits: ParseLimits,
+    events: EventSender,
 }
 
 impl SessionLocalRequest {
-    pub(super) fn new(stream: Stream<BiLocal, H3>) -> Self {
-        Self { stream }
+    pub(super) fn new(
+        stream: Stream<BiLocal, H3>,
+        reserved_counts: Arc<ReservedCounts>,
+        parse_limits: ParseLimits,
+        events: EventSender,
+    ) -> Self {
+        Self {
+            stream,
+            reserved_counts,
+            parse_limits,
+            events,
+        }
     }
 
     pub async fn request(mut self) -> Result<SessionRemoteResponse, SessionError> {
@@ -104,23 +296,43 @@ impl SessionLocalRequest {
 
         Ok(SessionRemoteResponse {
             stream: self.stream,
+            request_headers,
+            reserved_counts: self.reserved_counts,
+            parse_limits: self.parse_limits,
+            events: self.events,
         })
     }
 }
 
 pub(crate) struct SessionRemoteResponse {
     stream: Stream<BiLocal, H3>,
+    request_headers: Headers,
+    reserved_counts: Arc<ReservedCounts>,
+    parse_limits: ParseLimits,
+    events: EventSender,
 }
 
 impl SessionRemoteResponse {
     pub async fn confirm(mut self) -> Result<Session, SessionError> {
         loop {
-            let frame = self.stream.read_frame().await.map_err(|frame_read_error| {
-                SessionError::with_frame_read_err(frame_read_error, "Unable to read reply SESSION")
-            })?;
+            let frame =
+                self.stream
+                    .read_frame(&self.parse_limits)
+                    .await
+                    .map_err(|frame_read_error| {
+                        SessionError::with_frame_read_err(
+                            frame_read_error,
+                            "Unable to read reply SESSION",
+                        )
+                    })?;
 
             let headers = match frame.kind() {
-                FrameKind::Headers => match Headers::with_frame(&frame, self.stream.id()) {
+                FrameKind::Headers => match Headers::with_frame_limits(
+                    &frame,
+                    self.stream.id(),
+                    ParseMode::Strict,
+                    &self.parse_limits,
+                ) {
                     Ok(header) => header,
                     Err(h3code) => {
                         return Err(SessionError::LocalClosed(H3Error::new(
@@ -129,7 +341,11 @@ impl SessionRemoteResponse {
                         )));
                     }
                 },
-                FrameKind::Exercise(_) => continue,
+                FrameKind::Exercise(_) => {
+                    self.reserved_counts.frame_discarded();
+                    continue;
+                }
+                FrameKind::Unknown(_) => continue,
                 _ => {
                     return Err(SessionError::LocalClosed(H3Error::new(
                         ErrorCode::FrameUnexpected,
@@ -138,38 +354,606 @@ impl SessionRemoteResponse {
                 }
             };
 
-            Self::validate_headers(headers)?;
+            Self::validate_headers(headers, self.request_headers)?;
 
-            return Ok(Session(self.stream.normalize()));
+            return Ok(Session::new(
+                self.stream.normalize(),
+                None,
+                self.reserved_counts,
+                self.parse_limits,
+                self.events,
+            ));
         }
     }
 
-    fn validate_headers(headers: Headers) -> Result<(), SessionError> {
-        let method = headers.get(":status").unwrap_or_default();
-        if method != "200" {
-            return Err(SessionError::LocalClosed(H3Error::new(
-                ErrorCode::Message,
-                "CONNECT refused",
-            )));
+    /// Accepts a 2xx response: other statuses reject the session, reporting
+    /// the response to the application.
+    fn validate_headers(headers: Headers, request_headers: Headers) -> Result<(), SessionError> {
+        let status = match headers.get(":status").map(str::parse::<u16>) {
+            Some(Ok(status)) => status,
+            Some(Err(_)) | None => {
+                return Err(SessionError::LocalClosed(H3Error::new(
+                    ErrorCode::Message,
+                    "Invalid status on SESSION reply",
+                )));
+            }
+        };
+
+        if (200..300).contains(&status) {
+            return Ok(());
         }
 
-        Ok(())
+        Err(SessionError::Rejected(SessionRejected::new(
+            RejectionReason::Refused { status },
+            request_headers,
+            Some(headers),
+        )))
     }
 }
 
-pub(crate) struct Session(Stream<Bi, Raw>);
+/// The reason a session has been terminated.
+#[derive(Clone, Debug)]
+pub(crate) enum SessionTermination {
+    /// The peer closed the session (either with a capsule or with a FIN).
+    RemotelyClosed { error_code: u32, reason: String },
+
+    /// The session has been closed locally.
+    LocallyClosed,
+
+    /// The session stream violated the protocol.
+    Error(H3Error),
+}
+
+/// Queues of the capsule types registered by the application.
+type CapsuleQueues = Arc<SyncMutex<HashMap<VarInt, mpsc::UnboundedSender<Vec<u8>>>>>;
+
+pub(crate) struct Session {
+    id: SessionId,
+    send_stream: Arc<Mutex<QuicSendStream>>,
+    termination: Arc<watch::Sender<Option<SessionTermination>>>,
+    draining: Arc<watch::Sender<bool>>,
+    trailers: Arc<watch::Sender<Option<Headers>>>,
+    capsule_queues: CapsuleQueues,
+    credit: Arc<SessionCredit>,
+    send_rate: Arc<SendRateLimiter>,
+    request_headers: Option<Headers>,
+    events: EventSender,
+    reader: Task<()>,
+    credit_writer: Task<()>,
+}
 
 impl Session {
+    fn new(
+        stream: Stream<Bi, H3>,
+        request_headers: Option<Headers>,
+        reserved_counts: Arc<ReservedCounts>,
+        parse_limits: ParseLimits,
+        events: EventSender,
+    ) -> Self {
+        // SAFETY: inner stream is a session stream by construction
+        let id = unsafe {
+            debug_assert!(stream.id().is_bidirectional() && stream.id().is_client_initiated());
+            SessionId::from_session_stream_unchecked(stream.id())
+        };
+
+        let (send_stream, recv_stream) = stream.split();
+        let send_stream = Arc::new(Mutex::new(send_stream));
+        let termination = Arc::new(watch::channel(None).0);
+        let draining = Arc::new(watch::channel(false).0);
+        let trailers = Arc::new(watch::channel(None).0);
+        let capsule_queues = CapsuleQueues::default();
+        let credit = Arc::new(SessionCredit::new());
+        let reader = runtime::spawn(Self::run_reader(
+            recv_stream,
+            termination.clone(),
+            SessionSignals {
+                draining: draining.clone(),
+                trailers: trailers.clone(),
+                capsule_queues: capsule_queues.clone(),
+                credit: credit.clone(),
+                session_id: id,
+                reserved_counts,
+                parse_limits,
+                events: events.clone(),
+            },
+        ));
+        let credit_writer = runtime::spawn(Self::run_credit_writer(
+            send_stream.clone(),
+            termination.clone(),
+            credit.clone(),
+        ));
+
+        Self {
+            id,
+            send_stream,
+            termination,
+            draining,
+            trailers,
+            capsule_queues,
+            credit,
+            send_rate: Arc::new(SendRateLimiter::new()),
+            request_headers,
+            events,
+            reader,
+            credit_writer,
+        }
+    }
+
     #[inline(always)]
     pub fn id(&self) -> SessionId {
-        // SAFETY: inner stream is a session stream by construction
-        unsafe {
-            debug_assert!(self.0.id().is_bidirectional() && self.0.id().is_client_initiated());
-            SessionId::from_session_stream_unchecked(self.0.id())
+        self.id
+    }
+
+    /// The headers of the session request (only for sessions accepted by a server).
+    #[inline(always)]
+    pub fn request_headers(&self) -> Option<&Headers> {
+        self.request_headers.as_ref()
+    }
+
+    /// The session-level flow control of the session streams.
+    #[inline(always)]
+    pub fn credit(&self) -> &Arc<SessionCredit> {
+        &self.credit
+    }
+
+    /// The limit of the data sent by the session.
+    #[inline(always)]
+    pub fn send_rate(&self) -> &Arc<SendRateLimiter> {
+        &self.send_rate
+    }
+
+    /// Closes the session sending a CLOSE_WEBTRANSPORT_SESSION capsule.
+    ///
+    /// `reason` is truncated to the maximum allowed length.
+    /// If `trailers` are provided, they are sent after the capsule,
+    /// right before the end of the session stream.
+    pub async fn close(&self, error_code: u32, reason: &str, trailers: Option<&Headers>) {
+        if !Self::terminate(&self.termination, SessionTermination::LocallyClosed) {
+            return;
+        }
+
+        let session_id = self.id;
+        self.events.emit(|| ConnectionEvent::CloseInitiated {
+            session_id,
+            locally: true,
+        });
+
+        let mut reason_len = reason.len().min(CloseWebTransportSession::MAX_REASON_SIZE);
+        while !reason.is_char_boundary(reason_len) {
+            reason_len -= 1;
+        }
+
+        let frame = CloseWebTransportSession::new(error_code, &reason[..reason_len])
+            .generate_capsule()
+            .generate_frame();
+
+        let mut send_stream = self.send_stream.lock().await;
+        if frame.write_async(&mut *send_stream).await.is_err() {
+            return;
+        }
+
+        if let Some(trailers) = trailers {
+            let frame = trailers.generate_frame(send_stream.id());
+            if frame.write_async(&mut *send_stream).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = send_stream.finish().await;
+    }
+
+    /// Asks the peer to gracefully drain the session sending
+    /// a DRAIN_WEBTRANSPORT_SESSION capsule.
+    ///
+    /// The session stays open: this is a no-op if the session is already terminated.
+    pub async fn drain(&self) {
+        if self.termination.borrow().is_some() {
+            return;
+        }
+
+        let frame = DrainWebTransportSession.generate_capsule().generate_frame();
+
+        let mut send_stream = self.send_stream.lock().await;
+        let _ = frame.write_async(&mut *send_stream).await;
+    }
+
+    /// Waits for the peer to request draining of the session.
+    ///
+    /// If the whole connection is closed before the request,
+    /// this never completes.
+    pub async fn draining(&self) {
+        let mut receiver = self.draining.subscribe();
+
+        loop {
+            if *receiver.borrow() {
+                return;
+            }
+
+            if receiver.changed().await.is_err() {
+                return pending().await;
+            }
+        }
+    }
+
+    /// Waits for the session to be terminated.
+    ///
+    /// If the whole connection is closed before the session termination,
+    /// this never completes.
+    pub async fn terminated(&self) -> SessionTermination {
+        let mut receiver = self.termination.subscribe();
+
+        loop {
+            if let Some(termination) = receiver.borrow().as_ref() {
+                return termination.clone();
+            }
+
+            if receiver.changed().await.is_err() {
+                return pending().await;
+            }
+        }
+    }
+
+    /// Returns the termination of the session, if it is terminated.
+    pub fn termination(&self) -> Option<SessionTermination> {
+        self.termination.borrow().clone()
+    }
+
+    /// Registers a capsule type, so that received capsules of that type
+    /// are delivered on the returned queue (instead of being skipped).
+    ///
+    /// Returns [`None`] if the type is already registered.
+    pub fn register_capsule(&self, id: VarInt) -> Option<mpsc::UnboundedReceiver<Vec<u8>>> {
+        let mut capsule_queues = self.capsule_queues.lock().unwrap();
+
+        if capsule_queues.contains_key(&id) {
+            return None;
+        }
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        capsule_queues.insert(id, sender);
+
+        Some(receiver)
+    }
+
+    /// Sends a capsule on the session stream.
+    ///
+    /// Returns `false` if the session is terminated or the capsule cannot be sent.
+    pub async fn send_capsule(&self, capsule: &Capsule<'_>) -> bool {
+        if self.termination.borrow().is_some() {
+            return false;
+        }
+
+        let frame = capsule.generate_frame();
+
+        let mut send_stream = self.send_stream.lock().await;
+        frame.write_async(&mut *send_stream).await.is_ok()
+    }
+
+    /// Returns the trailing headers received on the session stream, if any.
+    pub fn trailers(&self) -> Option<Headers> {
+        self.trailers.borrow().clone()
+    }
+
+    /// Sets the termination reason, unless the session is already terminated.
+    ///
+    /// Returns `true` if the termination has been set.
+    fn terminate(
+        termination: &watch::Sender<Option<SessionTermination>>,
+        reason: SessionTermination,
+    ) -> bool {
+        termination.send_if_modified(|termination| {
+            if termination.is_none() {
+                *termination = Some(reason);
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    async fn run_reader(
+        mut recv_stream: QuicRecvStream,
+        termination: Arc<watch::Sender<Option<SessionTermination>>>,
+        signals: SessionSignals,
+    ) {
+        if let Some(reason) = Self::read_termination(&mut recv_stream, &signals).await {
+            if let SessionTermination::Error(h3error) = &reason {
+                recv_stream.stop(h3error.code().to_code());
+            }
+
+            let locally = matches!(reason, SessionTermination::Error(_));
+
+            if Self::terminate(&termination, reason) {
+                signals.events.emit(|| ConnectionEvent::CloseInitiated {
+                    session_id: signals.session_id,
+                    locally,
+                });
+            }
+        }
+    }
+
+    /// Writes the flow control capsules on the session stream.
+    ///
+    /// If the peer exceeds the granted credit, the session stream is reset
+    /// with `WEBTRANSPORT_FLOW_CONTROL_ERROR`.
+    async fn run_credit_writer(
+        send_stream: Arc<Mutex<QuicSendStream>>,
+        termination: Arc<watch::Sender<Option<SessionTermination>>>,
+        credit: Arc<SessionCredit>,
+    ) {
+        let mut terminated = termination.subscribe();
+
+        loop {
+            tokio::select! {
+                () = credit.updated() => {}
+                result = terminated.changed() => {
+                    if result.is_err() || terminated.borrow().is_some() {
+                        credit.close();
+                        return;
+                    }
+                    continue;
+                }
+            }
+
+            let (capsules, violated) = credit.take_updates();
+
+            if violated {
+                Self::terminate(
+                    &termination,
+                    SessionTermination::Error(H3Error::new(
+                        ErrorCode::FlowControl,
+                        "Session flow control limit exceeded",
+                    )),
+                );
+
+                send_stream
+                    .lock()
+                    .await
+                    .reset(ErrorCode::FlowControl.to_code());
+                credit.close();
+                return;
+            }
+
+            let mut send_stream = send_stream.lock().await;
+            for capsule in capsules {
+                let frame = capsule.generate_capsule().generate_frame();
+                if frame.write_async(&mut *send_stream).await.is_err() {
+                    credit.close();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Reads capsules from the session stream until the session is terminated.
+    ///
+    /// A HEADERS frame carries trailers: only the end of the stream can follow it.
+    ///
+    /// Capsules are buffered up to [`ParseLimits::max_capsule_size`]; the
+    /// payload of capsules that are not delivered (of unknown and unregistered
+    /// type) is skipped without buffering.
+    ///
+    /// Returns [`None`] if the connection is closed.
+    async fn read_termination(
+        recv_stream: &mut QuicRecvStream,
+        signals: &SessionSignals,
+    ) -> Option<SessionTermination> {
+        let trailers = &signals.trailers;
+        let mut capsules_buffer = Vec::new();
+        let mut skip = 0;
+
+        loop {
+            let frame = match Frame::read_async_with_limits(
+                recv_stream,
+                FRAME_PARSE_MODE,
+                &signals.parse_limits,
+            )
+            .await
+            {
+                Ok(frame) => frame,
+                Err(frame_read_error) => match FrameReadError::from(frame_read_error) {
+                    FrameReadError::EndOfStream => {
+                        return Some(SessionTermination::RemotelyClosed {
+                            error_code: 0,
+                            reason: String::new(),
+                        });
+                    }
+                    FrameReadError::ConnectionClosed => return None,
+                    FrameReadError::UnknownFrame | FrameReadError::InvalidSessionId => {
+                        return Some(SessionTermination::Error(H3Error::new(
+                            ErrorCode::FrameUnexpected,
+                            "Invalid frame on session stream",
+                        )));
+                    }
+                    FrameReadError::ExcessiveLoad => {
+                        return Some(SessionTermination::Error(H3Error::new(
+                            ErrorCode::ExcessiveLoad,
+                            "Trailers exceed limits on session stream",
+                        )));
+                    }
+                },
+            };
+
+            if trailers.borrow().is_some() && !matches!(frame.kind(), FrameKind::Exercise(_)) {
+                return Some(SessionTermination::Error(H3Error::new(
+                    ErrorCode::FrameUnexpected,
+                    "Frame after trailers on session stream",
+                )));
+            }
+
+            match frame.kind() {
+                FrameKind::Data => {
+                    let payload = frame.payload();
+                    let skipped = payload.len().min(skip);
+                    skip -= skipped;
+
+                    capsules_buffer.extend_from_slice(&payload[skipped..]);
+
+                    let mut buffer_reader = BufferReader::new(&capsules_buffer);
+
+                    while let Some(capsule) = Capsule::read_from_buffer(&mut buffer_reader) {
+                        if let Some(termination) = Self::process_capsule(&capsule, signals) {
+                            return Some(termination);
+                        }
+                    }
+
+                    let consumed = buffer_reader.offset();
+                    capsules_buffer.drain(..consumed);
+
+                    // The buffer now holds (at most) the beginning of a capsule
+                    let Some((kind, header_len, payload_len)) =
+                        Self::capsule_header(&capsules_buffer)
+                    else {
+                        continue;
+                    };
+
+                    if !Self::is_capsule_delivered(kind, signals) {
+                        signals.events.emit(|| ConnectionEvent::CapsuleReceived {
+                            session_id: signals.session_id,
+                            capsule_type: kind.id(),
+                        });
+
+                        skip = payload_len - (capsules_buffer.len() - header_len);
+                        capsules_buffer.clear();
+                    } else if payload_len > signals.parse_limits.max_capsule_size() {
+                        return Some(SessionTermination::Error(H3Error::new(
+                            ErrorCode::ExcessiveLoad,
+                            "Capsule exceeds limits on session stream",
+                        )));
+                    }
+                }
+                FrameKind::Headers => match Headers::with_frame_limits(
+                    &frame,
+                    recv_stream.id(),
+                    ParseMode::Strict,
+                    &signals.parse_limits,
+                ) {
+                    Ok(headers) if headers.iter().all(|(name, _)| !name.starts_with(':')) => {
+                        trailers.send_replace(Some(headers));
+                    }
+                    Ok(_) => {
+                        return Some(SessionTermination::Error(H3Error::new(
+                            ErrorCode::Message,
+                            "Pseudo-header in trailers on session stream",
+                        )));
+                    }
+                    Err(h3code) => {
+                        return Some(SessionTermination::Error(H3Error::new(
+                            h3code,
+                            "Malformed trailers on session stream",
+                        )));
+                    }
+                },
+                FrameKind::Exercise(_) => signals.reserved_counts.frame_discarded(),
+                FrameKind::Unknown(_) => {}
+                FrameKind::Settings | FrameKind::GoAway | FrameKind::WebTransport => {
+                    return Some(SessionTermination::Error(H3Error::new(
+                        ErrorCode::FrameUnexpected,
+                        "Unexpected frame on session stream",
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Parses the type and payload length of a capsule from the beginning of
+    /// `buffer`, together with the header length.
+    ///
+    /// Returns [`None`] if `buffer` does not contain an entire header.
+    fn capsule_header(buffer: &[u8]) -> Option<(CapsuleKind, usize, usize)> {
+        let mut buffer_reader = BufferReader::new(buffer);
+
+        let kind = CapsuleKind::from_id(buffer_reader.get_varint()?);
+        let payload_len =
+            usize::try_from(buffer_reader.get_varint()?.into_inner()).unwrap_or(usize::MAX);
+
+        Some((kind, buffer_reader.offset(), payload_len))
+    }
+
+    /// Whether capsules of type `kind` are processed (rather than skipped).
+    fn is_capsule_delivered(kind: CapsuleKind, signals: &SessionSignals) -> bool {
+        match kind {
+            CapsuleKind::Unknown(id) => signals.capsule_queues.lock().unwrap().contains_key(&id),
+            _ => true,
+        }
+    }
+
+    fn process_capsule(capsule: &Capsule, signals: &SessionSignals) -> Option<SessionTermination> {
+        signals.events.emit(|| ConnectionEvent::CapsuleReceived {
+            session_id: signals.session_id,
+            capsule_type: capsule.kind().id(),
+        });
+
+        match capsule.kind() {
+            CapsuleKind::CloseWebTransportSession => {
+                match CloseWebTransportSession::with_capsule(capsule) {
+                    Ok(close) => Some(SessionTermination::RemotelyClosed {
+                        error_code: close.error_code(),
+                        reason: close.reason().to_string(),
+                    }),
+                    Err(h3code) => Some(SessionTermination::Error(H3Error::new(
+                        h3code,
+                        "Malformed CLOSE_WEBTRANSPORT_SESSION capsule",
+                    ))),
+                }
+            }
+            CapsuleKind::DrainWebTransportSession => {
+                match DrainWebTransportSession::with_capsule(capsule) {
+                    Ok(DrainWebTransportSession) => {
+                        signals.draining.send_replace(true);
+                        None
+                    }
+                    Err(h3code) => Some(SessionTermination::Error(H3Error::new(
+                        h3code,
+                        "Malformed DRAIN_WEBTRANSPORT_SESSION capsule",
+                    ))),
+                }
+            }
+            CapsuleKind::WtMaxData
+            | CapsuleKind::WtMaxStreamsBidi
+            | CapsuleKind::WtMaxStreamsUni
+            | CapsuleKind::WtDataBlocked
+            | CapsuleKind::WtStreamsBlockedBidi
+            | CapsuleKind::WtStreamsBlockedUni => match FlowControlCapsule::with_capsule(capsule) {
+                Ok(flow_control) => {
+                    signals.credit.on_capsule(flow_control);
+                    None
+                }
+                Err(h3code) => Some(SessionTermination::Error(H3Error::new(
+                    h3code,
+                    "Malformed flow control capsule",
+                ))),
+            },
+            CapsuleKind::Unknown(id) => {
+                // Types registered by the application are delivered, others are skipped
+                if let Some(queue) = signals.capsule_queues.lock().unwrap().get(&id) {
+                    let _ = queue.send(capsule.payload().to_vec());
+                }
+                None
+            }
         }
     }
 }
 
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.reader.abort();
+        self.credit_writer.abort();
+    }
+}
+
+/// State the session stream reader updates on received capsules and frames.
+struct SessionSignals {
+    draining: Arc<watch::Sender<bool>>,
+    trailers: Arc<watch::Sender<Option<Headers>>>,
+    capsule_queues: CapsuleQueues,
+    credit: Arc<SessionCredit>,
+    session_id: SessionId,
+    reserved_counts: Arc<ReservedCounts>,
+    parse_limits: ParseLimits,
+    events: EventSender,
+}
+
 impl SessionError {
     fn with_frame_write_err<S>(frame_write_error: FrameWriteError, reason: S) -> Self
     where
@@ -194,6 +978,9 @@ impl SessionError {
             FrameReadError::InvalidSessionId => {
                 SessionError::LocalClosed(H3Error::new(ErrorCode::FrameUnexpected, reason))
             }
+            FrameReadError::ExcessiveLoad => {
+                SessionError::LocalClosed(H3Error::new(ErrorCode::ExcessiveLoad, reason))
+            }
             FrameReadError::EndOfStream => {
                 SessionError::LocalClosed(H3Error::new(ErrorCode::ClosedCriticalStream, reason))
             }
@@ -1,12 +1,18 @@
 use crate::error::StreamError;
+use bytes::Bytes;
+use std::future::Future;
 use std::pin::Pin;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::task::ready;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
 use tokio::io::ReadBuf;
 use wtransport_proto::bytes::AsyncRead;
 use wtransport_proto::bytes::AsyncWrite;
 use wtransport_proto::bytes::IoError;
+use wtransport_proto::error::h3_error_codes;
 use wtransport_proto::frame::Frame;
 use wtransport_proto::ids::SessionId;
 use wtransport_proto::ids::StreamId;
@@ -14,6 +20,12 @@ use wtransport_proto::stream::StreamHeader;
 use wtransport_proto::stream::StreamHeaderReadAsyncError;
 use wtransport_proto::stream::StreamHeaderReadError;
 use wtransport_proto::varint::VarInt;
+use wtransport_proto::ParseLimits;
+use wtransport_proto::ParseMode;
+
+/// How HTTP3 frames are read: frames of unknown type (e.g., *grease* or
+/// extensions) are skipped, as required by RFC 9114 (section 9).
+pub(crate) const FRAME_PARSE_MODE: ParseMode = ParseMode::Lenient;
 
 pub(crate) struct Raw;
 pub(crate) struct H3(Option<StreamHeader>);
@@ -50,6 +62,10 @@ impl Stream<BiRemote, Raw> {
         self.kind.1.stop(code)
     }
 
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
     fn bi_remote_raw(stream: (QuicSendStream, QuicRecvStream)) -> Self {
         Self {
             kind: BiRemote(stream.0, stream.1),
@@ -118,7 +134,10 @@ impl Stream<UniLocal, Raw> {
         mut self,
         header: StreamHeader,
     ) -> Result<Stream<UniLocal, H3>, UpgradeError> {
-        header.write_async(&mut self.kind.0).await?;
+        let mut guard = HeaderGuard::new(&mut self.kind.0);
+        header.write_async(guard.stream()).await?;
+        guard.disarm();
+
         Ok(Stream {
             kind: self.kind,
             stage: H3(Some(header)),
@@ -134,8 +153,12 @@ impl Stream<UniLocal, Raw> {
 }
 
 impl Stream<BiRemote, H3> {
-    pub(crate) async fn read_frame<'a>(&mut self) -> Result<Frame<'a>, FrameReadError> {
-        let frame = Frame::read_async(&mut self.kind.1).await?;
+    pub(crate) async fn read_frame<'a>(
+        &mut self,
+        limits: &ParseLimits,
+    ) -> Result<Frame<'a>, FrameReadError> {
+        let frame =
+            Frame::read_async_with_limits(&mut self.kind.1, FRAME_PARSE_MODE, limits).await?;
         Ok(frame)
     }
 
@@ -159,17 +182,60 @@ impl Stream<BiRemote, H3> {
         self.kind.1.stop(code)
     }
 
-    pub(crate) fn normalize(self) -> Stream<Bi, Raw> {
+    pub(crate) async fn finish(&mut self) -> Result<(), StreamError> {
+        self.kind.0.finish().await
+    }
+
+    /// Resets the sending side and stops the receiving side.
+    pub(crate) fn abort(mut self, code: VarInt) {
+        self.kind.0.reset(code);
+        self.kind.1.stop(code);
+    }
+
+    pub(crate) fn normalize(self) -> Stream<Bi, H3> {
         Stream {
             kind: Bi(self.kind.0, self.kind.1),
-            stage: Raw,
+            stage: H3(None),
         }
     }
+
+    /// Reads the type and the payload length of the next frame, leaving its
+    /// payload on the stream (see [`Self::read_payload`]).
+    ///
+    /// It returns [`None`] if the stream is finished.
+    pub(crate) async fn read_frame_header(
+        &mut self,
+    ) -> Result<Option<(VarInt, u64)>, FrameReadError> {
+        use wtransport_proto::bytes::BytesReaderAsync;
+
+        let frame_type = match self.kind.1.get_varint().await {
+            Ok(frame_type) => frame_type,
+            Err(IoError::Closed) => return Ok(None),
+            Err(IoError::NotConnected) => return Err(FrameReadError::ConnectionClosed),
+        };
+
+        let payload_len = self.kind.1.get_varint().await?;
+
+        Ok(Some((frame_type, payload_len.into_inner())))
+    }
+
+    /// Reads (part of) the payload of the frame whose header has been read
+    /// with [`Self::read_frame_header`].
+    pub(crate) async fn read_payload(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<Option<usize>, StreamError> {
+        self.kind.1.read(buf).await
+    }
 }
 
 impl Stream<BiLocal, H3> {
-    pub(crate) async fn read_frame<'a>(&mut self) -> Result<Frame<'a>, FrameReadError> {
-        let frame = Frame::read_async(&mut self.kind.1).await?;
+    pub(crate) async fn read_frame<'a>(
+        &mut self,
+        limits: &ParseLimits,
+    ) -> Result<Frame<'a>, FrameReadError> {
+        let frame =
+            Frame::read_async_with_limits(&mut self.kind.1, FRAME_PARSE_MODE, limits).await?;
         Ok(frame)
     }
 
@@ -182,9 +248,12 @@ impl Stream<BiLocal, H3> {
         mut self,
         session_id: SessionId,
     ) -> Result<Stream<BiLocal, Wt>, UpgradeError> {
+        let mut guard = HeaderGuard::new(&mut self.kind.0);
         Frame::new_webtransport(session_id)
-            .write_async(&mut self.kind.0)
+            .write_async(guard.stream())
             .await?;
+        guard.disarm();
+
         Ok(Stream {
             kind: self.kind,
             stage: Wt(session_id),
@@ -195,10 +264,10 @@ impl Stream<BiLocal, H3> {
         self.kind.0.id()
     }
 
-    pub(crate) fn normalize(self) -> Stream<Bi, Raw> {
+    pub(crate) fn normalize(self) -> Stream<Bi, H3> {
         Stream {
             kind: Bi(self.kind.0, self.kind.1),
-            stage: Raw,
+            stage: H3(None),
         }
     }
 }
@@ -211,8 +280,21 @@ impl Stream<UniRemote, H3> {
             .expect("Uni H3 stream must have header")
     }
 
-    pub(crate) async fn read_frame<'a>(&mut self) -> Result<Frame<'a>, FrameReadError> {
-        let frame = Frame::read_async(&mut self.kind.0).await?;
+    pub(crate) async fn read_frame<'a>(
+        &mut self,
+        limits: &ParseLimits,
+    ) -> Result<Frame<'a>, FrameReadError> {
+        let frame =
+            Frame::read_async_with_limits(&mut self.kind.0, FRAME_PARSE_MODE, limits).await?;
+        Ok(frame)
+    }
+
+    /// Reads a frame, returning frames of unknown type (e.g., extension frames)
+    /// instead of skipping them.
+    pub(crate) async fn read_frame_with_unknown<'a>(
+        &mut self,
+    ) -> Result<Frame<'a>, FrameReadError> {
+        let frame = Frame::read_async_with_unknown(&mut self.kind.0).await?;
         Ok(frame)
     }
 
@@ -228,9 +310,17 @@ impl Stream<UniRemote, H3> {
         }
     }
 
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
     pub(crate) fn raw(self) -> QuicRecvStream {
         self.kind.0
     }
+
+    pub(crate) fn stop(mut self, code: VarInt) {
+        self.kind.0.stop(code)
+    }
 }
 
 impl Stream<UniLocal, H3> {
@@ -258,47 +348,141 @@ impl Stream<UniLocal, H3> {
         }
     }
 
-    pub(crate) async fn stopped(&mut self) -> Result<(), StreamError> {
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
+    pub(crate) async fn stopped(&mut self) -> Result<VarInt, StreamError> {
         self.kind.0.stopped().await
     }
+
+    pub(crate) async fn finish(&mut self) -> Result<(), StreamError> {
+        self.kind.0.finish().await
+    }
 }
 
 impl Stream<BiRemote, Wt> {
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
     pub(crate) fn raw(self) -> (QuicSendStream, QuicRecvStream) {
         (self.kind.0, self.kind.1)
     }
+
+    pub(crate) fn session_id(&self) -> SessionId {
+        self.stage.0
+    }
+
+    pub(crate) fn stop(mut self, code: VarInt) {
+        self.kind.1.stop(code)
+    }
 }
 
 impl Stream<BiLocal, Wt> {
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
     pub(crate) fn raw(self) -> (QuicSendStream, QuicRecvStream) {
         (self.kind.0, self.kind.1)
     }
 }
 
 impl Stream<UniRemote, Wt> {
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
     pub(crate) fn raw(self) -> QuicRecvStream {
         self.kind.0
     }
+
+    pub(crate) fn session_id(&self) -> SessionId {
+        self.stage.0
+    }
+
+    pub(crate) fn stop(mut self, code: VarInt) {
+        self.kind.0.stop(code)
+    }
 }
 
 impl Stream<UniLocal, Wt> {
+    pub(crate) fn id(&self) -> StreamId {
+        self.kind.0.id()
+    }
+
     pub(crate) fn raw(self) -> QuicSendStream {
         self.kind.0
     }
 }
 
-impl Stream<Bi, Raw> {
+impl Stream<Bi, H3> {
     pub(crate) fn id(&self) -> StreamId {
         self.kind.0.id()
     }
+
+    pub(crate) fn split(self) -> (QuicSendStream, QuicRecvStream) {
+        (self.kind.0, self.kind.1)
+    }
+}
+
+/// Write progress of all the streams of a connection.
+///
+/// Each completed write advances the *generation*. It allows understanding
+/// whether a stalled stream is the only one not progressing.
+pub(crate) struct WriteProgress {
+    quic_connection: quinn::Connection,
+    generation: AtomicU64,
+}
+
+impl WriteProgress {
+    /// Lower bound of the observation period, so that very small RTTs
+    /// (e.g., loopback) leave other streams the chance to progress.
+    const MIN_OBSERVATION_PERIOD: Duration = Duration::from_millis(10);
+
+    pub(crate) fn new(quic_connection: quinn::Connection) -> Self {
+        Self {
+            quic_connection,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn advance(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn observation_period(&self) -> Duration {
+        self.quic_connection.rtt().max(Self::MIN_OBSERVATION_PERIOD)
+    }
 }
 
 pub(crate) struct QuicSendStream(quinn::SendStream);
 
 impl QuicSendStream {
-    pub(crate) async fn write(&mut self, buf: &[u8]) -> Result<usize, StreamError> {
-        let written = self.0.write(buf).await?;
-        Ok(written)
+    pub(crate) fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, StreamError>> {
+        // Writes are attempted at every poll: a new future does not lose progress
+        let write = self.0.write(buf);
+        tokio::pin!(write);
+
+        let written = ready!(write.poll(cx))?;
+        Poll::Ready(Ok(written))
+    }
+
+    /// Writes chunks without copying them, returning the number of bytes written.
+    ///
+    /// Fully written chunks are left empty, a partially written one is advanced.
+    pub(crate) async fn write_chunks(&mut self, bufs: &mut [Bytes]) -> Result<usize, StreamError> {
+        let written = self.0.write_chunks(bufs).await?;
+        Ok(written.bytes)
     }
 
     pub(crate) async fn write_all(&mut self, buf: &[u8]) -> Result<(), StreamError> {
@@ -311,9 +495,38 @@ impl QuicSendStream {
         Ok(())
     }
 
-    pub(crate) async fn stopped(&mut self) -> Result<(), StreamError> {
-        self.0.stopped().await?;
-        Ok(())
+    pub(crate) fn poll_finish(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), StreamError>> {
+        // The finishing state is kept by the stream: a new future does not lose progress
+        let finish = self.0.finish();
+        tokio::pin!(finish);
+
+        ready!(finish.poll(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    pub(crate) async fn stopped(&mut self) -> Result<VarInt, StreamError> {
+        let code = self.0.stopped().await?;
+
+        // SAFETY: varint conversion
+        Ok(unsafe {
+            debug_assert!(code.into_inner() <= VarInt::MAX.into_inner());
+            VarInt::from_u64_unchecked(code.into_inner())
+        })
+    }
+
+    pub(crate) fn reset(&mut self, error_code: VarInt) {
+        // SAFETY: varint conversion
+        let quic_varint = unsafe {
+            debug_assert!(error_code.into_inner() <= quinn::VarInt::MAX.into_inner());
+            quinn::VarInt::from_u64_unchecked(error_code.into_inner())
+        };
+
+        let _ = self.0.reset(quic_varint);
+    }
+
+    pub(crate) fn set_priority(&self, priority: i32) {
+        // The stream might be already finished or reset: nothing to schedule anymore
+        let _ = self.0.set_priority(priority);
     }
 
     #[inline(always)]
@@ -384,6 +597,19 @@ impl QuicRecvStream {
         }
     }
 
+    pub(crate) fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<Option<usize>, StreamError>> {
+        // Reads are attempted at every poll: a new future does not lose data
+        let read = self.0.read(buf);
+        tokio::pin!(read);
+
+        let read = ready!(read.poll(cx))?;
+        Poll::Ready(Ok(read))
+    }
+
     pub(crate) fn stop(&mut self, error_code: VarInt) {
         // SAFETY: varint conversion
         let quic_varint = unsafe {
@@ -435,6 +661,35 @@ impl tokio::io::AsyncRead for QuicRecvStream {
     }
 }
 
+/// Resets the stream if the header is not written completely.
+///
+/// When the future writing the header is dropped (or fails) midway, the
+/// stream would be implicitly finished, delivering a truncated header
+/// to the peer: it is reset (`H3_REQUEST_CANCELLED`) instead.
+struct HeaderGuard<'a>(Option<&'a mut QuicSendStream>);
+
+impl<'a> HeaderGuard<'a> {
+    fn new(stream: &'a mut QuicSendStream) -> Self {
+        Self(Some(stream))
+    }
+
+    fn stream(&mut self) -> &mut QuicSendStream {
+        self.0.as_mut().expect("Guard already disarmed")
+    }
+
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for HeaderGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(stream) = self.0.take() {
+            stream.reset(h3_error_codes::H3_REQUEST_CANCELLED);
+        }
+    }
+}
+
 pub(crate) enum UpgradeError {
     UnknownStream,
     InvalidSessionId,
@@ -468,6 +723,7 @@ impl From<StreamHeaderReadAsyncError> for UpgradeError {
 pub(crate) enum FrameReadError {
     UnknownFrame,
     InvalidSessionId,
+    ExcessiveLoad,
     EndOfStream,
     ConnectionClosed,
 }
@@ -492,6 +748,9 @@ impl From<wtransport_proto::frame::FrameReadAsyncError> for FrameReadError {
             frame::FrameReadAsyncError::Frame(frame::FrameReadError::InvalidSessionId) => {
                 FrameReadError::InvalidSessionId
             }
+            frame::FrameReadAsyncError::Frame(frame::FrameReadError::ExcessiveLoad) => {
+                FrameReadError::ExcessiveLoad
+            }
             frame::FrameReadAsyncError::IO(io_error) => io_error.into(),
         }
     }
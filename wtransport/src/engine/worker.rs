@@ -1,3 +1,10 @@
+use crate::config::AdaptiveKeepAlive;
+use crate::config::Grease;
+use crate::datagram::Datagram;
+use crate::engine::demux::Demux;
+use crate::engine::grease;
+use crate::engine::grease::ReservedCounts;
+use crate::engine::keep_alive::KeepAliveScheduler;
 use crate::engine::session::SessionRemoteRequest;
 use crate::engine::stream::BiRemote;
 use crate::engine::stream::FrameReadError;
@@ -8,22 +15,32 @@ use crate::engine::stream::Stream;
 use crate::engine::stream::UniLocal;
 use crate::engine::stream::UniRemote;
 use crate::engine::stream::UpgradeError;
-use crate::engine::stream::Wt;
 use crate::engine::stream::H3;
 use crate::error::H3Error;
 use crate::error::StreamError;
+use crate::events::EventSender;
+use crate::qlog::QlogTrace;
+use crate::runtime;
+use crate::runtime::Task;
+use crate::trace;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::future::pending;
+use std::sync::Arc;
+use std::sync::Mutex;
 use tokio::sync::mpsc;
 use tokio::sync::watch;
-use tokio::task::JoinHandle;
 use wtransport_proto::error::ErrorCode;
 use wtransport_proto::frame::Frame;
 use wtransport_proto::frame::FrameKind;
 use wtransport_proto::headers::Headers;
+use wtransport_proto::ids::StreamId;
 use wtransport_proto::settings::Settings;
 use wtransport_proto::stream::StreamHeader;
 use wtransport_proto::stream::StreamKind;
 use wtransport_proto::varint::VarInt;
+use wtransport_proto::ParseLimits;
+use wtransport_proto::ParseMode;
 
 type WorkerResult<T> = Result<T, WorkerError>;
 
@@ -34,13 +51,13 @@ pub(crate) enum WorkerError {
 }
 
 pub(super) struct WorkerHandler {
-    join_handle: Option<JoinHandle<WorkerResult<()>>>,
+    join_handle: Option<Task<WorkerResult<()>>>,
     result: Option<WorkerError>,
 }
 
 impl WorkerHandler {
     pub(super) fn run_worker(worker: Worker) -> Self {
-        let join_handle = tokio::spawn(worker.run());
+        let join_handle = runtime::spawn(trace::in_current_span(worker.run()));
 
         Self {
             join_handle: Some(join_handle),
@@ -60,8 +77,12 @@ impl WorkerHandler {
             .expect("Worker should be still executing")
             .await
         {
-            Ok(result) => result.expect_err("Worker cannot return OK"),
-            Err(_) => todo!(),
+            Some(result) => result.expect_err("Worker cannot return OK"),
+            // The worker task was cancelled (e.g. runtime shutting down)
+            None => WorkerError::LocalClosed(H3Error::new(
+                ErrorCode::InternalError,
+                "Connection worker terminated unexpectedly",
+            )),
         };
 
         self.result = Some(result.clone());
@@ -69,44 +90,134 @@ impl WorkerHandler {
     }
 }
 
+/// Queues of the extension frames received on the control stream, by frame type.
+pub(super) type ExtensionFrameQueues = Arc<Mutex<HashMap<VarInt, mpsc::UnboundedSender<Vec<u8>>>>>;
+
+/// Per-connection options of the worker.
+pub(super) struct WorkerOptions {
+    pub(super) qlog: Option<Arc<QlogTrace>>,
+    pub(super) adaptive_keep_alive: Option<AdaptiveKeepAlive>,
+    pub(super) grease: Grease,
+    pub(super) local_settings: Arc<Settings>,
+    /// Concurrent sessions advertised to the peer (for peers of draft-07).
+    pub(super) max_sessions: VarInt,
+    pub(super) extension_frames: ExtensionFrames,
+    pub(super) reserved_counts: Arc<ReservedCounts>,
+    pub(super) parse_limits: ParseLimits,
+    pub(super) events: EventSender,
+}
+
+/// Extension frames received and sent on the control streams.
+pub(super) struct ExtensionFrames {
+    pub(super) incoming: ExtensionFrameQueues,
+    pub(super) outgoing: mpsc::UnboundedReceiver<(VarInt, Vec<u8>)>,
+}
+
 pub(super) struct Worker {
     quic_connection: quinn::Connection,
     inc_settings_channel: watch::Sender<Option<Settings>>,
-    inc_bi_wt_channel: mpsc::Sender<Stream<BiRemote, Wt>>,
-    inc_uni_wt_channel: mpsc::Sender<Stream<UniRemote, Wt>>,
     inc_sessions_channel: mpsc::Sender<SessionRemoteRequest>,
+    demux: Arc<Demux>,
+    qlog: Option<Arc<QlogTrace>>,
+    adaptive_keep_alive: Option<AdaptiveKeepAlive>,
+    grease: Grease,
+    local_settings: Arc<Settings>,
+    max_sessions: VarInt,
+    incoming_extension_frames: ExtensionFrameQueues,
+    outgoing_extension_frames: Option<mpsc::UnboundedReceiver<(VarInt, Vec<u8>)>>,
+    goaway_request: watch::Receiver<bool>,
+    inc_goaway_channel: watch::Sender<Option<VarInt>>,
+    goaway_sent: Option<VarInt>,
+    next_request_id: u64,
+    reserved_counts: Arc<ReservedCounts>,
+    parse_limits: ParseLimits,
+    events: EventSender,
 }
 
 impl Worker {
     pub(super) fn new(
         quic_connection: quinn::Connection,
         inc_settings_channel: watch::Sender<Option<Settings>>,
-        inc_bi_wt_channel: mpsc::Sender<Stream<BiRemote, Wt>>,
-        inc_uni_wt_channel: mpsc::Sender<Stream<UniRemote, Wt>>,
         inc_sessions_channel: mpsc::Sender<SessionRemoteRequest>,
+        demux: Arc<Demux>,
+        goaway_request: watch::Receiver<bool>,
+        inc_goaway_channel: watch::Sender<Option<VarInt>>,
+        options: WorkerOptions,
     ) -> Self {
         Self {
             quic_connection,
             inc_settings_channel,
-            inc_bi_wt_channel,
-            inc_uni_wt_channel,
             inc_sessions_channel,
+            demux,
+            qlog: options.qlog,
+            adaptive_keep_alive: options.adaptive_keep_alive,
+            grease: options.grease,
+            local_settings: options.local_settings,
+            max_sessions: options.max_sessions,
+            incoming_extension_frames: options.extension_frames.incoming,
+            outgoing_extension_frames: Some(options.extension_frames.outgoing),
+            goaway_request,
+            inc_goaway_channel,
+            goaway_sent: None,
+            next_request_id: 0,
+            reserved_counts: options.reserved_counts,
+            parse_limits: options.parse_limits,
+            events: options.events,
         }
     }
 
-    async fn run(mut self) -> WorkerResult<()> {
+    async fn run(self) -> WorkerResult<()> {
+        let demux = self.demux.clone();
+        let result = self.run_impl().await;
+        demux.terminate();
+        result
+    }
+
+    async fn run_impl(mut self) -> WorkerResult<()> {
         let mut inc_uni_h3_channel = mpsc::channel(1024);
         let mut inc_bi_h3_channel = mpsc::channel(1024);
+        let mut violation_channel = mpsc::unbounded_channel();
 
         let mut local_settings_stream = LocalSettingsStream::new();
+        let mut local_qpack_enc_stream = LocalQPackEncStream::new();
+        let mut local_qpack_dec_stream = LocalQPackDecStream::new();
         let mut remote_settings_stream = RemoteSettingsStream::new();
         let mut remote_qpack_enc_stream = RemoteQPackEncStream::new();
         let mut remote_qpack_dec_stream = RemoteQPackDecStream::new();
 
         local_settings_stream
-            .on_ready_connection(&self.quic_connection)
+            .on_ready_connection(
+                &self.quic_connection,
+                &self.local_settings,
+                self.max_sessions,
+                self.grease,
+                self.qlog.as_deref(),
+            )
+            .await?;
+        trace::settings_sent();
+
+        local_qpack_enc_stream
+            .on_ready_connection(&self.quic_connection, self.qlog.as_deref())
+            .await?;
+
+        local_qpack_dec_stream
+            .on_ready_connection(&self.quic_connection, self.qlog.as_deref())
             .await?;
 
+        if self.grease.streams() {
+            self.open_grease_stream();
+        }
+
+        let mut keep_alive_scheduler = self
+            .adaptive_keep_alive
+            .map(|config| KeepAliveScheduler::new(config, &self.quic_connection));
+
+        let mut goaway_request = self.goaway_request.clone();
+        let mut outgoing_extension_frames = self
+            .outgoing_extension_frames
+            .take()
+            .expect("Worker runs once");
+
         loop {
             tokio::select! {  // TODO(bfesta): add bias to this select. maybe?
                 h3_uni = inc_uni_h3_channel.1.recv() => {
@@ -124,13 +235,60 @@ impl Worker {
                 }
 
                 accept_uni = self.accept_uni(&inc_uni_h3_channel.0) => {
-                    let (stream, h3slot, wtslot) = accept_uni?;
-                    Self::process_inc_uni(stream, h3slot, wtslot);
+                    let (stream, h3slot) = accept_uni?;
+                    Self::process_inc_uni(
+                        stream,
+                        h3slot,
+                        self.demux.clone(),
+                        self.qlog.clone(),
+                        violation_channel.0.clone(),
+                    );
                 }
 
                 accept_bi = self.accept_bi(&inc_bi_h3_channel.0) => {
-                    let (stream, h3slot, wtslot) = accept_bi?;
-                    Self::process_inc_bi(stream, h3slot, wtslot);
+                    let (stream, h3slot) = accept_bi?;
+                    self.next_request_id = self.next_request_id.max(stream.id().into_u64() + 4);
+                    Self::process_inc_bi(
+                        stream,
+                        h3slot,
+                        self.demux.clone(),
+                        self.qlog.clone(),
+                        self.reserved_counts.clone(),
+                        self.parse_limits,
+                        violation_channel.0.clone(),
+                    );
+                }
+
+                violation = violation_channel.1.recv() => {
+                    let violation = violation.expect("Sender cannot be dropped");
+                    return Err(WorkerError::LocalClosed(violation));
+                }
+
+                quic_dgram = self.quic_connection.read_datagram() => {
+                    let quic_dgram = quic_dgram.map_err(|_| WorkerError::RemoteClosed)?;
+                    let dgram = Datagram::read(quic_dgram).map_err(WorkerError::LocalClosed)?;
+                    self.demux.route_datagram(dgram).map_err(WorkerError::LocalClosed)?;
+                }
+
+                () = KeepAliveScheduler::tick(keep_alive_scheduler.as_mut(), &self.quic_connection) => {
+                    local_settings_stream.send_keep_alive().await?;
+                }
+
+                () = Self::goaway_requested(&mut goaway_request), if self.goaway_sent.is_none() => {
+                    // Requests on streams accepted so far are still processed
+                    let goaway_id = VarInt::try_from_u64(self.next_request_id)
+                        .expect("Stream IDs are valid varints");
+                    if self.grease.frames() {
+                        local_settings_stream.send_grease(self.qlog.as_deref()).await?;
+                    }
+                    local_settings_stream.send_goaway(goaway_id, self.qlog.as_deref()).await?;
+                    self.goaway_sent = Some(goaway_id);
+                }
+
+                Some((frame_type, payload)) = outgoing_extension_frames.recv() => {
+                    local_settings_stream
+                        .send_extension(frame_type, payload, self.qlog.as_deref())
+                        .await?;
                 }
 
                 error = local_settings_stream.done() => {
@@ -138,7 +296,22 @@ impl Worker {
                     return error;
                 }
 
-                error = remote_settings_stream.done() => {
+                error = local_qpack_enc_stream.done() => {
+                    debug_assert!(error.is_err());
+                    return error;
+                }
+
+                error = local_qpack_dec_stream.done() => {
+                    debug_assert!(error.is_err());
+                    return error;
+                }
+
+                error = remote_settings_stream.done(
+                    &self.inc_goaway_channel,
+                    &self.incoming_extension_frames,
+                    &self.reserved_counts,
+                    self.qlog.as_deref(),
+                ) => {
                     debug_assert!(error.is_err());
                     return error;
                 }
@@ -166,13 +339,23 @@ impl Worker {
         match stream.header().kind() {
             StreamKind::Control => {
                 remote_settings_stream
-                    .on_stream_recv(&self.inc_settings_channel, stream)
+                    .on_stream_recv(
+                        &self.inc_settings_channel,
+                        stream,
+                        &self.parse_limits,
+                        self.qlog.as_deref(),
+                    )
                     .await
             }
             StreamKind::QPackEncoder => remote_qpack_enc_stream.on_stream_recv(stream),
             StreamKind::QPackDecoder => remote_qpack_dec_stream.on_stream_recv(stream),
             StreamKind::WebTransport => unreachable!(),
-            StreamKind::Exercise(_) => Ok(()),
+            StreamKind::Exercise(_) => {
+                // Reserved stream types must be ignored by the receiver
+                self.reserved_counts.stream_discarded();
+                stream.stop(ErrorCode::StreamCreation.to_code());
+                Ok(())
+            }
         }
     }
 
@@ -184,7 +367,12 @@ impl Worker {
         match first_frame.kind() {
             FrameKind::Data => Ok(()),
             FrameKind::Headers => {
-                let headers = match Headers::with_frame(&first_frame, stream.id()) {
+                let headers = match Headers::with_frame_limits(
+                    &first_frame,
+                    stream.id(),
+                    ParseMode::Strict,
+                    &self.parse_limits,
+                ) {
                     Ok(headers) => headers,
                     Err(h3code) => {
                         return Err(WorkerError::LocalClosed(H3Error::new(
@@ -194,6 +382,12 @@ impl Worker {
                     }
                 };
 
+                if matches!(self.goaway_sent, Some(goaway_id) if stream.id().into_varint() >= goaway_id)
+                {
+                    stream.stop(ErrorCode::RequestRejected.to_code());
+                    return Ok(());
+                }
+
                 let slot = match self.inc_sessions_channel.try_reserve() {
                     Ok(slot) => slot,
                     Err(_) => {
@@ -202,15 +396,69 @@ impl Worker {
                     }
                 };
 
-                slot.send(SessionRemoteRequest::new(stream, headers));
+                slot.send(SessionRemoteRequest::new(
+                    stream,
+                    headers,
+                    self.reserved_counts.clone(),
+                    self.parse_limits,
+                    self.events.clone(),
+                ));
                 Ok(())
             }
             FrameKind::Settings => Err(WorkerError::LocalClosed(H3Error::new(
                 ErrorCode::FrameUnexpected,
                 "Unexpected SETTINGS frame",
             ))),
+            FrameKind::GoAway => Err(WorkerError::LocalClosed(H3Error::new(
+                ErrorCode::FrameUnexpected,
+                "Unexpected GOAWAY frame",
+            ))),
             FrameKind::WebTransport => unreachable!(),
-            FrameKind::Exercise(_) => Ok(()),
+            FrameKind::Exercise(_) | FrameKind::Unknown(_) => Ok(()),
+        }
+    }
+
+    /// Opens a uni-directional stream of reserved type, which the peer ignores.
+    ///
+    /// It is opened in background, as it might wait for the peer to grant more streams.
+    fn open_grease_stream(&self) {
+        let quic_connection = self.quic_connection.clone();
+        let qlog = self.qlog.clone();
+
+        runtime::spawn(async move {
+            let Some(stream) = Stream::open_uni(&quic_connection).await else {
+                return;
+            };
+
+            let header = StreamHeader::new_exercise(grease::reserved_id());
+            let Ok(mut stream) = stream.upgrade(header).await else {
+                return;
+            };
+
+            let frame =
+                Frame::new_exercise(grease::reserved_id(), Cow::Owned(grease::random_payload()));
+
+            if let Some(qlog) = qlog {
+                qlog.stream_opened(stream.id(), true);
+                qlog.stream_type_set(stream.id(), stream.header().kind(), true);
+                qlog.frame(stream.id(), &frame, true);
+            }
+
+            // The stream is finished when dropped
+            let _ = stream.write_frame(frame).await;
+        });
+    }
+
+    /// Waits for the engine to ask for a GOAWAY.
+    async fn goaway_requested(goaway_request: &mut watch::Receiver<bool>) {
+        loop {
+            if *goaway_request.borrow_and_update() {
+                return;
+            }
+
+            if goaway_request.changed().await.is_err() {
+                pending::<()>().await;
+            }
         }
     }
 
@@ -220,7 +468,6 @@ impl Worker {
     ) -> WorkerResult<(
         Stream<UniRemote, Raw>,
         mpsc::OwnedPermit<Stream<UniRemote, H3>>,
-        mpsc::OwnedPermit<Stream<UniRemote, Wt>>,
     )> {
         loop {
             let stream = Stream::accept_uni(&self.quic_connection)
@@ -236,18 +483,7 @@ impl Worker {
                 Err(mpsc::error::TrySendError::Closed(_)) => unreachable!(),
             };
 
-            let w3slot = match self.inc_uni_wt_channel.clone().try_reserve_owned() {
-                Ok(slot) => slot,
-                Err(mpsc::error::TrySendError::Full(_)) => {
-                    stream.stop(ErrorCode::BufferedStreamRejected.to_code());
-                    continue;
-                }
-                Err(mpsc::error::TrySendError::Closed(_)) => {
-                    return Err(WorkerError::RemoteClosed);
-                }
-            };
-
-            return Ok((stream, h3slot, w3slot));
+            return Ok((stream, h3slot));
         }
     }
 
@@ -257,7 +493,6 @@ impl Worker {
     ) -> WorkerResult<(
         Stream<BiRemote, Raw>,
         mpsc::OwnedPermit<(Stream<BiRemote, H3>, Frame<'a>)>,
-        mpsc::OwnedPermit<Stream<BiRemote, Wt>>,
     )> {
         loop {
             let stream = Stream::accept_bi(&self.quic_connection)
@@ -273,38 +508,39 @@ impl Worker {
                 Err(mpsc::error::TrySendError::Closed(_)) => unreachable!(),
             };
 
-            let w3slot = match self.inc_bi_wt_channel.clone().try_reserve_owned() {
-                Ok(slot) => slot,
-                Err(mpsc::error::TrySendError::Full(_)) => {
-                    stream.stop(ErrorCode::BufferedStreamRejected.to_code());
-                    continue;
-                }
-                Err(mpsc::error::TrySendError::Closed(_)) => {
-                    return Err(WorkerError::RemoteClosed);
-                }
-            };
-
-            return Ok((stream, h3slot, w3slot));
+            return Ok((stream, h3slot));
         }
     }
 
     fn process_inc_uni(
         stream: Stream<UniRemote, Raw>,
         h3slot: mpsc::OwnedPermit<Stream<UniRemote, H3>>,
-        wtslot: mpsc::OwnedPermit<Stream<UniRemote, Wt>>,
+        demux: Arc<Demux>,
+        qlog: Option<Arc<QlogTrace>>,
+        violations: mpsc::UnboundedSender<H3Error>,
     ) {
-        tokio::spawn(async move {
+        runtime::spawn(async move {
             let stream = match stream.upgrade().await {
                 Ok(stream) => stream,
                 Err(UpgradeError::UnknownStream) => return,
-                Err(UpgradeError::InvalidSessionId) => return,
+                Err(UpgradeError::InvalidSessionId) => {
+                    let _ = violations.send(Self::invalid_session_id());
+                    return;
+                }
                 Err(UpgradeError::ConnectionClosed) => return,
                 Err(UpgradeError::EndOfStream) => return,
             };
 
+            if let Some(qlog) = qlog {
+                qlog.stream_opened(stream.id(), false);
+                qlog.stream_type_set(stream.id(), stream.header().kind(), false);
+            }
+
             match stream.header().kind() {
                 StreamKind::WebTransport => {
-                    wtslot.send(stream.upgrade());
+                    if let Err(violation) = demux.route_uni(stream.upgrade()) {
+                        let _ = violations.send(violation);
+                    }
                 }
                 _ => {
                     h3slot.send(stream);
@@ -316,23 +552,51 @@ impl Worker {
     fn process_inc_bi(
         stream: Stream<BiRemote, Raw>,
         h3slot: mpsc::OwnedPermit<(Stream<BiRemote, H3>, Frame<'static>)>,
-        wtslot: mpsc::OwnedPermit<Stream<BiRemote, Wt>>,
+        demux: Arc<Demux>,
+        qlog: Option<Arc<QlogTrace>>,
+        reserved_counts: Arc<ReservedCounts>,
+        parse_limits: ParseLimits,
+        violations: mpsc::UnboundedSender<H3Error>,
     ) {
-        tokio::spawn(async move {
+        runtime::spawn(async move {
             let mut stream = stream.upgrade();
 
-            let frame = match stream.read_frame().await {
-                Ok(frame) => frame,
-                Err(FrameReadError::UnknownFrame) => return,
-                Err(FrameReadError::InvalidSessionId) => return,
-                Err(FrameReadError::ConnectionClosed) => return,
-                Err(FrameReadError::EndOfStream) => return,
+            if let Some(qlog) = qlog.as_deref() {
+                qlog.stream_opened(stream.id(), false);
+            }
+
+            // Reserved frames can precede the first meaningful one
+            let frame = loop {
+                let frame = match stream.read_frame(&parse_limits).await {
+                    Ok(frame) => frame,
+                    Err(FrameReadError::UnknownFrame) => return,
+                    Err(FrameReadError::InvalidSessionId) => {
+                        let _ = violations.send(Self::invalid_session_id());
+                        return;
+                    }
+                    Err(FrameReadError::ExcessiveLoad) => {
+                        stream.abort(ErrorCode::ExcessiveLoad.to_code());
+                        return;
+                    }
+                    Err(FrameReadError::ConnectionClosed) => return,
+                    Err(FrameReadError::EndOfStream) => return,
+                };
+
+                if let Some(qlog) = qlog.as_deref() {
+                    qlog.frame(stream.id(), &frame, false);
+                }
+
+                match frame.kind() {
+                    FrameKind::Exercise(_) => reserved_counts.frame_discarded(),
+                    _ => break frame,
+                }
             };
 
             match frame.session_id() {
                 Some(session_id) => {
-                    let stream = stream.upgrade(session_id);
-                    wtslot.send(stream);
+                    if let Err(violation) = demux.route_bi(stream.upgrade(session_id)) {
+                        let _ = violations.send(violation);
+                    }
                 }
                 None => {
                     h3slot.send((stream, frame));
@@ -340,26 +604,49 @@ impl Worker {
             }
         });
     }
+
+    /// Streams must refer to sessions by client-initiated bidirectional stream IDs.
+    fn invalid_session_id() -> H3Error {
+        H3Error::new(ErrorCode::Id, "Invalid session ID on WebTransport stream")
+    }
 }
 
 struct LocalSettingsStream(Option<Stream<UniLocal, H3>>);
 
 impl LocalSettingsStream {
+    /// Reserved frame type (`0x1f * N + 0x21`) used for keep-alives.
+    const KEEP_ALIVE_FRAME_TYPE: VarInt = VarInt::from_u32(0x21);
+
     fn new() -> Self {
         Self(None)
     }
 
+    /// Opens the control stream and sends the local SETTINGS.
+    ///
+    /// `extra_settings` are added to (or override) the default ones, except
+    /// for QPACK settings: the dynamic table is not supported.
     async fn on_ready_connection(
         &mut self,
         quic_connection: &quinn::Connection,
+        extra_settings: &Settings,
+        max_sessions: VarInt,
+        grease: Grease,
+        qlog: Option<&QlogTrace>,
     ) -> WorkerResult<()> {
         debug_assert!(self.0.is_none());
 
-        let local_settings = Settings::builder()
+        // Every known dialect is advertised, so that peers of any draft recognize it
+        let mut local_settings = Settings::builder()
+            .enable_webtransport_compat(max_sessions)
+            .extend(extra_settings);
+
+        if grease.settings() {
+            local_settings = local_settings.grease(grease::reserved_id(), grease::random_value());
+        }
+
+        let local_settings = local_settings
             .qpack_max_table_capacity(VarInt::from_u32(0))
             .qpack_blocked_streams(VarInt::from_u32(0))
-            .enable_webtransport()
-            .enable_h3_datagrams()
             .build();
 
         let mut stream = Stream::open_uni(quic_connection)
@@ -371,8 +658,17 @@ impl LocalSettingsStream {
                 WorkerError::with_upgrade_err(upgrade_error, "Unable to send SETTINGS")
             })?;
 
+        let frame = local_settings.generate_frame();
+
+        if let Some(qlog) = qlog {
+            qlog.stream_opened(stream.id(), true);
+            qlog.stream_type_set(stream.id(), stream.header().kind(), true);
+            qlog.frame(stream.id(), &frame, true);
+            qlog.parameters_set(&local_settings, true);
+        }
+
         stream
-            .write_frame(local_settings.generate_frame())
+            .write_frame(frame)
             .await
             .map_err(|frame_write_error| {
                 WorkerError::with_frame_write_err(frame_write_error, "Unable to send SETTINGS")
@@ -380,14 +676,95 @@ impl LocalSettingsStream {
 
         self.0 = Some(stream);
 
+        if grease.frames() {
+            self.send_grease(qlog).await?;
+        }
+
         Ok(())
     }
 
+    /// Sends a reserved frame, with random type and payload, which the peer ignores.
+    async fn send_grease(&mut self, qlog: Option<&QlogTrace>) -> WorkerResult<()> {
+        let stream = self.0.as_mut().expect("Control stream must be open");
+        let frame =
+            Frame::new_exercise(grease::reserved_id(), Cow::Owned(grease::random_payload()));
+
+        if let Some(qlog) = qlog {
+            qlog.frame(stream.id(), &frame, true);
+        }
+
+        stream
+            .write_frame(frame)
+            .await
+            .map_err(|frame_write_error| {
+                WorkerError::with_frame_write_err(frame_write_error, "Unable to send grease")
+            })
+    }
+
+    /// Sends an extension frame (see [`Engine::send_extension_frame`](super::Engine::send_extension_frame)).
+    async fn send_extension(
+        &mut self,
+        frame_type: VarInt,
+        payload: Vec<u8>,
+        qlog: Option<&QlogTrace>,
+    ) -> WorkerResult<()> {
+        let stream = self.0.as_mut().expect("Control stream must be open");
+        let frame = Frame::new_unknown(frame_type, Cow::Owned(payload));
+
+        if let Some(qlog) = qlog {
+            qlog.frame(stream.id(), &frame, true);
+        }
+
+        stream
+            .write_frame(frame)
+            .await
+            .map_err(|frame_write_error| {
+                WorkerError::with_frame_write_err(
+                    frame_write_error,
+                    "Unable to send extension frame",
+                )
+            })
+    }
+
+    /// Sends a reserved frame, which the peer ignores, so that an
+    /// ack-eliciting packet refreshes the NAT bindings on the path.
+    async fn send_keep_alive(&mut self) -> WorkerResult<()> {
+        let stream = self.0.as_mut().expect("Control stream must be open");
+
+        stream
+            .write_frame(Frame::new_exercise(
+                Self::KEEP_ALIVE_FRAME_TYPE,
+                Cow::Borrowed(&[]),
+            ))
+            .await
+            .map_err(|frame_write_error| {
+                WorkerError::with_frame_write_err(frame_write_error, "Unable to send keep-alive")
+            })
+    }
+
+    /// Sends a GOAWAY frame: requests on streams with `id` or greater
+    /// (or pushes, for clients) are not going to be processed.
+    async fn send_goaway(&mut self, id: VarInt, qlog: Option<&QlogTrace>) -> WorkerResult<()> {
+        let stream = self.0.as_mut().expect("Control stream must be open");
+        let frame = Frame::new_goaway(id);
+
+        if let Some(qlog) = qlog {
+            qlog.frame(stream.id(), &frame, true);
+        }
+
+        stream
+            .write_frame(frame)
+            .await
+            .map_err(|frame_write_error| {
+                WorkerError::with_frame_write_err(frame_write_error, "Unable to send GOAWAY")
+            })
+    }
+
     async fn done(&mut self) -> WorkerResult<()> {
         match self.0 {
             Some(ref mut stream) => match stream.stopped().await {
                 Err(StreamError::ConnectionClosed) => Err(WorkerError::RemoteClosed),
-                Ok(()) | Err(StreamError::Stopped) => Err(WorkerError::LocalClosed(H3Error::new(
+                Ok(_) | Err(StreamError::Stopped) => Err(WorkerError::LocalClosed(H3Error::new(
                     ErrorCode::ClosedCriticalStream,
                     "Closed local control stream",
                 ))),
@@ -397,6 +774,108 @@ impl LocalSettingsStream {
     }
 }
 
+/// Local QPACK encoder stream.
+///
+/// The dynamic table is never used (static-only mode), so no instruction
+/// is ever sent on this stream; it is only opened as required by RFC 9204.
+struct LocalQPackEncStream(Option<Stream<UniLocal, H3>>);
+
+impl LocalQPackEncStream {
+    fn new() -> Self {
+        Self(None)
+    }
+
+    async fn on_ready_connection(
+        &mut self,
+        quic_connection: &quinn::Connection,
+        qlog: Option<&QlogTrace>,
+    ) -> WorkerResult<()> {
+        debug_assert!(self.0.is_none());
+
+        let stream = Stream::open_uni(quic_connection)
+            .await
+            .ok_or(WorkerError::RemoteClosed)?
+            .upgrade(StreamHeader::new_qpack_encoder())
+            .await
+            .map_err(|upgrade_error| {
+                WorkerError::with_upgrade_err(upgrade_error, "Unable to open QPACK.ENC stream")
+            })?;
+
+        if let Some(qlog) = qlog {
+            qlog.stream_opened(stream.id(), true);
+            qlog.stream_type_set(stream.id(), stream.header().kind(), true);
+        }
+
+        self.0 = Some(stream);
+
+        Ok(())
+    }
+
+    async fn done(&mut self) -> WorkerResult<()> {
+        match self.0 {
+            Some(ref mut stream) => match stream.stopped().await {
+                Err(StreamError::ConnectionClosed) => Err(WorkerError::RemoteClosed),
+                Ok(_) | Err(StreamError::Stopped) => Err(WorkerError::LocalClosed(H3Error::new(
+                    ErrorCode::ClosedCriticalStream,
+                    "Closed local QPACK.ENC stream",
+                ))),
+            },
+            None => pending().await,
+        }
+    }
+}
+
+/// Local QPACK decoder stream.
+///
+/// The dynamic table is never used (static-only mode), so no instruction
+/// is ever sent on this stream; it is only opened as required by RFC 9204.
+struct LocalQPackDecStream(Option<Stream<UniLocal, H3>>);
+
+impl LocalQPackDecStream {
+    fn new() -> Self {
+        Self(None)
+    }
+
+    async fn on_ready_connection(
+        &mut self,
+        quic_connection: &quinn::Connection,
+        qlog: Option<&QlogTrace>,
+    ) -> WorkerResult<()> {
+        debug_assert!(self.0.is_none());
+
+        let stream = Stream::open_uni(quic_connection)
+            .await
+            .ok_or(WorkerError::RemoteClosed)?
+            .upgrade(StreamHeader::new_qpack_decoder())
+            .await
+            .map_err(|upgrade_error| {
+                WorkerError::with_upgrade_err(upgrade_error, "Unable to open QPACK.DEC stream")
+            })?;
+
+        if let Some(qlog) = qlog {
+            qlog.stream_opened(stream.id(), true);
+            qlog.stream_type_set(stream.id(), stream.header().kind(), true);
+        }
+
+        self.0 = Some(stream);
+
+        Ok(())
+    }
+
+    async fn done(&mut self) -> WorkerResult<()> {
+        match self.0 {
+            Some(ref mut stream) => match stream.stopped().await {
+                Err(StreamError::ConnectionClosed) => Err(WorkerError::RemoteClosed),
+                Ok(_) | Err(StreamError::Stopped) => Err(WorkerError::LocalClosed(H3Error::new(
+                    ErrorCode::ClosedCriticalStream,
+                    "Closed local QPACK.DEC stream",
+                ))),
+            },
+            None => pending().await,
+        }
+    }
+}
+
 struct RemoteSettingsStream(Option<Stream<UniRemote, H3>>);
 
 impl RemoteSettingsStream {
@@ -408,6 +887,8 @@ impl RemoteSettingsStream {
         &mut self,
         inc_settings_channel: &watch::Sender<Option<Settings>>,
         mut stream: Stream<UniRemote, H3>,
+        limits: &ParseLimits,
+        qlog: Option<&QlogTrace>,
     ) -> WorkerResult<()> {
         debug_assert!(matches!(stream.header().kind(), StreamKind::Control));
 
@@ -418,20 +899,36 @@ impl RemoteSettingsStream {
             )));
         }
 
-        let frame = stream.read_frame().await.map_err(|frame_read_error| {
-            WorkerError::with_frame_read_err(frame_read_error, "Unable to receive SETTINGS")
-        })?;
+        let frame = stream
+            .read_frame(limits)
+            .await
+            .map_err(|frame_read_error| {
+                WorkerError::with_frame_read_err(frame_read_error, "Unable to receive SETTINGS")
+            })?;
+
+        if let Some(qlog) = qlog {
+            qlog.frame(stream.id(), &frame, false);
+        }
+
+        if fail_point!(SettingsParse) {
+            return Err(WorkerError::LocalClosed(H3Error::new(
+                ErrorCode::Settings,
+                "Injected failure",
+            )));
+        }
 
         let settings = match frame.kind() {
-            FrameKind::Settings => match Settings::with_frame(&frame) {
-                Ok(settings) => settings,
-                Err(h3code) => {
-                    return Err(WorkerError::LocalClosed(H3Error::new(
-                        h3code,
-                        "Unable to receive SETTINGS",
-                    )));
+            FrameKind::Settings => {
+                match Settings::with_frame_limits(&frame, ParseMode::Strict, limits) {
+                    Ok(settings) => settings,
+                    Err(h3code) => {
+                        return Err(WorkerError::LocalClosed(H3Error::new(
+                            h3code,
+                            "Unable to receive SETTINGS",
+                        )));
+                    }
                 }
-            },
+            }
             _ => {
                 return Err(WorkerError::LocalClosed(H3Error::new(
                     ErrorCode::MissingSettings,
@@ -440,6 +937,10 @@ impl RemoteSettingsStream {
             }
         };
 
+        if let Some(qlog) = qlog {
+            qlog.parameters_set(&settings, false);
+        }
+
         match inc_settings_channel.send(Some(settings)) {
             Ok(()) => {
                 self.0 = Some(stream);
@@ -449,25 +950,97 @@ impl RemoteSettingsStream {
         }
     }
 
-    async fn done(&mut self) -> WorkerResult<()> {
+    async fn done(
+        &mut self,
+        inc_goaway_channel: &watch::Sender<Option<VarInt>>,
+        extension_frames: &ExtensionFrameQueues,
+        reserved_counts: &ReservedCounts,
+        qlog: Option<&QlogTrace>,
+    ) -> WorkerResult<()> {
         match self.0 {
             Some(ref mut stream) => loop {
-                let frame = stream.read_frame().await.map_err(|frame_read_error| {
-                    WorkerError::with_frame_read_err(frame_read_error, "Error on control stream")
-                })?;
-
-                if !matches!(frame.kind(), FrameKind::Exercise(_)) {
-                    return Err(WorkerError::LocalClosed(H3Error::new(
-                        ErrorCode::FrameUnexpected,
-                        "Unexpected frame on remote control stream",
-                    )));
+                let frame = stream
+                    .read_frame_with_unknown()
+                    .await
+                    .map_err(|frame_read_error| {
+                        WorkerError::with_frame_read_err(
+                            frame_read_error,
+                            "Error on control stream",
+                        )
+                    })?;
+
+                if let Some(qlog) = qlog {
+                    qlog.frame(stream.id(), &frame, false);
+                }
+
+                match frame.kind() {
+                    FrameKind::Exercise(_) => reserved_counts.frame_discarded(),
+                    FrameKind::Unknown(frame_type) => {
+                        // Frames of types not registered are skipped
+                        if let Some(queue) = extension_frames.lock().unwrap().get(&frame_type) {
+                            let _ = queue.send(frame.payload().to_vec());
+                        }
+                    }
+                    FrameKind::GoAway => {
+                        // The remote control stream is initiated by the peer
+                        let peer_is_server = !stream.id().is_client_initiated();
+                        let goaway_id = Self::validate_goaway(
+                            &frame,
+                            peer_is_server,
+                            *inc_goaway_channel.borrow(),
+                        )?;
+                        inc_goaway_channel.send_replace(Some(goaway_id));
+                    }
+                    _ => {
+                        return Err(WorkerError::LocalClosed(H3Error::new(
+                            ErrorCode::FrameUnexpected,
+                            "Unexpected frame on remote control stream",
+                        )));
+                    }
                 }
             },
             None => pending().await,
         }
     }
+
+    /// Checks the ID of a GOAWAY frame: servers must send client-initiated
+    /// bidirectional stream IDs, and IDs must never increase.
+    fn validate_goaway(
+        frame: &Frame,
+        peer_is_server: bool,
+        previous_id: Option<VarInt>,
+    ) -> WorkerResult<VarInt> {
+        let goaway_id = frame
+            .goaway_id()
+            .expect("Frame is GOAWAY")
+            .map_err(|h3code| {
+                WorkerError::LocalClosed(H3Error::new(h3code, "Malformed GOAWAY frame"))
+            })?;
+
+        let stream_id = StreamId::new(goaway_id);
+
+        if peer_is_server && !(stream_id.is_bidirectional() && stream_id.is_client_initiated()) {
+            return Err(WorkerError::LocalClosed(H3Error::new(
+                ErrorCode::Id,
+                "Invalid stream ID in GOAWAY frame",
+            )));
+        }
+
+        if matches!(previous_id, Some(previous_id) if goaway_id > previous_id) {
+            return Err(WorkerError::LocalClosed(H3Error::new(
+                ErrorCode::Id,
+                "Increasing ID in GOAWAY frame",
+            )));
+        }
+
+        Ok(goaway_id)
+    }
 }
 
+/// Remote QPACK encoder stream.
+///
+/// Instructions are read and discarded: as the dynamic table is disabled
+/// in local SETTINGS, none of them can reference state we need to track.
 struct RemoteQPackEncStream(Option<QuicRecvStream>);
 
 impl RemoteQPackEncStream {
@@ -510,6 +1083,10 @@ impl RemoteQPackEncStream {
     }
 }
 
+/// Remote QPACK decoder stream.
+///
+/// Instructions are read and discarded: as the dynamic table is disabled
+/// in local SETTINGS, none of them can reference state we need to track.
 struct RemoteQPackDecStream(Option<QuicRecvStream>);
 
 impl RemoteQPackDecStream {
@@ -594,6 +1171,9 @@ impl WorkerError {
             FrameReadError::InvalidSessionId => {
                 WorkerError::LocalClosed(H3Error::new(ErrorCode::FrameUnexpected, reason))
             }
+            FrameReadError::ExcessiveLoad => {
+                WorkerError::LocalClosed(H3Error::new(ErrorCode::ExcessiveLoad, reason))
+            }
             FrameReadError::EndOfStream => {
                 WorkerError::LocalClosed(H3Error::new(ErrorCode::ClosedCriticalStream, reason))
             }
@@ -1,8 +1,12 @@
 use crate::engine::session::SessionError;
+use crate::engine::session::SessionTermination;
 use crate::engine::worker::WorkerError;
 use std::fmt::Debug;
+use std::fmt::Display;
 use std::fmt::Formatter;
+use std::time::Duration;
 use wtransport_proto::error::ErrorCode;
+use wtransport_proto::headers::Headers;
 use wtransport_proto::varint::VarInt;
 
 /// An enumeration representing various errors that can occur during a WebTransport connection.
@@ -22,9 +26,24 @@ pub enum ConnectionError {
 
     /// An error occurred in the QUIC layer.
     QuicError,
+
+    /// A session request has been rejected: on servers, the peer's request
+    /// was malformed or not supported; on clients, the server replied with
+    /// a non-2xx status.
+    SessionRejected(SessionRejected),
+
+    /// The server refused the connection (e.g., because it reached its maximum
+    /// number of connections).
+    ConnectionRefused,
+
+    /// The host name of the server could not be resolved into any address.
+    UnresolvedHost,
 }
 
 impl ConnectionError {
+    /// QUIC transport error code `CONNECTION_REFUSED`.
+    const QUIC_CONNECTION_REFUSED: u64 = 0x02;
+
     pub(crate) fn close_worker_error(
         worker_error: WorkerError,
         quic_connection: &quinn::Connection,
@@ -55,6 +74,7 @@ impl ConnectionError {
     ) -> Self {
         match session_error {
             SessionError::LocalClosed(h3error) => ConnectionError::H3(h3error),
+            SessionError::Rejected(rejected) => ConnectionError::SessionRejected(rejected),
             SessionError::RemoteClosed => quic_connection
                 .close_reason()
                 .expect("Worker closed before connection ended")
@@ -100,6 +120,107 @@ impl Debug for ConnectionClosed {
     }
 }
 
+/// Report of a session request rejected by the server.
+///
+/// On servers, the peer has been notified of the rejection: either with an
+/// error response (e.g., `400` or `429`) or, if the request was malformed,
+/// with a stream error (H3_MESSAGE_ERROR).
+///
+/// On clients, it reports the error response of the server, so that the
+/// application can react to the status (e.g., re-authenticating on `401`, or
+/// backing off on `429`, see [`Self::retry_after`]).
+#[derive(Debug)]
+pub struct SessionRejected {
+    reason: RejectionReason,
+    headers: Headers,
+    response_headers: Option<Headers>,
+}
+
+impl SessionRejected {
+    pub(crate) fn new(
+        reason: RejectionReason,
+        headers: Headers,
+        response_headers: Option<Headers>,
+    ) -> Self {
+        Self {
+            reason,
+            headers,
+            response_headers,
+        }
+    }
+
+    /// Why the request has been rejected.
+    #[inline(always)]
+    pub fn reason(&self) -> RejectionReason {
+        self.reason
+    }
+
+    /// The headers of the rejected request.
+    #[inline(always)]
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// The headers of the error response (including `:status`).
+    ///
+    /// It is [`None`] if the request was malformed: no response is sent then.
+    #[inline(always)]
+    pub fn response_headers(&self) -> Option<&Headers> {
+        self.response_headers.as_ref()
+    }
+
+    /// The status of the error response (e.g., `429`).
+    pub fn status(&self) -> Option<u16> {
+        self.response_headers.as_ref()?.get(":status")?.parse().ok()
+    }
+
+    /// The delay the server asked to wait before retrying, from the
+    /// `retry-after` response header (in seconds).
+    ///
+    /// The HTTP-date form of the header is not supported.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.response_headers
+            .as_ref()?
+            .get("retry-after")?
+            .trim()
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+}
+
+/// The reason a session request has been rejected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RejectionReason {
+    /// The request is missing mandatory pseudo-headers.
+    Malformed,
+
+    /// The `:method` is not `CONNECT`.
+    MethodNotAllowed,
+
+    /// The `:protocol` is missing or it is not `webtransport`.
+    UnsupportedProtocol,
+
+    /// The `:scheme` is not `https`.
+    InvalidScheme,
+
+    /// The `origin` header is not a valid serialized origin.
+    InvalidOrigin,
+
+    /// The connection reached its maximum number of sessions
+    /// (see [`ServerConfig::set_max_sessions`](crate::ServerConfig::set_max_sessions)).
+    TooManySessions,
+
+    /// An accept hook refused the request with the response `status`
+    /// (see [`ServerConfig::add_accept_hook`](crate::ServerConfig::add_accept_hook)).
+    ///
+    /// On clients, this is the non-2xx `status` the server replied with.
+    Refused {
+        /// The response status.
+        status: u16,
+    },
+}
+
 /// A struct representing an error in the HTTP/3 layer.
 #[derive(Clone)]
 pub struct H3Error {
@@ -145,6 +266,84 @@ pub enum StreamError {
     Stopped,
 }
 
+/// Error when reading an exact amount of data from a stream.
+///
+/// See [`RecvStream::read_exact`](crate::RecvStream::read_exact).
+#[derive(Debug)]
+pub enum StreamReadExactError {
+    /// The peer finished the stream before the buffer was filled.
+    ///
+    /// It carries the number of bytes read.
+    FinishedEarly(usize),
+
+    /// An error occurred on the stream.
+    Stream(StreamError),
+}
+
+/// Error when reading a whole stream.
+///
+/// See [`RecvStream::read_to_end`](crate::RecvStream::read_to_end).
+#[derive(Debug)]
+pub enum StreamReadToEndError {
+    /// The stream is longer than the size limit.
+    TooLong,
+
+    /// An error occurred on the stream.
+    Stream(StreamError),
+}
+
+/// Error when sending the content of a reader.
+///
+/// See [`SendStream::send_from`](crate::SendStream::send_from).
+#[derive(Debug)]
+pub enum SendFromError {
+    /// Reading from the source failed.
+    Read(std::io::Error),
+
+    /// An error occurred on the stream.
+    Stream(StreamError),
+}
+
+/// A session has been rejected because its identity is already
+/// registered by another session.
+///
+/// See [`SessionRegistry::register`](crate::registry::SessionRegistry::register).
+#[derive(Debug)]
+pub struct DuplicateSession;
+
+/// Keying material cannot be exported, because the requested output is
+/// too long for the negotiated cipher suite.
+///
+/// See [`Connection::export_keying_material`](crate::Connection::export_keying_material).
+#[derive(Debug)]
+pub struct ExportKeyingMaterialError;
+
+/// The URL of a proxy is not valid.
+///
+/// See [`ProxyUrl::parse`](crate::proxy::ProxyUrl::parse).
+#[derive(Debug)]
+pub struct InvalidProxyUrl;
+
+/// Error when sending or receiving length-prefixed messages.
+///
+/// See [`MessageStream`](crate::codec::MessageStream).
+#[derive(Debug)]
+pub enum MessageError {
+    /// The message is larger than the maximum size.
+    TooLarge,
+
+    /// The stream ended in the middle of a message.
+    Truncated,
+
+    /// The underlying stream failed (e.g., it was reset or stopped by the peer).
+    Io(std::io::Error),
+
+    /// The message cannot be serialized or deserialized.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    Json(serde_json::Error),
+}
+
 /// Error when dealing with application datagrams.
 #[derive(Debug)]
 pub enum DatagramError {
@@ -154,15 +353,88 @@ pub enum DatagramError {
     /// Datagrams are not supported by peer.
     UnsupportedByPeer,
 
+    /// The datagram is larger than the current maximum size
+    /// (see [`Connection::max_datagram_size`](crate::Connection::max_datagram_size)).
+    TooLarge,
+
+    /// The datagram exceeds the send rate limit of the session
+    /// (see [`Connection::set_send_rate_limit`](crate::Connection::set_send_rate_limit)).
+    RateLimited,
+
     /// Error at QUIC protocol layer.
     Protocol,
 }
 
+/// Error of an operation with a timeout (e.g.,
+/// [`Connection::accept_bi_timeout`](crate::Connection::accept_bi_timeout)).
+#[derive(Debug)]
+pub enum TimeoutError<E> {
+    /// The timeout elapsed before the operation completed.
+    Elapsed,
+
+    /// The operation failed.
+    Failed(E),
+}
+
+/// Error when sending a message with
+/// [`Connection::send_with_deadline`](crate::Connection::send_with_deadline).
+#[derive(Debug)]
+pub enum SendDeadlineError {
+    /// The stream cannot be opened.
+    Connection(ConnectionError),
+
+    /// The stream failed before the deadline.
+    Stream(StreamError),
+}
+
+/// Error when sending a message with
+/// [`FragmentedDatagrams`](crate::fragment::FragmentedDatagrams).
+#[derive(Debug)]
+pub enum FragmentError {
+    /// The message needs more fragments than allowed.
+    TooLarge,
+
+    /// A fragment cannot be sent.
+    Datagram(DatagramError),
+}
+
+/// Error when registering an application-defined type (see, for example,
+/// [`Connection::register_capsule_type`](crate::Connection::register_capsule_type)).
+#[derive(Debug)]
+pub enum RegisterError {
+    /// The type has been already registered.
+    AlreadyRegistered,
+
+    /// The type is defined by HTTP3 or WebTransport.
+    Predefined,
+
+    /// The type is reserved (e.g., for grease).
+    Reserved,
+}
+
+/// Error when sending serialized datagrams
+/// (see [`TypedDatagrams`](crate::datagram::TypedDatagrams)).
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+#[derive(Debug)]
+pub enum TypedDatagramError {
+    /// The datagram cannot be sent.
+    Datagram(DatagramError),
+
+    /// The value cannot be serialized.
+    Json(serde_json::Error),
+}
+
 impl From<quinn::ConnectionError> for ConnectionError {
     fn from(error: quinn::ConnectionError) -> Self {
         match error {
             quinn::ConnectionError::VersionMismatch => ConnectionError::QuicError,
             quinn::ConnectionError::TransportError(_) => ConnectionError::QuicError,
+            quinn::ConnectionError::ConnectionClosed(quic_close)
+                if u64::from(quic_close.error_code) == ConnectionError::QUIC_CONNECTION_REFUSED =>
+            {
+                ConnectionError::ConnectionRefused
+            }
             quinn::ConnectionError::ConnectionClosed(quic_close) => {
                 ConnectionError::ConnectionClosed(ConnectionClosed {
                     code: VarInt::try_from_u64(u64::from(quic_close.error_code))
@@ -189,6 +461,21 @@ impl From<quinn::ConnectionError> for ConnectionError {
     }
 }
 
+impl From<SessionTermination> for ConnectionError {
+    fn from(termination: SessionTermination) -> Self {
+        match termination {
+            SessionTermination::RemotelyClosed { error_code, reason } => {
+                ConnectionError::ConnectionClosed(ConnectionClosed {
+                    code: VarInt::from_u32(error_code),
+                    reason: reason.into_bytes(),
+                })
+            }
+            SessionTermination::LocallyClosed => ConnectionError::LocallyClosed,
+            SessionTermination::Error(h3error) => ConnectionError::H3(h3error),
+        }
+    }
+}
+
 impl From<quinn::WriteError> for StreamError {
     fn from(error: quinn::WriteError) -> Self {
         match error {
@@ -227,8 +514,306 @@ impl From<quinn::SendDatagramError> for DatagramError {
         match error {
             quinn::SendDatagramError::UnsupportedByPeer => DatagramError::UnsupportedByPeer,
             quinn::SendDatagramError::Disabled => Self::Protocol,
-            quinn::SendDatagramError::TooLarge => Self::Protocol,
+            quinn::SendDatagramError::TooLarge => DatagramError::TooLarge,
             quinn::SendDatagramError::ConnectionLost(_) => DatagramError::ConnectionClosed,
         }
     }
 }
+
+impl From<std::io::Error> for MessageError {
+    fn from(error: std::io::Error) -> Self {
+        MessageError::Io(error)
+    }
+}
+
+impl Display for ConnectionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionError::ConnectionClosed(_) => write!(f, "connection closed by peer"),
+            ConnectionError::TimedOut => write!(f, "connection timed out"),
+            ConnectionError::LocallyClosed => write!(f, "connection locally closed"),
+            ConnectionError::H3(_) => write!(f, "HTTP3 protocol error"),
+            ConnectionError::QuicError => write!(f, "QUIC protocol error"),
+            ConnectionError::SessionRejected(_) => write!(f, "session rejected"),
+            ConnectionError::ConnectionRefused => write!(f, "connection refused"),
+            ConnectionError::UnresolvedHost => write!(f, "host name not resolved"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConnectionError::ConnectionClosed(closed) => Some(closed),
+            ConnectionError::H3(h3error) => Some(h3error),
+            ConnectionError::SessionRejected(rejected) => Some(rejected),
+            ConnectionError::TimedOut
+            | ConnectionError::LocallyClosed
+            | ConnectionError::QuicError
+            | ConnectionError::ConnectionRefused
+            | ConnectionError::UnresolvedHost => None,
+        }
+    }
+}
+
+impl Display for ConnectionClosed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ConnectionClosed {}
+
+impl Display for SessionRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.reason, f)
+    }
+}
+
+impl std::error::Error for SessionRejected {}
+
+impl Display for RejectionReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectionReason::Malformed => write!(f, "malformed request"),
+            RejectionReason::MethodNotAllowed => write!(f, "method not allowed"),
+            RejectionReason::UnsupportedProtocol => write!(f, "unsupported protocol"),
+            RejectionReason::InvalidScheme => write!(f, "invalid scheme"),
+            RejectionReason::InvalidOrigin => write!(f, "invalid origin"),
+            RejectionReason::TooManySessions => write!(f, "too many sessions"),
+            RejectionReason::Refused { status } => write!(f, "refused ({status})"),
+        }
+    }
+}
+
+impl Display for H3Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for H3Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.code)
+    }
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::ConnectionClosed => write!(f, "connection closed"),
+            StreamError::Stopped => write!(f, "stream stopped"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl Display for StreamReadExactError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamReadExactError::FinishedEarly(read) => {
+                write!(f, "stream finished early ({read} bytes read)")
+            }
+            StreamReadExactError::Stream(_) => write!(f, "cannot read from stream"),
+        }
+    }
+}
+
+impl std::error::Error for StreamReadExactError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamReadExactError::FinishedEarly(_) => None,
+            StreamReadExactError::Stream(error) => Some(error),
+        }
+    }
+}
+
+impl Display for StreamReadToEndError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamReadToEndError::TooLong => write!(f, "stream longer than the size limit"),
+            StreamReadToEndError::Stream(_) => write!(f, "cannot read from stream"),
+        }
+    }
+}
+
+impl std::error::Error for StreamReadToEndError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StreamReadToEndError::TooLong => None,
+            StreamReadToEndError::Stream(error) => Some(error),
+        }
+    }
+}
+
+impl Display for SendFromError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendFromError::Read(_) => write!(f, "cannot read from source"),
+            SendFromError::Stream(_) => write!(f, "cannot write to stream"),
+        }
+    }
+}
+
+impl std::error::Error for SendFromError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SendFromError::Read(error) => Some(error),
+            SendFromError::Stream(error) => Some(error),
+        }
+    }
+}
+
+impl Display for DuplicateSession {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "session already registered")
+    }
+}
+
+impl std::error::Error for DuplicateSession {}
+
+impl Display for ExportKeyingMaterialError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "keying material output too long")
+    }
+}
+
+impl std::error::Error for ExportKeyingMaterialError {}
+
+impl Display for InvalidProxyUrl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid proxy URL")
+    }
+}
+
+impl std::error::Error for InvalidProxyUrl {}
+
+impl Display for MessageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageError::TooLarge => write!(f, "message too large"),
+            MessageError::Truncated => write!(f, "stream ended in the middle of a message"),
+            MessageError::Io(_) => write!(f, "stream error"),
+            #[cfg(feature = "serde")]
+            MessageError::Json(_) => write!(f, "invalid JSON message"),
+        }
+    }
+}
+
+impl std::error::Error for MessageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MessageError::TooLarge | MessageError::Truncated => None,
+            MessageError::Io(error) => Some(error),
+            #[cfg(feature = "serde")]
+            MessageError::Json(error) => Some(error),
+        }
+    }
+}
+
+impl Display for DatagramError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatagramError::ConnectionClosed => write!(f, "connection closed"),
+            DatagramError::UnsupportedByPeer => write!(f, "datagrams not supported by peer"),
+            DatagramError::TooLarge => write!(f, "datagram too large"),
+            DatagramError::RateLimited => write!(f, "datagram send rate limit exceeded"),
+            DatagramError::Protocol => write!(f, "QUIC protocol error"),
+        }
+    }
+}
+
+impl std::error::Error for DatagramError {}
+
+impl<E> Display for TimeoutError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeoutError::Elapsed => write!(f, "timed out"),
+            TimeoutError::Failed(error) => error.fmt(f),
+        }
+    }
+}
+
+impl<E> std::error::Error for TimeoutError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TimeoutError::Elapsed => None,
+            TimeoutError::Failed(error) => error.source(),
+        }
+    }
+}
+
+impl Display for SendDeadlineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendDeadlineError::Connection(_) => write!(f, "cannot open stream"),
+            SendDeadlineError::Stream(_) => write!(f, "stream error"),
+        }
+    }
+}
+
+impl std::error::Error for SendDeadlineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SendDeadlineError::Connection(error) => Some(error),
+            SendDeadlineError::Stream(error) => Some(error),
+        }
+    }
+}
+
+impl Display for FragmentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FragmentError::TooLarge => write!(f, "message too large"),
+            FragmentError::Datagram(_) => write!(f, "datagram error"),
+        }
+    }
+}
+
+impl std::error::Error for FragmentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FragmentError::TooLarge => None,
+            FragmentError::Datagram(error) => Some(error),
+        }
+    }
+}
+
+impl Display for RegisterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegisterError::AlreadyRegistered => write!(f, "type already registered"),
+            RegisterError::Predefined => write!(f, "type defined by the protocol"),
+            RegisterError::Reserved => write!(f, "reserved type"),
+        }
+    }
+}
+
+impl std::error::Error for RegisterError {}
+
+#[cfg(feature = "serde")]
+impl Display for TypedDatagramError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypedDatagramError::Datagram(_) => write!(f, "datagram error"),
+            TypedDatagramError::Json(_) => write!(f, "invalid JSON datagram"),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for TypedDatagramError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TypedDatagramError::Datagram(error) => Some(error),
+            TypedDatagramError::Json(error) => Some(error),
+        }
+    }
+}
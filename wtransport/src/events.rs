@@ -0,0 +1,240 @@
+use crate::metrics::HandshakeFailure;
+use crate::metrics::Metrics;
+use futures_core::Stream;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use tokio::sync::broadcast;
+use wtransport_proto::ids::SessionId;
+use wtransport_proto::ids::StreamId;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::varint::VarInt;
+
+/// An event of a connection, see [`Connection::events`](crate::Connection::events).
+///
+/// Events refer to the whole QUIC connection, hence they include the ones
+/// of the other sessions pooled on it (see the session IDs).
+#[derive(Clone, Debug)]
+pub enum ConnectionEvent {
+    /// The SETTINGS of the peer have been received.
+    SettingsReceived(Settings),
+
+    /// The peer sent a GOAWAY: it does not accept requests with a stream ID
+    /// greater than or equal to `id`.
+    GoAwayReceived {
+        /// The identifier carried by the GOAWAY frame.
+        id: VarInt,
+    },
+
+    /// A capsule has been received on the stream of a session.
+    CapsuleReceived {
+        /// The session the capsule belongs to.
+        session_id: SessionId,
+
+        /// The type of the capsule.
+        capsule_type: VarInt,
+    },
+
+    /// A WebTransport stream has been opened (locally or by the peer).
+    StreamOpened {
+        /// The identifier of the stream.
+        stream_id: StreamId,
+
+        /// Whether the stream has been opened by this endpoint.
+        local: bool,
+    },
+
+    /// The application dropped the handles of a stream.
+    StreamClosed {
+        /// The identifier of the stream.
+        stream_id: StreamId,
+    },
+
+    /// An application datagram has been discarded locally.
+    ///
+    /// See [`ConnectionStats::dropped_datagrams`](crate::connection::ConnectionStats::dropped_datagrams).
+    DatagramDropped,
+
+    /// The network path of the connection changed, see
+    /// [`Connection::path_changed`](crate::Connection::path_changed).
+    PathMigrated {
+        /// The (possibly new) peer's address.
+        remote_address: SocketAddr,
+    },
+
+    /// The closing of a session has begun.
+    CloseInitiated {
+        /// The session being closed.
+        session_id: SessionId,
+
+        /// Whether the session is closed by this endpoint (as requested by
+        /// the application, or because of a protocol violation of the peer).
+        locally: bool,
+    },
+
+    /// Some events have been missed, because the [`Events`] stream was not
+    /// polled fast enough.
+    Lagged {
+        /// The number of missed events.
+        missed: u64,
+    },
+}
+
+/// Sends the events of a connection to its subscribers.
+#[derive(Clone)]
+pub(crate) struct EventSender(broadcast::Sender<ConnectionEvent>);
+
+impl EventSender {
+    /// Events buffered for each subscriber, before it lags behind.
+    const CAPACITY: usize = 256;
+
+    pub(crate) fn new() -> Self {
+        Self(broadcast::channel(Self::CAPACITY).0)
+    }
+
+    /// Sends the event built by `event`, if anyone is subscribed.
+    pub(crate) fn emit<F>(&self, event: F)
+    where
+        F: FnOnce() -> ConnectionEvent,
+    {
+        if self.0.receiver_count() > 0 {
+            let _ = self.0.send(event());
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> Events {
+        Events::new(self.0.subscribe())
+    }
+}
+
+type Receive = Pin<
+    Box<
+        dyn Future<
+                Output = (
+                    broadcast::Receiver<ConnectionEvent>,
+                    Result<ConnectionEvent, broadcast::error::RecvError>,
+                ),
+            > + Send,
+    >,
+>;
+
+/// [`Stream`] of the events of a connection.
+///
+/// Created by [`Connection::events`](crate::Connection::events).
+/// It ends once the connection (along with its sessions and streams) is dropped.
+pub struct Events {
+    receive: Option<Receive>,
+}
+
+impl Events {
+    fn new(receiver: broadcast::Receiver<ConnectionEvent>) -> Self {
+        Self {
+            receive: Some(Self::receive(receiver)),
+        }
+    }
+
+    fn receive(mut receiver: broadcast::Receiver<ConnectionEvent>) -> Receive {
+        Box::pin(async move {
+            let result = receiver.recv().await;
+            (receiver, result)
+        })
+    }
+}
+
+impl Stream for Events {
+    type Item = ConnectionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Some(receive) = self.receive.as_mut() else {
+            return Poll::Ready(None);
+        };
+
+        let (receiver, result) = std::task::ready!(receive.as_mut().poll(cx));
+
+        match result {
+            Ok(event) => {
+                self.receive = Some(Self::receive(receiver));
+                Poll::Ready(Some(event))
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                self.receive = Some(Self::receive(receiver));
+                Poll::Ready(Some(ConnectionEvent::Lagged { missed }))
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                self.receive = None;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+/// [`Metrics`] of a connection, also emitting [`ConnectionEvent::DatagramDropped`].
+pub(crate) struct EventMetrics {
+    inner: Arc<dyn Metrics>,
+    events: EventSender,
+}
+
+impl EventMetrics {
+    pub(crate) fn new(inner: Arc<dyn Metrics>, events: EventSender) -> Self {
+        Self { inner, events }
+    }
+}
+
+impl Metrics for EventMetrics {
+    fn handshake_completed(&self) {
+        self.inner.handshake_completed();
+    }
+
+    fn handshake_failed(&self, cause: HandshakeFailure) {
+        self.inner.handshake_failed(cause);
+    }
+
+    fn session_opened(&self) {
+        self.inner.session_opened();
+    }
+
+    fn session_closed(&self) {
+        self.inner.session_closed();
+    }
+
+    fn connection_closed(&self, bytes_sent: u64, bytes_received: u64) {
+        self.inner.connection_closed(bytes_sent, bytes_received);
+    }
+
+    fn stream_opened(&self) {
+        self.inner.stream_opened();
+    }
+
+    fn datagram_sent(&self) {
+        self.inner.datagram_sent();
+    }
+
+    fn datagram_dropped(&self) {
+        self.inner.datagram_dropped();
+        self.events.emit(|| ConnectionEvent::DatagramDropped);
+    }
+}
+
+/// Emits [`ConnectionEvent::StreamClosed`] once dropped, that is once both
+/// halves of a stream are dropped.
+pub(crate) struct StreamClosedNotice {
+    stream_id: StreamId,
+    events: EventSender,
+}
+
+impl StreamClosedNotice {
+    pub(crate) fn new(stream_id: StreamId, events: EventSender) -> Arc<Self> {
+        Arc::new(Self { stream_id, events })
+    }
+}
+
+impl Drop for StreamClosedNotice {
+    fn drop(&mut self) {
+        let stream_id = self.stream_id;
+        self.events
+            .emit(|| ConnectionEvent::StreamClosed { stream_id });
+    }
+}
@@ -0,0 +1,61 @@
+use crate::connection::Connection;
+use crate::error::ConnectionError;
+use tokio::sync::mpsc;
+use tokio::sync::Mutex;
+use wtransport_proto::varint::VarInt;
+
+/// Sends and receives HTTP3 extension frames of a given type on the control streams.
+///
+/// It can be obtained with [`Connection::register_extension_frame_type`].
+pub struct ExtensionFrameChannel {
+    frame_type: VarInt,
+    connection: Connection,
+    queue: Mutex<mpsc::UnboundedReceiver<Vec<u8>>>,
+}
+
+impl ExtensionFrameChannel {
+    pub(crate) fn new(
+        frame_type: VarInt,
+        connection: Connection,
+        queue: mpsc::UnboundedReceiver<Vec<u8>>,
+    ) -> Self {
+        Self {
+            frame_type,
+            connection,
+            queue: Mutex::new(queue),
+        }
+    }
+
+    /// Sends a frame carrying `payload` on the local control stream.
+    pub async fn send(&self, payload: &[u8]) -> Result<(), ConnectionError> {
+        if self
+            .connection
+            .send_extension_frame(self.frame_type, payload.to_vec())
+        {
+            Ok(())
+        } else {
+            Err(self.connection.closed().await)
+        }
+    }
+
+    /// Receives the payload of the next frame.
+    pub async fn receive(&self) -> Result<Vec<u8>, ConnectionError> {
+        let mut queue = self.queue.lock().await;
+
+        let payload = tokio::select! {
+            payload = queue.recv() => payload,
+            error = self.connection.closed() => return Err(error),
+        };
+
+        match payload {
+            Some(payload) => Ok(payload),
+            None => Err(self.connection.closed().await),
+        }
+    }
+
+    /// Returns the frame type of this channel.
+    #[inline(always)]
+    pub fn frame_type(&self) -> VarInt {
+        self.frame_type
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// A point where an artificial failure can be injected.
+///
+/// Failures are injected with [`FailPoint::arm`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FailPoint {
+    /// The connection establishment (both client and server side).
+    ///
+    /// The QUIC connection is closed right after the handshake, and the
+    /// [`Connecting`](crate::connection::Connecting) future fails with
+    /// [`ConnectionError::QuicError`](crate::error::ConnectionError::QuicError).
+    Handshake,
+
+    /// The parsing of the peer's SETTINGS.
+    ///
+    /// The connection is closed with an `H3_SETTINGS_ERROR`.
+    SettingsParse,
+
+    /// The acceptance of an incoming stream (either bi-directional or uni-directional).
+    ///
+    /// The connection is closed with an `H3_STREAM_CREATION_ERROR`.
+    StreamAccept,
+
+    /// The sending of a datagram.
+    ///
+    /// The send operation fails with
+    /// [`DatagramError::Protocol`](crate::error::DatagramError::Protocol).
+    /// The connection is not affected.
+    DatagramSend,
+}
+
+impl FailPoint {
+    const ALL: [FailPoint; 4] = [
+        FailPoint::Handshake,
+        FailPoint::SettingsParse,
+        FailPoint::StreamAccept,
+        FailPoint::DatagramSend,
+    ];
+
+    /// Injects a failure for the next `times` times the point is reached.
+    ///
+    /// Use [`u64::MAX`] for failing until [`FailPoint::disarm`].
+    ///
+    /// **Note**: fail points are global to the process (they affect all the
+    /// endpoints and connections). Tests using them should not run concurrently.
+    pub fn arm(self, times: u64) {
+        self.counter().store(times, Ordering::Relaxed);
+    }
+
+    /// Stops injecting failures.
+    pub fn disarm(self) {
+        self.arm(0);
+    }
+
+    /// Stops injecting failures at every point.
+    pub fn disarm_all() {
+        for point in Self::ALL {
+            point.disarm();
+        }
+    }
+
+    /// Reports whether a failure must be injected now (consuming one of the armed times).
+    pub(crate) fn triggered(self) -> bool {
+        self.counter()
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |times| match times {
+                0 => None,
+                u64::MAX => Some(u64::MAX),
+                times => Some(times - 1),
+            })
+            .is_ok()
+    }
+
+    fn counter(self) -> &'static AtomicU64 {
+        static COUNTERS: [AtomicU64; FailPoint::ALL.len()] = [
+            AtomicU64::new(0),
+            AtomicU64::new(0),
+            AtomicU64::new(0),
+            AtomicU64::new(0),
+        ];
+
+        &COUNTERS[self as usize]
+    }
+}
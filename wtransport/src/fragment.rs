@@ -0,0 +1,451 @@
+use crate::error::DatagramError;
+use crate::error::FragmentError;
+use crate::Connection;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+use wtransport_proto::bytes::BytesReader;
+use wtransport_proto::varint::VarInt;
+
+/// Datagrams carrying messages larger than the path MTU.
+///
+/// Each message is split in fragments sent as separate datagrams, and
+/// reassembled by the receiver. Fragments are unreliable: a message is
+/// delivered only if all its fragments arrive (in any order) within the
+/// reassembly timeout. Incomplete messages are discarded.
+///
+/// Every fragment starts with a header of three *varints*: the message
+/// sequence number, the fragment index and the number of fragments of the
+/// message (at most [`Self::MAX_FRAGMENTS`]).
+///
+/// Both peers must use this layer: datagrams sent with
+/// [`Connection::send_datagram`] are not compatible with it.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// use wtransport::fragment::FragmentedDatagrams;
+///
+/// # async fn run(connection: Connection) {
+/// let mut datagrams = FragmentedDatagrams::new(connection);
+///
+/// datagrams.send(&[0; 10_000]).unwrap();
+/// let message = datagrams.receive().await.unwrap();
+/// # }
+/// ```
+pub struct FragmentedDatagrams {
+    connection: Connection,
+    next_sequence: u64,
+    reassembler: Reassembler,
+}
+
+impl FragmentedDatagrams {
+    /// Maximum number of fragments of a message.
+    pub const MAX_FRAGMENTS: usize = 16383;
+
+    /// Default time to wait for the missing fragments of a message.
+    pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Default maximum number of bytes buffered for incomplete messages.
+    pub const DEFAULT_MAX_BUFFERED: usize = 4 * 1024 * 1024;
+
+    /// Creates the layer on `connection`, with the default reassembly
+    /// timeout and memory cap.
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            connection,
+            next_sequence: 0,
+            reassembler: Reassembler {
+                partials: HashMap::new(),
+                buffered: 0,
+                timeout: Self::DEFAULT_REASSEMBLY_TIMEOUT,
+                max_buffered: Self::DEFAULT_MAX_BUFFERED,
+            },
+        }
+    }
+
+    /// Sets how long to wait for the missing fragments of a message.
+    pub fn with_reassembly_timeout(mut self, timeout: Duration) -> Self {
+        self.reassembler.timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum number of bytes buffered for incomplete messages.
+    ///
+    /// When the cap is reached, the oldest incomplete messages are discarded.
+    pub fn with_max_buffered(mut self, max_buffered: usize) -> Self {
+        self.reassembler.max_buffered = max_buffered;
+        self
+    }
+
+    /// Returns the underlying connection.
+    #[inline(always)]
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Sends a message, fragmenting it if it does not fit in a datagram.
+    ///
+    /// It fails with [`FragmentError::TooLarge`] if the message needs more
+    /// than [`Self::MAX_FRAGMENTS`] fragments.
+    pub fn send(&mut self, message: &[u8]) -> Result<(), FragmentError> {
+        let max_datagram_size = self
+            .connection
+            .max_datagram_size()
+            .ok_or(FragmentError::Datagram(DatagramError::UnsupportedByPeer))?;
+
+        let datagrams = Self::split(self.next_sequence, message, max_datagram_size)?;
+        self.next_sequence += 1;
+
+        for datagram in datagrams {
+            self.connection
+                .send_datagram(datagram)
+                .map_err(FragmentError::Datagram)?;
+        }
+
+        Ok(())
+    }
+
+    /// Splits `message` in the datagrams of its fragments.
+    fn split(
+        sequence: u64,
+        message: &[u8],
+        max_datagram_size: usize,
+    ) -> Result<Vec<Vec<u8>>, FragmentError> {
+        let sequence =
+            VarInt::try_from_u64(sequence).expect("Sequence numbers cannot be exhausted");
+
+        // Fragment index and count take 2 bytes at most
+        let header_size = sequence.size() + 2 * 2;
+        let fragment_size = max_datagram_size
+            .checked_sub(header_size)
+            .filter(|&size| size > 0)
+            .ok_or(FragmentError::Datagram(DatagramError::TooLarge))?;
+
+        // An empty message is sent as a single empty fragment
+        let fragments = message.chunks(fragment_size);
+        let count = fragments.len().max(1);
+        if count > Self::MAX_FRAGMENTS {
+            return Err(FragmentError::TooLarge);
+        }
+
+        let count = VarInt::from_u32(count as u32);
+
+        let fragments = fragments.chain(message.is_empty().then_some(&[][..]));
+
+        Ok(fragments
+            .enumerate()
+            .map(|(index, fragment)| {
+                let index = VarInt::from_u32(index as u32);
+
+                let mut datagram = Vec::with_capacity(header_size + fragment.len());
+                for varint in [sequence, index, count] {
+                    let (bytes, len) = varint.encode_to_array();
+                    datagram.extend_from_slice(&bytes[..len]);
+                }
+                datagram.extend_from_slice(fragment);
+
+                datagram
+            })
+            .collect())
+    }
+
+    /// Receives the next complete message.
+    ///
+    /// Malformed datagrams are discarded.
+    pub async fn receive(&mut self) -> Result<Bytes, DatagramError> {
+        loop {
+            let datagram = self.connection.receive_datagram().await?;
+
+            if let Some(message) = self.reassembler.push(datagram.payload(), Instant::now()) {
+                return Ok(message);
+            }
+        }
+    }
+}
+
+/// Receive side: collects fragments until their message is complete.
+struct Reassembler {
+    partials: HashMap<u64, Partial>,
+    buffered: usize,
+    timeout: Duration,
+    max_buffered: usize,
+}
+
+struct Partial {
+    fragments: Vec<Option<Bytes>>,
+    missing: usize,
+    size: usize,
+    started: Instant,
+}
+
+impl Reassembler {
+    /// Processes a fragment, returning its message if it is complete.
+    fn push(&mut self, datagram: Bytes, now: Instant) -> Option<Bytes> {
+        let mut header = &datagram[..];
+        let sequence = header.get_varint()?.into_inner();
+        let index = usize::try_from(header.get_varint()?.into_inner()).ok()?;
+        let count = usize::try_from(header.get_varint()?.into_inner()).ok()?;
+
+        if count == 0 || count > FragmentedDatagrams::MAX_FRAGMENTS || index >= count {
+            return None;
+        }
+
+        let fragment = datagram.slice(datagram.len() - header.len()..);
+
+        if count == 1 {
+            return Some(fragment);
+        }
+
+        self.expire(now);
+
+        let partial = self.partials.entry(sequence).or_insert_with(|| Partial {
+            fragments: vec![None; count],
+            missing: count,
+            size: 0,
+            started: now,
+        });
+
+        if partial.fragments.len() != count || partial.fragments[index].is_some() {
+            return None;
+        }
+
+        partial.size += fragment.len();
+        partial.missing -= 1;
+        self.buffered += fragment.len();
+        partial.fragments[index] = Some(fragment);
+
+        if partial.missing == 0 {
+            let partial = self
+                .partials
+                .remove(&sequence)
+                .expect("Partial just updated");
+            self.buffered -= partial.size;
+
+            let mut message = Vec::with_capacity(partial.size);
+            for fragment in partial.fragments.into_iter().flatten() {
+                message.extend_from_slice(&fragment);
+            }
+
+            return Some(Bytes::from(message));
+        }
+
+        self.enforce_cap(sequence);
+
+        None
+    }
+
+    /// Discards the messages not completed within the timeout.
+    fn expire(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        let mut expired = 0;
+
+        self.partials.retain(|_, partial| {
+            let alive = now.duration_since(partial.started) < timeout;
+            if !alive {
+                expired += partial.size;
+            }
+            alive
+        });
+
+        self.buffered -= expired;
+    }
+
+    /// Discards the oldest messages until the buffered bytes are within the cap.
+    ///
+    /// The message `current` is discarded last.
+    fn enforce_cap(&mut self, current: u64) {
+        while self.buffered > self.max_buffered {
+            let oldest = self
+                .partials
+                .iter()
+                .filter(|(sequence, _)| **sequence != current)
+                .min_by_key(|(_, partial)| partial.started)
+                .map(|(sequence, _)| *sequence)
+                .unwrap_or(current);
+
+            if let Some(partial) = self.partials.remove(&oldest) {
+                self.buffered -= partial.size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reassembler(timeout: Duration, max_buffered: usize) -> Reassembler {
+        Reassembler {
+            partials: HashMap::new(),
+            buffered: 0,
+            timeout,
+            max_buffered,
+        }
+    }
+
+    fn message(len: usize) -> Vec<u8> {
+        (0..len).map(|index| index as u8).collect()
+    }
+
+    fn split(sequence: u64, message: &[u8], max_datagram_size: usize) -> Vec<Bytes> {
+        FragmentedDatagrams::split(sequence, message, max_datagram_size)
+            .unwrap()
+            .into_iter()
+            .map(Bytes::from)
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let now = Instant::now();
+        let mut reassembler = reassembler(Duration::from_secs(1), usize::MAX);
+
+        // Fragments of 95 bytes (after a header of 5 bytes at most)
+        for len in [0, 1, 95, 96, 1000] {
+            let message = message(len);
+            let datagrams = split(0, &message, 100);
+
+            assert_eq!(datagrams.len(), (len.max(1) + 94) / 95);
+            assert!(datagrams.iter().all(|datagram| datagram.len() <= 100));
+
+            let (last, others) = datagrams.split_last().unwrap();
+            for datagram in others {
+                assert!(reassembler.push(datagram.clone(), now).is_none());
+            }
+            assert_eq!(reassembler.push(last.clone(), now).unwrap(), message);
+            assert!(reassembler.partials.is_empty());
+            assert_eq!(reassembler.buffered, 0);
+        }
+    }
+
+    #[test]
+    fn out_of_order() {
+        let now = Instant::now();
+        let mut reassembler = reassembler(Duration::from_secs(1), usize::MAX);
+
+        let first = message(500);
+        let second = message(300);
+        let second_datagrams = split(1, &second, 100);
+
+        // Messages interleaved, the first in reverse order
+        let mut datagrams = Vec::new();
+        for (index, datagram) in split(0, &first, 100).into_iter().rev().enumerate() {
+            datagrams.push(datagram);
+            datagrams.extend(second_datagrams.get(index).cloned());
+        }
+
+        let messages = datagrams
+            .into_iter()
+            .filter_map(|datagram| reassembler.push(datagram, now))
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages, [Bytes::from(second), Bytes::from(first)]);
+        assert_eq!(reassembler.buffered, 0);
+    }
+
+    #[test]
+    fn duplicates() {
+        let now = Instant::now();
+        let mut reassembler = reassembler(Duration::from_secs(1), usize::MAX);
+
+        let message = message(250);
+        let datagrams = split(0, &message, 100);
+        assert_eq!(datagrams.len(), 3);
+
+        assert!(reassembler.push(datagrams[1].clone(), now).is_none());
+        let buffered = reassembler.buffered;
+        assert!(reassembler.push(datagrams[1].clone(), now).is_none());
+        assert_eq!(reassembler.buffered, buffered);
+
+        assert!(reassembler.push(datagrams[0].clone(), now).is_none());
+        assert_eq!(
+            reassembler.push(datagrams[2].clone(), now).unwrap(),
+            message
+        );
+
+        // A late duplicate starts a new (incomplete) message
+        assert!(reassembler.push(datagrams[0].clone(), now).is_none());
+        assert_eq!(reassembler.partials.len(), 1);
+    }
+
+    #[test]
+    fn expiry() {
+        let now = Instant::now();
+        let timeout = Duration::from_secs(1);
+        let mut reassembler = reassembler(timeout, usize::MAX);
+
+        let first = split(0, &message(250), 100);
+        let second = split(1, &message(250), 100);
+
+        assert!(reassembler.push(first[0].clone(), now).is_none());
+        assert!(reassembler.push(first[1].clone(), now).is_none());
+        assert!(reassembler
+            .push(second[0].clone(), now + timeout / 2)
+            .is_none());
+        assert_eq!(reassembler.partials.len(), 2);
+
+        // The first message expires, its fragment starts it over
+        assert!(reassembler.push(first[2].clone(), now + timeout).is_none());
+        assert_eq!(reassembler.partials.len(), 2);
+        assert_eq!(
+            reassembler.buffered,
+            first[2].len() + second[0].len() - 2 * 3
+        );
+
+        assert!(reassembler.push(second[1].clone(), now + timeout).is_none());
+        assert!(reassembler.push(second[2].clone(), now + timeout).is_some());
+    }
+
+    #[test]
+    fn max_buffered() {
+        let now = Instant::now();
+        let mut reassembler = reassembler(Duration::from_secs(1), 200);
+
+        let first = split(0, &message(250), 100);
+        let second = split(1, &message(250), 100);
+
+        assert!(reassembler.push(first[0].clone(), now).is_none());
+        assert!(reassembler.push(second[0].clone(), now).is_none());
+
+        // Over the cap: the other message is discarded
+        assert!(reassembler.push(second[1].clone(), now).is_none());
+        assert!(!reassembler.partials.contains_key(&0));
+        assert_eq!(reassembler.buffered, 190);
+
+        assert!(reassembler.push(second[2].clone(), now).is_some());
+    }
+
+    #[test]
+    fn malformed() {
+        let now = Instant::now();
+        let mut reassembler = reassembler(Duration::from_secs(1), usize::MAX);
+
+        // Sequence 0, index 2 of 2
+        assert!(reassembler
+            .push(Bytes::from(vec![0, 2, 2, 0]), now)
+            .is_none());
+        // Sequence 0, index 0 of 0
+        assert!(reassembler.push(Bytes::from(vec![0, 0, 0]), now).is_none());
+        // Truncated header
+        assert!(reassembler.push(Bytes::from(vec![0, 0]), now).is_none());
+
+        assert!(reassembler.partials.is_empty());
+    }
+
+    #[test]
+    fn too_large() {
+        assert!(matches!(
+            FragmentedDatagrams::split(0, &[0; 100], 5),
+            Err(FragmentError::Datagram(DatagramError::TooLarge))
+        ));
+
+        let message = vec![0; FragmentedDatagrams::MAX_FRAGMENTS * 5 + 1];
+        assert!(matches!(
+            FragmentedDatagrams::split(0, &message, 10),
+            Err(FragmentError::TooLarge)
+        ));
+        assert!(FragmentedDatagrams::split(0, &message[1..], 10).is_ok());
+    }
+}
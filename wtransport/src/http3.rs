@@ -0,0 +1,242 @@
+use crate::engine::stream::BiRemote;
+use crate::engine::stream::FrameReadError;
+use crate::engine::stream::FrameWriteError;
+use crate::engine::stream::Stream;
+use crate::engine::stream::H3;
+use crate::error::StreamError;
+use std::borrow::Cow;
+use wtransport_proto::error::ErrorCode;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::headers::Headers;
+use wtransport_proto::varint::VarInt;
+
+/// A plain HTTP3 request (i.e., not a WebTransport session request)
+/// received by a server endpoint.
+///
+/// Requests are handed to the handler installed with
+/// [`ServerConfig::set_request_handler`]. A request dropped without a
+/// response is rejected (its stream is reset with `H3_REQUEST_REJECTED`).
+///
+/// The request body (if any) is read incrementally with [`Self::read_body`],
+/// and the response body can be streamed with [`Self::respond_streaming`].
+///
+/// [`ServerConfig::set_request_handler`]: crate::ServerConfig::set_request_handler
+pub struct Request {
+    stream: Option<Stream<BiRemote, H3>>,
+    headers: Headers,
+    data_remaining: u64,
+}
+
+impl Request {
+    /// Type of the DATA frames carrying the body (RFC 9114, section 7.2.1).
+    const DATA_FRAME_TYPE: VarInt = VarInt::from_u32(0x00);
+
+    pub(crate) fn new(stream: Stream<BiRemote, H3>, headers: Headers) -> Self {
+        Self {
+            stream: Some(stream),
+            headers,
+            data_remaining: 0,
+        }
+    }
+
+    /// Returns the headers of the request.
+    #[inline(always)]
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// Returns the `:method` pseudo-header (e.g., `"GET"`).
+    #[inline(always)]
+    pub fn method(&self) -> Option<&str> {
+        self.headers.get(":method")
+    }
+
+    /// Returns the `:path` pseudo-header (e.g., `"/health"`).
+    #[inline(always)]
+    pub fn path(&self) -> Option<&str> {
+        self.headers.get(":path")
+    }
+
+    /// Reads the next chunk of the request body into `buf`, returning the
+    /// number of bytes read.
+    ///
+    /// The body is read as it arrives, without being buffered: a chunk is at
+    /// most the remaining part of the current DATA frame. It returns [`None`]
+    /// when the body is complete. Frames other than DATA (e.g., trailers) are
+    /// skipped. A body truncated in the middle of a frame is reported as
+    /// [`StreamError::Stopped`].
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is **not** cancel-safe: if the future is dropped before
+    /// completing, the rest of the body cannot be read.
+    pub async fn read_body(&mut self, buf: &mut [u8]) -> Result<Option<usize>, StreamError> {
+        let stream = self.stream.as_mut().expect("Request not answered yet");
+
+        if buf.is_empty() {
+            return Ok(Some(0));
+        }
+
+        while self.data_remaining == 0 {
+            let header = stream.read_frame_header().await.map_err(Self::read_error)?;
+
+            let Some((frame_type, payload_len)) = header else {
+                return Ok(None);
+            };
+
+            if frame_type == Self::DATA_FRAME_TYPE {
+                self.data_remaining = payload_len;
+            } else {
+                Self::skip_payload(stream, payload_len).await?;
+            }
+        }
+
+        let max_len = usize::try_from(self.data_remaining).unwrap_or(usize::MAX);
+        let max_len = buf.len().min(max_len);
+
+        match stream.read_payload(&mut buf[..max_len]).await? {
+            Some(read) => {
+                self.data_remaining -= read as u64;
+                Ok(Some(read))
+            }
+            None => Err(StreamError::Stopped),
+        }
+    }
+
+    /// Sends the response and finishes the stream.
+    ///
+    /// `headers` are sent along with the `:status` pseudo-header; `body` is
+    /// sent in a single DATA frame (no frame if it is empty).
+    pub async fn respond(
+        self,
+        status: u16,
+        headers: &Headers,
+        body: &[u8],
+    ) -> Result<(), StreamError> {
+        let mut response_body = self.respond_streaming(status, headers).await?;
+        response_body.write(body).await?;
+        response_body.finish().await
+    }
+
+    /// Sends the response headers, returning the [`ResponseBody`] which
+    /// streams the response body.
+    ///
+    /// `headers` are sent along with the `:status` pseudo-header. The rest
+    /// of the request body (if any) is discarded: read it beforehand with
+    /// [`Self::read_body`] if needed.
+    pub async fn respond_streaming(
+        mut self,
+        status: u16,
+        headers: &Headers,
+    ) -> Result<ResponseBody, StreamError> {
+        let mut stream = self.stream.take().expect("Request not answered yet");
+
+        let response_headers = std::iter::once((":status", status.to_string()))
+            .chain(
+                headers
+                    .iter()
+                    .filter(|(name, _)| !name.starts_with(':'))
+                    .map(|(name, value)| (name, value.to_string())),
+            )
+            .collect::<Headers>();
+
+        stream
+            .write_frame(response_headers.generate_frame(stream.id()))
+            .await
+            .map_err(write_error)?;
+
+        Ok(ResponseBody {
+            stream: Some(stream),
+        })
+    }
+
+    /// Discards the payload of a frame which is not part of the body.
+    async fn skip_payload(
+        stream: &mut Stream<BiRemote, H3>,
+        mut payload_len: u64,
+    ) -> Result<(), StreamError> {
+        let mut buffer = [0; 256];
+
+        while payload_len > 0 {
+            let chunk_len = payload_len.min(buffer.len() as u64) as usize;
+
+            match stream.read_payload(&mut buffer[..chunk_len]).await? {
+                Some(read) => payload_len -= read as u64,
+                None => return Err(StreamError::Stopped),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_error(frame_read_error: FrameReadError) -> StreamError {
+        match frame_read_error {
+            FrameReadError::ConnectionClosed => StreamError::ConnectionClosed,
+            FrameReadError::UnknownFrame
+            | FrameReadError::InvalidSessionId
+            | FrameReadError::ExcessiveLoad
+            | FrameReadError::EndOfStream => StreamError::Stopped,
+        }
+    }
+}
+
+impl Drop for Request {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            stream.abort(ErrorCode::RequestRejected.to_code());
+        }
+    }
+}
+
+/// The body of a response sent with [`Request::respond_streaming`].
+///
+/// Each [`Self::write`] sends a DATA frame, and [`Self::finish`] completes
+/// the response. A body dropped before being finished resets the stream
+/// (with `H3_INTERNAL_ERROR`), so that the client does not mistake a
+/// truncated body for a complete one.
+pub struct ResponseBody {
+    stream: Option<Stream<BiRemote, H3>>,
+}
+
+impl ResponseBody {
+    /// Sends a chunk of the body in a DATA frame (no frame if `data` is empty).
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), StreamError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.stream
+            .as_mut()
+            .expect("Response not finished yet")
+            .write_frame(Frame::new_data(Cow::Borrowed(data)))
+            .await
+            .map_err(write_error)
+    }
+
+    /// Finishes the response body (and the stream).
+    pub async fn finish(mut self) -> Result<(), StreamError> {
+        let mut stream = self.stream.take().expect("Response not finished yet");
+
+        stream.finish().await?;
+
+        // The request body is not needed anymore
+        stream.stop(ErrorCode::NoError.to_code());
+
+        Ok(())
+    }
+}
+
+impl Drop for ResponseBody {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            stream.abort(ErrorCode::InternalError.to_code());
+        }
+    }
+}
+
+fn write_error(frame_write_error: FrameWriteError) -> StreamError {
+    match frame_write_error {
+        FrameWriteError::EndOfStream => StreamError::Stopped,
+        FrameWriteError::ConnectionClosed => StreamError::ConnectionClosed,
+    }
+}
@@ -21,6 +21,23 @@
 //! ```
 #![warn(missing_docs)]
 
+/// Evaluates to `true` if a failure must be injected at the given `FailPoint`.
+///
+/// It is always `false` without the `failpoints` feature.
+#[cfg(feature = "failpoints")]
+macro_rules! fail_point {
+    ($point:ident) => {
+        crate::failpoints::FailPoint::$point.triggered()
+    };
+}
+
+#[cfg(not(feature = "failpoints"))]
+macro_rules! fail_point {
+    ($point:ident) => {
+        false
+    };
+}
+
 /// Client and server configurations.
 pub mod config;
 
@@ -39,6 +56,64 @@ pub mod tls;
 /// Datagrams module.
 pub mod datagram;
 
+/// Connection tracing in qlog format.
+pub mod qlog;
+
+/// Application-defined capsules.
+pub mod capsule;
+
+/// Duplicate-session detection.
+pub mod registry;
+
+/// HTTP3 extension frames on the control streams.
+pub mod extension;
+
+/// Length-prefixed messages over streams.
+pub mod codec;
+
+/// Messages larger than the MTU over datagrams.
+pub mod fragment;
+
+/// Datagrams with sequence numbers, loss detection and retransmission.
+pub mod sequenced;
+
+/// Client sessions re-established automatically.
+pub mod reconnect;
+
+/// Counters of connection events, for monitoring.
+pub mod metrics;
+
+/// Connection through HTTP proxies (CONNECT-UDP).
+pub mod proxy;
+
+/// Plain HTTP3 requests served alongside WebTransport.
+pub mod http3;
+
+/// Hooks deciding whether session requests are accepted.
+pub mod accept;
+
+/// Dispatch of sessions to handlers by request path.
+pub mod router;
+
+/// Structured events of connections.
+pub mod events;
+
+/// Async runtimes the endpoints run on.
+pub mod runtime;
+
+/// Protocol types used in the API (re-exported from `wtransport-proto`).
+pub mod proto;
+
+/// In-process network, for testing without UDP sockets.
+#[cfg(feature = "mock-network")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mock-network")))]
+pub mod mock;
+
+/// Error injection, for testing application error handling.
+#[cfg(feature = "failpoints")]
+#[cfg_attr(docsrs, doc(cfg(feature = "failpoints")))]
+pub mod failpoints;
+
 #[doc(inline)]
 pub use config::ClientConfig;
 
@@ -57,5 +132,12 @@ pub use stream::RecvStream;
 #[doc(inline)]
 pub use stream::SendStream;
 
+/// Re-export of the [`rustls`] version in use (e.g., for [`ServerConfigBuilder::with_custom_tls`]).
+///
+/// [`ServerConfigBuilder::with_custom_tls`]: config::ServerConfigBuilder::with_custom_tls
+pub use rustls;
+
 mod endpoint;
 mod engine;
+mod marking;
+mod trace;
@@ -0,0 +1,261 @@
+use crate::connection::EcnCounts;
+use quinn::AsyncTimer;
+use quinn::AsyncUdpSocket;
+use quinn::RecvMeta;
+use quinn::Runtime;
+use quinn::Transmit;
+use quinn::UdpState;
+use socket2::SockRef;
+use std::collections::HashMap;
+use std::io::IoSliceMut;
+use std::net::SocketAddr;
+use std::net::UdpSocket;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Marking of the IP header of outgoing packets.
+///
+/// See [`ServerConfig::set_dscp`](crate::ServerConfig::set_dscp) and
+/// [`ServerConfig::set_ecn`](crate::ServerConfig::set_ecn).
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct PacketMarking {
+    pub(crate) dscp: Option<u8>,
+    pub(crate) ecn: bool,
+}
+
+impl PacketMarking {
+    /// Whether packets are marked as QUIC would do by itself.
+    fn is_default(&self) -> bool {
+        self.dscp.is_none() && self.ecn
+    }
+}
+
+impl Default for PacketMarking {
+    fn default() -> Self {
+        Self {
+            dscp: None,
+            ecn: true,
+        }
+    }
+}
+
+/// ECN codepoints of the packets received by an endpoint, per peer address.
+#[derive(Default)]
+pub(crate) struct EcnCounters(Mutex<HashMap<SocketAddr, EcnCounts>>);
+
+impl EcnCounters {
+    /// The codepoints received from `address`.
+    pub(crate) fn get(&self, address: SocketAddr) -> EcnCounts {
+        self.0
+            .lock()
+            .expect("Mutex poisoned")
+            .get(&address)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Forgets the peers whose connections are gone.
+    pub(crate) fn retain(&self, live: &[SocketAddr]) {
+        self.0
+            .lock()
+            .expect("Mutex poisoned")
+            .retain(|address, _| live.contains(address));
+    }
+
+    fn record(&self, meta: &[RecvMeta]) {
+        let mut counters = self.0.lock().expect("Mutex poisoned");
+
+        for meta in meta {
+            let Some(ecn) = meta.ecn else {
+                continue;
+            };
+
+            // Segmentation offload: a buffer may hold multiple packets
+            let packets = (meta.len / meta.stride.max(1)).max(1) as u64;
+            let counts = counters.entry(meta.addr).or_default();
+
+            match ecn as u8 {
+                0b10 => counts.ect0 += packets,
+                0b01 => counts.ect1 += packets,
+                _ => counts.ce += packets,
+            }
+        }
+    }
+}
+
+/// A UDP socket marking the IP header of outgoing packets, and counting the
+/// ECN codepoints of incoming ones.
+///
+/// Packets are received (with their codepoints) by the socket of the QUIC
+/// runtime. Unless the marking is the default one, they are sent by this
+/// socket instead: the runtime sets the traffic class of each packet to its
+/// ECN codepoint only, which would clear the DSCP.
+pub(crate) struct MarkingSocket {
+    inner: Box<dyn AsyncUdpSocket>,
+    sender: Option<MarkedSender>,
+    ecn_counters: Arc<EcnCounters>,
+}
+
+impl MarkingSocket {
+    pub(crate) fn new(
+        socket: UdpSocket,
+        marking: PacketMarking,
+        runtime: &Arc<dyn Runtime>,
+        ecn_counters: Arc<EcnCounters>,
+    ) -> std::io::Result<Self> {
+        let sender = if marking.is_default() {
+            None
+        } else {
+            let socket = socket.try_clone()?;
+            socket.set_nonblocking(true)?;
+
+            Some(MarkedSender {
+                socket,
+                marking,
+                traffic_class: Mutex::new(None),
+                retry: Mutex::new(None),
+                runtime: runtime.clone(),
+            })
+        };
+
+        Ok(Self {
+            inner: runtime.wrap_udp_socket(socket)?,
+            sender,
+            ecn_counters,
+        })
+    }
+}
+
+impl std::fmt::Debug for MarkingSocket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MarkingSocket")
+            .field(
+                "marking",
+                &self.sender.as_ref().map(|sender| sender.marking),
+            )
+            .finish()
+    }
+}
+
+impl AsyncUdpSocket for MarkingSocket {
+    fn poll_send(
+        &self,
+        state: &UdpState,
+        cx: &mut Context,
+        transmits: &[Transmit],
+    ) -> Poll<std::io::Result<usize>> {
+        match &self.sender {
+            Some(sender) => sender.poll_send(cx, transmits),
+            None => self.inner.poll_send(state, cx, transmits),
+        }
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        bufs: &mut [IoSliceMut<'_>],
+        meta: &mut [RecvMeta],
+    ) -> Poll<std::io::Result<usize>> {
+        let received = std::task::ready!(self.inner.poll_recv(cx, bufs, meta))?;
+        self.ecn_counters.record(&meta[..received]);
+        Poll::Ready(Ok(received))
+    }
+
+    fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn may_fragment(&self) -> bool {
+        self.inner.may_fragment()
+    }
+}
+
+/// Sends packets with the traffic class of the marking.
+struct MarkedSender {
+    socket: UdpSocket,
+    marking: PacketMarking,
+    traffic_class: Mutex<Option<u8>>,
+    retry: Mutex<Option<Pin<Box<dyn AsyncTimer>>>>,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl MarkedSender {
+    /// Interval between send attempts while the socket buffer is full.
+    ///
+    /// The runtime is not notified when the (cloned) socket becomes writable.
+    const RETRY_INTERVAL: Duration = Duration::from_millis(1);
+
+    fn poll_send(&self, cx: &mut Context, transmits: &[Transmit]) -> Poll<std::io::Result<usize>> {
+        for (sent, transmit) in transmits.iter().enumerate() {
+            let ecn = transmit
+                .ecn
+                .filter(|_| self.marking.ecn)
+                .map_or(0, |ecn| ecn as u8);
+            self.set_traffic_class(self.marking.dscp.unwrap_or(0) << 2 | ecn)?;
+
+            // Segmentation offload: contents are multiple datagrams of the same size
+            let segment_size = transmit.segment_size.unwrap_or(transmit.contents.len());
+
+            for segment in transmit.contents.chunks(segment_size.max(1)) {
+                match self.socket.send_to(segment, transmit.destination) {
+                    Ok(_) => {}
+                    Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                        if sent > 0 {
+                            return Poll::Ready(Ok(sent));
+                        }
+
+                        self.schedule_retry(cx);
+                        return Poll::Pending;
+                    }
+                    Err(error) => return Poll::Ready(Err(error)),
+                }
+            }
+        }
+
+        Poll::Ready(Ok(transmits.len()))
+    }
+
+    fn set_traffic_class(&self, traffic_class: u8) -> std::io::Result<()> {
+        let mut current = self.traffic_class.lock().expect("Mutex poisoned");
+        if *current == Some(traffic_class) {
+            return Ok(());
+        }
+
+        let socket = SockRef::from(&self.socket);
+
+        if self.socket.local_addr()?.is_ipv6() {
+            #[cfg(any(
+                target_os = "android",
+                target_os = "freebsd",
+                target_os = "ios",
+                target_os = "linux",
+                target_os = "macos",
+            ))]
+            socket.set_tclass_v6(traffic_class.into())?;
+
+            // Dual-stack sockets send to IPv4 peers with the IPv4 option
+            let _ = socket.set_tos(traffic_class.into());
+        } else {
+            socket.set_tos(traffic_class.into())?;
+        }
+
+        *current = Some(traffic_class);
+        Ok(())
+    }
+
+    fn schedule_retry(&self, cx: &mut Context) {
+        let deadline = Instant::now() + Self::RETRY_INTERVAL;
+        let mut retry = self.retry.lock().expect("Mutex poisoned");
+        let timer = retry.get_or_insert_with(|| self.runtime.new_timer(deadline));
+
+        timer.as_mut().reset(deadline);
+        if timer.as_mut().poll(cx).is_ready() {
+            cx.waker().wake_by_ref();
+        }
+    }
+}
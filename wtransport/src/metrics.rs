@@ -0,0 +1,344 @@
+use crate::error::ConnectionError;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+/// Receiver of the countable events of the connections of an endpoint.
+///
+/// It is installed with [`ServerConfig::set_metrics`] or
+/// [`ClientConfig::set_metrics`]. Methods are invoked from the tasks driving
+/// the connections: they must be cheap and must not block.
+///
+/// Every method does nothing by default. See [`Counters`] for a ready-to-use
+/// implementation.
+///
+/// [`ServerConfig::set_metrics`]: crate::ServerConfig::set_metrics
+/// [`ClientConfig::set_metrics`]: crate::ClientConfig::set_metrics
+pub trait Metrics: Send + Sync {
+    /// A QUIC handshake has completed.
+    fn handshake_completed(&self) {}
+
+    /// A connection attempt has failed (either in the QUIC handshake or while
+    /// establishing the session).
+    fn handshake_failed(&self, cause: HandshakeFailure) {
+        let _ = cause;
+    }
+
+    /// A session has been established.
+    fn session_opened(&self) {}
+
+    /// A session has been released (i.e., its [`Connection`] has been dropped).
+    ///
+    /// [`Connection`]: crate::Connection
+    fn session_closed(&self) {}
+
+    /// A QUIC connection has been released (i.e., all of its sessions have
+    /// been dropped).
+    ///
+    /// `bytes_sent` and `bytes_received` are the UDP bytes it transferred
+    /// over its lifetime.
+    fn connection_closed(&self, bytes_sent: u64, bytes_received: u64) {
+        let _ = (bytes_sent, bytes_received);
+    }
+
+    /// A stream has been opened or accepted.
+    fn stream_opened(&self) {}
+
+    /// A datagram has been sent (or queued for pacing).
+    fn datagram_sent(&self) {}
+
+    /// A datagram has been discarded, either incoming (its session buffer
+    /// was full) or outgoing (its pacing queue was full, or QUIC rejected it).
+    fn datagram_dropped(&self) {}
+}
+
+/// The cause of a failed connection attempt, derived from [`ConnectionError`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum HandshakeFailure {
+    /// The attempt timed out.
+    TimedOut,
+
+    /// The peer closed the connection.
+    ClosedByPeer,
+
+    /// The server refused the connection.
+    Refused,
+
+    /// The session request has been rejected.
+    SessionRejected,
+
+    /// A QUIC or HTTP3 protocol error occurred.
+    Protocol,
+
+    /// The connection was closed locally.
+    LocallyClosed,
+}
+
+impl HandshakeFailure {
+    /// All the causes.
+    pub const ALL: [HandshakeFailure; 6] = [
+        HandshakeFailure::TimedOut,
+        HandshakeFailure::ClosedByPeer,
+        HandshakeFailure::Refused,
+        HandshakeFailure::SessionRejected,
+        HandshakeFailure::Protocol,
+        HandshakeFailure::LocallyClosed,
+    ];
+
+    /// Returns the cause as a label (e.g., `"timed_out"`).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HandshakeFailure::TimedOut => "timed_out",
+            HandshakeFailure::ClosedByPeer => "closed_by_peer",
+            HandshakeFailure::Refused => "refused",
+            HandshakeFailure::SessionRejected => "session_rejected",
+            HandshakeFailure::Protocol => "protocol",
+            HandshakeFailure::LocallyClosed => "locally_closed",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl From<&ConnectionError> for HandshakeFailure {
+    fn from(error: &ConnectionError) -> Self {
+        match error {
+            ConnectionError::ConnectionClosed(_) => HandshakeFailure::ClosedByPeer,
+            ConnectionError::TimedOut => HandshakeFailure::TimedOut,
+            ConnectionError::LocallyClosed | ConnectionError::UnresolvedHost => {
+                HandshakeFailure::LocallyClosed
+            }
+            ConnectionError::H3(_) | ConnectionError::QuicError => HandshakeFailure::Protocol,
+            ConnectionError::SessionRejected(_) => HandshakeFailure::SessionRejected,
+            ConnectionError::ConnectionRefused => HandshakeFailure::Refused,
+        }
+    }
+}
+
+impl fmt::Display for HandshakeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`Metrics`] implementation with atomic counters.
+///
+/// It is meant to be shared between the endpoint and the code exporting the
+/// metrics (e.g., an HTTP handler serving [`Counters::encode_prometheus`]).
+///
+/// # Example
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use wtransport::ServerConfig;
+/// use wtransport::metrics::Counters;
+///
+/// # fn run(mut config: ServerConfig) {
+/// let counters = Arc::new(Counters::new());
+/// config.set_metrics(Some(counters.clone()));
+///
+/// // Later, when scraped
+/// let text = counters.encode_prometheus();
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Counters {
+    handshakes: AtomicU64,
+    handshake_failures: [AtomicU64; HandshakeFailure::ALL.len()],
+    sessions_opened: AtomicU64,
+    sessions_closed: AtomicU64,
+    streams_opened: AtomicU64,
+    datagrams_sent: AtomicU64,
+    datagrams_dropped: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl Counters {
+    /// Creates counters starting from zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of completed QUIC handshakes.
+    pub fn handshakes(&self) -> u64 {
+        self.handshakes.load(Ordering::Relaxed)
+    }
+
+    /// The number of failed connection attempts with `cause`.
+    pub fn handshake_failures(&self, cause: HandshakeFailure) -> u64 {
+        self.handshake_failures[cause.index()].load(Ordering::Relaxed)
+    }
+
+    /// The number of sessions established.
+    pub fn sessions_opened(&self) -> u64 {
+        self.sessions_opened.load(Ordering::Relaxed)
+    }
+
+    /// The number of sessions established and not released yet.
+    pub fn active_sessions(&self) -> u64 {
+        // Closed sessions are loaded first: they can never exceed the opened ones
+        let closed = self.sessions_closed.load(Ordering::Relaxed);
+        self.sessions_opened().saturating_sub(closed)
+    }
+
+    /// The number of streams opened or accepted.
+    pub fn streams_opened(&self) -> u64 {
+        self.streams_opened.load(Ordering::Relaxed)
+    }
+
+    /// The number of datagrams sent.
+    pub fn datagrams_sent(&self) -> u64 {
+        self.datagrams_sent.load(Ordering::Relaxed)
+    }
+
+    /// The number of datagrams discarded.
+    pub fn datagrams_dropped(&self) -> u64 {
+        self.datagrams_dropped.load(Ordering::Relaxed)
+    }
+
+    /// The UDP bytes sent by released connections.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// The UDP bytes received by released connections.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Encodes the counters in the Prometheus text exposition format.
+    ///
+    /// Metric names are prefixed with `wtransport_`.
+    pub fn encode_prometheus(&self) -> String {
+        let mut text = String::new();
+
+        Self::encode_metric(
+            &mut text,
+            "handshakes_total",
+            "counter",
+            "Completed QUIC handshakes.",
+            self.handshakes(),
+        );
+
+        Self::encode_header(
+            &mut text,
+            "handshake_failures_total",
+            "counter",
+            "Failed connection attempts, by cause.",
+        );
+        for cause in HandshakeFailure::ALL {
+            let _ = writeln!(
+                text,
+                "wtransport_handshake_failures_total{{cause=\"{cause}\"}} {}",
+                self.handshake_failures(cause)
+            );
+        }
+
+        Self::encode_metric(
+            &mut text,
+            "sessions_total",
+            "counter",
+            "Established sessions.",
+            self.sessions_opened(),
+        );
+        Self::encode_metric(
+            &mut text,
+            "active_sessions",
+            "gauge",
+            "Sessions established and not released yet.",
+            self.active_sessions(),
+        );
+        Self::encode_metric(
+            &mut text,
+            "streams_opened_total",
+            "counter",
+            "Streams opened or accepted.",
+            self.streams_opened(),
+        );
+        Self::encode_metric(
+            &mut text,
+            "datagrams_sent_total",
+            "counter",
+            "Datagrams sent.",
+            self.datagrams_sent(),
+        );
+        Self::encode_metric(
+            &mut text,
+            "datagrams_dropped_total",
+            "counter",
+            "Datagrams discarded.",
+            self.datagrams_dropped(),
+        );
+        Self::encode_metric(
+            &mut text,
+            "sent_bytes_total",
+            "counter",
+            "UDP bytes sent by released connections.",
+            self.bytes_sent(),
+        );
+        Self::encode_metric(
+            &mut text,
+            "received_bytes_total",
+            "counter",
+            "UDP bytes received by released connections.",
+            self.bytes_received(),
+        );
+
+        text
+    }
+
+    fn encode_metric(text: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+        Self::encode_header(text, name, kind, help);
+        let _ = writeln!(text, "wtransport_{name} {value}");
+    }
+
+    fn encode_header(text: &mut String, name: &str, kind: &str, help: &str) {
+        // Writing to a `String` cannot fail
+        let _ = writeln!(text, "# HELP wtransport_{name} {help}");
+        let _ = writeln!(text, "# TYPE wtransport_{name} {kind}");
+    }
+}
+
+impl Metrics for Counters {
+    fn handshake_completed(&self) {
+        self.handshakes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn handshake_failed(&self, cause: HandshakeFailure) {
+        self.handshake_failures[cause.index()].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn session_opened(&self) {
+        self.sessions_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn session_closed(&self) {
+        self.sessions_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn connection_closed(&self, bytes_sent: u64, bytes_received: u64) {
+        self.bytes_sent.fetch_add(bytes_sent, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes_received, Ordering::Relaxed);
+    }
+
+    fn stream_opened(&self) {
+        self.streams_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn datagram_sent(&self) {
+        self.datagrams_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn datagram_dropped(&self) {
+        self.datagrams_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The [`Metrics`] of endpoints without metrics: every event is ignored.
+pub(crate) struct NoMetrics;
+
+impl Metrics for NoMetrics {}
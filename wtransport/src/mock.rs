@@ -0,0 +1,381 @@
+use crate::runtime;
+use crate::runtime::Sleep;
+use quinn::udp::RecvMeta;
+use quinn::udp::UdpState;
+use quinn::AsyncUdpSocket;
+use quinn::Transmit;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io::IoSliceMut;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Characteristics of the links of a [`MockNetwork`].
+///
+/// They apply to every packet, in both directions.
+#[derive(Copy, Clone, Debug)]
+pub struct LinkConditions {
+    latency: Duration,
+    jitter: Duration,
+    loss: f64,
+    seed: u64,
+}
+
+impl LinkConditions {
+    /// A perfect link: no latency, no loss.
+    pub fn new() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            loss: 0.0,
+            seed: 0x5eed,
+        }
+    }
+
+    /// Sets the one-way delay of every packet.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Adds a random delay in `[0, jitter)` to every packet.
+    ///
+    /// Packets sent closer than `jitter` to each other can be reordered.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets the probability (from `0.0` to `1.0`) a packet is lost.
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the seed of the random generator deciding losses and jitter.
+    ///
+    /// The same seed produces the same sequence of decisions.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// In-process network connecting endpoints without UDP sockets, for tests.
+///
+/// Endpoints are attached to a network with
+/// [`ServerConfig::set_mock_network`](crate::ServerConfig::set_mock_network) and
+/// [`ClientConfig::set_mock_network`](crate::ClientConfig::set_mock_network):
+/// their bind addresses are virtual, and packets are exchanged in memory,
+/// according to the [`LinkConditions`] of the network.
+///
+/// The network can be cloned: clones refer to the same network.
+///
+/// # Example
+/// ```no_run
+/// # use std::time::Duration;
+/// # use wtransport::tls::Certificate;
+/// use wtransport::mock::LinkConditions;
+/// use wtransport::mock::MockNetwork;
+/// use wtransport::ClientConfig;
+/// use wtransport::Endpoint;
+/// use wtransport::ServerConfig;
+///
+/// # async fn run(certificate: Certificate) {
+/// let network = MockNetwork::new(
+///     LinkConditions::new()
+///         .with_latency(Duration::from_millis(20))
+///         .with_loss(0.01),
+/// );
+///
+/// let mut server_config = ServerConfig::builder()
+///     .with_bind_address("127.0.0.1:4433".parse().unwrap())
+///     .with_certificate(certificate);
+/// server_config.set_mock_network(Some(network.clone()));
+///
+/// let mut client_config = ClientConfig::builder()
+///     .with_bind_address("127.0.0.1:0".parse().unwrap())
+///     .with_native_certs();
+/// client_config.set_mock_network(Some(network));
+///
+/// let server = Endpoint::server(server_config).unwrap();
+/// let client = Endpoint::client(client_config).unwrap();
+///
+/// let connecting = client
+///     .connect("127.0.0.1:4433".parse().unwrap(), "localhost")
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct MockNetwork(Arc<Mutex<Network>>);
+
+impl MockNetwork {
+    /// First port assigned to endpoints bound to port `0`.
+    const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+    /// Creates an empty network.
+    pub fn new(conditions: LinkConditions) -> Self {
+        Self(Arc::new(Mutex::new(Network {
+            conditions,
+            rng: Rng::new(conditions.seed),
+            sockets: HashMap::new(),
+            next_port: Self::FIRST_EPHEMERAL_PORT,
+            next_seq: 0,
+        })))
+    }
+
+    /// Changes the link conditions (e.g., for simulating an outage with a loss of `1.0`).
+    ///
+    /// Packets already in flight are not affected.
+    pub fn set_conditions(&self, conditions: LinkConditions) {
+        let mut network = self.lock();
+        network.conditions = conditions;
+        network.rng = Rng::new(conditions.seed);
+    }
+
+    /// Attaches a socket to the network.
+    ///
+    /// Port `0` is replaced by a free port.
+    pub(crate) fn bind(&self, mut address: SocketAddr) -> std::io::Result<MockSocket> {
+        let mut network = self.lock();
+
+        if address.port() == 0 {
+            let port = (network.next_port..=u16::MAX)
+                .chain(Self::FIRST_EPHEMERAL_PORT..network.next_port)
+                .find(|&port| {
+                    !network
+                        .sockets
+                        .contains_key(&SocketAddr::new(address.ip(), port))
+                })
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::AddrNotAvailable))?;
+
+            network.next_port = port.checked_add(1).unwrap_or(Self::FIRST_EPHEMERAL_PORT);
+            address.set_port(port);
+        }
+
+        if network.sockets.contains_key(&address) {
+            return Err(std::io::ErrorKind::AddrInUse.into());
+        }
+
+        network.sockets.insert(address, Inbox::default());
+
+        Ok(MockSocket {
+            network: self.clone(),
+            local_addr: address,
+            timer: Mutex::new(None),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Network> {
+        self.0.lock().expect("Mutex poisoned")
+    }
+}
+
+impl fmt::Debug for MockNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let network = self.lock();
+        f.debug_struct("MockNetwork")
+            .field("conditions", &network.conditions)
+            .field("sockets", &network.sockets.keys())
+            .finish()
+    }
+}
+
+/// A socket attached to a [`MockNetwork`].
+#[derive(Debug)]
+pub(crate) struct MockSocket {
+    network: MockNetwork,
+    local_addr: SocketAddr,
+    timer: Mutex<Option<Sleep>>,
+}
+
+impl AsyncUdpSocket for MockSocket {
+    fn poll_send(
+        &self,
+        _state: &UdpState,
+        _cx: &mut Context,
+        transmits: &[Transmit],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut network = self.network.lock();
+
+        for transmit in transmits {
+            // Segmentation offload: contents are multiple datagrams of the same size
+            let segment_size = transmit.segment_size.unwrap_or(transmit.contents.len());
+
+            for segment in transmit.contents.chunks(segment_size.max(1)) {
+                network.route(self.local_addr, transmit.destination, segment);
+            }
+        }
+
+        Poll::Ready(Ok(transmits.len()))
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        bufs: &mut [IoSliceMut<'_>],
+        meta: &mut [RecvMeta],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut network = self.network.lock();
+        let now = Instant::now();
+
+        let Some(inbox) = network.sockets.get_mut(&self.local_addr) else {
+            return Poll::Ready(Err(std::io::ErrorKind::NotConnected.into()));
+        };
+
+        let mut count = 0;
+        while count < bufs.len().min(meta.len()) {
+            match inbox.packets.peek() {
+                Some(Reverse(packet)) if packet.deliver_at <= now => {
+                    let Reverse(packet) = inbox.packets.pop().expect("Packet peeked");
+                    let len = packet.contents.len().min(bufs[count].len());
+
+                    bufs[count][..len].copy_from_slice(&packet.contents[..len]);
+                    meta[count] = RecvMeta {
+                        addr: packet.source,
+                        len,
+                        stride: len,
+                        ecn: None,
+                        dst_ip: None,
+                    };
+
+                    count += 1;
+                }
+                _ => break,
+            }
+        }
+
+        if count > 0 {
+            return Poll::Ready(Ok(count));
+        }
+
+        inbox.waker = Some(cx.waker().clone());
+        let next_delivery = inbox
+            .packets
+            .peek()
+            .map(|Reverse(packet)| packet.deliver_at);
+        drop(network);
+
+        // Packets in flight: wake up when the first one is delivered
+        if let Some(deliver_at) = next_delivery {
+            let mut timer = self.timer.lock().expect("Mutex poisoned");
+            let timer = timer.insert(runtime::sleep_until(deliver_at));
+
+            if timer.as_mut().poll(cx).is_ready() {
+                cx.waker().wake_by_ref();
+            }
+        }
+
+        Poll::Pending
+    }
+
+    fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl Drop for MockSocket {
+    fn drop(&mut self) {
+        if let Ok(mut network) = self.network.0.lock() {
+            network.sockets.remove(&self.local_addr);
+        }
+    }
+}
+
+struct Network {
+    conditions: LinkConditions,
+    rng: Rng,
+    sockets: HashMap<SocketAddr, Inbox>,
+    next_port: u16,
+    next_seq: u64,
+}
+
+impl Network {
+    /// Delivers a datagram to the socket bound to `destination` (if any), applying
+    /// the link conditions.
+    fn route(&mut self, source: SocketAddr, destination: SocketAddr, contents: &[u8]) {
+        if self.rng.next_f64() < self.conditions.loss {
+            return;
+        }
+
+        let deliver_at = Instant::now()
+            + self.conditions.latency
+            + self.conditions.jitter.mul_f64(self.rng.next_f64());
+
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        // A socket bound to an unspecified address receives on any address
+        let inbox = if self.sockets.contains_key(&destination) {
+            self.sockets.get_mut(&destination)
+        } else {
+            self.sockets.iter_mut().find_map(|(address, inbox)| {
+                (address.ip().is_unspecified() && address.port() == destination.port())
+                    .then_some(inbox)
+            })
+        };
+
+        if let Some(inbox) = inbox {
+            inbox.packets.push(Reverse(Packet {
+                deliver_at,
+                seq,
+                source,
+                contents: contents.to_vec(),
+            }));
+
+            if let Some(waker) = inbox.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inbox {
+    packets: BinaryHeap<Reverse<Packet>>,
+    waker: Option<Waker>,
+}
+
+/// A packet in flight, ordered by delivery time (and then by sending order).
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Packet {
+    deliver_at: Instant,
+    seq: u64,
+    source: SocketAddr,
+    contents: Vec<u8>,
+}
+
+/// Deterministic pseudo-random generator (xorshift64*).
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero
+        Self(seed.max(1))
+    }
+
+    /// Returns a value in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        let value = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d);
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,57 @@
+//! Types of the API of this crate which are defined by [`wtransport_proto`].
+//!
+//! Using them from here, applications do not need a direct dependency on
+//! `wtransport-proto` (and to keep its version in lockstep with this crate).
+//!
+//! Only the types listed here are part of the API of this crate: any
+//! breaking change to them is a breaking change of `wtransport`.
+
+#[doc(inline)]
+pub use wtransport_proto::varint::VarInt;
+
+#[doc(inline)]
+pub use wtransport_proto::varint::VarIntBoundsExceeded;
+
+#[doc(inline)]
+pub use wtransport_proto::ids::SessionId;
+
+#[doc(inline)]
+pub use wtransport_proto::ids::StreamId;
+
+#[doc(inline)]
+pub use wtransport_proto::headers::Headers;
+
+#[doc(inline)]
+pub use wtransport_proto::error::ErrorCode;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::SettingId;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::Settings;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::SettingsBuilder;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::Dialect;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::WebTransportDraft;
+
+#[doc(inline)]
+pub use wtransport_proto::settings::DatagramDraft;
+
+#[doc(inline)]
+pub use wtransport_proto::discovery::AltSvc;
+
+#[doc(inline)]
+pub use wtransport_proto::ParseLimits;
+
+/// Entry points of the parsers for arbitrary input (fuzzing).
+#[cfg(feature = "fuzzing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "fuzzing")))]
+pub mod fuzzing {
+    #[doc(inline)]
+    pub use wtransport_proto::fuzzing::*;
+}
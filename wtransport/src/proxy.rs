@@ -0,0 +1,677 @@
+use crate::engine::stream::BiLocal;
+use crate::engine::stream::Stream;
+use crate::engine::stream::UniLocal;
+use crate::engine::stream::H3;
+use crate::error::InvalidProxyUrl;
+use crate::runtime;
+use crate::runtime::Runtime;
+use crate::runtime::Task;
+use bytes::Bytes;
+use quinn::udp::RecvMeta;
+use quinn::udp::UdpState;
+use quinn::AsyncUdpSocket;
+use quinn::ClientConfig as QuicClientConfig;
+use quinn::Endpoint as QuicEndpoint;
+use quinn::EndpointConfig;
+use quinn::Transmit;
+use quinn::TransportConfig;
+use rustls::ClientConfig as TlsClientConfig;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::IoSliceMut;
+use std::net::SocketAddr;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+use tokio::sync::mpsc;
+use wtransport_proto::datagram::ContextDatagram;
+use wtransport_proto::frame::FrameKind;
+use wtransport_proto::headers::Headers;
+use wtransport_proto::ids::QStreamId;
+use wtransport_proto::ids::SessionId;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::stream::StreamHeader;
+use wtransport_proto::varint::VarInt;
+use wtransport_proto::ParseLimits;
+
+/// The URL of an HTTP proxy supporting *CONNECT-UDP* ([RFC 9298](https://www.rfc-editor.org/rfc/rfc9298)).
+///
+/// It must be an `https` URL: the proxy is reached with HTTP/3. Its path is the URI
+/// template of UDP tunnels, with the `{target_host}` and `{target_port}` variables.
+/// If the path is omitted, the default template
+/// `/.well-known/masque/udp/{target_host}/{target_port}/` is used.
+///
+/// # Example
+/// ```
+/// use wtransport::proxy::ProxyUrl;
+///
+/// let url: ProxyUrl = "https://proxy.example.com:8443".parse().unwrap();
+///
+/// assert_eq!(url.host(), "proxy.example.com");
+/// assert_eq!(url.port(), 8443);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProxyUrl {
+    host: String,
+    port: u16,
+    template: String,
+}
+
+impl ProxyUrl {
+    const DEFAULT_PORT: u16 = 443;
+    const DEFAULT_TEMPLATE: &str = "/.well-known/masque/udp/{target_host}/{target_port}/";
+
+    /// Parses a proxy URL (e.g., `https://proxy.example.com` or
+    /// `https://proxy.example.com:8443/masque?h={target_host}&p={target_port}`).
+    ///
+    /// Credentials cannot be part of the URL: see [`ProxyCredentials`].
+    pub fn parse(url: &str) -> Result<Self, InvalidProxyUrl> {
+        let rest = url.strip_prefix("https://").ok_or(InvalidProxyUrl)?;
+
+        let (authority, template) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            let (host, port) = bracketed.split_once(']').ok_or(InvalidProxyUrl)?;
+            match port {
+                "" => (host, None),
+                port => (host, Some(port.strip_prefix(':').ok_or(InvalidProxyUrl)?)),
+            }
+        } else {
+            match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            }
+        };
+
+        if host.is_empty()
+            || host.contains(|c: char| matches!(c, '@' | '?' | '#') || c.is_whitespace())
+        {
+            return Err(InvalidProxyUrl);
+        }
+
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| InvalidProxyUrl)?,
+            None => Self::DEFAULT_PORT,
+        };
+
+        let template = match template {
+            "" | "/" => Self::DEFAULT_TEMPLATE.to_string(),
+            template
+                if template.contains("{target_host}") && template.contains("{target_port}") =>
+            {
+                template.to_string()
+            }
+            _ => return Err(InvalidProxyUrl),
+        };
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            template,
+        })
+    }
+
+    /// Returns the host of the proxy (IPv6 literals without brackets).
+    #[inline(always)]
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the port of the proxy.
+    #[inline(always)]
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The `:authority` of requests to the proxy.
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// The `:path` of the request for a tunnel towards `target`.
+    fn path(&self, target: SocketAddr) -> String {
+        // Colons of IPv6 addresses are percent-encoded (RFC 9298, section 2)
+        let target_host = target.ip().to_string().replace(':', "%3A");
+
+        self.template
+            .replace("{target_host}", &target_host)
+            .replace("{target_port}", &target.port().to_string())
+    }
+}
+
+impl FromStr for ProxyUrl {
+    type Err = InvalidProxyUrl;
+
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        Self::parse(url)
+    }
+}
+
+/// Credentials presented to the proxy, in the `Proxy-Authorization` header.
+#[derive(Clone)]
+pub struct ProxyCredentials {
+    authorization: String,
+}
+
+impl ProxyCredentials {
+    /// HTTP *Basic* authentication ([RFC 7617](https://www.rfc-editor.org/rfc/rfc7617)).
+    pub fn basic(username: &str, password: &str) -> Self {
+        Self {
+            authorization: format!(
+                "Basic {}",
+                base64(format!("{username}:{password}").as_bytes())
+            ),
+        }
+    }
+
+    /// A *Bearer* token ([RFC 6750](https://www.rfc-editor.org/rfc/rfc6750)).
+    pub fn bearer(token: &str) -> Self {
+        Self {
+            authorization: format!("Bearer {token}"),
+        }
+    }
+}
+
+impl fmt::Debug for ProxyCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyCredentials").finish_non_exhaustive()
+    }
+}
+
+/// The proxy of a client endpoint.
+#[derive(Clone, Debug)]
+pub(crate) struct Proxy {
+    pub(crate) url: ProxyUrl,
+    pub(crate) credentials: Option<ProxyCredentials>,
+}
+
+impl Proxy {
+    /// The request headers of a tunnel towards `target`.
+    fn request(&self, target: SocketAddr) -> Headers {
+        let mut fields = vec![
+            (":method", "CONNECT".to_string()),
+            (":protocol", "connect-udp".to_string()),
+            (":scheme", "https".to_string()),
+            (":authority", self.url.authority()),
+            (":path", self.url.path(target)),
+            ("capsule-protocol", "?1".to_string()),
+        ];
+
+        if let Some(credentials) = &self.credentials {
+            fields.push(("proxy-authorization", credentials.authorization.clone()));
+        }
+
+        fields.into_iter().collect()
+    }
+}
+
+/// A socket whose packets are tunneled through a CONNECT-UDP proxy.
+///
+/// The QUIC connection to the proxy is established with the first packet sent,
+/// and re-established (on a later packet) if it is closed. Every destination
+/// gets its own tunnel: packets are queued until the proxy accepts it.
+pub(crate) struct ProxySocket {
+    local_addr: SocketAddr,
+    outgoing: mpsc::UnboundedSender<(SocketAddr, Bytes)>,
+    inbox: Arc<Inbox>,
+    task: Task<()>,
+}
+
+impl ProxySocket {
+    /// UDP payload size of the connection to the proxy.
+    ///
+    /// Tunneled QUIC packets are at least 1200 bytes long: the packets carrying
+    /// them must fit them plus their own overhead.
+    const INITIAL_MTU: u16 = 1300;
+
+    /// Creates the socket, reaching the proxy through `socket`.
+    ///
+    /// The proxy host is resolved here, so that resolution errors are reported
+    /// when the endpoint is created.
+    pub(crate) fn bind(
+        proxy: Proxy,
+        tls_config: Arc<TlsClientConfig>,
+        socket: UdpSocket,
+        runtime: Arc<dyn Runtime>,
+    ) -> std::io::Result<Self> {
+        let local_addr = socket.local_addr()?;
+
+        let address = (proxy.url.host(), proxy.url.port())
+            .to_socket_addrs()?
+            .min_by_key(|address| address.is_ipv4() != local_addr.is_ipv4())
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::AddrNotAvailable))?;
+
+        let endpoint = QuicEndpoint::new(
+            EndpointConfig::default(),
+            None,
+            socket,
+            runtime::quic_runtime(runtime.clone()),
+        )?;
+
+        let mut transport_config = TransportConfig::default();
+        transport_config.initial_mtu(Self::INITIAL_MTU);
+
+        let mut quic_config = QuicClientConfig::new(tls_config);
+        quic_config.transport_config(Arc::new(transport_config));
+
+        let inbox = Arc::new(Inbox::default());
+        let (outgoing, packets) = mpsc::unbounded_channel();
+
+        let client = ProxyClient {
+            endpoint,
+            quic_config,
+            address,
+            proxy,
+            inbox: inbox.clone(),
+        };
+
+        Ok(Self {
+            local_addr,
+            outgoing,
+            inbox,
+            task: runtime::spawn_on(&*runtime, client.run(packets)),
+        })
+    }
+}
+
+impl fmt::Debug for ProxySocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxySocket")
+            .field("local_addr", &self.local_addr)
+            .finish()
+    }
+}
+
+impl AsyncUdpSocket for ProxySocket {
+    fn poll_send(
+        &self,
+        _state: &UdpState,
+        _cx: &mut Context,
+        transmits: &[Transmit],
+    ) -> Poll<std::io::Result<usize>> {
+        for transmit in transmits {
+            // Segmentation offload: contents are multiple datagrams of the same size
+            let segment_size = transmit.segment_size.unwrap_or(transmit.contents.len());
+
+            for segment in transmit.contents.chunks(segment_size.max(1)) {
+                let packet = (transmit.destination, Bytes::copy_from_slice(segment));
+
+                if self.outgoing.send(packet).is_err() {
+                    return Poll::Ready(Err(std::io::ErrorKind::NotConnected.into()));
+                }
+            }
+        }
+
+        Poll::Ready(Ok(transmits.len()))
+    }
+
+    fn poll_recv(
+        &self,
+        cx: &mut Context,
+        bufs: &mut [IoSliceMut<'_>],
+        meta: &mut [RecvMeta],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut inbox = self.inbox.lock();
+
+        let mut count = 0;
+        while count < bufs.len().min(meta.len()) {
+            let Some((source, packet)) = inbox.packets.pop_front() else {
+                break;
+            };
+
+            let len = packet.len().min(bufs[count].len());
+
+            bufs[count][..len].copy_from_slice(&packet[..len]);
+            meta[count] = RecvMeta {
+                addr: source,
+                len,
+                stride: len,
+                ecn: None,
+                dst_ip: None,
+            };
+
+            count += 1;
+        }
+
+        if count > 0 {
+            return Poll::Ready(Ok(count));
+        }
+
+        inbox.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+}
+
+impl Drop for ProxySocket {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Packets received through tunnels, waiting to be read from the [`ProxySocket`].
+#[derive(Default)]
+struct Inbox(Mutex<InboxState>);
+
+#[derive(Default)]
+struct InboxState {
+    packets: VecDeque<(SocketAddr, Bytes)>,
+    waker: Option<Waker>,
+}
+
+impl Inbox {
+    /// Maximum number of packets waiting to be read (like a socket receive buffer).
+    const CAPACITY: usize = 1024;
+
+    fn push(&self, source: SocketAddr, packet: Bytes) {
+        let mut inbox = self.lock();
+
+        if inbox.packets.len() >= Self::CAPACITY {
+            return;
+        }
+
+        inbox.packets.push_back((source, packet));
+
+        if let Some(waker) = inbox.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, InboxState> {
+        self.0.lock().expect("Mutex poisoned")
+    }
+}
+
+/// Forwards the packets of a [`ProxySocket`] to the proxy.
+struct ProxyClient {
+    endpoint: QuicEndpoint,
+    quic_config: QuicClientConfig,
+    address: SocketAddr,
+    proxy: Proxy,
+    inbox: Arc<Inbox>,
+}
+
+impl ProxyClient {
+    async fn run(self, mut packets: mpsc::UnboundedReceiver<(SocketAddr, Bytes)>) {
+        let mut connection: Option<ProxyConnection> = None;
+        let mut tunnels = HashMap::new();
+
+        while let Some((destination, packet)) = packets.recv().await {
+            if connection.as_ref().map_or(true, ProxyConnection::is_closed) {
+                // Packets are lost while the proxy is unreachable: QUIC retransmits them
+                tunnels.clear();
+                connection = self.connect().await;
+            }
+
+            let Some(connection) = &connection else {
+                continue;
+            };
+
+            let tunnel = tunnels
+                .entry(destination)
+                .or_insert_with(|| connection.open_tunnel(&self.proxy, destination));
+
+            // The tunnel has been closed by the proxy: it is re-opened
+            if let Err(mpsc::error::SendError(packet)) = tunnel.send(packet) {
+                *tunnel = connection.open_tunnel(&self.proxy, destination);
+                let _ = tunnel.send(packet);
+            }
+        }
+    }
+
+    async fn connect(&self) -> Option<ProxyConnection> {
+        let quic_connection = self
+            .endpoint
+            .connect_with(
+                self.quic_config.clone(),
+                self.address,
+                self.proxy.url.host(),
+            )
+            .ok()?
+            .await
+            .ok()?;
+
+        ProxyConnection::new(quic_connection, self.inbox.clone()).await
+    }
+}
+
+/// HTTP/3 connection to the proxy.
+struct ProxyConnection {
+    quic_connection: quinn::Connection,
+    routes: Arc<Mutex<HashMap<QStreamId, SocketAddr>>>,
+    _control: Stream<UniLocal, H3>,
+    tasks: [Task<()>; 2],
+}
+
+impl ProxyConnection {
+    /// Context ID of datagrams carrying UDP payloads (RFC 9298, section 4).
+    const UDP_PAYLOAD_CONTEXT: VarInt = VarInt::from_u32(0);
+
+    async fn new(quic_connection: quinn::Connection, inbox: Arc<Inbox>) -> Option<Self> {
+        let local_settings = Settings::builder()
+            .enable_h3_datagrams()
+            .qpack_max_table_capacity(VarInt::from_u32(0))
+            .qpack_blocked_streams(VarInt::from_u32(0))
+            .build();
+
+        let mut control = Stream::open_uni(&quic_connection)
+            .await?
+            .upgrade(StreamHeader::new_control())
+            .await
+            .ok()?;
+
+        control
+            .write_frame(local_settings.generate_frame())
+            .await
+            .ok()?;
+
+        let routes = Arc::new(Mutex::new(HashMap::new()));
+
+        let tasks = [
+            runtime::spawn(Self::receive_datagrams(
+                quic_connection.clone(),
+                routes.clone(),
+                inbox,
+            )),
+            runtime::spawn(Self::hold_streams(quic_connection.clone())),
+        ];
+
+        Some(Self {
+            quic_connection,
+            routes,
+            _control: control,
+            tasks,
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.quic_connection.close_reason().is_some()
+    }
+
+    /// Opens a tunnel towards `target`, returning the sender of its packets.
+    fn open_tunnel(&self, proxy: &Proxy, target: SocketAddr) -> mpsc::UnboundedSender<Bytes> {
+        let (sender, packets) = mpsc::unbounded_channel();
+
+        runtime::spawn(Self::run_tunnel(
+            self.quic_connection.clone(),
+            self.routes.clone(),
+            proxy.request(target),
+            target,
+            packets,
+        ));
+
+        sender
+    }
+
+    async fn run_tunnel(
+        quic_connection: quinn::Connection,
+        routes: Arc<Mutex<HashMap<QStreamId, SocketAddr>>>,
+        request: Headers,
+        target: SocketAddr,
+        mut packets: mpsc::UnboundedReceiver<Bytes>,
+    ) {
+        let Some(stream) = Stream::open_bi(&quic_connection).await else {
+            return;
+        };
+        let mut stream = stream.upgrade();
+
+        if stream
+            .write_frame(request.generate_frame(stream.id()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        // Meanwhile, packets are queued in `packets`
+        if !Self::tunnel_accepted(&mut stream).await {
+            return;
+        }
+
+        let qstream_id = QStreamId::from_session_id(
+            SessionId::try_from_session_stream(stream.id()).expect("Client bi-directional stream"),
+        );
+
+        routes
+            .lock()
+            .expect("Mutex poisoned")
+            .insert(qstream_id, target);
+
+        // Capsules from the proxy are ignored: the tunnel lasts as long as the stream
+        let limits = ParseLimits::default();
+        let closed = async { while stream.read_frame(&limits).await.is_ok() {} };
+        tokio::pin!(closed);
+
+        let mut buffer = Vec::new();
+
+        loop {
+            tokio::select! {
+                packet = packets.recv() => {
+                    let Some(packet) = packet else {
+                        break;
+                    };
+
+                    let datagram =
+                        ContextDatagram::new(qstream_id, Self::UDP_PAYLOAD_CONTEXT, &packet);
+
+                    buffer.resize(datagram.write_size(), 0);
+                    datagram.write(&mut buffer).expect("Buffer has capacity");
+
+                    // Like UDP, a packet too large or not fitting the send buffer is lost
+                    let _ = quic_connection.send_datagram(Bytes::copy_from_slice(&buffer));
+                }
+                () = &mut closed => break,
+            }
+        }
+
+        routes.lock().expect("Mutex poisoned").remove(&qstream_id);
+    }
+
+    /// Waits for the response of the proxy to a tunnel request.
+    async fn tunnel_accepted(stream: &mut Stream<BiLocal, H3>) -> bool {
+        loop {
+            let Ok(frame) = stream.read_frame(&ParseLimits::default()).await else {
+                return false;
+            };
+
+            if !matches!(frame.kind(), FrameKind::Headers) {
+                continue;
+            }
+
+            let Ok(headers) = Headers::with_frame(&frame, stream.id()) else {
+                return false;
+            };
+
+            match headers.get(":status").unwrap_or_default().as_bytes() {
+                // Interim responses precede the final one
+                [b'1', ..] => continue,
+                [b'2', ..] => return true,
+                _ => return false,
+            }
+        }
+    }
+
+    async fn receive_datagrams(
+        quic_connection: quinn::Connection,
+        routes: Arc<Mutex<HashMap<QStreamId, SocketAddr>>>,
+        inbox: Arc<Inbox>,
+    ) {
+        while let Ok(quic_datagram) = quic_connection.read_datagram().await {
+            let Ok(datagram) = ContextDatagram::read(&quic_datagram) else {
+                continue;
+            };
+
+            if datagram.context_id() != Self::UDP_PAYLOAD_CONTEXT {
+                continue;
+            }
+
+            let source = routes
+                .lock()
+                .expect("Mutex poisoned")
+                .get(&datagram.qstream_id())
+                .copied();
+
+            if let Some(source) = source {
+                inbox.push(source, quic_datagram.slice_ref(datagram.payload()));
+            }
+        }
+    }
+
+    /// Accepts the uni-directional streams of the proxy (control and QPACK streams),
+    /// keeping them open: closing them is a connection error.
+    async fn hold_streams(quic_connection: quinn::Connection) {
+        let mut streams = Vec::new();
+
+        while let Some(stream) = Stream::accept_uni(&quic_connection).await {
+            streams.push(stream);
+        }
+    }
+}
+
+impl Drop for ProxyConnection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Base64 encoding, with padding ([RFC 4648](https://www.rfc-editor.org/rfc/rfc4648)).
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, &byte)| {
+            bits | (u32::from(byte) << (16 - 8 * index))
+        });
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (bits >> (18 - 6 * index)) & 0x3f;
+                encoded.push(char::from(ALPHABET[sextet as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
@@ -0,0 +1,260 @@
+use std::fmt::Write as _;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use wtransport_proto::frame::Frame;
+use wtransport_proto::frame::FrameKind;
+use wtransport_proto::ids::StreamId;
+use wtransport_proto::settings::SettingId;
+use wtransport_proto::settings::Settings;
+use wtransport_proto::stream::StreamKind;
+
+/// The endpoint side a qlog trace is recorded from.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VantagePoint {
+    /// The trace is recorded by a client.
+    Client,
+
+    /// The trace is recorded by a server.
+    Server,
+}
+
+impl VantagePoint {
+    fn as_str(self) -> &'static str {
+        match self {
+            VantagePoint::Client => "client",
+            VantagePoint::Server => "server",
+        }
+    }
+}
+
+/// Destination of qlog traces.
+///
+/// For each connection, [`QlogSink::open`] is invoked to obtain the writer
+/// where the connection trace is written (in *JSON-SEQ* format, i.e., `.sqlog` files).
+///
+/// It is implemented for closures with the same signature of [`QlogSink::open`].
+/// See also [`QlogDir`].
+pub trait QlogSink: Send + Sync {
+    /// Returns the writer for the trace of a new connection with `remote_address`.
+    ///
+    /// If [`None`] is returned, the connection is not traced.
+    fn open(
+        &self,
+        vantage_point: VantagePoint,
+        remote_address: SocketAddr,
+    ) -> Option<Box<dyn Write + Send>>;
+}
+
+impl<F> QlogSink for F
+where
+    F: Fn(VantagePoint, SocketAddr) -> Option<Box<dyn Write + Send>> + Send + Sync,
+{
+    fn open(
+        &self,
+        vantage_point: VantagePoint,
+        remote_address: SocketAddr,
+    ) -> Option<Box<dyn Write + Send>> {
+        self(vantage_point, remote_address)
+    }
+}
+
+/// A [`QlogSink`] writing each connection trace in a new file in a directory.
+///
+/// Files are named `<timestamp>-<vantage point>-<remote address>.sqlog`.
+#[derive(Clone, Debug)]
+pub struct QlogDir(PathBuf);
+
+impl QlogDir {
+    /// Creates a sink writing traces in `path` (which must exist).
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self(path.into())
+    }
+}
+
+impl QlogSink for QlogDir {
+    fn open(
+        &self,
+        vantage_point: VantagePoint,
+        remote_address: SocketAddr,
+    ) -> Option<Box<dyn Write + Send>> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let file_name = format!(
+            "{}-{}-{}.sqlog",
+            timestamp,
+            vantage_point.as_str(),
+            remote_address.to_string().replace(':', "_")
+        );
+
+        let file = std::fs::File::create(self.0.join(file_name)).ok()?;
+
+        Some(Box::new(std::io::BufWriter::new(file)))
+    }
+}
+
+/// The qlog trace of a connection.
+///
+/// Only events of the HTTP3 and WebTransport layers are recorded, as the
+/// QUIC implementation does not expose packet-level events.
+pub(crate) struct QlogTrace {
+    start: Instant,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl QlogTrace {
+    /// Record separator of JSON-SEQ (RFC 7464).
+    const RECORD_SEPARATOR: char = '\u{1e}';
+
+    pub(crate) fn new(
+        sink: &dyn QlogSink,
+        vantage_point: VantagePoint,
+        remote_address: SocketAddr,
+    ) -> Option<Self> {
+        let writer = sink.open(vantage_point, remote_address)?;
+
+        let trace = Self {
+            start: Instant::now(),
+            writer: Mutex::new(writer),
+        };
+
+        trace.write_record(&format!(
+            r#"{{"qlog_version":"0.3","qlog_format":"JSON-SEQ","title":"wtransport","trace":{{"vantage_point":{{"type":"{}"}},"common_fields":{{"protocol_type":["QUIC","HTTP3"],"time_format":"relative"}}}}}}"#,
+            vantage_point.as_str()
+        ));
+
+        Some(trace)
+    }
+
+    /// Records an incoming or outgoing stream being opened.
+    pub(crate) fn stream_opened(&self, stream_id: StreamId, local: bool) {
+        let stream_type = if stream_id.is_bidirectional() {
+            "bidirectional"
+        } else {
+            "unidirectional"
+        };
+
+        self.event(
+            "transport:stream_state_updated",
+            &format!(
+                r#"{{"stream_id":{},"stream_type":"{}","stream_side":"{}","new":"open"}}"#,
+                stream_id,
+                stream_type,
+                if local { "sending" } else { "receiving" }
+            ),
+        );
+    }
+
+    /// Records the type of a uni-directional stream.
+    pub(crate) fn stream_type_set(&self, stream_id: StreamId, kind: StreamKind, local: bool) {
+        let stream_type = match kind {
+            StreamKind::Control => "control",
+            StreamKind::QPackEncoder => "qpack_encode",
+            StreamKind::QPackDecoder => "qpack_decode",
+            StreamKind::WebTransport => "webtransport",
+            StreamKind::Exercise(_) => "reserved",
+        };
+
+        self.event(
+            "http:stream_type_set",
+            &format!(
+                r#"{{"owner":"{}","stream_id":{},"new":"{}"}}"#,
+                Self::owner(local),
+                stream_id,
+                stream_type
+            ),
+        );
+    }
+
+    /// Records an HTTP3 frame sent (`local`) or received on a stream.
+    pub(crate) fn frame(&self, stream_id: StreamId, frame: &Frame, local: bool) {
+        let frame_type = match frame.kind() {
+            FrameKind::Data => "data".to_string(),
+            FrameKind::Headers => "headers".to_string(),
+            FrameKind::Settings => "settings".to_string(),
+            FrameKind::GoAway => "goaway".to_string(),
+            FrameKind::WebTransport => "webtransport_stream".to_string(),
+            FrameKind::Exercise(id) => format!("reserved:{}", id),
+            FrameKind::Unknown(id) => format!("unknown:{}", id),
+        };
+
+        self.event(
+            if local {
+                "http:frame_created"
+            } else {
+                "http:frame_parsed"
+            },
+            &format!(
+                r#"{{"stream_id":{},"length":{},"frame":{{"frame_type":"{}"}}}}"#,
+                stream_id,
+                frame.payload().len(),
+                frame_type
+            ),
+        );
+    }
+
+    /// Records the SETTINGS sent (`local`) or received.
+    pub(crate) fn parameters_set(&self, settings: &Settings, local: bool) {
+        const SETTINGS: [(SettingId, &str); 7] = [
+            (SettingId::QPackMaxTableCapacity, "qpack_max_table_capacity"),
+            (SettingId::MaxFieldSectionSize, "max_field_section_size"),
+            (SettingId::QPackBlockedStreams, "qpack_blocked_streams"),
+            (SettingId::H3Datagram, "h3_datagram"),
+            (SettingId::H3DatagramRfc, "h3_datagram_rfc"),
+            (SettingId::EnableWebTransport, "enable_webtransport"),
+            (
+                SettingId::WebTransportMaxSessions,
+                "webtransport_max_sessions",
+            ),
+        ];
+
+        let mut data = format!(r#"{{"owner":"{}""#, Self::owner(local));
+        for (id, name) in SETTINGS {
+            if let Some(value) = settings.get(id) {
+                let _ = write!(data, r#","{}":{}"#, name, value);
+            }
+        }
+        data.push('}');
+
+        self.event("http:parameters_set", &data);
+    }
+
+    fn event(&self, name: &str, data: &str) {
+        let time = self.start.elapsed().as_secs_f64() * 1000.0;
+
+        self.write_record(&format!(
+            r#"{{"time":{:.3},"name":"{}","data":{}}}"#,
+            time, name, data
+        ));
+    }
+
+    fn write_record(&self, record: &str) {
+        let mut writer = self.writer.lock().expect("Qlog writer poisoned");
+
+        // Tracing is best-effort: I/O errors are ignored
+        let _ = writeln!(writer, "{}{}", Self::RECORD_SEPARATOR, record);
+    }
+
+    fn owner(local: bool) -> &'static str {
+        if local {
+            "local"
+        } else {
+            "remote"
+        }
+    }
+}
+
+impl Drop for QlogTrace {
+    fn drop(&mut self) {
+        if let Ok(writer) = self.writer.get_mut() {
+            let _ = writer.flush();
+        }
+    }
+}
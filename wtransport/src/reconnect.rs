@@ -0,0 +1,373 @@
+use crate::connection::Connection;
+use crate::endpoint::Client;
+use crate::error::ConnectionError;
+use crate::error::DatagramError;
+use crate::runtime;
+use crate::runtime::Task;
+use crate::stream::RecvStream;
+use crate::stream::SendStream;
+use crate::Endpoint;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Delays between the attempts of reconnection (exponential backoff).
+#[derive(Copy, Clone, Debug)]
+pub struct Backoff {
+    initial_delay: Duration,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+}
+
+impl Backoff {
+    /// Creates a backoff starting from `initial_delay`, doubling the delay
+    /// at every attempt up to `max_delay`.
+    ///
+    /// Attempts are unlimited (see [`Self::with_max_attempts`]).
+    pub fn new(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            max_delay: max_delay.max(initial_delay),
+            max_attempts: None,
+        }
+    }
+
+    /// Gives up reconnecting after `max_attempts` failed attempts.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Returns the delay before the attempt number `attempt` (starting from `0`).
+    fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+impl Default for Backoff {
+    /// From 100 milliseconds up to 10 seconds, with unlimited attempts.
+    fn default() -> Self {
+        Self::new(Duration::from_millis(100), Duration::from_secs(10))
+    }
+}
+
+/// The generation of the session of a [`ReconnectingConnection`].
+///
+/// It is incremented every time the session is re-established: streams and
+/// datagrams of previous epochs are lost (they are not resumed on the new
+/// session).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionEpoch(u64);
+
+impl SessionEpoch {
+    /// Returns the epoch as an integer (the first session is epoch `0`).
+    #[inline(always)]
+    pub fn into_u64(self) -> u64 {
+        self.0
+    }
+
+    fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+impl fmt::Display for SessionEpoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A client session which is re-established automatically when lost.
+///
+/// When the underlying [`Connection`] is closed for any reason other than a
+/// local closure, the server is dialed again (with [`Backoff`] between the
+/// attempts), and a new session starts with a new [`SessionEpoch`].
+///
+/// Operations return the epoch they were performed on, and
+/// [`Self::reconnected`] notifies new epochs: everything belonging to
+/// previous epochs (streams, datagrams in flight) is lost.
+///
+/// # Example
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use wtransport::ClientConfig;
+/// use wtransport::reconnect::Backoff;
+/// use wtransport::reconnect::ReconnectingConnection;
+/// use wtransport::Endpoint;
+///
+/// # async fn run(config: ClientConfig) {
+/// let endpoint = Arc::new(Endpoint::client(config).unwrap());
+///
+/// let connection = ReconnectingConnection::connect(
+///     endpoint,
+///     "127.0.0.1:4433".parse().unwrap(),
+///     "localhost",
+///     Backoff::default(),
+/// )
+/// .await
+/// .unwrap();
+///
+/// let (mut stream, epoch) = connection.open_uni().await.unwrap();
+/// stream.write_all(b"hello").await.unwrap();
+/// # }
+/// ```
+pub struct ReconnectingConnection {
+    state: Arc<watch::Sender<State>>,
+    supervisor: Task<()>,
+}
+
+impl ReconnectingConnection {
+    /// Connects to a remote endpoint (see [`Endpoint::connect`]).
+    ///
+    /// The first connection is attempted only once: it fails if the server
+    /// is not reachable. Later reconnections follow `backoff`.
+    pub async fn connect(
+        endpoint: Arc<Endpoint<Client>>,
+        remote_address: SocketAddr,
+        server_name: &str,
+        backoff: Backoff,
+    ) -> Result<Self, ConnectionError> {
+        let connection = endpoint.connect(remote_address, server_name)?.await?;
+
+        let state = Arc::new(watch::channel(State::Connected(connection, SessionEpoch(0))).0);
+
+        let supervisor = runtime::spawn(Self::supervise(
+            Dialer {
+                endpoint,
+                remote_address,
+                server_name: server_name.to_string(),
+                backoff,
+            },
+            state.clone(),
+        ));
+
+        Ok(Self { state, supervisor })
+    }
+
+    /// Returns the current connection and its epoch.
+    ///
+    /// If the session is being re-established, it waits for the new one.
+    /// It fails if the connection has been closed locally or the reconnection
+    /// gave up (see [`Backoff::with_max_attempts`]).
+    pub async fn connection(&self) -> Result<(Connection, SessionEpoch), ConnectionError> {
+        let mut state = self.state.subscribe();
+
+        loop {
+            match &*state.borrow_and_update() {
+                State::Connected(connection, epoch) => return Ok((connection.clone(), *epoch)),
+                State::Reconnecting(_) => {}
+                State::Closed(_) => return Err(ConnectionError::LocallyClosed),
+                State::GaveUp(_) => return Err(ConnectionError::TimedOut),
+            }
+
+            if state.changed().await.is_err() {
+                return Err(ConnectionError::LocallyClosed);
+            }
+        }
+    }
+
+    /// Returns the epoch of the current session (or of the last one, while
+    /// reconnecting).
+    pub fn epoch(&self) -> SessionEpoch {
+        self.state.borrow().epoch()
+    }
+
+    /// Waits for the session to be re-established, returning the new epoch.
+    pub async fn reconnected(&self) -> Result<SessionEpoch, ConnectionError> {
+        self.reconnected_after(self.epoch()).await
+    }
+
+    /// Waits for a session with an epoch greater than `current`.
+    async fn reconnected_after(
+        &self,
+        current: SessionEpoch,
+    ) -> Result<SessionEpoch, ConnectionError> {
+        let mut state = self.state.subscribe();
+
+        loop {
+            match &*state.borrow_and_update() {
+                State::Connected(_, epoch) if *epoch > current => return Ok(*epoch),
+                State::Connected(..) | State::Reconnecting(_) => {}
+                State::Closed(_) => return Err(ConnectionError::LocallyClosed),
+                State::GaveUp(_) => return Err(ConnectionError::TimedOut),
+            }
+
+            if state.changed().await.is_err() {
+                return Err(ConnectionError::LocallyClosed);
+            }
+        }
+    }
+
+    /// Opens a bidirectional stream (see [`Connection::open_bi`]).
+    ///
+    /// If the session is lost while opening, the stream is opened on the
+    /// re-established session.
+    pub async fn open_bi(
+        &self,
+    ) -> Result<((SendStream, RecvStream), SessionEpoch), ConnectionError> {
+        loop {
+            let (connection, epoch) = self.connection().await?;
+
+            match connection.open_bi().await {
+                Ok(stream) => return Ok((stream, epoch)),
+                Err(error) => self.on_error(&connection, error)?,
+            }
+
+            self.reconnected_after(epoch).await?;
+        }
+    }
+
+    /// Opens a unidirectional stream (see [`Connection::open_uni`]).
+    ///
+    /// If the session is lost while opening, the stream is opened on the
+    /// re-established session.
+    pub async fn open_uni(&self) -> Result<(SendStream, SessionEpoch), ConnectionError> {
+        loop {
+            let (connection, epoch) = self.connection().await?;
+
+            match connection.open_uni().await {
+                Ok(stream) => return Ok((stream, epoch)),
+                Err(error) => self.on_error(&connection, error)?,
+            }
+
+            self.reconnected_after(epoch).await?;
+        }
+    }
+
+    /// Sends a datagram on the current session (see [`Connection::send_datagram`]).
+    ///
+    /// While reconnecting, it fails with [`DatagramError::ConnectionClosed`]
+    /// (datagrams are unreliable anyway).
+    pub fn send_datagram<D>(&self, data: D) -> Result<SessionEpoch, DatagramError>
+    where
+        D: AsRef<[u8]>,
+    {
+        let (connection, epoch) = match &*self.state.borrow() {
+            State::Connected(connection, epoch) => (connection.clone(), *epoch),
+            _ => return Err(DatagramError::ConnectionClosed),
+        };
+
+        connection.send_datagram(data).map(|()| epoch)
+    }
+
+    /// Closes the session and stops reconnecting.
+    pub async fn close(&self, error_code: u32, reason: &str) {
+        self.supervisor.abort();
+
+        let epoch = self.epoch();
+        let previous = self.state.send_replace(State::Closed(epoch));
+        if let State::Connected(connection, _) = previous {
+            connection.close(error_code, reason).await;
+        }
+    }
+
+    /// Decides whether a failed open can be retried on a new session.
+    ///
+    /// The operation is retried only if the session was lost in the meantime
+    /// (the supervisor is re-establishing it).
+    fn on_error(
+        &self,
+        connection: &Connection,
+        error: ConnectionError,
+    ) -> Result<(), ConnectionError> {
+        match connection.close_reason() {
+            Some(ConnectionError::LocallyClosed) | None => Err(error),
+            Some(_) => Ok(()),
+        }
+    }
+
+    async fn supervise(dialer: Dialer, state: Arc<watch::Sender<State>>) {
+        loop {
+            let (connection, epoch) = match &*state.borrow() {
+                State::Connected(connection, epoch) => (connection.clone(), *epoch),
+                _ => return,
+            };
+
+            if let ConnectionError::LocallyClosed = connection.closed().await {
+                state.send_replace(State::Closed(epoch));
+                return;
+            }
+
+            drop(connection);
+            state.send_replace(State::Reconnecting(epoch));
+
+            match dialer.dial().await {
+                Some(connection) => {
+                    state.send_replace(State::Connected(connection, epoch.next()));
+                }
+                None => {
+                    state.send_replace(State::GaveUp(epoch));
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ReconnectingConnection {
+    fn drop(&mut self) {
+        self.supervisor.abort();
+    }
+}
+
+/// State of a [`ReconnectingConnection`], along with the epoch of the
+/// current (or last) session.
+enum State {
+    Connected(Connection, SessionEpoch),
+    Reconnecting(SessionEpoch),
+    Closed(SessionEpoch),
+    GaveUp(SessionEpoch),
+}
+
+impl State {
+    fn epoch(&self) -> SessionEpoch {
+        match self {
+            State::Connected(_, epoch)
+            | State::Reconnecting(epoch)
+            | State::Closed(epoch)
+            | State::GaveUp(epoch) => *epoch,
+        }
+    }
+}
+
+struct Dialer {
+    endpoint: Arc<Endpoint<Client>>,
+    remote_address: SocketAddr,
+    server_name: String,
+    backoff: Backoff,
+}
+
+impl Dialer {
+    /// Dials the server until a session is established.
+    ///
+    /// Returns [`None`] if the attempts are exhausted or the endpoint is closed.
+    async fn dial(&self) -> Option<Connection> {
+        let mut attempt = 0;
+
+        loop {
+            if matches!(self.backoff.max_attempts, Some(max_attempts) if attempt >= max_attempts) {
+                return None;
+            }
+
+            runtime::sleep(self.backoff.delay(attempt)).await;
+            attempt += 1;
+
+            let connecting = match self
+                .endpoint
+                .connect(self.remote_address, &self.server_name)
+            {
+                Ok(connecting) => connecting,
+                Err(ConnectionError::LocallyClosed) => return None,
+                Err(_) => continue,
+            };
+
+            if let Ok(connection) = connecting.await {
+                return Some(connection);
+            }
+        }
+    }
+}
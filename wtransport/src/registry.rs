@@ -0,0 +1,336 @@
+use crate::connection::Connection;
+use crate::connection::WeakConnection;
+use crate::error::DuplicateSession;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// What to do when a session registers with an identity already in use.
+///
+/// See [`SessionRegistry`].
+#[derive(Copy, Clone, Debug)]
+pub enum TakeoverPolicy {
+    /// The new session is closed with `error_code`. The existing one is kept.
+    RejectNew {
+        /// The application error code the new session is closed with.
+        error_code: u32,
+    },
+
+    /// The existing session is closed with `error_code` and replaced by the new one.
+    CloseExisting {
+        /// The application error code the existing session is closed with.
+        error_code: u32,
+    },
+}
+
+/// Tracks sessions by an application identity (e.g., a user ID resolved
+/// when authenticating the session request), detecting duplicates.
+///
+/// When a session registers with the identity of an already-registered
+/// session, one of the two is closed according to the [`TakeoverPolicy`].
+///
+/// A session stays registered as long as its [`SessionRegistration`] is
+/// alive, and as long as the session itself is (the registry does not keep
+/// sessions alive).
+///
+/// The registry can be cloned: clones share the same sessions.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// use wtransport::registry::SessionRegistry;
+/// use wtransport::registry::TakeoverPolicy;
+///
+/// # async fn run(connection: Connection, user_id: u64) {
+/// let registry = SessionRegistry::new(TakeoverPolicy::CloseExisting { error_code: 4001 });
+///
+/// let Ok(_registration) = registry.register(user_id, &connection).await else {
+///     return;
+/// };
+///
+/// // Serve the session...
+/// # }
+/// ```
+pub struct SessionRegistry<I> {
+    policy: TakeoverPolicy,
+    sessions: Arc<Mutex<Sessions<I>>>,
+}
+
+impl<I> SessionRegistry<I>
+where
+    I: Hash + Eq + Clone,
+{
+    /// Reason sent to the peer of a session closed by [`TakeoverPolicy::RejectNew`].
+    pub const REJECTED_REASON: &str = "duplicate session";
+
+    /// Reason sent to the peer of a session closed by [`TakeoverPolicy::CloseExisting`].
+    pub const TAKEN_OVER_REASON: &str = "session taken over";
+
+    /// Creates an empty registry.
+    pub fn new(policy: TakeoverPolicy) -> Self {
+        Self {
+            policy,
+            sessions: Arc::new(Mutex::new(Sessions {
+                entries: HashMap::new(),
+                next_id: 0,
+            })),
+        }
+    }
+
+    /// Registers `connection` with `identity`.
+    ///
+    /// If another live session is registered with the same `identity`:
+    ///  * with [`TakeoverPolicy::RejectNew`], `connection` is closed and
+    ///    [`DuplicateSession`] is returned;
+    ///  * with [`TakeoverPolicy::CloseExisting`], the other session is closed
+    ///    and `connection` takes its place.
+    pub async fn register(
+        &self,
+        identity: I,
+        connection: &Connection,
+    ) -> Result<SessionRegistration<I>, DuplicateSession> {
+        let (id, existing) = self.sessions.lock().expect("Mutex poisoned").insert(
+            identity.clone(),
+            connection.downgrade(),
+            matches!(self.policy, TakeoverPolicy::RejectNew { .. }),
+        );
+
+        let Some(id) = id else {
+            if let TakeoverPolicy::RejectNew { error_code } = self.policy {
+                connection.close(error_code, Self::REJECTED_REASON).await;
+            }
+            return Err(DuplicateSession);
+        };
+
+        if let (TakeoverPolicy::CloseExisting { error_code }, Some(existing)) =
+            (self.policy, existing)
+        {
+            existing.close(error_code, Self::TAKEN_OVER_REASON).await;
+        }
+
+        Ok(SessionRegistration {
+            identity,
+            id,
+            sessions: self.sessions.clone(),
+        })
+    }
+
+    /// Returns the live session registered with `identity`, if any.
+    pub fn get(&self, identity: &I) -> Option<Connection> {
+        self.sessions.lock().expect("Mutex poisoned").get(identity)
+    }
+
+    /// Returns the number of registered sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().expect("Mutex poisoned").entries.len()
+    }
+
+    /// Returns `true` if no session is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<I> Clone for SessionRegistry<I> {
+    fn clone(&self) -> Self {
+        Self {
+            policy: self.policy,
+            sessions: self.sessions.clone(),
+        }
+    }
+}
+
+/// The registration of a session in a [`SessionRegistry`].
+///
+/// The session is unregistered when this is dropped (unless another
+/// session has taken over its identity in the meantime).
+pub struct SessionRegistration<I>
+where
+    I: Hash + Eq,
+{
+    identity: I,
+    id: u64,
+    sessions: Arc<Mutex<Sessions<I>>>,
+}
+
+impl<I> SessionRegistration<I>
+where
+    I: Hash + Eq,
+{
+    /// The identity the session is registered with.
+    #[inline(always)]
+    pub fn identity(&self) -> &I {
+        &self.identity
+    }
+}
+
+impl<I> Drop for SessionRegistration<I>
+where
+    I: Hash + Eq,
+{
+    fn drop(&mut self) {
+        if let Ok(mut sessions) = self.sessions.lock() {
+            sessions.remove(&self.identity, self.id);
+        }
+    }
+}
+
+/// A handle to a registered session, not keeping it alive.
+trait WeakSession {
+    type Session;
+
+    fn upgrade(&self) -> Option<Self::Session>;
+}
+
+impl WeakSession for WeakConnection {
+    type Session = Connection;
+
+    fn upgrade(&self) -> Option<Connection> {
+        WeakConnection::upgrade(self)
+    }
+}
+
+struct Sessions<I, W = WeakConnection> {
+    entries: HashMap<I, Entry<W>>,
+    next_id: u64,
+}
+
+impl<I, W> Sessions<I, W>
+where
+    I: Hash + Eq,
+    W: WeakSession,
+{
+    /// Registers `session` with `identity`, unless `reject_duplicate` is set
+    /// and another live session is registered with it.
+    ///
+    /// Returns the id of the registration ([`None`] if rejected) and the other
+    /// live session, if any.
+    fn insert(
+        &mut self,
+        identity: I,
+        session: W,
+        reject_duplicate: bool,
+    ) -> (Option<u64>, Option<W::Session>) {
+        let existing = self.get(&identity);
+
+        if existing.is_some() && reject_duplicate {
+            return (None, existing);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.insert(identity, Entry { id, session });
+
+        (Some(id), existing)
+    }
+
+    fn get(&self, identity: &I) -> Option<W::Session> {
+        self.entries
+            .get(identity)
+            .and_then(|entry| entry.session.upgrade())
+    }
+
+    /// Unregisters the registration `id` of `identity`, unless it has been
+    /// taken over.
+    fn remove(&mut self, identity: &I, id: u64) {
+        if matches!(self.entries.get(identity), Some(entry) if entry.id == id) {
+            self.entries.remove(identity);
+        }
+    }
+}
+
+struct Entry<W> {
+    id: u64,
+    session: W,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Weak;
+
+    impl<T> WeakSession for Weak<T> {
+        type Session = Arc<T>;
+
+        fn upgrade(&self) -> Option<Arc<T>> {
+            Weak::upgrade(self)
+        }
+    }
+
+    fn sessions() -> Sessions<&'static str, Weak<u32>> {
+        Sessions {
+            entries: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    #[test]
+    fn register() {
+        let mut sessions = sessions();
+        let alice = Arc::new(1);
+        let bob = Arc::new(2);
+
+        assert!(sessions.get(&"alice").is_none());
+
+        let (id, existing) = sessions.insert("alice", Arc::downgrade(&alice), true);
+        assert_eq!(id, Some(0));
+        assert!(existing.is_none());
+
+        let (id, existing) = sessions.insert("bob", Arc::downgrade(&bob), true);
+        assert_eq!(id, Some(1));
+        assert!(existing.is_none());
+
+        assert_eq!(sessions.get(&"alice").as_deref(), Some(&1));
+        assert_eq!(sessions.get(&"bob").as_deref(), Some(&2));
+        assert_eq!(sessions.entries.len(), 2);
+
+        sessions.remove(&"alice", 0);
+        assert!(sessions.get(&"alice").is_none());
+        assert_eq!(sessions.get(&"bob").as_deref(), Some(&2));
+    }
+
+    #[test]
+    fn reject_duplicate() {
+        let mut sessions = sessions();
+        let first = Arc::new(1);
+        let second = Arc::new(2);
+
+        sessions.insert("alice", Arc::downgrade(&first), true);
+
+        let (id, existing) = sessions.insert("alice", Arc::downgrade(&second), true);
+        assert!(id.is_none());
+        assert_eq!(existing.as_deref(), Some(&1));
+        assert_eq!(sessions.get(&"alice").as_deref(), Some(&1));
+
+        // A session no longer alive does not count
+        drop(first);
+        drop(existing);
+
+        let (id, existing) = sessions.insert("alice", Arc::downgrade(&second), true);
+        assert_eq!(id, Some(1));
+        assert!(existing.is_none());
+        assert_eq!(sessions.get(&"alice").as_deref(), Some(&2));
+    }
+
+    #[test]
+    fn takeover() {
+        let mut sessions = sessions();
+        let first = Arc::new(1);
+        let second = Arc::new(2);
+
+        sessions.insert("alice", Arc::downgrade(&first), false);
+
+        let (id, existing) = sessions.insert("alice", Arc::downgrade(&second), false);
+        assert_eq!(id, Some(1));
+        assert_eq!(existing.as_deref(), Some(&1));
+        assert_eq!(sessions.get(&"alice").as_deref(), Some(&2));
+
+        // The registration taken over does not unregister the new one
+        sessions.remove(&"alice", 0);
+        assert_eq!(sessions.get(&"alice").as_deref(), Some(&2));
+
+        sessions.remove(&"alice", 1);
+        assert!(sessions.entries.is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::accept::Refusal;
+use crate::endpoint::Server;
+use crate::runtime;
+use crate::Connection;
+use crate::Endpoint;
+use crate::ServerConfig;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+type Handler =
+    Arc<dyn Fn(Connection, Params) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>;
+
+/// Dispatches sessions to handlers according to the `:path` of their request.
+///
+/// Routes are path patterns made of `/`-separated segments: a segment is
+/// either literal (e.g., `game`) or a parameter (e.g., `{room}`), which
+/// matches any single segment. The query string (if any) is ignored when
+/// matching. Routes are tried in the order they are added.
+///
+/// Once installed on the server configuration (see [`Router::install`]),
+/// requests whose path matches no route (or without path) are refused with
+/// `404 Not Found`.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::ServerConfig;
+/// # use wtransport::Endpoint;
+/// use wtransport::router::Router;
+///
+/// # async fn run(mut config: ServerConfig) {
+/// let router = Router::new()
+///     .route("/game/{room}", |connection, params| async move {
+///         let room = params.get("room").unwrap_or_default();
+///         // ...
+///     })
+///     .route("/chat", |connection, _params| async move {
+///         // ...
+///     });
+///
+/// router.install(&mut config);
+///
+/// let endpoint = Endpoint::server(config).unwrap();
+/// router.serve(&endpoint).await;
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// Creates a router with no routes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route: `handler` is invoked with the sessions whose path
+    /// matches `pattern`, along with the parameters extracted from it.
+    pub fn route<F, Fut>(mut self, pattern: &str, handler: F) -> Self
+    where
+        F: Fn(Connection, Params) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.routes.push(Route {
+            pattern: Segment::parse(pattern),
+            handler: Arc::new(move |connection, params| Box::pin(handler(connection, params))),
+        });
+        self
+    }
+
+    /// Refuses the session requests whose path matches no route with
+    /// `404 Not Found`, before they are accepted.
+    ///
+    /// The check is added to the accept hooks of `config` (see
+    /// [`ServerConfig::add_accept_hook`]).
+    pub fn install(&self, config: &mut ServerConfig) {
+        let router = self.clone();
+
+        config.add_accept_hook(move |request| {
+            let found = router.find(request.path()).is_some();
+
+            async move {
+                if found {
+                    Ok(request)
+                } else {
+                    Err(Refusal::new(404))
+                }
+            }
+        });
+    }
+
+    /// Runs the handler of the route matching the path of `connection`, and
+    /// waits for it to complete.
+    ///
+    /// If no route matches (or the request has no path), the session is closed.
+    pub async fn dispatch(&self, connection: Connection) {
+        match self.find(connection.request_path()) {
+            Some((handler, params)) => handler(connection, params).await,
+            None => connection.close(0, "Not found").await,
+        }
+    }
+
+    /// Accepts the incoming connections of `endpoint`, and dispatches each
+    /// one in its own task (see [`Router::dispatch`]).
+    ///
+    /// It returns once the endpoint is closed, or it is shutting down.
+    /// Connections which fail to establish are discarded.
+    pub async fn serve(&self, endpoint: &Endpoint<Server>) {
+        let router = Arc::new(self.clone());
+
+        while let Some(connecting) = endpoint.accept().await {
+            let router = router.clone();
+
+            runtime::spawn(async move {
+                if let Ok(connection) = connecting.await {
+                    router.dispatch(connection).await;
+                }
+            });
+        }
+    }
+
+    fn find(&self, path: Option<&str>) -> Option<(Handler, Params)> {
+        let path = path?;
+        let path = path.split_once('?').map_or(path, |(path, _query)| path);
+
+        self.routes.iter().find_map(|route| {
+            route
+                .matches(path)
+                .map(|params| (route.handler.clone(), params))
+        })
+    }
+}
+
+/// The parameters extracted from the path of a session request by its route.
+#[derive(Clone, Debug, Default)]
+pub struct Params(Vec<(String, String)>);
+
+impl Params {
+    /// Returns the value of the parameter `name` (e.g., `"room"` for `{room}`).
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all the parameters, in the order they appear in the pattern.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+}
+
+#[derive(Clone)]
+struct Route {
+    pattern: Vec<Segment>,
+    handler: Handler,
+}
+
+impl Route {
+    fn matches(&self, path: &str) -> Option<Params> {
+        let mut segments = path.strip_prefix('/').unwrap_or(path).split('/');
+        let mut params = Vec::new();
+
+        for expected in &self.pattern {
+            let segment = segments.next()?;
+
+            match expected {
+                Segment::Literal(literal) if literal == segment => {}
+                Segment::Param(name) if !segment.is_empty() => {
+                    params.push((name.clone(), segment.to_string()));
+                }
+                Segment::Literal(_) | Segment::Param(_) => return None,
+            }
+        }
+
+        match segments.next() {
+            None => Some(Params(params)),
+            Some(_) => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Segment {
+    Literal(String),
+    Param(String),
+}
+
+impl Segment {
+    fn parse(pattern: &str) -> Vec<Self> {
+        pattern
+            .strip_prefix('/')
+            .unwrap_or(pattern)
+            .split('/')
+            .map(|segment| {
+                match segment
+                    .strip_prefix('{')
+                    .and_then(|segment| segment.strip_suffix('}'))
+                {
+                    Some(name) => Segment::Param(name.to_string()),
+                    None => Segment::Literal(segment.to_string()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router(patterns: &[&str]) -> Router {
+        patterns.iter().fold(Router::new(), |router, pattern| {
+            router.route(pattern, |_connection, _params| async {})
+        })
+    }
+
+    fn params(router: &Router, path: &str) -> Option<Vec<(String, String)>> {
+        router.find(Some(path)).map(|(_handler, params)| params.0)
+    }
+
+    fn param(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn exact() {
+        let router = router(&["/chat", "/game/lobby"]);
+
+        assert_eq!(params(&router, "/chat"), Some(vec![]));
+        assert_eq!(params(&router, "/chat?user=1"), Some(vec![]));
+        assert_eq!(params(&router, "/game/lobby"), Some(vec![]));
+
+        assert!(params(&router, "/").is_none());
+        assert!(params(&router, "/cha").is_none());
+        assert!(params(&router, "/chat/").is_none());
+        assert!(params(&router, "/game").is_none());
+        assert!(params(&router, "/game/lobby/1").is_none());
+    }
+
+    #[test]
+    fn parameters() {
+        let router = router(&["/game/{room}/{player}"]);
+
+        assert_eq!(
+            params(&router, "/game/1/alice?spectate"),
+            Some(vec![param("room", "1"), param("player", "alice")])
+        );
+
+        // Parameters match exactly one non-empty segment
+        assert!(params(&router, "/game/1").is_none());
+        assert!(params(&router, "/game//alice").is_none());
+        assert!(params(&router, "/game/1/alice/2").is_none());
+
+        let (_handler, found) = router.find(Some("/game/2/bob")).unwrap();
+        assert_eq!(found.get("player"), Some("bob"));
+        assert_eq!(found.get("score"), None);
+        assert_eq!(
+            found.iter().collect::<Vec<_>>(),
+            [("room", "2"), ("player", "bob")]
+        );
+    }
+
+    #[test]
+    fn order() {
+        let router = router(&["/game/lobby", "/game/{room}", "/{page}", "/"]);
+
+        // The first matching route wins
+        assert_eq!(params(&router, "/game/lobby"), Some(vec![]));
+        assert_eq!(params(&router, "/game/7"), Some(vec![param("room", "7")]));
+
+        // Fallback on the catch-all routes
+        assert_eq!(
+            params(&router, "/about"),
+            Some(vec![param("page", "about")])
+        );
+        assert_eq!(params(&router, "/"), Some(vec![]));
+        assert!(params(&router, "/about/team").is_none());
+    }
+
+    #[test]
+    fn missing_path() {
+        let router = router(&["/"]);
+
+        assert!(router.find(None).is_none());
+        assert!(router.find(Some("/")).is_some());
+
+        assert!(Router::new().find(Some("/")).is_none());
+    }
+}
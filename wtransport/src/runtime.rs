@@ -0,0 +1,484 @@
+use crate::config::DriverRuntime;
+use crate::marking::EcnCounters;
+use crate::marking::MarkingSocket;
+use crate::marking::PacketMarking;
+#[cfg(feature = "mock-network")]
+use crate::mock::MockNetwork;
+#[cfg(feature = "mock-network")]
+use crate::mock::MockSocket;
+use crate::proxy::Proxy;
+use crate::proxy::ProxySocket;
+use quinn::AsyncTimer;
+use quinn::Endpoint as QuicEndpoint;
+use quinn::EndpointConfig;
+use quinn::ServerConfig as QuicServerConfig;
+use rustls::ClientConfig as TlsClientConfig;
+use std::fmt;
+use std::fmt::Debug;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::net::UdpSocket;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
+#[cfg(feature = "tokio")]
+use tokio::runtime::Handle;
+use tokio::sync::oneshot;
+use tokio::sync::Notify;
+
+/// Re-export of the quinn socket interface (see [`Runtime::wrap_udp_socket`]).
+pub use quinn::AsyncUdpSocket;
+
+/// An async runtime: it runs the tasks and the timers of the endpoints, and
+/// performs their UDP I/O.
+///
+/// `TokioRuntime` (with the `tokio` feature, enabled by default) is the
+/// default one. With the `async-std` feature, `AsyncStdRuntime` lets
+/// applications based on *async-std* or *smol* use wtransport without running
+/// a tokio runtime (the `tokio` feature can be disabled then).
+///
+/// Custom runtimes are installed with [`DriverRuntime::Custom`].
+pub trait Runtime: Send + Sync + Debug + 'static {
+    /// Runs `future` in the background.
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>);
+
+    /// Returns a timer, completing at `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    /// Registers `socket` with the I/O driver of the runtime.
+    fn wrap_udp_socket(&self, socket: UdpSocket) -> std::io::Result<Box<dyn AsyncUdpSocket>>;
+}
+
+/// The [tokio](https://tokio.rs) [`Runtime`].
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct TokioRuntime(Handle);
+
+#[cfg(feature = "tokio")]
+impl TokioRuntime {
+    /// The runtime of `handle`.
+    pub fn new(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    /// The runtime the caller runs on.
+    ///
+    /// It fails outside a tokio runtime.
+    pub fn current() -> std::io::Result<Self> {
+        Handle::try_current()
+            .map(Self)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        self.0.spawn(future);
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let _guard = self.0.enter();
+        Box::pin(tokio::time::sleep_until(deadline.into()))
+    }
+
+    fn wrap_udp_socket(&self, socket: UdpSocket) -> std::io::Result<Box<dyn AsyncUdpSocket>> {
+        let _guard = self.0.enter();
+        quinn::Runtime::wrap_udp_socket(&quinn::TokioRuntime, socket)
+    }
+}
+
+/// The [async-std](https://async.rs) [`Runtime`].
+///
+/// Its reactor and executor are the ones of *smol* too: applications based on
+/// *smol* can use it as well.
+#[cfg(feature = "async-std")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AsyncStdRuntime;
+
+#[cfg(feature = "async-std")]
+impl Runtime for AsyncStdRuntime {
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        quinn::Runtime::spawn(&quinn::AsyncStdRuntime, future);
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let mut timer = quinn::Runtime::new_timer(&quinn::AsyncStdRuntime, deadline);
+        Box::pin(std::future::poll_fn(move |cx| timer.as_mut().poll(cx)))
+    }
+
+    fn wrap_udp_socket(&self, socket: UdpSocket) -> std::io::Result<Box<dyn AsyncUdpSocket>> {
+        quinn::Runtime::wrap_udp_socket(&quinn::AsyncStdRuntime, socket)
+    }
+}
+
+/// A timer of a [`Runtime`].
+pub(crate) type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Runs `f` with the runtime the caller runs on: tokio (if any, with the
+/// `tokio` feature), otherwise async-std (with the `async-std` feature).
+///
+/// # Panics
+///
+/// Panics outside a tokio runtime, without the `async-std` feature.
+fn with_current<F, R>(f: F) -> R
+where
+    F: FnOnce(&dyn Runtime) -> R,
+{
+    #[cfg(feature = "tokio")]
+    if let Ok(handle) = Handle::try_current() {
+        return f(&TokioRuntime(handle));
+    }
+
+    #[cfg(feature = "async-std")]
+    {
+        f(&AsyncStdRuntime)
+    }
+
+    #[cfg(not(feature = "async-std"))]
+    {
+        let _ = f;
+        panic!("wtransport must be used within a tokio runtime")
+    }
+}
+
+/// Runs `future` on the runtime the caller runs on.
+pub(crate) fn spawn<F>(future: F) -> Task<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    with_current(|runtime| spawn_on(runtime, future))
+}
+
+/// Runs `future` on `runtime`.
+pub(crate) fn spawn_on<F>(runtime: &dyn Runtime, future: F) -> Task<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let abort = Arc::new(Notify::new());
+    let (output_sender, output) = oneshot::channel();
+
+    let aborted = abort.clone();
+    runtime.spawn(Box::pin(async move {
+        tokio::select! {
+            output = future => {
+                let _ = output_sender.send(output);
+            }
+            () = aborted.notified() => {}
+        }
+    }));
+
+    Task { abort, output }
+}
+
+/// Runs the blocking `f` off the runtime threads, returning its output
+/// (or [`None`] if it panicked).
+///
+/// It runs on the blocking pool of tokio (if the caller runs on it, with the
+/// `tokio` feature), otherwise on its own thread.
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> Option<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(feature = "tokio")]
+    if let Ok(handle) = Handle::try_current() {
+        return handle.spawn_blocking(f).await.ok();
+    }
+
+    let (output_sender, output) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = output_sender.send(f());
+    });
+
+    output.await.ok()
+}
+
+/// Completes after `duration`, on the runtime the caller runs on.
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    sleep_until(Instant::now() + duration)
+}
+
+/// Completes at `deadline`, on the runtime the caller runs on.
+pub(crate) fn sleep_until(deadline: Instant) -> Sleep {
+    with_current(|runtime| runtime.sleep_until(deadline))
+}
+
+/// Runs `future` for up to `duration`.
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Result<F::Output, Elapsed>
+where
+    F: Future,
+{
+    timeout_at(Instant::now() + duration, future).await
+}
+
+/// Runs `future` until `deadline`.
+pub(crate) async fn timeout_at<F>(deadline: Instant, future: F) -> Result<F::Output, Elapsed>
+where
+    F: Future,
+{
+    tokio::select! {
+        biased;
+        output = future => Ok(output),
+        () = sleep_until(deadline) => Err(Elapsed),
+    }
+}
+
+/// The error of [`timeout`] and [`timeout_at`].
+#[derive(Debug)]
+pub(crate) struct Elapsed;
+
+/// A task spawned on a [`Runtime`].
+///
+/// Awaiting it returns its output, or [`None`] if it has been aborted.
+/// Dropping it does not abort the task.
+pub(crate) struct Task<T> {
+    abort: Arc<Notify>,
+    output: oneshot::Receiver<T>,
+}
+
+impl<T> Task<T> {
+    /// Stops the task at its next suspension point.
+    pub(crate) fn abort(&self) {
+        self.abort.notify_one();
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.output).poll(cx).map(Result::ok)
+    }
+}
+
+/// Adapts a [`Runtime`] to quinn.
+pub(crate) fn quic_runtime(runtime: Arc<dyn Runtime>) -> Arc<dyn quinn::Runtime> {
+    Arc::new(QuicRuntime(runtime))
+}
+
+/// Name of the threads running a [`DriverRuntime::Dedicated`] runtime.
+#[cfg(feature = "tokio")]
+const DEDICATED_THREAD_NAME: &str = "wtransport-driver";
+
+/// Where the endpoint sends and receives packets.
+pub(crate) enum DriverSocket {
+    /// A UDP socket bound to the address.
+    Udp(SocketAddr),
+
+    /// An already-bound UDP socket.
+    Bound(UdpSocket),
+
+    /// A tunnel through a proxy, reached with the UDP socket.
+    Proxy(Proxy, Arc<TlsClientConfig>, UdpSocket),
+
+    /// A socket of an in-process network, bound to the (virtual) address.
+    #[cfg(feature = "mock-network")]
+    Mock(MockNetwork, SocketAddr),
+}
+
+/// Socket and runtime for the QUIC endpoint driver.
+pub(crate) struct EndpointDriver {
+    socket: BoundSocket,
+    runtime: Arc<dyn Runtime>,
+    dedicated: Option<DedicatedThread>,
+}
+
+impl EndpointDriver {
+    pub(crate) fn bind(
+        driver_socket: DriverSocket,
+        driver_runtime: &DriverRuntime,
+    ) -> std::io::Result<Self> {
+        let (runtime, dedicated): (Arc<dyn Runtime>, _) = match driver_runtime {
+            DriverRuntime::Current => (Self::current()?, None),
+            #[cfg(feature = "tokio")]
+            DriverRuntime::Handle(handle) => (Arc::new(TokioRuntime::new(handle.clone())), None),
+            #[cfg(feature = "tokio")]
+            DriverRuntime::Dedicated => {
+                let dedicated = DedicatedThread::spawn()?;
+                (
+                    Arc::new(TokioRuntime::new(dedicated.handle.clone())),
+                    Some(dedicated),
+                )
+            }
+            DriverRuntime::Custom(runtime) => (runtime.clone(), None),
+        };
+
+        let socket = match driver_socket {
+            DriverSocket::Udp(bind_address) => BoundSocket::Udp(UdpSocket::bind(bind_address)?),
+            DriverSocket::Bound(socket) => BoundSocket::Udp(socket),
+            DriverSocket::Proxy(proxy, tls_config, socket) => BoundSocket::Proxy(
+                ProxySocket::bind(proxy, tls_config, socket, runtime.clone())?,
+            ),
+            #[cfg(feature = "mock-network")]
+            DriverSocket::Mock(network, bind_address) => {
+                BoundSocket::Mock(network.bind(bind_address)?)
+            }
+        };
+
+        Ok(Self {
+            socket,
+            runtime,
+            dedicated,
+        })
+    }
+
+    /// The runtime the caller runs on (see [`DriverRuntime::Current`]).
+    fn current() -> std::io::Result<Arc<dyn Runtime>> {
+        #[cfg(feature = "tokio")]
+        if let Ok(runtime) = TokioRuntime::current() {
+            return Ok(Arc::new(runtime));
+        }
+
+        #[cfg(feature = "async-std")]
+        {
+            Ok(Arc::new(AsyncStdRuntime))
+        }
+
+        #[cfg(not(feature = "async-std"))]
+        {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "no tokio runtime is running",
+            ))
+        }
+    }
+
+    /// The runtime the driver runs on.
+    pub(crate) fn runtime(&self) -> &Arc<dyn Runtime> {
+        &self.runtime
+    }
+
+    /// Creates the QUIC endpoint (with its driver).
+    ///
+    /// `marking` applies to UDP sockets only (i.e., not to proxy tunnels), whose
+    /// received ECN codepoints are counted in `ecn_counters`.
+    ///
+    /// The dedicated runtime thread, if any, must be kept as long as the endpoint.
+    pub(crate) fn into_endpoint(
+        self,
+        server_config: Option<QuicServerConfig>,
+        marking: PacketMarking,
+        ecn_counters: Arc<EcnCounters>,
+    ) -> std::io::Result<(QuicEndpoint, Option<DedicatedThread>)> {
+        let runtime = quic_runtime(self.runtime);
+
+        let endpoint = match self.socket {
+            BoundSocket::Udp(socket) => QuicEndpoint::new_with_abstract_socket(
+                EndpointConfig::default(),
+                server_config,
+                MarkingSocket::new(socket, marking, &runtime, ecn_counters)?,
+                runtime,
+            )?,
+            BoundSocket::Proxy(socket) => QuicEndpoint::new_with_abstract_socket(
+                EndpointConfig::default(),
+                server_config,
+                socket,
+                runtime,
+            )?,
+            #[cfg(feature = "mock-network")]
+            BoundSocket::Mock(socket) => QuicEndpoint::new_with_abstract_socket(
+                EndpointConfig::default(),
+                server_config,
+                socket,
+                runtime,
+            )?,
+        };
+
+        Ok((endpoint, self.dedicated))
+    }
+}
+
+enum BoundSocket {
+    Udp(UdpSocket),
+    Proxy(ProxySocket),
+    #[cfg(feature = "mock-network")]
+    Mock(MockSocket),
+}
+
+/// A quinn runtime spawning tasks and timers on a [`Runtime`], regardless of
+/// the context the endpoint is used from.
+#[derive(Debug)]
+struct QuicRuntime(Arc<dyn Runtime>);
+
+impl quinn::Runtime for QuicRuntime {
+    fn new_timer(&self, deadline: Instant) -> Pin<Box<dyn AsyncTimer>> {
+        Box::pin(QuicTimer {
+            runtime: self.0.clone(),
+            sleep: self.0.sleep_until(deadline),
+        })
+    }
+
+    fn spawn(&self, future: Pin<Box<dyn Future<Output = ()> + Send>>) {
+        self.0.spawn(future);
+    }
+
+    fn wrap_udp_socket(&self, socket: UdpSocket) -> std::io::Result<Box<dyn AsyncUdpSocket>> {
+        self.0.wrap_udp_socket(socket)
+    }
+}
+
+/// A quinn timer backed by the timers of a [`Runtime`].
+struct QuicTimer {
+    runtime: Arc<dyn Runtime>,
+    sleep: Sleep,
+}
+
+impl AsyncTimer for QuicTimer {
+    fn reset(mut self: Pin<&mut Self>, deadline: Instant) {
+        self.sleep = self.runtime.sleep_until(deadline);
+    }
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.sleep.as_mut().poll(cx)
+    }
+}
+
+impl Debug for QuicTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicTimer").finish()
+    }
+}
+
+/// A *current-thread* runtime running on its own OS thread.
+///
+/// The thread exits (and all the tasks running on it are dropped) when this is dropped.
+#[cfg(feature = "tokio")]
+pub(crate) struct DedicatedThread {
+    handle: Handle,
+    _shutdown: oneshot::Sender<()>,
+}
+
+/// Without the `tokio` feature there are no dedicated runtimes.
+#[cfg(not(feature = "tokio"))]
+pub(crate) enum DedicatedThread {}
+
+#[cfg(feature = "tokio")]
+impl DedicatedThread {
+    fn spawn() -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let handle = runtime.handle().clone();
+        let (shutdown, shutdown_recv) = oneshot::channel();
+
+        std::thread::Builder::new()
+            .name(DEDICATED_THREAD_NAME.to_string())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    let _ = shutdown_recv.await;
+                });
+            })?;
+
+        Ok(Self {
+            handle,
+            _shutdown: shutdown,
+        })
+    }
+}
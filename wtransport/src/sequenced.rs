@@ -0,0 +1,785 @@
+use crate::error::DatagramError;
+use crate::runtime;
+use crate::Connection;
+use bytes::Bytes;
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::time::Duration;
+use std::time::Instant;
+use wtransport_proto::bytes::BytesReader;
+use wtransport_proto::varint::VarInt;
+
+/// Datagrams with sequence numbers, detecting losses and reordering.
+///
+/// Two delivery modes are available:
+///  - *latest-only* ([`Self::latest_only`]): a message is delivered only if it
+///    is newer than all the ones delivered before, late messages are
+///    discarded. It suits state updates, where only the newest one matters.
+///  - *retransmission* ([`Self::with_retransmission`]): messages are delivered
+///    in order. The receiver reports the gaps with NACKs, and the sender
+///    retransmits the missing messages, a bounded number of times. Messages
+///    still missing after [`Retransmission::with_max_wait`] are given up, and
+///    the following ones are delivered.
+///
+/// Every datagram starts with a *varint* kind. Messages carry their sequence
+/// number (a *varint*) followed by the payload; NACKs carry a list of missing
+/// ranges (pairs of *varints*: first sequence number and length).
+///
+/// NACKs are processed by [`Self::receive`]: in the retransmission mode, the
+/// sender must keep receiving as well.
+///
+/// Both peers must use this layer, in the same mode, from the start of the
+/// session: datagrams sent with [`Connection::send_datagram`] are not
+/// compatible with it.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// use wtransport::sequenced::Retransmission;
+/// use wtransport::sequenced::SequencedDatagrams;
+///
+/// # async fn run(connection: Connection) {
+/// let mut datagrams = SequencedDatagrams::with_retransmission(connection, Retransmission::new());
+///
+/// datagrams.send(b"input").unwrap();
+/// let message = datagrams.receive().await.unwrap();
+/// println!("#{}: {:?}", message.sequence(), message.payload());
+///
+/// println!("Loss rate: {}", datagrams.stats().loss_rate());
+/// # }
+/// ```
+pub struct SequencedDatagrams {
+    connection: Connection,
+    retransmission: Option<Retransmission>,
+    next_sequence: u64,
+    history: VecDeque<Sent>,
+    receiver: Receiver,
+    stats: SequenceStats,
+}
+
+impl SequencedDatagrams {
+    const MESSAGE: VarInt = VarInt::from_u32(0);
+    const NACK: VarInt = VarInt::from_u32(1);
+
+    /// Creates the layer on `connection`, delivering only the latest messages.
+    pub fn latest_only(connection: Connection) -> Self {
+        Self::new(connection, None)
+    }
+
+    /// Creates the layer on `connection`, delivering messages in order and
+    /// retransmitting the lost ones.
+    pub fn with_retransmission(connection: Connection, retransmission: Retransmission) -> Self {
+        Self::new(connection, Some(retransmission))
+    }
+
+    fn new(connection: Connection, retransmission: Option<Retransmission>) -> Self {
+        Self {
+            connection,
+            retransmission,
+            next_sequence: 0,
+            history: VecDeque::new(),
+            receiver: Receiver::new(),
+            stats: SequenceStats::default(),
+        }
+    }
+
+    /// Returns the underlying connection.
+    #[inline(always)]
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Returns the statistics of the layer.
+    #[inline(always)]
+    pub fn stats(&self) -> SequenceStats {
+        self.stats
+    }
+
+    /// Sends a message, returning its sequence number.
+    ///
+    /// The sequence number takes up to 9 bytes of the datagram (see
+    /// [`Connection::max_datagram_size`]).
+    pub fn send(&mut self, payload: &[u8]) -> Result<u64, DatagramError> {
+        let sequence = self.next_sequence;
+        let sequence_varint =
+            VarInt::try_from_u64(sequence).expect("Sequence numbers cannot be exhausted");
+
+        let mut datagram =
+            Vec::with_capacity(Self::MESSAGE.size() + sequence_varint.size() + payload.len());
+        for varint in [Self::MESSAGE, sequence_varint] {
+            let (bytes, len) = varint.encode_to_array();
+            datagram.extend_from_slice(&bytes[..len]);
+        }
+        datagram.extend_from_slice(payload);
+
+        self.connection.send_datagram(&datagram)?;
+
+        self.next_sequence += 1;
+        self.stats.sent += 1;
+
+        if let Some(retransmission) = &self.retransmission {
+            if self.history.len() == retransmission.history {
+                self.history.pop_front();
+            }
+
+            self.history.push_back(Sent {
+                sequence,
+                datagram: Bytes::from(datagram),
+                retransmits: 0,
+            });
+        }
+
+        Ok(sequence)
+    }
+
+    /// Receives the next message.
+    ///
+    /// Malformed datagrams are discarded.
+    pub async fn receive(&mut self) -> Result<SequencedMessage, DatagramError> {
+        loop {
+            let wake_at = self.handle_timers(Instant::now());
+
+            if let Some(message) = self.receiver.ready.pop_front() {
+                return Ok(message);
+            }
+
+            let datagram = match wake_at {
+                Some(wake_at) => tokio::select! {
+                    datagram = self.connection.receive_datagram() => datagram?,
+                    () = runtime::sleep_until(wake_at) => continue,
+                },
+                None => self.connection.receive_datagram().await?,
+            };
+
+            self.process(datagram.payload(), Instant::now());
+        }
+    }
+
+    fn process(&mut self, datagram: Bytes, now: Instant) {
+        let mut reader = &datagram[..];
+
+        match reader.get_varint() {
+            Some(Self::MESSAGE) => {
+                let Some(sequence) = reader.get_varint() else {
+                    return;
+                };
+                let payload = datagram.slice(datagram.len() - reader.len()..);
+
+                self.stats.received += 1;
+
+                let missing = self.receiver.push(
+                    sequence.into_inner(),
+                    payload,
+                    now,
+                    self.retransmission.as_ref(),
+                    &mut self.stats,
+                );
+
+                if let Some(missing) = missing {
+                    self.send_nack(&[missing]);
+                }
+            }
+            Some(Self::NACK) => {
+                while let (Some(first), Some(len)) = (reader.get_varint(), reader.get_varint()) {
+                    self.retransmit(first.into_inner(), len.into_inner());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Gives up the messages missing for too long and repeats the NACKs.
+    ///
+    /// Returns when it has to be called again.
+    fn handle_timers(&mut self, now: Instant) -> Option<Instant> {
+        let retransmission = self.retransmission.as_ref()?;
+        let nack_interval = retransmission.nack_interval;
+        let max_wait = retransmission.max_wait;
+
+        let mut give_up_at = self.receiver.give_up_at(max_wait);
+        while matches!(give_up_at, Some(deadline) if deadline <= now) {
+            self.receiver.give_up(&mut self.stats);
+            give_up_at = self.receiver.give_up_at(max_wait);
+        }
+
+        if self.receiver.pending.is_empty() {
+            self.receiver.next_nack = None;
+            return None;
+        }
+
+        let next_nack = *self.receiver.next_nack.get_or_insert(now + nack_interval);
+
+        let next_nack = if next_nack <= now {
+            let missing = self.receiver.missing();
+            self.send_nack(&missing);
+            let next_nack = now + nack_interval;
+            self.receiver.next_nack = Some(next_nack);
+            next_nack
+        } else {
+            next_nack
+        };
+
+        give_up_at.map(|give_up_at| give_up_at.min(next_nack))
+    }
+
+    /// Sends a NACK with the `missing` ranges (as many as fit in a datagram).
+    fn send_nack(&mut self, missing: &[(u64, u64)]) {
+        let Some(max_datagram_size) = self.connection.max_datagram_size() else {
+            return;
+        };
+
+        let (bytes, len) = Self::NACK.encode_to_array();
+        let mut datagram = bytes[..len].to_vec();
+
+        for &(first, len) in missing {
+            let (Ok(first), Ok(len)) = (VarInt::try_from_u64(first), VarInt::try_from_u64(len))
+            else {
+                break;
+            };
+
+            if datagram.len() + first.size() + len.size() > max_datagram_size {
+                break;
+            }
+
+            for varint in [first, len] {
+                let (bytes, len) = varint.encode_to_array();
+                datagram.extend_from_slice(&bytes[..len]);
+            }
+        }
+
+        if self.connection.send_datagram(datagram).is_ok() {
+            self.stats.nacks_sent += 1;
+        }
+    }
+
+    /// Retransmits the messages of a NACKed range still in the history.
+    fn retransmit(&mut self, first: u64, len: u64) {
+        let Some(retransmission) = &self.retransmission else {
+            return;
+        };
+
+        let Some(oldest) = self.history.front().map(|sent| sent.sequence) else {
+            return;
+        };
+
+        for index in history_range(oldest, self.history.len(), first, len) {
+            let sent = &mut self.history[index];
+
+            if sent.retransmits >= retransmission.max_retransmits {
+                continue;
+            }
+
+            if self.connection.send_datagram(&sent.datagram).is_ok() {
+                sent.retransmits += 1;
+                self.stats.retransmitted += 1;
+            }
+        }
+    }
+}
+
+/// Settings of the retransmission mode of [`SequencedDatagrams`].
+#[derive(Clone, Debug)]
+pub struct Retransmission {
+    history: usize,
+    max_retransmits: u32,
+    nack_interval: Duration,
+    max_wait: Duration,
+}
+
+impl Retransmission {
+    /// Default number of messages kept for retransmission.
+    pub const DEFAULT_HISTORY: usize = 256;
+
+    /// Default number of retransmissions of a message.
+    pub const DEFAULT_MAX_RETRANSMITS: u32 = 2;
+
+    /// Default interval between the NACKs of missing messages.
+    pub const DEFAULT_NACK_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// Default time to wait for a missing message.
+    pub const DEFAULT_MAX_WAIT: Duration = Duration::from_millis(250);
+
+    /// Creates the default settings.
+    pub fn new() -> Self {
+        Self {
+            history: Self::DEFAULT_HISTORY,
+            max_retransmits: Self::DEFAULT_MAX_RETRANSMITS,
+            nack_interval: Self::DEFAULT_NACK_INTERVAL,
+            max_wait: Self::DEFAULT_MAX_WAIT,
+        }
+    }
+
+    /// Sets how many sent messages are kept for retransmission.
+    ///
+    /// It also bounds the messages buffered by the receiver while waiting for
+    /// a missing one.
+    pub fn with_history(mut self, history: usize) -> Self {
+        self.history = history.max(1);
+        self
+    }
+
+    /// Sets how many times a message is retransmitted at most.
+    pub fn with_max_retransmits(mut self, max_retransmits: u32) -> Self {
+        self.max_retransmits = max_retransmits;
+        self
+    }
+
+    /// Sets how often the receiver repeats the NACKs of missing messages.
+    pub fn with_nack_interval(mut self, nack_interval: Duration) -> Self {
+        self.nack_interval = nack_interval;
+        self
+    }
+
+    /// Sets how long the receiver waits for a missing message before giving
+    /// it up (and delivering the following ones).
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+}
+
+impl Default for Retransmission {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A message received by [`SequencedDatagrams`].
+#[derive(Clone, Debug)]
+pub struct SequencedMessage {
+    sequence: u64,
+    payload: Bytes,
+}
+
+impl SequencedMessage {
+    /// Returns the sequence number of the message.
+    #[inline(always)]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns the payload of the message.
+    #[inline(always)]
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Returns the payload of the message, consuming it.
+    #[inline(always)]
+    pub fn into_payload(self) -> Bytes {
+        self.payload
+    }
+}
+
+/// Statistics of [`SequencedDatagrams`], see [`SequencedDatagrams::stats`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SequenceStats {
+    sent: u64,
+    retransmitted: u64,
+    nacks_sent: u64,
+    received: u64,
+    delivered: u64,
+    lost: u64,
+    discarded: u64,
+}
+
+impl SequenceStats {
+    /// The number of messages sent (excluding retransmissions).
+    #[inline(always)]
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// The number of messages retransmitted upon NACKs.
+    #[inline(always)]
+    pub fn retransmitted(&self) -> u64 {
+        self.retransmitted
+    }
+
+    /// The number of NACKs sent.
+    #[inline(always)]
+    pub fn nacks_sent(&self) -> u64 {
+        self.nacks_sent
+    }
+
+    /// The number of messages received (including duplicates).
+    #[inline(always)]
+    pub fn received(&self) -> u64 {
+        self.received
+    }
+
+    /// The number of messages delivered to the application.
+    #[inline(always)]
+    pub fn delivered(&self) -> u64 {
+        self.delivered
+    }
+
+    /// The number of messages never delivered: skipped in the latest-only
+    /// mode, or given up in the retransmission mode.
+    #[inline(always)]
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    /// The number of messages discarded because received late or twice.
+    #[inline(always)]
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// The fraction of messages lost, out of the delivered and lost ones.
+    pub fn loss_rate(&self) -> f64 {
+        let total = self.delivered + self.lost;
+
+        if total == 0 {
+            0.0
+        } else {
+            self.lost as f64 / total as f64
+        }
+    }
+}
+
+/// Returns the indexes, in a history of `history_len` messages starting at
+/// sequence number `oldest`, of the messages of a NACKed range.
+///
+/// Messages of the range no longer (or not yet) in the history are ignored.
+fn history_range(oldest: u64, history_len: usize, first: u64, len: u64) -> Range<usize> {
+    let end = first
+        .saturating_add(len)
+        .saturating_sub(oldest)
+        .min(history_len as u64);
+    let start = first.saturating_sub(oldest).min(end);
+
+    start as usize..end as usize
+}
+
+/// A message kept for retransmission.
+struct Sent {
+    sequence: u64,
+    datagram: Bytes,
+    retransmits: u32,
+}
+
+/// Receive side: orders the messages and detects the gaps.
+struct Receiver {
+    /// The sequence number of the next message to deliver.
+    next: u64,
+    /// Messages received out of order, with their arrival time.
+    pending: BTreeMap<u64, (Bytes, Instant)>,
+    ready: VecDeque<SequencedMessage>,
+    next_nack: Option<Instant>,
+}
+
+impl Receiver {
+    fn new() -> Self {
+        Self {
+            next: 0,
+            pending: BTreeMap::new(),
+            ready: VecDeque::new(),
+            next_nack: None,
+        }
+    }
+
+    /// Processes a message, returning the range of messages newly detected
+    /// as missing (in the retransmission mode).
+    fn push(
+        &mut self,
+        sequence: u64,
+        payload: Bytes,
+        now: Instant,
+        retransmission: Option<&Retransmission>,
+        stats: &mut SequenceStats,
+    ) -> Option<(u64, u64)> {
+        if sequence < self.next || self.pending.contains_key(&sequence) {
+            stats.discarded += 1;
+            return None;
+        }
+
+        let Some(retransmission) = retransmission else {
+            stats.lost += sequence - self.next;
+            self.deliver(sequence, payload, stats);
+            return None;
+        };
+
+        if sequence == self.next {
+            self.deliver(sequence, payload, stats);
+            self.drain(stats);
+            return None;
+        }
+
+        let highest = self
+            .pending
+            .keys()
+            .next_back()
+            .map_or(self.next, |highest| highest + 1);
+
+        self.pending.insert(sequence, (payload, now));
+
+        // The window of buffered messages cannot exceed the sender's history
+        let window_start = (sequence + 1).saturating_sub(retransmission.history as u64);
+        if window_start > self.next {
+            self.skip_to(window_start, stats);
+        }
+
+        let from = highest.max(self.next);
+        (sequence > from).then(|| (from, sequence - from))
+    }
+
+    /// Returns when the first missing message has to be given up.
+    fn give_up_at(&self, max_wait: Duration) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|(_, arrival)| *arrival)
+            .min()
+            .map(|arrival| arrival + max_wait)
+    }
+
+    /// Gives up the messages missing before the first buffered one.
+    fn give_up(&mut self, stats: &mut SequenceStats) {
+        if let Some(&first) = self.pending.keys().next() {
+            self.skip_to(first, stats);
+        }
+    }
+
+    /// Returns the ranges (first sequence number and length) of the missing
+    /// messages.
+    fn missing(&self) -> Vec<(u64, u64)> {
+        let mut ranges = Vec::new();
+        let mut from = self.next;
+
+        for &sequence in self.pending.keys() {
+            if sequence > from {
+                ranges.push((from, sequence - from));
+            }
+            from = sequence + 1;
+        }
+
+        ranges
+    }
+
+    /// Delivers the buffered messages before `target`, counting the missing
+    /// ones as lost, then the ones following in order.
+    fn skip_to(&mut self, target: u64, stats: &mut SequenceStats) {
+        let later = self.pending.split_off(&target);
+
+        for (sequence, (payload, _)) in std::mem::replace(&mut self.pending, later) {
+            stats.lost += sequence - self.next;
+            self.deliver(sequence, payload, stats);
+        }
+
+        stats.lost += target.saturating_sub(self.next);
+        self.next = self.next.max(target);
+
+        self.drain(stats);
+    }
+
+    /// Delivers the buffered messages following in order.
+    fn drain(&mut self, stats: &mut SequenceStats) {
+        while let Some((payload, _)) = self.pending.remove(&self.next) {
+            self.deliver(self.next, payload, stats);
+        }
+    }
+
+    fn deliver(&mut self, sequence: u64, payload: Bytes, stats: &mut SequenceStats) {
+        self.ready.push_back(SequencedMessage { sequence, payload });
+        self.next = sequence + 1;
+        stats.delivered += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn push(
+        receiver: &mut Receiver,
+        sequence: u64,
+        retransmission: Option<&Retransmission>,
+        stats: &mut SequenceStats,
+    ) -> Option<(u64, u64)> {
+        receiver.push(
+            sequence,
+            Bytes::from(sequence.to_string()),
+            Instant::now(),
+            retransmission,
+            stats,
+        )
+    }
+
+    fn ready(receiver: &Receiver) -> Vec<u64> {
+        receiver
+            .ready
+            .iter()
+            .map(|message| message.sequence)
+            .collect()
+    }
+
+    #[test]
+    fn in_order() {
+        let retransmission = Retransmission::new();
+        let mut receiver = Receiver::new();
+        let mut stats = SequenceStats::default();
+
+        for sequence in 0..3 {
+            assert!(push(&mut receiver, sequence, Some(&retransmission), &mut stats).is_none());
+        }
+
+        assert_eq!(ready(&receiver), [0, 1, 2]);
+        assert_eq!(receiver.ready[1].payload().as_ref(), b"1");
+        assert!(receiver.missing().is_empty());
+        assert_eq!(stats.delivered(), 3);
+        assert_eq!(stats.lost(), 0);
+        assert_eq!(stats.discarded(), 0);
+    }
+
+    #[test]
+    fn gaps() {
+        let retransmission = Retransmission::new();
+        let mut receiver = Receiver::new();
+        let mut stats = SequenceStats::default();
+
+        assert!(push(&mut receiver, 0, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(
+            push(&mut receiver, 3, Some(&retransmission), &mut stats),
+            Some((1, 2))
+        );
+        assert_eq!(
+            push(&mut receiver, 5, Some(&retransmission), &mut stats),
+            Some((4, 1))
+        );
+        assert_eq!(receiver.missing(), [(1, 2), (4, 1)]);
+
+        // Gaps already reported are not reported again
+        assert!(push(&mut receiver, 2, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(receiver.missing(), [(1, 1), (4, 1)]);
+        assert_eq!(ready(&receiver), [0]);
+
+        assert!(push(&mut receiver, 1, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(ready(&receiver), [0, 1, 2, 3]);
+        assert_eq!(receiver.missing(), [(4, 1)]);
+
+        assert!(push(&mut receiver, 4, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(ready(&receiver), [0, 1, 2, 3, 4, 5]);
+        assert!(receiver.missing().is_empty());
+        assert_eq!(stats.delivered(), 6);
+        assert_eq!(stats.lost(), 0);
+    }
+
+    #[test]
+    fn window_overflow() {
+        let retransmission = Retransmission::new().with_history(4);
+        let mut receiver = Receiver::new();
+        let mut stats = SequenceStats::default();
+
+        assert!(push(&mut receiver, 0, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(
+            push(&mut receiver, 2, Some(&retransmission), &mut stats),
+            Some((1, 1))
+        );
+
+        // The window slides to 3..=6: 1 is lost, 2 is delivered
+        assert_eq!(
+            push(&mut receiver, 6, Some(&retransmission), &mut stats),
+            Some((3, 3))
+        );
+        assert_eq!(ready(&receiver), [0, 2]);
+        assert_eq!(receiver.missing(), [(3, 3)]);
+        assert_eq!(stats.lost(), 1);
+
+        // The window slides to 6..=9: 3, 4 and 5 are lost
+        assert_eq!(
+            push(&mut receiver, 9, Some(&retransmission), &mut stats),
+            Some((7, 2))
+        );
+        assert_eq!(ready(&receiver), [0, 2, 6]);
+        assert_eq!(receiver.missing(), [(7, 2)]);
+        assert_eq!(stats.lost(), 4);
+        assert_eq!(stats.delivered(), 3);
+    }
+
+    #[test]
+    fn give_up() {
+        let retransmission = Retransmission::new();
+        let max_wait = retransmission.max_wait;
+        let mut receiver = Receiver::new();
+        let mut stats = SequenceStats::default();
+
+        assert!(receiver.give_up_at(max_wait).is_none());
+
+        let now = Instant::now();
+        receiver.push(0, Bytes::new(), now, Some(&retransmission), &mut stats);
+        receiver.push(3, Bytes::new(), now, Some(&retransmission), &mut stats);
+        receiver.push(4, Bytes::new(), now, Some(&retransmission), &mut stats);
+
+        assert_eq!(receiver.give_up_at(max_wait), Some(now + max_wait));
+
+        receiver.give_up(&mut stats);
+
+        assert_eq!(ready(&receiver), [0, 3, 4]);
+        assert!(receiver.give_up_at(max_wait).is_none());
+        assert_eq!(stats.lost(), 2);
+        assert_eq!(stats.delivered(), 3);
+    }
+
+    #[test]
+    fn duplicates_and_late() {
+        let retransmission = Retransmission::new();
+        let mut receiver = Receiver::new();
+        let mut stats = SequenceStats::default();
+
+        push(&mut receiver, 0, Some(&retransmission), &mut stats);
+        push(&mut receiver, 2, Some(&retransmission), &mut stats);
+
+        // Already delivered
+        assert!(push(&mut receiver, 0, Some(&retransmission), &mut stats).is_none());
+        // Already buffered
+        assert!(push(&mut receiver, 2, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(stats.discarded(), 2);
+
+        receiver.give_up(&mut stats);
+
+        // Given up
+        assert!(push(&mut receiver, 1, Some(&retransmission), &mut stats).is_none());
+        assert_eq!(stats.discarded(), 3);
+        assert_eq!(ready(&receiver), [0, 2]);
+    }
+
+    #[test]
+    fn latest_only() {
+        let mut receiver = Receiver::new();
+        let mut stats = SequenceStats::default();
+
+        assert!(push(&mut receiver, 0, None, &mut stats).is_none());
+        assert!(push(&mut receiver, 3, None, &mut stats).is_none());
+        assert!(push(&mut receiver, 1, None, &mut stats).is_none());
+        assert!(push(&mut receiver, 3, None, &mut stats).is_none());
+        assert!(push(&mut receiver, 4, None, &mut stats).is_none());
+
+        assert_eq!(ready(&receiver), [0, 3, 4]);
+        assert!(receiver.pending.is_empty());
+        assert_eq!(stats.delivered(), 3);
+        assert_eq!(stats.lost(), 2);
+        assert_eq!(stats.discarded(), 2);
+        assert_eq!(stats.loss_rate(), 0.4);
+    }
+
+    #[test]
+    fn retransmit_range() {
+        // History holds 10..14
+        assert_eq!(history_range(10, 4, 11, 2), 1..3);
+        assert_eq!(history_range(10, 4, 8, 4), 0..2);
+        assert_eq!(history_range(10, 4, 12, 10), 2..4);
+        assert_eq!(history_range(10, 4, 0, 100), 0..4);
+        assert_eq!(history_range(10, 4, u64::MAX, u64::MAX), 4..4);
+
+        assert!(history_range(10, 4, 0, 10).is_empty());
+        assert!(history_range(10, 4, 14, 3).is_empty());
+        assert!(history_range(10, 0, 10, 1).is_empty());
+    }
+
+    #[test]
+    fn loss_rate() {
+        assert_eq!(SequenceStats::default().loss_rate(), 0.0);
+    }
+}
@@ -1,18 +1,78 @@
+use crate::engine::flow_control::Reservation;
+use crate::engine::flow_control::SessionCredit;
+use crate::engine::rate_limit::SendRateLimiter;
 use crate::engine::stream::QuicRecvStream;
 use crate::engine::stream::QuicSendStream;
+use crate::engine::stream::WriteProgress;
+use crate::error::SendFromError;
 use crate::error::StreamError;
+use crate::error::StreamReadExactError;
+use crate::error::StreamReadToEndError;
+use crate::events::StreamClosedNotice;
+use crate::runtime;
+use crate::runtime::Sleep;
+use crate::trace;
+use bytes::Buf;
+use bytes::Bytes;
+use bytes::BytesMut;
+use std::future::pending;
+use std::future::poll_fn;
+use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::ready;
 use std::task::Context;
 use std::task::Poll;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
 use tokio::io::ReadBuf;
+use tokio::sync::watch;
+use wtransport_proto::error::ErrorCode;
 use wtransport_proto::ids::StreamId;
 
 /// A stream that can only be used to send data.
-pub struct SendStream(QuicSendStream);
+///
+/// It implements [`tokio::io::AsyncWrite`], so it can be used with the tokio
+/// I/O utilities (e.g., [`tokio::io::copy`]).
+pub struct SendStream {
+    stream: QuicSendStream,
+    progress: Arc<WriteProgress>,
+    stall: watch::Sender<Option<u64>>,
+    bytes_written: u64,
+    priority: i32,
+    credit: Arc<SessionCredit>,
+    send_rate: Arc<SendRateLimiter>,
+    /// Wakes the writer blocked by the send rate limit.
+    send_rate_timer: Mutex<Option<Sleep>>,
+    /// Shared by both halves of a bi-directional stream.
+    _closed_notice: Arc<StreamClosedNotice>,
+}
 
 impl SendStream {
-    pub(crate) fn new(stream: QuicSendStream) -> Self {
-        Self(stream)
+    pub(crate) fn new(
+        stream: QuicSendStream,
+        progress: Arc<WriteProgress>,
+        priority: i32,
+        credit: Arc<SessionCredit>,
+        send_rate: Arc<SendRateLimiter>,
+        closed_notice: Arc<StreamClosedNotice>,
+    ) -> Self {
+        if priority != 0 {
+            stream.set_priority(priority);
+        }
+
+        Self {
+            stream,
+            progress,
+            stall: watch::channel(None).0,
+            bytes_written: 0,
+            priority,
+            credit,
+            send_rate,
+            send_rate_timer: Mutex::new(None),
+            _closed_notice: closed_notice,
+        }
     }
 
     /// Writes bytes to the stream.
@@ -20,49 +80,669 @@ impl SendStream {
     /// On success, returns the number of bytes written.
     /// Congestion and flow control may cause this to be shorter than `buf.len()`,
     /// indicating that only a prefix of `buf` was written.
+    ///
+    /// Writing an empty `buf` is a no-op: it returns `Ok(0)` immediately.
+    /// Otherwise, `Ok(0)` is never returned (at least one byte is written).
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no data has been written.
     pub async fn write(&mut self, buf: &[u8]) -> Result<usize, StreamError> {
-        self.0.write(buf).await
+        poll_fn(|cx| self.poll_write(cx, buf)).await
+    }
+
+    /// Attempts to write bytes to the stream (the poll-based version of
+    /// [`Self::write`]).
+    ///
+    /// It allows to drive the stream from a hand-written state machine (or a
+    /// non-tokio executor): on [`Poll::Pending`], the task is woken when the
+    /// stream can accept more data. The semantics of the result are the same
+    /// as [`Self::write`] (including the empty `buf`).
+    pub fn poll_write(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, StreamError>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let reservation = ready!(self.poll_credit(cx, buf.len()));
+
+        let poll = self.stream.poll_write(cx, &buf[..reservation.amount()]);
+        Self::track_progress(&self.progress, &self.stall, poll.is_ready());
+
+        let written = ready!(poll)?;
+        reservation.commit(written);
+        self.send_rate.consume(written);
+        self.bytes_written += written as u64;
+        Poll::Ready(Ok(written))
+    }
+
+    /// Writes chunks of bytes to the stream, handing them to the transport
+    /// without copying.
+    ///
+    /// On success, returns the number of bytes written: fully written chunks
+    /// are left empty, and a partially written chunk is advanced past the
+    /// written bytes.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no data has been written (and `bufs` is untouched).
+    pub async fn write_chunks(&mut self, bufs: &mut [Bytes]) -> Result<usize, StreamError> {
+        if bufs.iter().all(Bytes::is_empty) {
+            return Ok(0);
+        }
+
+        let total = bufs.iter().map(Bytes::len).sum();
+        let reservation = poll_fn(|cx| self.poll_credit(cx, total)).await;
+
+        // Not enough session credit for all the chunks: a prefix is copied
+        if reservation.amount() < total {
+            drop(reservation);
+
+            let chunk = bufs
+                .iter_mut()
+                .find(|buf| !buf.is_empty())
+                .expect("Not all chunks are empty");
+            let written = self.write(chunk).await?;
+            chunk.advance(written);
+
+            return Ok(written);
+        }
+
+        let written =
+            Self::tracked(&self.progress, &self.stall, self.stream.write_chunks(bufs)).await?;
+
+        reservation.commit(written);
+        self.send_rate.consume(written);
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    /// Convenience method to write all the chunks to the stream, without copying.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, `bufs` holds exactly the data not written yet (written
+    /// chunks are left empty), so that writing can be resumed.
+    pub async fn write_all_chunks(&mut self, bufs: &mut [Bytes]) -> Result<(), StreamError> {
+        let mut first = 0;
+
+        while let Some(offset) = bufs[first..].iter().position(|buf| !buf.is_empty()) {
+            first += offset;
+            self.write_chunks(&mut bufs[first..]).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the whole content of `buf` to the stream, advancing it.
+    ///
+    /// `buf` is advanced only past the bytes actually written. To hand
+    /// [`Bytes`] to the transport without copying, see [`Self::write_all_chunks`].
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, `buf` holds exactly the data not written yet, so that
+    /// writing can be resumed.
+    pub async fn write_all_buf<B>(&mut self, buf: &mut B) -> Result<(), StreamError>
+    where
+        B: Buf,
+    {
+        while buf.has_remaining() {
+            let written = self.write(buf.chunk()).await?;
+            buf.advance(written);
+        }
+
+        Ok(())
     }
 
     /// Convenience method to write an entire buffer to the stream.
-    pub async fn write_all(&mut self, buf: &[u8]) -> Result<(), StreamError> {
-        self.0.write_all(buf).await
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is **not** cancel-safe: if the future is dropped before
+    /// completing, an unknown prefix of `buf` has been written. Use
+    /// [`Self::write_all_buf`] (or [`Self::write_all_chunks`]) to keep track
+    /// of the progress.
+    pub async fn write_all(&mut self, mut buf: &[u8]) -> Result<(), StreamError> {
+        while !buf.is_empty() {
+            let written = self.write(buf).await?;
+            buf = &buf[written..];
+        }
+
+        Ok(())
+    }
+
+    /// Sends the whole content of `reader` (e.g., a file) to the stream.
+    ///
+    /// On success, returns the number of bytes sent. The stream is not
+    /// finished (see [`Self::finish`]).
+    ///
+    /// Data is read in chunks which are handed to the transport without
+    /// copying, and their memory is reused once sent. Reading stops while
+    /// flow control blocks the stream, so that at most a chunk is buffered.
+    /// `len_hint` is the expected length of the content (if known): it
+    /// sizes the chunks of short contents.
+    ///
+    /// See [`Self::send_from_with_progress`] to be notified of the progress.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is **not** cancel-safe: if the future is dropped before
+    /// completing, an unknown prefix of the content has been sent.
+    pub async fn send_from<R>(
+        &mut self,
+        reader: R,
+        len_hint: Option<u64>,
+    ) -> Result<u64, SendFromError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.send_from_with_progress(reader, len_hint, |_| {}).await
+    }
+
+    /// Sends the whole content of `reader` to the stream (see
+    /// [`Self::send_from`]), invoking `progress` with the total number of
+    /// bytes sent after each chunk.
+    pub async fn send_from_with_progress<R, F>(
+        &mut self,
+        mut reader: R,
+        len_hint: Option<u64>,
+        mut progress: F,
+    ) -> Result<u64, SendFromError>
+    where
+        R: AsyncRead + Unpin,
+        F: FnMut(u64),
+    {
+        /// Size of the chunks read from the source.
+        const CHUNK_SIZE: usize = 64 * 1024;
+
+        let chunk_size = len_hint.map_or(CHUNK_SIZE, |len_hint| {
+            usize::try_from(len_hint).map_or(CHUNK_SIZE, |len_hint| len_hint.clamp(1, CHUNK_SIZE))
+        });
+
+        let mut buffer = BytesMut::with_capacity(chunk_size);
+        let mut sent = 0;
+
+        loop {
+            // It reuses the memory of the chunks already sent
+            buffer.reserve(chunk_size);
+
+            let read = reader
+                .read_buf(&mut buffer)
+                .await
+                .map_err(SendFromError::Read)?;
+
+            if read == 0 {
+                return Ok(sent);
+            }
+
+            let mut chunk = [buffer.split().freeze()];
+            self.write_all_chunks(&mut chunk)
+                .await
+                .map_err(SendFromError::Stream)?;
+
+            sent += read as u64;
+            progress(sent);
+        }
+    }
+
+    /// Returns a future which completes when a write on this stream stalls
+    /// because of flow control.
+    ///
+    /// The future reports which limit has been hit: see [`BlockedLimit`].
+    /// It does not borrow the stream, so that it can be awaited concurrently
+    /// with write operations.
+    ///
+    /// **Note**: the limit is inferred observing the other streams on the same
+    /// connection for about a round-trip time after the stall (unless it is
+    /// the session limit).
+    pub fn blocked(&self) -> impl Future<Output = BlockedLimit> + Send + 'static {
+        let progress = self.progress.clone();
+        let credit = self.credit.clone();
+        let mut stall = self.stall.subscribe();
+
+        async move {
+            loop {
+                let generation = *stall.borrow_and_update();
+
+                let generation = match generation {
+                    Some(generation) => generation,
+                    None => {
+                        if stall.changed().await.is_err() {
+                            return pending().await;
+                        }
+                        continue;
+                    }
+                };
+
+                if credit.is_data_blocked() {
+                    return BlockedLimit::Session;
+                }
+
+                // Let the other streams of the connection the chance to progress
+                runtime::sleep(progress.observation_period()).await;
+
+                if *stall.borrow() == Some(generation) {
+                    if progress.generation() > generation {
+                        return BlockedLimit::Stream;
+                    } else {
+                        return BlockedLimit::Connection;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the current statistics of the stream.
+    ///
+    /// **Note**: the amount of data buffered but not yet sent, and the stream
+    /// flow-control limit, are not exposed by the underlying QUIC implementation.
+    /// Use [`Self::blocked`] to know which limit a stalled write is blocked by.
+    pub fn stats(&self) -> SendStreamStats {
+        SendStreamStats {
+            bytes_written: self.bytes_written,
+            blocked: self.stall.borrow().is_some(),
+        }
+    }
+
+    /// Sets the priority of the stream.
+    ///
+    /// When multiple streams of the same connection have data to send, the
+    /// data of streams with higher priority is sent first (e.g., so that
+    /// control messages preempt bulk transfers). Streams with the same
+    /// priority share the bandwidth in a round-robin fashion.
+    ///
+    /// The initial priority is set with
+    /// [`ServerConfig::set_default_stream_priority`](crate::ServerConfig::set_default_stream_priority)
+    /// (or the client counterpart).
+    pub fn set_priority(&mut self, priority: i32) {
+        self.stream.set_priority(priority);
+        self.priority = priority;
+    }
+
+    /// Returns the priority of the stream (see [`Self::set_priority`]).
+    #[inline(always)]
+    pub fn priority(&self) -> i32 {
+        self.priority
     }
 
     /// Shut down the stream gracefully.
     ///
     /// No new data may be written after calling this method. Completes when the peer has
     /// acknowledged all sent data, retransmitting data as needed.
+    ///
+    /// A stream can be finished without writing any data: the peer
+    /// observes the end of the stream on its first read.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: once polled, the stream is finished even
+    /// if the future is dropped before the peer acknowledges the data.
     pub async fn finish(&mut self) -> Result<(), StreamError> {
-        self.0.finish().await
+        self.stream.finish().await
+    }
+
+    /// Attempts to finish the stream (the poll-based version of [`Self::finish`]).
+    ///
+    /// The stream is finished at the first poll: [`Poll::Ready`] is returned
+    /// once the peer has acknowledged all the data.
+    pub fn poll_finish(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), StreamError>> {
+        self.stream.poll_finish(cx)
+    }
+
+    /// Waits for the peer to ask to stop sending data on the stream.
+    ///
+    /// On success, returns the application-level error code the peer used
+    /// (see [`RecvStream::stop`]). It is [`None`] if the code is not in the
+    /// range reserved to applications (e.g., the peer's session is gone).
+    pub async fn stopped(&mut self) -> Result<Option<u32>, StreamError> {
+        let code = self.stream.stopped().await?;
+        Ok(ErrorCode::from_http3(code))
+    }
+
+    /// Abruptly closes the stream, discarding any data not yet delivered.
+    ///
+    /// The peer is notified with `error_code`, an application-level code
+    /// (it is mapped into the HTTP3 error space reserved to WebTransport).
+    pub fn reset(&mut self, error_code: u32) {
+        trace::stream_reset(self.id(), error_code);
+        self.stream.reset(ErrorCode::to_http3(error_code));
     }
 
     /// Returns the [`StreamId`] associated.
     #[inline(always)]
     pub fn id(&self) -> StreamId {
-        self.0.id()
+        self.stream.id()
+    }
+
+    /// Reserves session credit for writing up to `amount` bytes, within
+    /// the send rate limit of the session.
+    ///
+    /// Waiting for credit is tracked as a stalled write.
+    fn poll_credit(&self, cx: &mut Context<'_>, amount: usize) -> Poll<Reservation> {
+        let poll = match self.poll_send_rate(cx, amount) {
+            Poll::Ready(amount) => self.credit.poll_reserve(cx, amount),
+            Poll::Pending => Poll::Pending,
+        };
+
+        if poll.is_pending() {
+            Self::track_progress(&self.progress, &self.stall, false);
+        }
+
+        poll
+    }
+
+    /// Returns how many of `amount` bytes the send rate limit allows now.
+    fn poll_send_rate(&self, cx: &mut Context<'_>, amount: usize) -> Poll<usize> {
+        let retry_at = match self.send_rate.available(amount) {
+            Ok(amount) => return Poll::Ready(amount),
+            Err(retry_at) => retry_at,
+        };
+
+        let mut timer = self
+            .send_rate_timer
+            .lock()
+            .expect("Send rate timer lock poisoned");
+
+        if timer
+            .insert(runtime::sleep_until(retry_at))
+            .as_mut()
+            .poll(cx)
+            .is_ready()
+        {
+            cx.waker().wake_by_ref();
+        }
+
+        Poll::Pending
+    }
+
+    /// Runs a write operation, tracking its progress.
+    async fn tracked<F, T>(
+        progress: &WriteProgress,
+        stall: &watch::Sender<Option<u64>>,
+        write: F,
+    ) -> T
+    where
+        F: Future<Output = T>,
+    {
+        tokio::pin!(write);
+
+        poll_fn(|cx| {
+            let poll = write.as_mut().poll(cx);
+            Self::track_progress(progress, stall, poll.is_ready());
+            poll
+        })
+        .await
     }
+
+    /// Records whether a write operation completed or stalled.
+    fn track_progress(progress: &WriteProgress, stall: &watch::Sender<Option<u64>>, ready: bool) {
+        if ready {
+            progress.advance();
+            stall.send_if_modified(|stall| stall.take().is_some());
+        } else {
+            let generation = progress.generation();
+            stall.send_if_modified(|stall| {
+                if stall.is_none() {
+                    *stall = Some(generation);
+                    true
+                } else {
+                    false
+                }
+            });
+        }
+    }
+}
+
+/// Statistics of a [`SendStream`], see [`SendStream::stats`].
+#[derive(Copy, Clone, Debug)]
+pub struct SendStreamStats {
+    bytes_written: u64,
+    blocked: bool,
+}
+
+impl SendStreamStats {
+    /// The number of bytes written to the stream so far.
+    ///
+    /// Written bytes are buffered and sent according to flow and
+    /// congestion control, so they might not have been sent yet.
+    #[inline(always)]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Whether a write operation is currently stalled, i.e., the stream
+    /// cannot accept more data until the peer grants more credit.
+    #[inline(always)]
+    pub fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+}
+
+/// The flow-control limit a stalled write is blocked by.
+///
+/// See [`SendStream::blocked`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlockedLimit {
+    /// The stream limit: other streams keep progressing, so
+    /// the peer is likely not reading from this stream.
+    Stream,
+
+    /// The connection limit: no stream is progressing, because of connection
+    /// flow control or congestion (e.g., a slow network).
+    Connection,
+
+    /// The session limit: the peer has not granted more data to the
+    /// streams of the session (`WT_MAX_DATA`).
+    Session,
 }
 
 /// A stream that can only be used to receive data.
-pub struct RecvStream(QuicRecvStream);
+///
+/// It implements [`tokio::io::AsyncRead`], so it can be used with the tokio
+/// I/O utilities (e.g., [`tokio::io::BufReader`]).
+pub struct RecvStream {
+    stream: QuicRecvStream,
+    credit: Arc<SessionCredit>,
+    /// Shared by both halves of a bi-directional stream.
+    _closed_notice: Arc<StreamClosedNotice>,
+}
 
 impl RecvStream {
-    pub(crate) fn new(stream: QuicRecvStream) -> Self {
-        Self(stream)
+    /// Size of the chunks read by [`Self::read_to_end`].
+    const READ_CHUNK_SIZE: usize = 8192;
+
+    pub(crate) fn new(
+        stream: QuicRecvStream,
+        credit: Arc<SessionCredit>,
+        closed_notice: Arc<StreamClosedNotice>,
+    ) -> Self {
+        Self {
+            stream,
+            credit,
+            _closed_notice: closed_notice,
+        }
     }
 
     /// Read data contiguously from the stream.
     ///
-    /// On success, returns the number of bytes read into `buf`.
+    /// On success, returns the number of bytes read into `buf`, or [`None`] if the
+    /// peer finished the stream. A stream finished without any data returns [`None`]
+    /// on the first read.
+    ///
+    /// Reading into an empty `buf` returns `Ok(Some(0))` immediately.
+    /// Otherwise, `Ok(Some(0))` is never returned (at least one byte is read).
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is cancel-safe: if the future is dropped before
+    /// completing, no data has been read.
     pub async fn read(&mut self, buf: &mut [u8]) -> Result<Option<usize>, StreamError> {
-        self.0.read(buf).await
+        poll_fn(|cx| self.poll_read(cx, buf)).await
+    }
+
+    /// Attempts to read data from the stream (the poll-based version of
+    /// [`Self::read`]).
+    ///
+    /// It allows to drive the stream from a hand-written state machine (or a
+    /// non-tokio executor): on [`Poll::Pending`], the task is woken when data
+    /// is available. The semantics of the result are the same as
+    /// [`Self::read`] (including the empty `buf`).
+    pub fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<Option<usize>, StreamError>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(Some(0)));
+        }
+
+        let read = ready!(self.stream.poll_read(cx, buf))?;
+        self.credit.on_read(read.unwrap_or(0));
+        Poll::Ready(Ok(read))
+    }
+
+    /// Reads data from the stream until `buf` is full.
+    ///
+    /// It fails with [`StreamReadExactError::FinishedEarly`] if the peer
+    /// finishes the stream before that.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is **not** cancel-safe: if the future is dropped before
+    /// completing, the data read so far (an unknown prefix of `buf`) is
+    /// consumed from the stream. Use [`Self::read`] in a loop instead.
+    pub async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), StreamReadExactError> {
+        let mut filled = 0;
+
+        while filled < buf.len() {
+            match self.read(&mut buf[filled..]).await {
+                Ok(Some(read)) => filled += read,
+                Ok(None) => return Err(StreamReadExactError::FinishedEarly(filled)),
+                Err(error) => return Err(StreamReadExactError::Stream(error)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads all the data until the peer finishes the stream.
+    ///
+    /// It fails with [`StreamReadToEndError::TooLong`] as soon as more than
+    /// `size_limit` bytes are received: the limit protects from peers sending
+    /// unbounded amounts of data.
+    ///
+    /// # Cancel safety
+    ///
+    /// This method is **not** cancel-safe: if the future is dropped before
+    /// completing, the data read so far is lost.
+    pub async fn read_to_end(
+        &mut self,
+        size_limit: usize,
+    ) -> Result<Vec<u8>, StreamReadToEndError> {
+        let mut data = Vec::new();
+        let mut chunk = vec![0; Self::READ_CHUNK_SIZE];
+
+        loop {
+            match self.read(&mut chunk).await {
+                Ok(Some(read)) => {
+                    if data.len() + read > size_limit {
+                        return Err(StreamReadToEndError::TooLong);
+                    }
+
+                    data.extend_from_slice(&chunk[..read]);
+                }
+                Ok(None) => return Ok(data),
+                Err(error) => return Err(StreamReadToEndError::Stream(error)),
+            }
+        }
+    }
+
+    /// Asks the peer to stop sending data on the stream.
+    ///
+    /// The peer is notified with `error_code`, an application-level code
+    /// (it is mapped into the HTTP3 error space reserved to WebTransport).
+    pub fn stop(&mut self, error_code: u32) {
+        trace::stream_stopped(self.id(), error_code);
+        self.stream.stop(ErrorCode::to_http3(error_code));
     }
 
     /// Returns the [`StreamId`] associated.
     #[inline(always)]
     pub fn id(&self) -> StreamId {
-        self.0.id()
+        self.stream.id()
+    }
+}
+
+/// A bi-directional stream, combining a [`SendStream`] and a [`RecvStream`].
+///
+/// It implements both [`tokio::io::AsyncRead`] and [`tokio::io::AsyncWrite`],
+/// so that it can be used with utilities requiring a single duplex I/O object,
+/// such as `tokio_util::codec::Framed`.
+///
+/// * When the peer finishes its sending side, reading returns EOF (i.e., the
+///   codec terminates its stream of frames).
+/// * When the peer resets or stops the stream, operations fail with
+///   [`std::io::ErrorKind::ConnectionReset`].
+/// * Shutting down (e.g., closing the sink) finishes the sending side.
+///
+/// # Example
+/// ```no_run
+/// # use wtransport::Connection;
+/// use tokio_util::codec::Framed;
+/// use tokio_util::codec::LengthDelimitedCodec;
+/// use wtransport::stream::StreamCompat;
+///
+/// # async fn run(connection: Connection) {
+/// let (send_stream, recv_stream) = connection.open_bi().await.unwrap();
+/// let framed = Framed::new(
+///     StreamCompat::new(send_stream, recv_stream),
+///     LengthDelimitedCodec::new(),
+/// );
+/// # }
+/// ```
+pub struct StreamCompat {
+    send_stream: SendStream,
+    recv_stream: RecvStream,
+}
+
+impl StreamCompat {
+    /// Combines the two sides of a bi-directional stream.
+    pub fn new(send_stream: SendStream, recv_stream: RecvStream) -> Self {
+        Self {
+            send_stream,
+            recv_stream,
+        }
+    }
+
+    /// Returns a reference to the sending side.
+    #[inline(always)]
+    pub fn send_stream(&self) -> &SendStream {
+        &self.send_stream
+    }
+
+    /// Returns a reference to the receiving side.
+    #[inline(always)]
+    pub fn recv_stream(&self) -> &RecvStream {
+        &self.recv_stream
+    }
+
+    /// Splits back into the two sides of the stream.
+    pub fn into_inner(self) -> (SendStream, RecvStream) {
+        (self.send_stream, self.recv_stream)
+    }
+}
+
+impl From<(SendStream, RecvStream)> for StreamCompat {
+    fn from((send_stream, recv_stream): (SendStream, RecvStream)) -> Self {
+        Self::new(send_stream, recv_stream)
     }
 }
 
@@ -72,15 +752,30 @@ impl tokio::io::AsyncWrite for SendStream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<std::io::Result<usize>> {
-        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.0), cx, buf)
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let this = &mut *self;
+        let reservation = ready!(this.poll_credit(cx, buf.len()));
+
+        let buf = &buf[..reservation.amount()];
+        let poll = tokio::io::AsyncWrite::poll_write(Pin::new(&mut this.stream), cx, buf);
+        Self::track_progress(&this.progress, &this.stall, poll.is_ready());
+        if let Poll::Ready(Ok(written)) = poll {
+            reservation.commit(written);
+            this.send_rate.consume(written);
+            this.bytes_written += written as u64;
+        }
+        poll
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
-        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.0), cx)
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.stream), cx)
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
-        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.0), cx)
+        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.stream), cx)
     }
 
     fn poll_write_vectored(
@@ -88,11 +783,34 @@ impl tokio::io::AsyncWrite for SendStream {
         cx: &mut Context<'_>,
         bufs: &[std::io::IoSlice<'_>],
     ) -> Poll<Result<usize, std::io::Error>> {
-        tokio::io::AsyncWrite::poll_write_vectored(Pin::new(&mut self.0), cx, bufs)
+        let total = bufs.iter().map(|buf| buf.len()).sum();
+
+        let this = &mut *self;
+        let reservation = ready!(this.poll_credit(cx, total));
+
+        // Not enough session credit for all the buffers: a prefix is written
+        if reservation.amount() < total {
+            drop(reservation);
+
+            let buf = bufs
+                .iter()
+                .find(|buf| !buf.is_empty())
+                .expect("Reserved less than a non-zero total");
+            return tokio::io::AsyncWrite::poll_write(self, cx, buf);
+        }
+
+        let poll = tokio::io::AsyncWrite::poll_write_vectored(Pin::new(&mut this.stream), cx, bufs);
+        Self::track_progress(&this.progress, &this.stall, poll.is_ready());
+        if let Poll::Ready(Ok(written)) = poll {
+            reservation.commit(written);
+            this.send_rate.consume(written);
+            this.bytes_written += written as u64;
+        }
+        poll
     }
 
     fn is_write_vectored(&self) -> bool {
-        tokio::io::AsyncWrite::is_write_vectored(&self.0)
+        tokio::io::AsyncWrite::is_write_vectored(&self.stream)
     }
 }
 
@@ -102,6 +820,58 @@ impl tokio::io::AsyncRead for RecvStream {
         cx: &mut Context<'_>,
         buf: &mut ReadBuf<'_>,
     ) -> Poll<std::io::Result<()>> {
-        tokio::io::AsyncRead::poll_read(Pin::new(&mut self.0), cx, buf)
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let filled = buf.filled().len();
+        ready!(tokio::io::AsyncRead::poll_read(
+            Pin::new(&mut self.stream),
+            cx,
+            buf
+        ))?;
+
+        self.credit.on_read(buf.filled().len() - filled);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl tokio::io::AsyncRead for StreamCompat {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        tokio::io::AsyncRead::poll_read(Pin::new(&mut self.recv_stream), cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for StreamCompat {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.send_stream), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.send_stream), cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.send_stream), cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        tokio::io::AsyncWrite::poll_write_vectored(Pin::new(&mut self.send_stream), cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        tokio::io::AsyncWrite::is_write_vectored(&self.send_stream)
     }
 }
@@ -1,12 +1,26 @@
+use ring::digest::digest;
+use ring::digest::SHA256;
+use rustls::sign::CertifiedKey;
+use rustls::SignatureScheme;
+use std::fmt;
 use std::path::Path;
+use std::sync::Arc;
 
-/// A server TLS certificate.
+/// A TLS certificate chain along with its private key.
 pub struct Certificate {
     pub(crate) certificates: Vec<rustls::Certificate>,
     pub(crate) key: rustls::PrivateKey,
 }
 
 impl Certificate {
+    /// Validity period of certificates generated by [`Self::self_signed`].
+    ///
+    /// This is the maximum validity allowed by browsers for `serverCertificateHashes`.
+    #[cfg(feature = "self-signed")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "self-signed")))]
+    pub const SELF_SIGNED_VALIDITY: std::time::Duration =
+        std::time::Duration::from_secs(14 * 24 * 60 * 60);
+
     /// Creates a certificate from encoded data.
     ///
     /// `certificates` is a chain where each certificate-data must be *DER-encoded* *X.509*.
@@ -37,4 +51,375 @@ impl Certificate {
 
         Ok(Self::new(certificates, private_key))
     }
+
+    /// Generates a self-signed certificate for development purposes.
+    ///
+    /// The certificate uses an *ECDSA* *P-256* key and it is valid for
+    /// [`Self::SELF_SIGNED_VALIDITY`] (starting from one day before generation,
+    /// to tolerate clock skews). That is, it meets the requirements for being
+    /// trusted by browsers via `serverCertificateHashes`: see [`Self::hashes`].
+    ///
+    /// `subject_alt_names` are the DNS names or IP addresses the certificate is
+    /// valid for (e.g., `["localhost", "127.0.0.1", "::1"]`).
+    #[cfg(feature = "self-signed")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "self-signed")))]
+    pub fn self_signed<I, S>(subject_alt_names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        use rcgen::CertificateParams;
+        use rcgen::DistinguishedName;
+        use rcgen::DnType;
+        use rcgen::PKCS_ECDSA_P256_SHA256;
+        use time::Duration;
+        use time::OffsetDateTime;
+
+        let subject_alt_names = subject_alt_names
+            .into_iter()
+            .map(|name| name.as_ref().to_string())
+            .collect::<Vec<_>>();
+
+        let mut dname = DistinguishedName::new();
+        dname.push(DnType::CommonName, "wtransport self-signed");
+
+        let not_before = OffsetDateTime::now_utc() - Duration::days(1);
+
+        let mut cert_params = CertificateParams::new(subject_alt_names);
+        cert_params.distinguished_name = dname;
+        cert_params.alg = &PKCS_ECDSA_P256_SHA256;
+        cert_params.not_before = not_before;
+        cert_params.not_after = not_before
+            + Duration::try_from(Self::SELF_SIGNED_VALIDITY).expect("Validity fits a Duration");
+
+        // Generation can fail only with an unsupported key algorithm
+        let certificate = rcgen::Certificate::from_params(cert_params)
+            .expect("ECDSA P-256 is supported for key generation");
+
+        Self::new(
+            vec![certificate
+                .serialize_der()
+                .expect("Self-signed certificate serialization")],
+            certificate.serialize_private_key_der(),
+        )
+    }
+
+    /// Converts this certificate into a [`CertifiedKey`], e.g., for
+    /// [`rustls::server::ResolvesServerCertUsingSni`].
+    ///
+    /// It returns [`Err`] if the private key type is not supported.
+    pub fn into_certified_key(self) -> Result<CertifiedKey, rustls::Error> {
+        let key = rustls::sign::any_supported_type(&self.key)
+            .map_err(|_| rustls::Error::General("Unsupported private key type".to_string()))?;
+
+        Ok(CertifiedKey::new(self.certificates, key))
+    }
+
+    /// Returns the SHA-256 digests of the certificates (DER-encoded) in the chain.
+    ///
+    /// These are the values a browser client passes as `serverCertificateHashes`
+    /// in order to trust a self-signed certificate.
+    pub fn hashes(&self) -> Vec<Sha256Digest> {
+        self.certificates
+            .iter()
+            .map(|certificate| Sha256Digest::compute(&certificate.0))
+            .collect()
+    }
+}
+
+/// A SHA-256 digest.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Sha256Digest([u8; 32]);
+
+impl Sha256Digest {
+    /// Creates a digest from its raw bytes.
+    #[inline(always)]
+    pub const fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Computes the digest of `data`.
+    pub fn compute(data: &[u8]) -> Self {
+        Self(
+            digest(&SHA256, data)
+                .as_ref()
+                .try_into()
+                .expect("SHA-256 digest is 32 bytes"),
+        )
+    }
+
+    /// Returns the raw bytes of the digest.
+    #[inline(always)]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<[u8; 32]> for Sha256Digest {
+    #[inline(always)]
+    fn from(bytes: [u8; 32]) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Sha256Digest {
+    #[inline(always)]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Sha256Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats the digest as lowercase hexadecimal string.
+impl fmt::Display for Sha256Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Client certificate resolver always presenting the same certificate.
+pub(crate) struct ClientCertificate(Arc<CertifiedKey>);
+
+impl ClientCertificate {
+    pub(crate) fn new(certified_key: Arc<CertifiedKey>) -> Self {
+        Self(certified_key)
+    }
+}
+
+impl rustls::client::ResolvesClientCert for ClientCertificate {
+    fn resolve(
+        &self,
+        _acceptable_issuers: &[&[u8]],
+        _sigschemes: &[SignatureScheme],
+    ) -> Option<Arc<CertifiedKey>> {
+        Some(self.0.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+/// Server certificate verification based on the certificate hash.
+///
+/// It implements the constraints of the `serverCertificateHashes` mechanism
+/// of the WebTransport browser API: the certificate must be a *X.509v3*
+/// certificate with an *ECDSA* public key, whose validity period is not
+/// longer than two weeks and it includes the current time.
+#[cfg(feature = "dangerous-configuration")]
+pub(crate) struct ServerHashVerification {
+    hashes: Vec<Sha256Digest>,
+}
+
+#[cfg(feature = "dangerous-configuration")]
+impl ServerHashVerification {
+    /// Maximum validity period of a certificate, in seconds.
+    const MAX_VALIDITY: i64 = 14 * 24 * 60 * 60;
+
+    pub(crate) fn new(hashes: Vec<Sha256Digest>) -> Self {
+        Self { hashes }
+    }
+}
+
+#[cfg(feature = "dangerous-configuration")]
+impl rustls::client::ServerCertVerifier for ServerHashVerification {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        _intermediates: &[rustls::Certificate],
+        _server_name: &rustls::ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        now: std::time::SystemTime,
+    ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+        use rustls::CertificateError;
+
+        if !self.hashes.contains(&Sha256Digest::compute(&end_entity.0)) {
+            return Err(rustls::Error::InvalidCertificate(
+                CertificateError::UnknownIssuer,
+            ));
+        }
+
+        let info = der::CertificateInfo::parse(&end_entity.0).ok_or(
+            rustls::Error::InvalidCertificate(CertificateError::BadEncoding),
+        )?;
+
+        if !info.is_v3 || !info.is_ecdsa || info.not_after - info.not_before > Self::MAX_VALIDITY {
+            return Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+
+        let now = now
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|_| rustls::Error::FailedToGetCurrentTime)?
+            .as_secs() as i64;
+
+        if now < info.not_before {
+            return Err(rustls::Error::InvalidCertificate(
+                CertificateError::NotValidYet,
+            ));
+        }
+
+        if now > info.not_after {
+            return Err(rustls::Error::InvalidCertificate(CertificateError::Expired));
+        }
+
+        Ok(rustls::client::ServerCertVerified::assertion())
+    }
+}
+
+/// Minimal DER decoding of the X.509 fields needed for certificate hash verification.
+#[cfg(feature = "dangerous-configuration")]
+mod der {
+    const SEQUENCE: u8 = 0x30;
+    const INTEGER: u8 = 0x02;
+    const OID: u8 = 0x06;
+    const UTC_TIME: u8 = 0x17;
+    const GENERALIZED_TIME: u8 = 0x18;
+    const EXPLICIT_VERSION: u8 = 0xa0;
+
+    /// id-ecPublicKey (1.2.840.10045.2.1).
+    const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+    pub(super) struct CertificateInfo {
+        pub(super) is_v3: bool,
+        pub(super) is_ecdsa: bool,
+        pub(super) not_before: i64,
+        pub(super) not_after: i64,
+    }
+
+    impl CertificateInfo {
+        pub(super) fn parse(certificate: &[u8]) -> Option<Self> {
+            let certificate = Reader(certificate).expect(SEQUENCE)?;
+            let mut tbs = Reader(Reader(certificate).expect(SEQUENCE)?);
+
+            let (tag, value) = tbs.read()?;
+            let is_v3 = if tag == EXPLICIT_VERSION {
+                let is_v3 = Reader(value).expect(INTEGER)? == [0x02];
+                tbs.expect(INTEGER)?; // serialNumber
+                is_v3
+            } else if tag == INTEGER {
+                false
+            } else {
+                return None;
+            };
+
+            tbs.expect(SEQUENCE)?; // signature
+            tbs.expect(SEQUENCE)?; // issuer
+
+            let mut validity = Reader(tbs.expect(SEQUENCE)?);
+            let not_before = parse_time(validity.read()?)?;
+            let not_after = parse_time(validity.read()?)?;
+
+            tbs.expect(SEQUENCE)?; // subject
+
+            let mut spki = Reader(tbs.expect(SEQUENCE)?);
+            let mut algorithm = Reader(spki.expect(SEQUENCE)?);
+            let is_ecdsa = algorithm.expect(OID)? == OID_EC_PUBLIC_KEY;
+
+            Some(Self {
+                is_v3,
+                is_ecdsa,
+                not_before,
+                not_after,
+            })
+        }
+    }
+
+    struct Reader<'a>(&'a [u8]);
+
+    impl<'a> Reader<'a> {
+        /// Reads the next TLV, returning its tag and value.
+        fn read(&mut self) -> Option<(u8, &'a [u8])> {
+            let (&tag, rest) = self.0.split_first()?;
+            let (&first, mut rest) = rest.split_first()?;
+
+            let len = if first & 0x80 == 0 {
+                first as usize
+            } else {
+                let num_bytes = (first & 0x7f) as usize;
+                if num_bytes == 0 || num_bytes > std::mem::size_of::<u32>() {
+                    return None;
+                }
+
+                let len_bytes = rest.get(..num_bytes)?;
+                rest = &rest[num_bytes..];
+                len_bytes
+                    .iter()
+                    .fold(0, |len, &byte| (len << 8) | byte as usize)
+            };
+
+            let value = rest.get(..len)?;
+            self.0 = &rest[len..];
+
+            Some((tag, value))
+        }
+
+        fn expect(&mut self, expected_tag: u8) -> Option<&'a [u8]> {
+            match self.read()? {
+                (tag, value) if tag == expected_tag => Some(value),
+                _ => None,
+            }
+        }
+    }
+
+    /// Parses an ASN.1 `Time` into seconds since UNIX epoch.
+    fn parse_time((tag, value): (u8, &[u8])) -> Option<i64> {
+        let value = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+
+        let (year, rest) = match tag {
+            UTC_TIME => {
+                let year = parse_digits(value.get(..2)?)?;
+                let year = if year >= 50 { 1900 + year } else { 2000 + year };
+                (year, value.get(2..)?)
+            }
+            GENERALIZED_TIME => (parse_digits(value.get(..4)?)?, value.get(4..)?),
+            _ => return None,
+        };
+
+        if rest.len() != 10 {
+            return None;
+        }
+
+        let month = parse_digits(&rest[0..2])?;
+        let day = parse_digits(&rest[2..4])?;
+        let hour = parse_digits(&rest[4..6])?;
+        let minute = parse_digits(&rest[6..8])?;
+        let second = parse_digits(&rest[8..10])?;
+
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+    }
+
+    fn parse_digits(digits: &str) -> Option<i64> {
+        if digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            digits.parse().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Number of days since UNIX epoch of a date in the proleptic Gregorian calendar.
+    fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+        era * 146097 + day_of_era - 719468
+    }
 }
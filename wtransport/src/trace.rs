@@ -0,0 +1,119 @@
+//! Events of the connection lifecycle, emitted with [`tracing`](https://docs.rs/tracing)
+//! (only with the `tracing` feature: otherwise these functions do nothing).
+//!
+//! Events are emitted at `DEBUG` level, within a `connection` span carrying the
+//! remote address and the QUIC connection ID (once the handshake completes).
+#![cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+
+use crate::error::ConnectionError;
+use std::future::Future;
+use std::net::SocketAddr;
+use wtransport_proto::ids::SessionId;
+use wtransport_proto::ids::StreamId;
+
+/// Runs a connection attempt within a new `connection` span.
+#[cfg(feature = "tracing")]
+pub(crate) fn connection_span<F>(
+    future: F,
+    side: &'static str,
+    remote_address: SocketAddr,
+) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    use tracing::Instrument;
+
+    future.instrument(tracing::debug_span!(
+        "connection",
+        side,
+        remote = %remote_address,
+        connection = tracing::field::Empty,
+    ))
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn connection_span<F>(
+    future: F,
+    side: &'static str,
+    remote_address: SocketAddr,
+) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    future
+}
+
+/// Runs a spawned task within the span of the caller.
+#[cfg(feature = "tracing")]
+pub(crate) fn in_current_span<F>(future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    tracing::Instrument::in_current_span(future)
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn in_current_span<F>(future: F) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    future
+}
+
+pub(crate) fn incoming_connection(remote_address: SocketAddr) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(remote = %remote_address, "Incoming connection");
+}
+
+pub(crate) fn handshake_completed(quic_connection: &quinn::Connection) {
+    #[cfg(feature = "tracing")]
+    {
+        tracing::Span::current().record("connection", quic_connection.stable_id());
+        tracing::debug!("QUIC handshake completed");
+    }
+}
+
+pub(crate) fn settings_sent() {
+    #[cfg(feature = "tracing")]
+    tracing::debug!("SETTINGS sent");
+}
+
+pub(crate) fn settings_received() {
+    #[cfg(feature = "tracing")]
+    tracing::debug!("SETTINGS received");
+}
+
+pub(crate) fn session_established(session_id: SessionId) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(session = %session_id, "Session established");
+}
+
+pub(crate) fn connection_failed(error: &ConnectionError) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(error = %error, "Connection failed");
+}
+
+pub(crate) fn stream_opened(session_id: SessionId, stream_id: StreamId, local: bool) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(session = %session_id, stream = %stream_id, local, "Stream opened");
+}
+
+pub(crate) fn stream_rejected(stream_id: StreamId) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(stream = %stream_id, "Stream rejected (session buffer full)");
+}
+
+pub(crate) fn stream_reset(stream_id: StreamId, error_code: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(stream = %stream_id, error_code, "Stream reset");
+}
+
+pub(crate) fn stream_stopped(stream_id: StreamId, error_code: u32) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(stream = %stream_id, error_code, "Stream stopped");
+}
+
+pub(crate) fn datagram_dropped(reason: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(reason, "Datagram dropped");
+}